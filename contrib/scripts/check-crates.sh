@@ -26,6 +26,9 @@ fi
 buildargs=(
     "-p webln --target wasm32-unknown-unknown"
     "-p webln --no-default-features --target wasm32-unknown-unknown"
+    "-p webln --no-default-features --features secp256k1 --target wasm32-unknown-unknown"
+    "-p webln --all-features --target wasm32-unknown-unknown"
+    "-p webln-yew --target wasm32-unknown-unknown"
 )
 
 for arg in "${buildargs[@]}"; do
@@ -0,0 +1,167 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! WebLN hooks for Dioxus
+//!
+//! Call [`use_webln_provider`] in the root component, then [`use_webln`] and [`use_payment`]
+//! anywhere below it. Async work runs on the Dioxus runtime and is cancelled when the component
+//! is dropped.
+//!
+//! ```rust,no_run
+//! use dioxus::prelude::*;
+//! use webln_dioxus::{use_payment, use_webln_provider, PaymentState};
+//!
+//! #[component]
+//! fn Pay(invoice: String) -> Element {
+//!     let mut payment = use_payment();
+//!     match payment.state() {
+//!         PaymentState::Idle => rsx! {
+//!             button { onclick: move |_| payment.send_payment(invoice.clone()), "Pay" }
+//!         },
+//!         PaymentState::Pending => rsx! { "Paying..." },
+//!         PaymentState::Paid(res) => rsx! { "Paid: {res.preimage}" },
+//!         PaymentState::Failed(e) => rsx! { "Failed: {e}" },
+//!     }
+//! }
+//!
+//! #[component]
+//! fn App() -> Element {
+//!     use_webln_provider();
+//!     rsx! { Pay { invoice: "lnbc..." } }
+//! }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::bare_urls)]
+
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+
+use dioxus::prelude::*;
+pub use webln;
+use webln::{Error, KeysendArgs, SendPaymentResponse, WebLN};
+
+/// Default time to wait for the provider injection
+pub const DETECTION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Result of a WebLN hook (errors are shared, since hook values must be cloneable)
+pub type WebLNResult<T> = Result<T, Rc<Error>>;
+
+/// Provider detection, shared as context by [`use_webln_provider`]
+pub type WebLNResource = Resource<WebLNResult<WebLN>>;
+
+/// Detect the provider and share it with the children
+pub fn use_webln_provider() -> WebLNResource {
+    let provider: WebLNResource = use_resource(|| async {
+        WebLN::wait_for_provider(DETECTION_TIMEOUT)
+            .await
+            .map_err(Rc::new)
+    });
+    use_context_provider(|| provider)
+}
+
+/// Get the provider detection
+///
+/// # Panics
+///
+/// Panics if [`use_webln_provider`] was not called by a parent component.
+pub fn use_webln() -> WebLNResource {
+    use_context()
+}
+
+/// Payment state
+#[derive(Debug, Clone, Default)]
+pub enum PaymentState {
+    /// No payment sent
+    #[default]
+    Idle,
+    /// Waiting for the provider
+    Pending,
+    /// Paid
+    Paid(SendPaymentResponse),
+    /// Failed (or rejected by the user)
+    Failed(Rc<Error>),
+}
+
+impl PaymentState {
+    /// Check if waiting for the provider
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Self::Pending)
+    }
+}
+
+/// [`use_payment`] handle
+#[derive(Clone, Copy)]
+pub struct UsePayment {
+    provider: WebLNResource,
+    state: Signal<PaymentState>,
+}
+
+impl UsePayment {
+    /// Current state
+    pub fn state(&self) -> PaymentState {
+        self.state.read().clone()
+    }
+
+    /// Pay a BOLT11 invoice
+    ///
+    /// Ignored while another payment is pending.
+    pub fn send_payment<S>(&mut self, invoice: S)
+    where
+        S: Into<String>,
+    {
+        let invoice: String = invoice.into();
+        self.run(move |webln| async move { webln.send_payment(&invoice).await });
+    }
+
+    /// Send a keysend payment
+    ///
+    /// Ignored while another payment is pending.
+    pub fn keysend(&mut self, args: KeysendArgs) {
+        self.run(move |webln| async move { webln.keysend(&args).await });
+    }
+
+    /// Go back to [`PaymentState::Idle`]
+    pub fn reset(&mut self) {
+        if !self.state.read().is_pending() {
+            self.state.set(PaymentState::Idle);
+        }
+    }
+
+    fn run<F, Fut>(&mut self, pay: F)
+    where
+        F: FnOnce(WebLN) -> Fut + 'static,
+        Fut: Future<Output = Result<SendPaymentResponse, Error>> + 'static,
+    {
+        if self.state.read().is_pending() {
+            return;
+        }
+
+        let provider: Option<WebLNResult<WebLN>> = self.provider.read().clone();
+        let mut state: Signal<PaymentState> = self.state;
+        state.set(PaymentState::Pending);
+        spawn(async move {
+            let res: WebLNResult<SendPaymentResponse> = match provider {
+                Some(Ok(webln)) => pay(webln).await.map_err(Rc::new),
+                Some(Err(e)) => Err(e),
+                // Still detecting: same error as a missing provider
+                None => Err(Rc::new(Error::NamespaceNotFound(String::from("webln")))),
+            };
+            state.set(match res {
+                Ok(res) => PaymentState::Paid(res),
+                Err(e) => PaymentState::Failed(e),
+            });
+        });
+    }
+}
+
+/// Payment action: send payments and track their state
+///
+/// Must be used below [`use_webln_provider`].
+pub fn use_payment() -> UsePayment {
+    UsePayment {
+        provider: use_webln(),
+        state: use_signal(PaymentState::default),
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! WebLN helpers for egui/eframe web apps
+//!
+//! egui has no async runtime: [`Task`] runs a WebLN future on the browser event loop and lets the
+//! update loop poll its output, requesting a repaint when it completes. [`Payment`] builds on it
+//! to track a payment from the enable prompt to the result.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use webln::{Error, WebLN};
+//! use webln_egui::Task;
+//!
+//! struct App {
+//!     provider: Task<Result<WebLN, Error>>,
+//! }
+//!
+//! impl App {
+//!     fn new(ctx: &egui::Context) -> Self {
+//!         Self {
+//!             provider: Task::spawn(ctx, WebLN::wait_for_provider(Duration::from_secs(3))),
+//!         }
+//!     }
+//!
+//!     fn ui(&mut self, ui: &mut egui::Ui) {
+//!         match self.provider.ready().as_deref() {
+//!             None => ui.spinner(),
+//!             Some(Ok(..)) => ui.label("Wallet detected"),
+//!             Some(Err(e)) => ui.label(format!("No wallet: {e}")),
+//!         };
+//!     }
+//! }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::bare_urls)]
+
+pub use webln;
+
+mod payment;
+mod task;
+
+pub use self::payment::{Payment, PaymentState};
+pub use self::task::Task;
@@ -0,0 +1,177 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Payment state machine
+
+use egui::Context;
+use webln::{Error, KeysendArgs, SendPaymentResponse, WebLN};
+
+use crate::Task;
+
+#[derive(Debug)]
+enum Request {
+    Invoice(String),
+    Keysend(KeysendArgs),
+}
+
+impl Request {
+    async fn send(&self, webln: &WebLN) -> Result<SendPaymentResponse, Error> {
+        match self {
+            Self::Invoice(invoice) => webln.send_payment(invoice).await,
+            Self::Keysend(args) => webln.keysend(args).await,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+enum Stage {
+    #[default]
+    Idle,
+    Enabling(Task<Result<(), Error>>, Request),
+    Paying(Task<Result<SendPaymentResponse, Error>>),
+    Paid(SendPaymentResponse),
+    Failed(Error),
+}
+
+/// Payment state, returned by [`Payment::poll`]
+#[derive(Debug)]
+pub enum PaymentState<'a> {
+    /// No payment sent
+    Idle,
+    /// Asking the user permission
+    Enabling,
+    /// Waiting for the payment
+    Paying,
+    /// Paid
+    Paid(&'a SendPaymentResponse),
+    /// Failed (or rejected by the user)
+    Failed(&'a Error),
+}
+
+impl<'a> PaymentState<'a> {
+    /// Check if waiting for the provider
+    pub fn is_busy(&self) -> bool {
+        matches!(self, Self::Enabling | Self::Paying)
+    }
+}
+
+/// Payment state machine: enable → pay → paid or failed
+///
+/// Keep it in the app state and call [`Payment::poll`] on every frame.
+///
+/// ```rust,no_run
+/// use webln_egui::{Payment, PaymentState};
+///
+/// fn ui(ui: &mut egui::Ui, payment: &mut Payment, invoice: &str) {
+///     match payment.poll(ui.ctx()) {
+///         PaymentState::Idle => {
+///             if ui.button("Pay").clicked() {
+///                 payment.send_payment(ui.ctx(), invoice);
+///             }
+///         }
+///         PaymentState::Enabling => {
+///             ui.label("Connecting...");
+///         }
+///         PaymentState::Paying => {
+///             ui.spinner();
+///         }
+///         PaymentState::Paid(res) => {
+///             ui.label(format!("Paid: {}", res.preimage));
+///         }
+///         PaymentState::Failed(e) => {
+///             ui.label(format!("Failed: {e}"));
+///             if ui.button("Retry").clicked() {
+///                 payment.send_payment(ui.ctx(), invoice);
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Payment {
+    webln: WebLN,
+    stage: Stage,
+}
+
+impl Payment {
+    /// New payment state machine
+    pub fn new(webln: WebLN) -> Self {
+        Self {
+            webln,
+            stage: Stage::Idle,
+        }
+    }
+
+    fn start(&mut self, ctx: &Context, request: Request) {
+        if self.poll(ctx).is_busy() {
+            return;
+        }
+
+        let webln: WebLN = self.webln.clone();
+        let task = Task::spawn(ctx, async move {
+            if !webln.is_enabled().await? {
+                webln.enable().await?;
+            }
+            Ok(())
+        });
+        self.stage = Stage::Enabling(task, request);
+    }
+
+    /// Pay a BOLT11 invoice
+    ///
+    /// Ignored while another payment is in progress.
+    pub fn send_payment<S>(&mut self, ctx: &Context, invoice: S)
+    where
+        S: Into<String>,
+    {
+        self.start(ctx, Request::Invoice(invoice.into()));
+    }
+
+    /// Send a keysend payment
+    ///
+    /// Ignored while another payment is in progress.
+    pub fn keysend(&mut self, ctx: &Context, args: KeysendArgs) {
+        self.start(ctx, Request::Keysend(args));
+    }
+
+    /// Advance the state machine and get the current state
+    pub fn poll(&mut self, ctx: &Context) -> PaymentState<'_> {
+        match &mut self.stage {
+            Stage::Enabling(task, ..) => {
+                match task.take() {
+                    Some(Ok(())) => {
+                        if let Stage::Enabling(_, request) = std::mem::take(&mut self.stage) {
+                            let webln: WebLN = self.webln.clone();
+                            self.stage = Stage::Paying(Task::spawn(ctx, async move {
+                                request.send(&webln).await
+                            }));
+                        }
+                    }
+                    Some(Err(e)) => self.stage = Stage::Failed(e),
+                    None => (),
+                }
+            }
+            Stage::Paying(task) => match task.take() {
+                Some(Ok(res)) => self.stage = Stage::Paid(res),
+                Some(Err(e)) => self.stage = Stage::Failed(e),
+                None => (),
+            },
+            Stage::Idle | Stage::Paid(..) | Stage::Failed(..) => (),
+        }
+
+        match &self.stage {
+            Stage::Idle => PaymentState::Idle,
+            Stage::Enabling(..) => PaymentState::Enabling,
+            Stage::Paying(..) => PaymentState::Paying,
+            Stage::Paid(res) => PaymentState::Paid(res),
+            Stage::Failed(e) => PaymentState::Failed(e),
+        }
+    }
+
+    /// Go back to [`PaymentState::Idle`], unless a payment is in progress
+    pub fn reset(&mut self) {
+        if let Stage::Paid(..) | Stage::Failed(..) = self.stage {
+            self.stage = Stage::Idle;
+        }
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Task
+
+use std::cell::{Ref, RefCell};
+use std::future::Future;
+use std::rc::Rc;
+
+use egui::Context;
+use wasm_bindgen_futures::spawn_local;
+
+/// Future running on the browser event loop, polled from the egui update loop
+///
+/// A repaint is requested when the future completes. Dropping the task doesn't cancel the
+/// future: its output is discarded.
+#[derive(Debug)]
+pub struct Task<T> {
+    output: Rc<RefCell<Option<T>>>,
+}
+
+impl<T> Task<T>
+where
+    T: 'static,
+{
+    /// Spawn `future`
+    pub fn spawn<F>(ctx: &Context, future: F) -> Self
+    where
+        F: Future<Output = T> + 'static,
+    {
+        let output: Rc<RefCell<Option<T>>> = Rc::new(RefCell::new(None));
+        let ctx: Context = ctx.clone();
+        let slot = output.clone();
+        spawn_local(async move {
+            let value: T = future.await;
+            *slot.borrow_mut() = Some(value);
+            ctx.request_repaint();
+        });
+        Self { output }
+    }
+
+    /// Check if the future completed
+    pub fn is_ready(&self) -> bool {
+        self.output.borrow().is_some()
+    }
+
+    /// Get the output, if the future completed
+    pub fn ready(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.output.borrow(), Option::as_ref).ok()
+    }
+
+    /// Take the output, if the future completed
+    pub fn take(&self) -> Option<T> {
+        self.output.borrow_mut().take()
+    }
+}
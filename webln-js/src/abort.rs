@@ -0,0 +1,84 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! `AbortSignal` support
+
+use alloc::boxed::Box;
+use core::future::{self, Future};
+use core::pin::Pin;
+use core::task::Poll;
+
+use js_sys::{Function, Promise};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::error::Result;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AbortSignal")]
+    pub type AbortSignal;
+
+    #[wasm_bindgen(method, getter)]
+    fn aborted(this: &AbortSignal) -> bool;
+
+    #[wasm_bindgen(method, getter)]
+    fn reason(this: &AbortSignal) -> JsValue;
+
+    #[wasm_bindgen(method, js_name = addEventListener)]
+    fn add_event_listener(this: &AbortSignal, event: &str, listener: &Function);
+
+    #[wasm_bindgen(method, js_name = removeEventListener)]
+    fn remove_event_listener(this: &AbortSignal, event: &str, listener: &Function);
+}
+
+/// Rejection value: the signal `reason` (an `AbortError` `DOMException` by default)
+fn abort_error(signal: &AbortSignal) -> JsValue {
+    let reason: JsValue = signal.reason();
+    if !reason.is_undefined() {
+        return reason;
+    }
+
+    let error = js_sys::Error::new("The operation was aborted");
+    error.set_name("AbortError");
+    error.into()
+}
+
+/// Await `future`, rejecting with the abort reason as soon as `signal` is aborted
+///
+/// The call is only abandoned: a wallet prompt already shown may still complete the payment.
+pub(crate) async fn abortable<F>(signal: Option<AbortSignal>, future: F) -> Result<F::Output>
+where
+    F: Future,
+{
+    let signal: AbortSignal = match signal {
+        Some(signal) => signal,
+        None => return Ok(future.await),
+    };
+    if signal.aborted() {
+        return Err(abort_error(&signal));
+    }
+
+    let mut listener: Option<Function> = None;
+    let mut aborted = JsFuture::from(Promise::new(&mut |resolve, _| {
+        signal.add_event_listener("abort", &resolve);
+        listener = Some(resolve);
+    }));
+    let mut future: Pin<Box<F>> = Box::pin(future);
+
+    let res = future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        match Pin::new(&mut aborted).poll(cx) {
+            Poll::Ready(..) => Poll::Ready(Err(abort_error(&signal))),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+    .await;
+
+    if let Some(listener) = listener {
+        signal.remove_event_listener("abort", &listener);
+    }
+    res
+}
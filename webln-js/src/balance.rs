@@ -4,7 +4,9 @@
 use alloc::string::String;
 
 use wasm_bindgen::prelude::*;
-use webln::BalanceResponse;
+use webln::{BalanceResponse, Currency};
+
+use crate::plain::object;
 
 #[wasm_bindgen(js_name = BalanceResponse)]
 pub struct JsBalanceResponse {
@@ -24,8 +26,37 @@ impl JsBalanceResponse {
         self.inner.balance
     }
 
+    /// Raw currency, as reported by the provider
     #[wasm_bindgen(getter)]
     pub fn currency(&self) -> Option<String> {
         self.inner.currency.clone()
     }
+
+    /// Normalized currency type: `sats`, `btc` or `fiat`
+    #[wasm_bindgen(getter, js_name = currencyType)]
+    pub fn currency_type(&self) -> String {
+        match self.inner.currency_type() {
+            Currency::Sats => String::from("sats"),
+            Currency::Btc => String::from("btc"),
+            _ => String::from("fiat"),
+        }
+    }
+
+    /// ISO 4217 code for fiat (i.e. `USD`), `BTC` for bitcoin, `undefined` for sats
+    #[wasm_bindgen(getter, js_name = currencyCode)]
+    pub fn currency_code(&self) -> Option<String> {
+        self.inner.currency_type().code().map(String::from)
+    }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[
+            ("balance", JsValue::from_f64(self.inner.balance)),
+            (
+                "currency",
+                self.inner.currency.as_deref().map(JsValue::from_str).into(),
+            ),
+        ])
+    }
 }
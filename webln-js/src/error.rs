@@ -3,14 +3,89 @@
 
 use alloc::string::ToString;
 
-use wasm_bindgen::JsValue;
+use js_sys::Reflect;
+use wasm_bindgen::prelude::*;
+use webln::{Error, ErrorCode};
 
 pub type Result<T, E = JsValue> = core::result::Result<T, E>;
 
-#[inline]
+#[wasm_bindgen(typescript_custom_section)]
+const WEBLN_ERROR: &'static str = r#"
+export type WebLNErrorCode =
+    | "MISSING_PROVIDER"
+    | "USER_REJECTED"
+    | "CONNECTION"
+    | "UNSUPPORTED_METHOD"
+    | "ROUTING"
+    | "INVALID_DATA"
+    | "EXPIRED"
+    | "INTERNAL";
+
+/** Error thrown by every WebLN method */
+export interface WebLNError extends Error {
+    code: WebLNErrorCode;
+    /** Original value thrown or rejected by the provider, if any */
+    cause?: unknown;
+}
+"#;
+
+/// Error class name, following the WebLN error taxonomy
+fn name(code: ErrorCode) -> &'static str {
+    match code {
+        ErrorCode::MissingProvider => "MissingProviderError",
+        ErrorCode::UserRejected => "RejectionError",
+        ErrorCode::Connection => "ConnectionError",
+        ErrorCode::UnsupportedMethod => "UnsupportedMethodError",
+        ErrorCode::Routing => "RoutingError",
+        ErrorCode::InvalidData => "InvalidDataError",
+        ErrorCode::Expired => "ExpiredError",
+        _ => "InternalError",
+    }
+}
+
+/// Build a JS `Error` with the `name` and `code` of the WebLN error taxonomy
+fn js_error(code: ErrorCode, message: &str) -> js_sys::Error {
+    let error = js_sys::Error::new(message);
+    error.set_name(name(code));
+    let _ = Reflect::set(
+        &error,
+        &JsValue::from_str("code"),
+        &JsValue::from_str(code.as_str()),
+    );
+    error
+}
+
+/// Convert to a JS `Error`
+///
+/// The value thrown or rejected by the provider, if any, is preserved as `cause`.
 pub fn into_err<E>(error: E) -> JsValue
 where
-    E: ToString,
+    E: Into<Error>,
 {
-    JsValue::from_str(&error.to_string())
+    let error: Error = error.into();
+    let js_error: js_sys::Error = js_error(error.code(), &error.to_string());
+    if let Some(raw) = error.js_value() {
+        js_error.set_cause(raw);
+    }
+    js_error.into()
+}
+
+/// Invalid argument error
+#[inline]
+pub fn invalid_data(message: &str) -> JsValue {
+    js_error(ErrorCode::InvalidData, message).into()
+}
+
+/// Largest integer exactly representable by a JS number (`Number.MAX_SAFE_INTEGER`)
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Convert a JS number to a SAT amount, rejecting fractional, negative and unsafe values
+pub fn into_amount(amount: f64) -> Result<u64> {
+    if (0.0..=MAX_SAFE_INTEGER).contains(&amount) && amount.fract() == 0.0 {
+        Ok(amount as u64)
+    } else {
+        Err(invalid_data(
+            "Invalid amount: expected a non-negative safe integer",
+        ))
+    }
 }
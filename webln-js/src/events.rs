@@ -0,0 +1,70 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! DOM `CustomEvent`s dispatched when `domEvents` is on
+
+#[cfg(feature = "dom_events")]
+use js_sys::{Array, Function, Reflect};
+#[cfg(feature = "dom_events")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "dom_events")]
+use crate::plain::object;
+
+/// The provider was enabled
+pub(crate) const ENABLED: &str = "webln:enabled";
+/// A payment succeeded
+pub(crate) const PAYMENT_SUCCESS: &str = "webln:payment:success";
+/// A payment failed (or was rejected)
+pub(crate) const PAYMENT_FAILURE: &str = "webln:payment:failure";
+
+#[cfg(feature = "dom_events")]
+#[wasm_bindgen(typescript_custom_section)]
+const WEBLN_EVENTS: &'static str = r#"
+/** `detail` of the DOM events dispatched when `webln.domEvents` is on */
+export interface WebLNEventMap {
+    "webln:enabled": CustomEvent<{}>;
+    "webln:payment:success": CustomEvent<{
+        method: "sendPayment" | "keysend" | "sendMultiPayment";
+        paymentRequest?: string;
+        destination?: string;
+        amount?: number;
+        preimage: string;
+    }>;
+    "webln:payment:failure": CustomEvent<{
+        method: "sendPayment" | "keysend" | "sendMultiPayment";
+        paymentRequest?: string;
+        destination?: string;
+        amount?: number;
+        error: WebLNError;
+    }>;
+}
+"#;
+
+/// Dispatch a `CustomEvent` on `document` (or on the global scope, i.e. in workers)
+///
+/// Best effort: listeners can't make the payment calls fail.
+#[cfg(feature = "dom_events")]
+pub(crate) fn dispatch(name: &str, detail: JsValue) {
+    let global: JsValue = js_sys::global().into();
+    let get = |obj: &JsValue, key: &str| Reflect::get(obj, &JsValue::from_str(key)).ok();
+
+    let target: JsValue = get(&global, "document")
+        .filter(JsValue::is_object)
+        .unwrap_or_else(|| global.clone());
+    let constructor: Function = match get(&global, "CustomEvent").and_then(|c| c.dyn_into().ok()) {
+        Some(constructor) => constructor,
+        None => return,
+    };
+    let dispatch_event: Function =
+        match get(&target, "dispatchEvent").and_then(|f| f.dyn_into().ok()) {
+            Some(dispatch_event) => dispatch_event,
+            None => return,
+        };
+
+    let init: JsValue = object(&[("detail", detail)]);
+    let args: Array = Array::of2(&JsValue::from_str(name), &init);
+    if let Ok(event) = Reflect::construct(&constructor, &args) {
+        let _ = dispatch_event.call1(&target, &event);
+    }
+}
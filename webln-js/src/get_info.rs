@@ -4,8 +4,88 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
+use js_sys::{Array, Object, Reflect};
 use wasm_bindgen::prelude::*;
-use webln::GetInfoResponse;
+use webln::{Capabilities, GetInfoMethod, GetInfoNode, GetInfoResponse};
+
+use crate::plain::object;
+
+#[wasm_bindgen(typescript_custom_section)]
+const CAPABILITIES: &'static str = r#"
+/** Supported methods: `true`/`false` for each method known by this package, plus the raw list */
+export interface WebLNCapabilities {
+    isEnabled: boolean;
+    enable: boolean;
+    getInfo: boolean;
+    keysend: boolean;
+    makeInvoice: boolean;
+    sendPayment: boolean;
+    sendPaymentAsync: boolean;
+    signMessage: boolean;
+    verifyMessage: boolean;
+    request: boolean;
+    lnurl: boolean;
+    on: boolean;
+    off: boolean;
+    getBalance: boolean;
+    sendMultiPayment: boolean;
+    raw: string[];
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "WebLNCapabilities")]
+    pub type JsCapabilities;
+}
+
+#[wasm_bindgen(js_name = GetInfoNode)]
+pub struct JsGetInfoNode {
+    inner: GetInfoNode,
+}
+
+impl From<GetInfoNode> for JsGetInfoNode {
+    fn from(inner: GetInfoNode) -> Self {
+        Self { inner }
+    }
+}
+
+#[wasm_bindgen(js_class = GetInfoNode)]
+impl JsGetInfoNode {
+    #[wasm_bindgen(getter)]
+    pub fn alias(&self) -> Option<String> {
+        self.inner.alias.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pubkey(&self) -> Option<String> {
+        self.inner.pubkey.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn color(&self) -> Option<String> {
+        self.inner.color.clone()
+    }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[
+            (
+                "alias",
+                self.inner.alias.as_deref().map(JsValue::from_str).into(),
+            ),
+            (
+                "pubkey",
+                self.inner.pubkey.as_deref().map(JsValue::from_str).into(),
+            ),
+            (
+                "color",
+                self.inner.color.as_deref().map(JsValue::from_str).into(),
+            ),
+        ])
+    }
+}
 
 #[wasm_bindgen(js_name = GetInfoResponse)]
 pub struct JsGetInfoResponse {
@@ -20,19 +100,71 @@ impl From<GetInfoResponse> for JsGetInfoResponse {
 
 #[wasm_bindgen(js_class = GetInfoResponse)]
 impl JsGetInfoResponse {
+    #[wasm_bindgen(getter)]
+    pub fn node(&self) -> JsGetInfoNode {
+        self.inner.node.clone().into()
+    }
+
+    #[wasm_bindgen(getter)]
     pub fn alias(&self) -> Option<String> {
         self.inner.node.alias.clone()
     }
 
+    #[wasm_bindgen(getter)]
     pub fn pubkey(&self) -> Option<String> {
         self.inner.node.pubkey.clone()
     }
 
+    #[wasm_bindgen(getter)]
     pub fn color(&self) -> Option<String> {
         self.inner.node.color.clone()
     }
 
+    #[wasm_bindgen(getter)]
     pub fn methods(&self) -> Vec<String> {
         self.inner.methods.iter().map(|m| m.to_string()).collect()
     }
+
+    /// Supported methods as `{ keysend: true, getBalance: false, ..., raw: [...] }`
+    #[wasm_bindgen(getter)]
+    pub fn capabilities(&self) -> JsCapabilities {
+        let supported: Capabilities = self.inner.capabilities();
+        let obj = Object::new();
+        for method in Capabilities::all().iter() {
+            let _ = Reflect::set(
+                &obj,
+                &JsValue::from_str(&method.to_string()),
+                &JsValue::from_bool(supported.contains(&method)),
+            );
+        }
+        let raw: Array = self
+            .inner
+            .methods
+            .iter()
+            .map(|m| JsValue::from_str(&m.to_string()))
+            .collect();
+        let _ = Reflect::set(&obj, &JsValue::from_str("raw"), &raw);
+        obj.unchecked_into()
+    }
+
+    /// Check if the provider supports `method` (i.e. `keysend`)
+    pub fn supports(&self, method: &str) -> bool {
+        self.inner.methods.contains(&GetInfoMethod::from(method))
+    }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[
+            ("node", self.node().to_json()),
+            (
+                "methods",
+                self.methods()
+                    .into_iter()
+                    .map(JsValue::from)
+                    .collect::<Array>()
+                    .into(),
+            ),
+        ])
+    }
 }
@@ -1,14 +1,16 @@
 // Copyright (c) 2024 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use alloc::format;
+use alloc::string::{String, ToString};
 use core::ops::Deref;
 use core::str::FromStr;
 
+use js_sys::{Array, Object};
 use wasm_bindgen::prelude::*;
-use webln::secp256k1::PublicKey;
-use webln::KeysendArgs;
+use webln::{KeysendArgs, NodeId};
 
-use crate::error::{into_err, Result};
+use crate::error::{into_amount, into_err, invalid_data, Result};
 
 #[wasm_bindgen(js_name = KeysendArgs)]
 pub struct JsKeysendArgs {
@@ -23,16 +25,52 @@ impl Deref for JsKeysendArgs {
     }
 }
 
+impl From<KeysendArgs> for JsKeysendArgs {
+    fn from(inner: KeysendArgs) -> Self {
+        Self { inner }
+    }
+}
+
 #[wasm_bindgen(js_class = KeysendArgs)]
 impl JsKeysendArgs {
-    pub fn new(destination: &str, amount: u32) -> Result<JsKeysendArgs> {
-        let destination: PublicKey = PublicKey::from_str(destination).map_err(into_err)?;
-        let amount: u64 = amount as u64;
+    #[wasm_bindgen(constructor)]
+    pub fn new(destination: &str, amount: f64) -> Result<JsKeysendArgs> {
+        let destination: NodeId = NodeId::from_str(destination).map_err(into_err)?;
+        let amount: u64 = into_amount(amount)?;
         Ok(Self {
-            inner: KeysendArgs {
-                destination,
-                amount,
-            },
+            inner: KeysendArgs::new(destination, amount),
         })
     }
+
+    /// Public key of the destination node
+    #[wasm_bindgen(getter)]
+    pub fn destination(&self) -> String {
+        self.inner.destination.to_string()
+    }
+
+    /// Amount in SAT
+    #[wasm_bindgen(getter)]
+    pub fn amount(&self) -> f64 {
+        self.inner.amount as f64
+    }
+
+    /// Add custom TLV records from an object of `{ "<record type>": "<plain string>" }`
+    #[wasm_bindgen(js_name = customRecords)]
+    pub fn custom_records(self, records: &Object) -> Result<JsKeysendArgs> {
+        let mut inner: KeysendArgs = self.inner;
+        for entry in Object::entries(records).iter() {
+            let entry: Array = entry.unchecked_into();
+            let key: String = entry.get(0).as_string().unwrap_or_default();
+            let key: u64 = key
+                .parse()
+                .map_err(|_| invalid_data(&format!("Invalid custom record type: {key}")))?;
+            let value: String = entry.get(1).as_string().ok_or_else(|| {
+                invalid_data(&format!(
+                    "Invalid custom record value for type {key}: expected a string"
+                ))
+            })?;
+            inner = inner.custom_record(key, value);
+        }
+        Ok(inner.into())
+    }
 }
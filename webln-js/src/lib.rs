@@ -8,26 +8,43 @@
 
 extern crate alloc;
 
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::Cell;
 use core::ops::Deref;
+use core::time::Duration;
 
+use js_sys::{Function, Object};
 use wasm_bindgen::prelude::*;
-use webln::WebLN;
+use webln::{Error, Event, GetInfoResponse, ProviderError, SendPaymentResponse, WebLN};
 
+mod abort;
 pub mod balance;
 pub mod error;
+mod events;
 pub mod get_info;
 pub mod keysend;
+pub mod plain;
 pub mod request_invoice;
 pub mod send_payment;
 pub mod sign_message;
 
+use self::abort::{abortable, AbortSignal};
 use self::balance::JsBalanceResponse;
 use self::error::{into_err, Result};
 use self::get_info::JsGetInfoResponse;
 use self::keysend::JsKeysendArgs;
+#[cfg(feature = "dom_events")]
+use self::plain::object;
+#[cfg(feature = "plain")]
+use self::plain::JsPlainWebLN;
 use self::request_invoice::{JsRequestInvoiceArgs, JsRequestInvoiceResponse};
-use self::send_payment::JsSendPaymentResponse;
-use self::sign_message::JsSignMessageResponse;
+use self::send_payment::{
+    JsSendMultiPaymentResponse, JsSendPaymentAsyncResponse, JsSendPaymentResponse,
+};
+use self::sign_message::{JsMessageEncoding, JsSignMessageResponse};
 
 #[cfg(feature = "console_error_panic_hook")]
 #[wasm_bindgen(start)]
@@ -39,6 +56,21 @@ pub fn start() {
 #[wasm_bindgen(js_name = WebLN)]
 pub struct JsWebLN {
     inner: WebLN,
+    dom_events: bool,
+    info_ttl: Option<Duration>,
+    /// When the cached `getInfo` response was fetched
+    info_fetched_at: Rc<Cell<Option<Duration>>>,
+}
+
+impl From<WebLN> for JsWebLN {
+    fn from(inner: WebLN) -> Self {
+        Self {
+            inner,
+            dom_events: false,
+            info_ttl: None,
+            info_fetched_at: Rc::new(Cell::new(None)),
+        }
+    }
 }
 
 impl Deref for JsWebLN {
@@ -52,11 +84,80 @@ impl Deref for JsWebLN {
 #[wasm_bindgen(js_class = WebLN)]
 impl JsWebLN {
     /// Compose new WebLN instance
+    ///
+    /// Uses `window.webln` unless a `provider` object is passed
+    /// (i.e. the one returned by Bitcoin Connect's `requestProvider()`).
+    ///
+    /// In a Web Worker, pass the provider or expose it as `globalThis.webln`.
     #[wasm_bindgen(constructor)]
-    pub fn new() -> Result<JsWebLN> {
-        Ok(Self {
-            inner: WebLN::new().map_err(into_err)?,
-        })
+    pub fn new(provider: Option<Object>) -> Result<JsWebLN> {
+        let inner: WebLN = match provider {
+            Some(provider) => WebLN::from_provider(provider),
+            None => WebLN::new().map_err(into_err)?,
+        };
+        Ok(Self::from(inner))
+    }
+
+    /// Same instance, returning plain serializable objects instead of wrapper classes
+    ///
+    /// Inherits the current `domEvents` and `getInfoTtl` settings, and shares the `getInfo` cache.
+    #[cfg(feature = "plain")]
+    pub fn plain(&self) -> JsPlainWebLN {
+        JsWebLN {
+            inner: self.inner.clone(),
+            dom_events: self.dom_events,
+            info_ttl: self.info_ttl,
+            info_fetched_at: self.info_fetched_at.clone(),
+        }
+        .into()
+    }
+
+    /// Dispatch `webln:*` `CustomEvent`s on `document` (default: `false`)
+    ///
+    /// Lets other parts of the page (analytics, widgets, ...) react to `webln:enabled`,
+    /// `webln:payment:success` and `webln:payment:failure`, see `WebLNEventMap`.
+    #[cfg(feature = "dom_events")]
+    #[wasm_bindgen(getter, js_name = domEvents)]
+    pub fn dom_events(&self) -> bool {
+        self.dom_events
+    }
+
+    #[cfg(feature = "dom_events")]
+    #[wasm_bindgen(setter, js_name = domEvents)]
+    pub fn set_dom_events(&mut self, enable: bool) {
+        self.dom_events = enable;
+    }
+
+    /// Serve `getInfo()` from a cache for `getInfoTtl` milliseconds (default: `undefined`, no cache)
+    ///
+    /// Avoids the provider round trip when many components query the capabilities. Setting it
+    /// clears the cache.
+    #[wasm_bindgen(getter, js_name = getInfoTtl)]
+    pub fn get_info_ttl(&self) -> Option<u32> {
+        self.info_ttl.map(|ttl| ttl.as_millis() as u32)
+    }
+
+    #[wasm_bindgen(setter, js_name = getInfoTtl)]
+    pub fn set_get_info_ttl(&mut self, ttl_ms: Option<u32>) {
+        self.info_ttl = ttl_ms
+            .filter(|ttl_ms| *ttl_ms > 0)
+            .map(|ttl_ms| Duration::from_millis(ttl_ms as u64));
+        self.info_fetched_at.set(None);
+    }
+
+    /// Check if a provider is injected as `window.webln`, without throwing
+    #[wasm_bindgen(js_name = isAvailable)]
+    pub fn is_available() -> bool {
+        WebLN::is_available()
+    }
+
+    /// Wait until a provider is injected as `window.webln`, up to `timeoutMs`
+    #[wasm_bindgen(js_name = waitForProvider)]
+    pub async fn wait_for_provider(timeout_ms: u32) -> Result<JsWebLN> {
+        let timeout: Duration = Duration::from_millis(timeout_ms as u64);
+        Ok(Self::from(
+            WebLN::wait_for_provider(timeout).await.map_err(into_err)?,
+        ))
     }
 
     /// Check if `webln` is enabled without explicitly enabling it through `webln.enable()`
@@ -69,33 +170,77 @@ impl JsWebLN {
     /// To begin interacting with WebLN APIs you'll first need to enable the provider.
     /// Calling `webln.enable()` will prompt the user for permission to use the WebLN capabilities of the browser.
     /// After that you are free to call any of the other API methods.
-    pub async fn enable(&self) -> Result<()> {
-        self.inner.enable().await.map_err(into_err)
+    ///
+    /// Resolves with whether the provider is now enabled (as reported by the provider, if any).
+    pub async fn enable(&self) -> Result<bool> {
+        let enabled: bool = self
+            .inner
+            .enable_with_raw()
+            .await
+            .map_err(into_err)?
+            .enabled;
+        if enabled {
+            self.emit(events::ENABLED, Vec::new());
+        }
+        Ok(enabled)
     }
 
     /// Get information about the connected node and what WebLN methods it supports.
+    ///
+    /// Served from the cache if `getInfoTtl` is set.
     #[wasm_bindgen(js_name = getInfo)]
     pub async fn get_info(&self) -> Result<JsGetInfoResponse> {
-        Ok(self.inner.get_info().await.map_err(into_err)?.into())
+        if let Some(info) = self.fresh_info() {
+            return Ok(info.into());
+        }
+
+        let info = self.inner.get_info().await.map_err(into_err)?;
+        if self.info_ttl.is_some() {
+            self.info_fetched_at.set(Some(self.inner.clock().now()));
+        }
+        Ok(info.into())
     }
 
     /// Request the user to send a keysend payment.
     /// This is a spontaneous payment that does not require an invoice and only needs a destination public key and and amount.
-    pub async fn keysend(&self, args: &JsKeysendArgs) -> Result<JsSendPaymentResponse> {
-        Ok(self
-            .inner
-            .keysend(args.deref())
-            .await
-            .map_err(into_err)?
-            .into())
+    ///
+    /// Rejects with the `signal` reason (an `AbortError`) if aborted: the wallet may still send the payment.
+    pub async fn keysend(
+        &self,
+        args: &JsKeysendArgs,
+        signal: Option<AbortSignal>,
+    ) -> Result<JsSendPaymentResponse> {
+        let res = abortable(signal, self.inner.keysend(args.deref())).await?;
+        let detail = vec![
+            ("method", JsValue::from_str("keysend")),
+            (
+                "destination",
+                JsValue::from_str(&args.destination.to_string()),
+            ),
+            ("amount", JsValue::from_f64(args.amount as f64)),
+        ];
+        Ok(self.emit_payment(detail, res)?.into())
+    }
+
+    /// Request the user to send a keysend payment, without waiting for the preimage.
+    /// Meant for streaming payments: there is no guarantee that the payment will be successfully sent.
+    #[wasm_bindgen(js_name = keysendAsync)]
+    pub async fn keysend_async(
+        &self,
+        args: &JsKeysendArgs,
+        signal: Option<AbortSignal>,
+    ) -> Result<()> {
+        abortable(signal, self.inner.keysend_async(args.deref()))
+            .await?
+            .map_err(into_err)
     }
 
     /// Request that the user creates an invoice to be used by the web app
+    ///
+    /// `args` is a `RequestInvoiceArgs` or a plain `{ amount, defaultMemo, ... }` object.
     #[wasm_bindgen(js_name = makeInvoice)]
-    pub async fn make_invoice(
-        &self,
-        args: &JsRequestInvoiceArgs,
-    ) -> Result<JsRequestInvoiceResponse> {
+    pub async fn make_invoice(&self, args: JsValue) -> Result<JsRequestInvoiceResponse> {
+        let args: JsRequestInvoiceArgs = JsRequestInvoiceArgs::from_js_value(args)?;
         Ok(self
             .inner
             .make_invoice(args.deref())
@@ -105,14 +250,20 @@ impl JsWebLN {
     }
 
     /// Request that the user sends a payment for an invoice.
+    ///
+    /// Rejects with the `signal` reason (an `AbortError`) if aborted: the wallet may still send the payment.
     #[wasm_bindgen(js_name = sendPayment)]
-    pub async fn send_payment(&self, invoice: &str) -> Result<JsSendPaymentResponse> {
-        Ok(self
-            .inner
-            .send_payment(invoice)
-            .await
-            .map_err(into_err)?
-            .into())
+    pub async fn send_payment(
+        &self,
+        invoice: &str,
+        signal: Option<AbortSignal>,
+    ) -> Result<JsSendPaymentResponse> {
+        let res = abortable(signal, self.inner.send_payment(invoice)).await?;
+        let detail = vec![
+            ("method", JsValue::from_str("sendPayment")),
+            ("paymentRequest", JsValue::from_str(invoice)),
+        ];
+        Ok(self.emit_payment(detail, res)?.into())
     }
 
     /// Request that the user sends a payment for an invoice.
@@ -120,11 +271,59 @@ impl JsWebLN {
     /// This is useful when paying HOLD Invoices. There is no guarantee that the payment will be successfully sent to the receiver.
     /// It's up to the receiver to check whether or not the invoice has been paid.
     #[wasm_bindgen(js_name = sendPaymentAsync)]
-    pub async fn send_payment_async(&self, invoice: &str) -> Result<()> {
-        self.inner
-            .send_payment_async(invoice)
-            .await
-            .map_err(into_err)
+    pub async fn send_payment_async(
+        &self,
+        invoice: &str,
+        signal: Option<AbortSignal>,
+    ) -> Result<JsSendPaymentAsyncResponse> {
+        Ok(abortable(signal, self.inner.send_payment_async(invoice))
+            .await?
+            .map_err(into_err)?
+            .into())
+    }
+
+    /// Request that the user sends payments for multiple invoices.
+    #[wasm_bindgen(js_name = sendMultiPayment)]
+    pub async fn send_multi_payment(
+        &self,
+        invoices: Vec<String>,
+        signal: Option<AbortSignal>,
+    ) -> Result<JsSendMultiPaymentResponse> {
+        let res = abortable(signal, self.inner.send_multi_payment(&invoices))
+            .await?
+            .map_err(into_err)?;
+
+        if self.dom_events {
+            let method = || ("method", JsValue::from_str("sendMultiPayment"));
+            for item in res.payments.iter() {
+                let detail = vec![
+                    method(),
+                    ("paymentRequest", JsValue::from_str(&item.payment_request)),
+                    ("preimage", JsValue::from_str(&item.preimage)),
+                ];
+                self.emit(events::PAYMENT_SUCCESS, detail);
+            }
+            for error in res.errors.iter() {
+                let provider_error = ProviderError::from(&JsValue::from_str(&error.message));
+                let detail = vec![
+                    method(),
+                    ("paymentRequest", JsValue::from_str(&error.payment_request)),
+                    ("error", into_err(Error::Provider(provider_error))),
+                ];
+                self.emit(events::PAYMENT_FAILURE, detail);
+            }
+        }
+
+        Ok(res.into())
+    }
+
+    /// Pay multiple invoices one at a time, reporting each result as it settles.
+    ///
+    /// Returns an async iterator (use with `for await`) of
+    /// `{ index, total, paymentRequest, preimage }` or `{ index, total, paymentRequest, error }`.
+    #[wasm_bindgen(js_name = sendMultiPaymentIter)]
+    pub fn send_multi_payment_iter(&self, invoices: Vec<String>) -> Object {
+        send_payment::progress_iterator(self.inner.clone(), invoices)
     }
 
     /// Request that the user signs an arbitrary string message.
@@ -138,9 +337,101 @@ impl JsWebLN {
             .into())
     }
 
+    /// Request that the user signs a binary message, encoded as `encoding` (default: `utf8`).
+    #[wasm_bindgen(js_name = signMessageBytes)]
+    pub async fn sign_message_bytes(
+        &self,
+        message: &[u8],
+        encoding: Option<JsMessageEncoding>,
+    ) -> Result<JsSignMessageResponse> {
+        Ok(self
+            .inner
+            .sign_message_bytes(message, encoding.map(Into::into).unwrap_or_default())
+            .await
+            .map_err(into_err)?
+            .into())
+    }
+
+    /// Call a node RPC method through the provider passthrough.
+    ///
+    /// Returns the provider result as-is.
+    pub async fn request(&self, method: &str, params: Option<Object>) -> Result<JsValue> {
+        self.inner
+            .request(method, params.as_deref())
+            .await
+            .map_err(into_err)
+    }
+
+    /// Listen for a provider event (i.e. `accountChanged`).
+    ///
+    /// Returns a function that removes the listener.
+    pub fn on(&self, event: &str, callback: Function) -> Result<Function> {
+        let event: Event = Event::from(event);
+        self.inner.on(&event, &callback).map_err(into_err)?;
+
+        let webln: WebLN = self.inner.clone();
+        let unsubscribe = Closure::once_into_js(move || {
+            let _ = webln.off(&event, &callback);
+        });
+        Ok(unsubscribe.unchecked_into())
+    }
+
+    /// Remove a listener previously registered with `on`.
+    pub fn off(&self, event: &str, callback: &Function) -> Result<()> {
+        self.inner
+            .off(&Event::from(event), callback)
+            .map_err(into_err)
+    }
+
     /// Fetch the balance of the current account.
     #[wasm_bindgen(js_name = getBalance)]
     pub async fn get_balance(&self) -> Result<JsBalanceResponse> {
         Ok(self.inner.get_balance().await.map_err(into_err)?.into())
     }
 }
+
+impl JsWebLN {
+    /// Cached `getInfo` response, if fetched less than `getInfoTtl` ago
+    ///
+    /// The core cache is cleared by `invalidate` (i.e. on `accountChanged`).
+    fn fresh_info(&self) -> Option<GetInfoResponse> {
+        let ttl: Duration = self.info_ttl?;
+        let fetched_at: Duration = self.info_fetched_at.get()?;
+        if self.inner.clock().now().saturating_sub(fetched_at) >= ttl {
+            return None;
+        }
+        self.inner.cached_info()
+    }
+
+    /// Dispatch a DOM event, if `domEvents` is on
+    fn emit(&self, name: &str, detail: Vec<(&str, JsValue)>) {
+        #[cfg(feature = "dom_events")]
+        if self.dom_events {
+            events::dispatch(name, object(&detail));
+        }
+
+        #[cfg(not(feature = "dom_events"))]
+        let _ = (name, detail);
+    }
+
+    /// Convert a payment result, dispatching `webln:payment:success` or `webln:payment:failure`
+    fn emit_payment(
+        &self,
+        mut detail: Vec<(&str, JsValue)>,
+        res: Result<SendPaymentResponse, Error>,
+    ) -> Result<SendPaymentResponse> {
+        match res {
+            Ok(res) => {
+                detail.push(("preimage", JsValue::from_str(&res.preimage)));
+                self.emit(events::PAYMENT_SUCCESS, detail);
+                Ok(res)
+            }
+            Err(e) => {
+                let error: JsValue = into_err(e);
+                detail.push(("error", error.clone()));
+                self.emit(events::PAYMENT_FAILURE, detail);
+                Err(error)
+            }
+        }
+    }
+}
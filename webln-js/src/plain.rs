@@ -0,0 +1,131 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+#[cfg(feature = "plain")]
+use alloc::string::String;
+#[cfg(feature = "plain")]
+use alloc::vec::Vec;
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "plain")]
+use crate::abort::AbortSignal;
+#[cfg(feature = "plain")]
+use crate::error::Result;
+#[cfg(feature = "plain")]
+use crate::JsWebLN;
+
+/// Build a plain JS object
+pub(crate) fn object(entries: &[(&str, JsValue)]) -> JsValue {
+    let obj = Object::new();
+    for (key, value) in entries.iter() {
+        let _ = Reflect::set(&obj, &JsValue::from_str(key), value);
+    }
+    obj.into()
+}
+
+/// WebLN instance returning plain, serializable objects instead of wrapper classes
+///
+/// Useful to put responses directly into stores (Redux, Pinia, ...) or to `postMessage` them.
+#[cfg(feature = "plain")]
+#[wasm_bindgen(js_name = PlainWebLN)]
+pub struct JsPlainWebLN {
+    inner: JsWebLN,
+}
+
+#[cfg(feature = "plain")]
+impl From<JsWebLN> for JsPlainWebLN {
+    fn from(inner: JsWebLN) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg(feature = "plain")]
+#[wasm_bindgen(js_class = PlainWebLN)]
+impl JsPlainWebLN {
+    #[wasm_bindgen(js_name = getInfo)]
+    pub async fn get_info(&self) -> Result<JsValue> {
+        Ok(self.inner.get_info().await?.to_json())
+    }
+
+    pub async fn keysend(
+        &self,
+        args: &crate::keysend::JsKeysendArgs,
+        signal: Option<AbortSignal>,
+    ) -> Result<JsValue> {
+        Ok(self.inner.keysend(args, signal).await?.to_json())
+    }
+
+    #[wasm_bindgen(js_name = keysendAsync)]
+    pub async fn keysend_async(
+        &self,
+        args: &crate::keysend::JsKeysendArgs,
+        signal: Option<AbortSignal>,
+    ) -> Result<()> {
+        self.inner.keysend_async(args, signal).await
+    }
+
+    #[wasm_bindgen(js_name = makeInvoice)]
+    pub async fn make_invoice(&self, args: JsValue) -> Result<JsValue> {
+        Ok(self.inner.make_invoice(args).await?.to_json())
+    }
+
+    #[wasm_bindgen(js_name = sendPayment)]
+    pub async fn send_payment(
+        &self,
+        invoice: &str,
+        signal: Option<AbortSignal>,
+    ) -> Result<JsValue> {
+        Ok(self.inner.send_payment(invoice, signal).await?.to_json())
+    }
+
+    #[wasm_bindgen(js_name = sendPaymentAsync)]
+    pub async fn send_payment_async(
+        &self,
+        invoice: &str,
+        signal: Option<AbortSignal>,
+    ) -> Result<JsValue> {
+        Ok(self
+            .inner
+            .send_payment_async(invoice, signal)
+            .await?
+            .to_json())
+    }
+
+    #[wasm_bindgen(js_name = sendMultiPayment)]
+    pub async fn send_multi_payment(
+        &self,
+        invoices: Vec<String>,
+        signal: Option<AbortSignal>,
+    ) -> Result<JsValue> {
+        Ok(self
+            .inner
+            .send_multi_payment(invoices, signal)
+            .await?
+            .to_json())
+    }
+
+    #[wasm_bindgen(js_name = signMessage)]
+    pub async fn sign_message(&self, message: &str) -> Result<JsValue> {
+        Ok(self.inner.sign_message(message).await?.to_json())
+    }
+
+    #[wasm_bindgen(js_name = signMessageBytes)]
+    pub async fn sign_message_bytes(
+        &self,
+        message: &[u8],
+        encoding: Option<crate::sign_message::JsMessageEncoding>,
+    ) -> Result<JsValue> {
+        Ok(self
+            .inner
+            .sign_message_bytes(message, encoding)
+            .await?
+            .to_json())
+    }
+
+    #[wasm_bindgen(js_name = getBalance)]
+    pub async fn get_balance(&self) -> Result<JsValue> {
+        Ok(self.inner.get_balance().await?.to_json())
+    }
+}
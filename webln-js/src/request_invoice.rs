@@ -1,12 +1,18 @@
 // Copyright (c) 2024 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use alloc::format;
 use alloc::string::String;
 use core::ops::Deref;
 
+use js_sys::{Object, Reflect};
+use wasm_bindgen::convert::TryFromJsValue;
 use wasm_bindgen::prelude::*;
 use webln::{RequestInvoiceArgs, RequestInvoiceResponse};
 
+use crate::error::{into_amount, invalid_data, Result};
+use crate::plain::object;
+
 #[wasm_bindgen(js_name = RequestInvoiceArgs)]
 pub struct JsRequestInvoiceArgs {
     inner: RequestInvoiceArgs,
@@ -26,6 +32,44 @@ impl From<RequestInvoiceArgs> for JsRequestInvoiceArgs {
     }
 }
 
+fn get_amount(obj: &Object, key: &str) -> Result<Option<u64>> {
+    let value: JsValue = Reflect::get(obj, &JsValue::from_str(key))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    match value.as_f64() {
+        Some(amount) => Ok(Some(into_amount(amount)?)),
+        None => Err(invalid_data(&format!("Invalid {key}: expected a number"))),
+    }
+}
+
+fn get_string(obj: &Object, key: &str) -> Result<Option<String>> {
+    let value: JsValue = Reflect::get(obj, &JsValue::from_str(key))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    match value.as_string() {
+        Some(value) => Ok(Some(value)),
+        None => Err(invalid_data(&format!("Invalid {key}: expected a string"))),
+    }
+}
+
+impl JsRequestInvoiceArgs {
+    /// Accept either a `RequestInvoiceArgs` instance (consumed, like the builder methods)
+    /// or a plain object
+    pub(crate) fn from_js_value(args: JsValue) -> Result<Self> {
+        match Self::try_from_js_value(args) {
+            Ok(args) => Ok(args),
+            Err(args) => match args.dyn_ref::<Object>() {
+                Some(obj) => Self::from_object(obj),
+                None => Err(invalid_data(
+                    "Invalid args: expected a RequestInvoiceArgs or a plain object",
+                )),
+            },
+        }
+    }
+}
+
 #[wasm_bindgen(js_class = RequestInvoiceArgs)]
 impl JsRequestInvoiceArgs {
     #[wasm_bindgen(constructor)]
@@ -35,23 +79,44 @@ impl JsRequestInvoiceArgs {
         }
     }
 
-    pub fn amount(self, amount: u32) -> Self {
-        self.inner.amount(amount as u64).into()
+    /// Build from a plain `{ amount, defaultAmount, minimumAmount, maximumAmount, defaultMemo }` object
+    #[wasm_bindgen(js_name = fromObject)]
+    pub fn from_object(obj: &Object) -> Result<JsRequestInvoiceArgs> {
+        let mut inner = RequestInvoiceArgs::new();
+        inner.amount = get_amount(obj, "amount")?;
+        inner.default_amount = get_amount(obj, "defaultAmount")?;
+        inner.minimum_amount = get_amount(obj, "minimumAmount")?;
+        inner.maximum_amount = get_amount(obj, "maximumAmount")?;
+        inner.default_memo = get_string(obj, "defaultMemo")?;
+        Ok(inner.into())
+    }
+
+    pub fn amount(self, amount: f64) -> Result<JsRequestInvoiceArgs> {
+        Ok(self.inner.amount(into_amount(amount)?).into())
     }
 
     #[wasm_bindgen(js_name = defaultAmount)]
-    pub fn default_amount(self, default_amount: u32) -> Self {
-        self.inner.default_amount(default_amount as u64).into()
+    pub fn default_amount(self, default_amount: f64) -> Result<JsRequestInvoiceArgs> {
+        Ok(self
+            .inner
+            .default_amount(into_amount(default_amount)?)
+            .into())
     }
 
     #[wasm_bindgen(js_name = minimumAmount)]
-    pub fn minimum_amount(self, minimum_amount: u32) -> Self {
-        self.inner.minimum_amount(minimum_amount as u64).into()
+    pub fn minimum_amount(self, minimum_amount: f64) -> Result<JsRequestInvoiceArgs> {
+        Ok(self
+            .inner
+            .minimum_amount(into_amount(minimum_amount)?)
+            .into())
     }
 
     #[wasm_bindgen(js_name = maximumAmount)]
-    pub fn maximum_amount(self, maximum_amount: u32) -> Self {
-        self.inner.maximum_amount(maximum_amount as u64).into()
+    pub fn maximum_amount(self, maximum_amount: f64) -> Result<JsRequestInvoiceArgs> {
+        Ok(self
+            .inner
+            .maximum_amount(into_amount(maximum_amount)?)
+            .into())
     }
 
     #[wasm_bindgen(js_name = defaultMemo)]
@@ -77,4 +142,10 @@ impl JsRequestInvoiceResponse {
     pub fn invoice(&self) -> String {
         self.inner.invoice.clone()
     }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[("paymentRequest", JsValue::from_str(&self.inner.invoice))])
+    }
 }
@@ -1,10 +1,22 @@
 // Copyright (c) 2024 Yuki Kishimoto
 // Distributed under the MIT software license
 
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
 
+use js_sys::{Array, Function, Object, Promise, Reflect, Symbol};
 use wasm_bindgen::prelude::*;
-use webln::SendPaymentResponse;
+use wasm_bindgen_futures::future_to_promise;
+use webln::{
+    MultiPaymentError, MultiPaymentItem, SendMultiPaymentResponse, SendPaymentAsyncResponse,
+    SendPaymentResponse, WebLN,
+};
+
+use crate::error::into_err;
+use crate::plain::object;
 
 #[wasm_bindgen(js_name = SendPaymentResponse)]
 pub struct JsSendPaymentResponse {
@@ -23,4 +35,245 @@ impl JsSendPaymentResponse {
     pub fn preimage(&self) -> String {
         self.inner.preimage.clone()
     }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[("preimage", JsValue::from_str(&self.inner.preimage))])
+    }
+}
+
+#[wasm_bindgen(js_name = SendPaymentAsyncResponse)]
+pub struct JsSendPaymentAsyncResponse {
+    inner: SendPaymentAsyncResponse,
+}
+
+impl From<SendPaymentAsyncResponse> for JsSendPaymentAsyncResponse {
+    fn from(inner: SendPaymentAsyncResponse) -> Self {
+        Self { inner }
+    }
+}
+
+#[wasm_bindgen(js_class = SendPaymentAsyncResponse)]
+impl JsSendPaymentAsyncResponse {
+    /// Always `true`: rejected payments throw
+    #[wasm_bindgen(getter)]
+    pub fn accepted(&self) -> bool {
+        true
+    }
+
+    /// Payment hash, if returned by the provider
+    #[wasm_bindgen(getter, js_name = paymentHash)]
+    pub fn payment_hash(&self) -> Option<String> {
+        self.inner.payment_hash.clone()
+    }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[
+            ("accepted", JsValue::TRUE),
+            (
+                "paymentHash",
+                self.inner
+                    .payment_hash
+                    .as_deref()
+                    .map(JsValue::from_str)
+                    .into(),
+            ),
+        ])
+    }
+}
+
+#[wasm_bindgen(js_name = MultiPaymentItem)]
+pub struct JsMultiPaymentItem {
+    inner: MultiPaymentItem,
+}
+
+impl From<MultiPaymentItem> for JsMultiPaymentItem {
+    fn from(inner: MultiPaymentItem) -> Self {
+        Self { inner }
+    }
+}
+
+#[wasm_bindgen(js_class = MultiPaymentItem)]
+impl JsMultiPaymentItem {
+    #[wasm_bindgen(getter, js_name = paymentRequest)]
+    pub fn payment_request(&self) -> String {
+        self.inner.payment_request.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn preimage(&self) -> String {
+        self.inner.preimage.clone()
+    }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[
+            (
+                "paymentRequest",
+                JsValue::from_str(&self.inner.payment_request),
+            ),
+            ("preimage", JsValue::from_str(&self.inner.preimage)),
+        ])
+    }
+}
+
+#[wasm_bindgen(js_name = MultiPaymentError)]
+pub struct JsMultiPaymentError {
+    inner: MultiPaymentError,
+}
+
+impl From<MultiPaymentError> for JsMultiPaymentError {
+    fn from(inner: MultiPaymentError) -> Self {
+        Self { inner }
+    }
+}
+
+#[wasm_bindgen(js_class = MultiPaymentError)]
+impl JsMultiPaymentError {
+    #[wasm_bindgen(getter, js_name = paymentRequest)]
+    pub fn payment_request(&self) -> String {
+        self.inner.payment_request.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.inner.message.clone()
+    }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[
+            (
+                "paymentRequest",
+                JsValue::from_str(&self.inner.payment_request),
+            ),
+            ("message", JsValue::from_str(&self.inner.message)),
+        ])
+    }
+}
+
+#[wasm_bindgen(js_name = SendMultiPaymentResponse)]
+pub struct JsSendMultiPaymentResponse {
+    inner: SendMultiPaymentResponse,
+}
+
+impl From<SendMultiPaymentResponse> for JsSendMultiPaymentResponse {
+    fn from(inner: SendMultiPaymentResponse) -> Self {
+        Self { inner }
+    }
+}
+
+#[wasm_bindgen(js_class = SendMultiPaymentResponse)]
+impl JsSendMultiPaymentResponse {
+    #[wasm_bindgen(getter)]
+    pub fn payments(&self) -> Vec<JsMultiPaymentItem> {
+        self.inner
+            .payments
+            .iter()
+            .cloned()
+            .map(|item| item.into())
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn errors(&self) -> Vec<JsMultiPaymentError> {
+        self.inner
+            .errors
+            .iter()
+            .cloned()
+            .map(|error| error.into())
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn duplicates(&self) -> Vec<String> {
+        self.inner.duplicates.clone()
+    }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[
+            (
+                "payments",
+                self.payments()
+                    .into_iter()
+                    .map(|p| p.to_json())
+                    .collect::<Array>()
+                    .into(),
+            ),
+            (
+                "errors",
+                self.errors()
+                    .into_iter()
+                    .map(|e| e.to_json())
+                    .collect::<Array>()
+                    .into(),
+            ),
+            (
+                "duplicates",
+                self.inner
+                    .duplicates
+                    .iter()
+                    .map(|i| JsValue::from_str(i))
+                    .collect::<Array>()
+                    .into(),
+            ),
+        ])
+    }
+}
+
+fn set(obj: &Object, key: &str, value: &JsValue) {
+    let _ = Reflect::set(obj, &JsValue::from_str(key), value);
+}
+
+fn iterator_result(value: &JsValue, done: bool) -> JsValue {
+    let result = Object::new();
+    set(&result, "value", value);
+    set(&result, "done", &JsValue::from_bool(done));
+    result.into()
+}
+
+/// Async iterator paying `invoices` one at a time
+///
+/// Yields `{ index, total, paymentRequest, preimage }` or `{ index, total, paymentRequest, error }`.
+pub(crate) fn progress_iterator(webln: WebLN, invoices: Vec<String>) -> Object {
+    let total: usize = invoices.len();
+    let queue: Rc<RefCell<VecDeque<(usize, String)>>> =
+        Rc::new(RefCell::new(invoices.into_iter().enumerate().collect()));
+
+    let next = Closure::<dyn FnMut() -> Promise>::new(move || {
+        let webln: WebLN = webln.clone();
+        let item: Option<(usize, String)> = queue.borrow_mut().pop_front();
+        future_to_promise(async move {
+            let (index, invoice) = match item {
+                Some(item) => item,
+                None => return Ok(iterator_result(&JsValue::UNDEFINED, true)),
+            };
+
+            let value = Object::new();
+            set(&value, "index", &JsValue::from(index as u32));
+            set(&value, "total", &JsValue::from(total as u32));
+            set(&value, "paymentRequest", &JsValue::from_str(&invoice));
+            match webln.send_payment(&invoice).await {
+                Ok(res) => set(&value, "preimage", &JsValue::from_str(&res.preimage)),
+                Err(e) => set(&value, "error", &into_err(e)),
+            }
+            Ok(iterator_result(&value, false))
+        })
+    });
+
+    let iterator = Object::new();
+    set(&iterator, "next", &next.into_js_value());
+    let _ = Reflect::set(
+        &iterator,
+        &Symbol::async_iterator(),
+        &Function::new_no_args("return this"),
+    );
+    iterator
 }
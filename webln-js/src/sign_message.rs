@@ -4,7 +4,28 @@
 use alloc::string::String;
 
 use wasm_bindgen::prelude::*;
-use webln::SignMessageResponse;
+use webln::{MessageEncoding, SignMessageResponse};
+
+use crate::plain::object;
+
+/// Encoding of the binary messages given to `signMessageBytes`
+#[wasm_bindgen(js_name = MessageEncoding)]
+#[derive(Clone, Copy)]
+pub enum JsMessageEncoding {
+    Utf8 = "utf8",
+    Hex = "hex",
+    Base64 = "base64",
+}
+
+impl From<JsMessageEncoding> for MessageEncoding {
+    fn from(encoding: JsMessageEncoding) -> Self {
+        match encoding {
+            JsMessageEncoding::Utf8 | JsMessageEncoding::__Invalid => Self::Utf8,
+            JsMessageEncoding::Hex => Self::Hex,
+            JsMessageEncoding::Base64 => Self::Base64,
+        }
+    }
+}
 
 #[wasm_bindgen(js_name = SignMessageResponse)]
 pub struct JsSignMessageResponse {
@@ -26,4 +47,13 @@ impl JsSignMessageResponse {
     pub fn signature(&self) -> String {
         self.inner.signature.clone()
     }
+
+    /// Plain, serializable object (used by `JSON.stringify`)
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> JsValue {
+        object(&[
+            ("message", JsValue::from_str(&self.inner.message)),
+            ("signature", JsValue::from_str(&self.inner.signature)),
+        ])
+    }
 }
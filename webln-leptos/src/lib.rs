@@ -0,0 +1,173 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! WebLN reactive signals and resources for Leptos
+//!
+//! Call [`provide_webln`] in the root component, then [`use_webln`] anywhere below it.
+//!
+//! All the values are [`LocalResource`]s: they are loaded in the browser only, so the crate is
+//! safe to use with server side rendering (enable the `ssr` feature on the server build).
+//!
+//! ```rust,no_run
+//! use leptos::prelude::*;
+//! use webln_leptos::{provide_webln, use_webln};
+//!
+//! #[component]
+//! fn Balance() -> impl IntoView {
+//!     let webln = use_webln();
+//!     view! {
+//!         <Suspense fallback=|| "Loading...">
+//!             {move || webln.balance.get().map(|res| match res.take() {
+//!                 Ok(balance) => format!("{} sats", balance.balance),
+//!                 Err(e) => format!("Error: {e}"),
+//!             })}
+//!         </Suspense>
+//!     }
+//! }
+//!
+//! #[component]
+//! fn App() -> impl IntoView {
+//!     let webln = provide_webln();
+//!     view! {
+//!         <Show when=move || webln.is_enabled().get() fallback=|| "Wallet not connected">
+//!             <Balance />
+//!         </Show>
+//!     }
+//! }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::bare_urls)]
+
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+
+use leptos::prelude::*;
+pub use webln;
+use webln::{BalanceResponse, Error, GetInfoResponse, Subscription, WebLN};
+
+/// Default time to wait for the provider injection
+pub const DETECTION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Result of a WebLN resource (errors are shared, since resources must be cloneable)
+pub type WebLNResult<T> = Result<T, Rc<Error>>;
+
+/// Reactive WebLN state
+///
+/// Cheap to copy. Resources are re-fetched when the account changes (`accountChanged` event)
+/// and on [`WebLNState::refresh`].
+#[derive(Clone, Copy)]
+pub struct WebLNState {
+    /// Provider
+    pub provider: LocalResource<WebLNResult<WebLN>>,
+    /// Enabled state
+    pub enabled: LocalResource<WebLNResult<bool>>,
+    /// Balance
+    pub balance: LocalResource<WebLNResult<BalanceResponse>>,
+    /// Node info
+    pub info: LocalResource<WebLNResult<GetInfoResponse>>,
+    generation: RwSignal<u64>,
+}
+
+impl WebLNState {
+    /// Compose new state, waiting up to `timeout` for the provider injection
+    ///
+    /// Use [`provide_webln`] to share it with the children.
+    pub fn new(timeout: Duration) -> Self {
+        let provider = LocalResource::new(move || async move {
+            WebLN::wait_for_provider(timeout).await.map_err(Rc::new)
+        });
+        let generation: RwSignal<u64> = RwSignal::new(0);
+
+        // Effects only run in the browser
+        Effect::new(move |_| -> Option<Subscription> {
+            match provider.get()?.take() {
+                Ok(webln) => webln
+                    .on_account_changed(false, move |_| {
+                        generation.update(|generation| *generation += 1)
+                    })
+                    .ok(),
+                Err(..) => None,
+            }
+        });
+
+        Self {
+            provider,
+            enabled: query(provider, generation, |webln| async move {
+                webln.is_enabled().await
+            }),
+            balance: query(provider, generation, |webln| async move {
+                webln.get_balance().await
+            }),
+            info: query(provider, generation, |webln| async move {
+                webln.get_info().await
+            }),
+            generation,
+        }
+    }
+
+    /// Provider detected
+    pub fn is_available(&self) -> Signal<bool> {
+        let provider = self.provider;
+        Signal::derive(move || matches!(provider.get().as_deref(), Some(Ok(..))))
+    }
+
+    /// Provider enabled (`false` while loading)
+    pub fn is_enabled(&self) -> Signal<bool> {
+        let enabled = self.enabled;
+        Signal::derive(move || matches!(enabled.get().as_deref(), Some(Ok(true))))
+    }
+
+    /// Re-fetch the resources
+    pub fn refresh(&self) {
+        self.generation.update(|generation| *generation += 1);
+    }
+
+    /// Request the user permission to use the provider
+    ///
+    /// On success, the resources are re-fetched.
+    pub async fn enable(&self) -> WebLNResult<()> {
+        let webln: WebLN = self.provider.await?;
+        webln.enable().await.map_err(Rc::new)?;
+        self.refresh();
+        Ok(())
+    }
+}
+
+/// Resource running `request` once the provider is detected
+fn query<T, F, Fut>(
+    provider: LocalResource<WebLNResult<WebLN>>,
+    generation: RwSignal<u64>,
+    request: F,
+) -> LocalResource<WebLNResult<T>>
+where
+    T: 'static,
+    F: Fn(WebLN) -> Fut + Copy + 'static,
+    Fut: Future<Output = Result<T, Error>> + 'static,
+{
+    LocalResource::new(move || {
+        generation.track();
+        async move {
+            let webln: WebLN = provider.await?;
+            request(webln).await.map_err(Rc::new)
+        }
+    })
+}
+
+/// Create the [`WebLNState`] and provide it as context
+pub fn provide_webln() -> WebLNState {
+    let state = WebLNState::new(DETECTION_TIMEOUT);
+    provide_context(state);
+    state
+}
+
+/// Get the [`WebLNState`]
+///
+/// # Panics
+///
+/// Panics if [`provide_webln`] was not called by a parent component.
+#[track_caller]
+pub fn use_webln() -> WebLNState {
+    expect_context()
+}
@@ -0,0 +1,214 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! WebLN reactive signals for Sycamore
+//!
+//! Call [`provide_webln`] in the root component, then [`use_webln`] anywhere below it.
+//!
+//! ```rust,no_run
+//! use sycamore::prelude::*;
+//! use webln_sycamore::{provide_webln, use_webln, Status};
+//!
+//! #[component]
+//! fn Balance() -> View {
+//!     let webln = use_webln();
+//!     view! {
+//!         (webln.balance.with(|balance| match balance {
+//!             Status::Loading => String::from("Loading..."),
+//!             Status::Ready(balance) => format!("{} sats", balance.balance),
+//!             Status::Failed(e) => format!("Error: {e}"),
+//!         }))
+//!     }
+//! }
+//!
+//! #[component]
+//! fn App() -> View {
+//!     provide_webln();
+//!     view! { Balance {} }
+//! }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::bare_urls)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use sycamore::futures::spawn_local_scoped;
+use sycamore::prelude::*;
+pub use webln;
+use webln::{BalanceResponse, Error, KeysendArgs, SendPaymentResponse, Subscription, WebLN};
+
+/// Default time to wait for the provider injection
+pub const DETECTION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Status of an async WebLN value
+#[derive(Debug, Clone, Default)]
+pub enum Status<T> {
+    /// Not available yet
+    #[default]
+    Loading,
+    /// Value
+    Ready(T),
+    /// Failed
+    Failed(Rc<Error>),
+}
+
+impl<T> Status<T> {
+    /// Get the value, if ready
+    pub fn ready(&self) -> Option<&T> {
+        match self {
+            Self::Ready(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T> From<Result<T, Error>> for Status<T> {
+    fn from(res: Result<T, Error>) -> Self {
+        match res {
+            Ok(value) => Self::Ready(value),
+            Err(e) => Self::Failed(Rc::new(e)),
+        }
+    }
+}
+
+/// Reactive WebLN state
+///
+/// Cheap to copy. Values are re-fetched when the account changes (`accountChanged` event),
+/// after a payment and on [`WebLNState::refresh`].
+#[derive(Clone, Copy)]
+pub struct WebLNState {
+    /// Provider
+    pub provider: ReadSignal<Status<WebLN>>,
+    /// Enabled state
+    pub enabled: ReadSignal<Status<bool>>,
+    /// Balance
+    pub balance: ReadSignal<Status<BalanceResponse>>,
+    generation: Signal<u64>,
+}
+
+impl WebLNState {
+    /// Compose new state, waiting up to `timeout` for the provider injection
+    ///
+    /// Use [`provide_webln`] to share it with the children.
+    pub fn new(timeout: Duration) -> Self {
+        let provider: Signal<Status<WebLN>> = create_signal(Status::Loading);
+        let enabled: Signal<Status<bool>> = create_signal(Status::Loading);
+        let balance: Signal<Status<BalanceResponse>> = create_signal(Status::Loading);
+        let generation: Signal<u64> = create_signal(0);
+
+        // Unsubscribe when the owner is dropped
+        let subscription: Rc<RefCell<Option<Subscription>>> = Rc::default();
+        on_cleanup({
+            let subscription = subscription.clone();
+            move || {
+                subscription.borrow_mut().take();
+            }
+        });
+
+        spawn_local_scoped(async move {
+            let res: Result<WebLN, Error> = WebLN::wait_for_provider(timeout).await;
+            if let Ok(webln) = &res {
+                // Not all providers emit events: ignore subscription errors
+                *subscription.borrow_mut() = webln
+                    .on_account_changed(false, move |_| {
+                        generation.update(|generation| *generation += 1)
+                    })
+                    .ok();
+            }
+            provider.set(res.into());
+        });
+
+        // Runs again when the provider is detected and on every generation bump
+        create_effect(move || {
+            generation.track();
+            match provider.with(|provider| provider.clone()) {
+                Status::Loading => (),
+                Status::Failed(e) => {
+                    enabled.set(Status::Failed(e.clone()));
+                    balance.set(Status::Failed(e));
+                }
+                Status::Ready(webln) => spawn_local_scoped(async move {
+                    enabled.set(webln.is_enabled().await.into());
+                    balance.set(webln.get_balance().await.into());
+                }),
+            }
+        });
+
+        Self {
+            provider: *provider,
+            enabled: *enabled,
+            balance: *balance,
+            generation,
+        }
+    }
+
+    /// Provider enabled (`false` while loading)
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+            .with(|enabled| matches!(enabled, Status::Ready(true)))
+    }
+
+    /// Re-fetch the values
+    pub fn refresh(&self) {
+        self.generation.update(|generation| *generation += 1);
+    }
+
+    fn webln(&self) -> Result<WebLN, Rc<Error>> {
+        match self.provider.get_clone_untracked() {
+            Status::Ready(webln) => Ok(webln),
+            Status::Failed(e) => Err(e),
+            // Still detecting: same error as a missing provider
+            Status::Loading => Err(Rc::new(Error::NamespaceNotFound(String::from("webln")))),
+        }
+    }
+
+    /// Request the user permission to use the provider
+    ///
+    /// On success, the values are re-fetched.
+    pub async fn enable(self) -> Result<(), Rc<Error>> {
+        self.webln()?.enable().await.map_err(Rc::new)?;
+        self.refresh();
+        Ok(())
+    }
+
+    /// Pay a BOLT11 invoice
+    ///
+    /// On success, the balance is re-fetched.
+    pub async fn send_payment(self, invoice: String) -> Result<SendPaymentResponse, Rc<Error>> {
+        let res: SendPaymentResponse = self
+            .webln()?
+            .send_payment(&invoice)
+            .await
+            .map_err(Rc::new)?;
+        self.refresh();
+        Ok(res)
+    }
+
+    /// Send a keysend payment
+    ///
+    /// On success, the balance is re-fetched.
+    pub async fn keysend(self, args: KeysendArgs) -> Result<SendPaymentResponse, Rc<Error>> {
+        let res: SendPaymentResponse = self.webln()?.keysend(&args).await.map_err(Rc::new)?;
+        self.refresh();
+        Ok(res)
+    }
+}
+
+/// Create the [`WebLNState`] and provide it as context
+pub fn provide_webln() -> WebLNState {
+    let state = WebLNState::new(DETECTION_TIMEOUT);
+    provide_context(state);
+    state
+}
+
+/// Get the [`WebLNState`]
+///
+/// # Panics
+///
+/// Panics if [`provide_webln`] was not called by a parent component.
+pub fn use_webln() -> WebLNState {
+    use_context()
+}
@@ -0,0 +1,278 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Pay and tip buttons
+
+use std::convert::Infallible;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use webln::{Error, ProviderError, SendPaymentResponse, WebLN};
+use yew::platform::spawn_local;
+use yew::prelude::*;
+
+use crate::{use_webln_context, WebLNContext};
+
+/// What to pay
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PaymentTarget {
+    /// BOLT11 invoice
+    Invoice(String),
+    /// LNURL (the provider asks the amount)
+    Lnurl(String),
+    /// Lightning address (the provider asks the amount)
+    LightningAddress(String),
+}
+
+/// Let the provider handle a LNURL or lightning address (i.e. asking the amount to pay)
+///
+/// `lnurl` is not part of the WebLN spec (Alby extension), so it's called on `window.webln`
+/// directly, like the [`WebLNProvider`](crate::WebLNProvider) detects it.
+async fn lnurl(lnurl: &str) -> Result<(), Error> {
+    let provider: JsValue = Reflect::get(&js_sys::global(), &JsValue::from_str("webln"))
+        .ok()
+        .filter(JsValue::is_object)
+        .ok_or_else(|| Error::NamespaceNotFound(String::from("webln")))?;
+    let func: Function = Reflect::get(&provider, &JsValue::from_str("lnurl"))
+        .ok()
+        .and_then(|func| func.dyn_into().ok())
+        .ok_or_else(|| Error::NamespaceNotFound(String::from("lnurl")))?;
+    let promise: Promise = Promise::resolve(&func.call1(&provider, &JsValue::from_str(lnurl))?);
+    let result: JsValue = JsFuture::from(promise).await?;
+
+    let get = |key: &str| {
+        Reflect::get(&result, &JsValue::from_str(key))
+            .ok()?
+            .as_string()
+    };
+    if get("status").as_deref() == Some("ERROR") {
+        let message: String = get("reason").unwrap_or_default();
+        return Err(Error::Provider(ProviderError::from(&JsValue::from_str(
+            &message,
+        ))));
+    }
+    Ok(())
+}
+
+impl PaymentTarget {
+    async fn pay(&self, webln: &WebLN) -> Result<Option<SendPaymentResponse>, Error> {
+        match self {
+            Self::Invoice(invoice) => webln.send_payment(invoice).await.map(Some),
+            Self::Lnurl(target) | Self::LightningAddress(target) => {
+                lnurl(target).await.map(|()| None)
+            }
+        }
+    }
+}
+
+impl FromStr for PaymentTarget {
+    type Err = Infallible;
+
+    /// Detect the target kind (a `lightning:` URI prefix is removed)
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        let target: &str = target.trim();
+        let target: &str = match target.get(..10) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("lightning:") => &target[10..],
+            _ => target,
+        };
+
+        let is_lnurl: bool = target
+            .get(..5)
+            .map_or(false, |prefix| prefix.eq_ignore_ascii_case("lnurl"));
+        if is_lnurl {
+            Ok(Self::Lnurl(target.to_string()))
+        } else if target.contains('@') {
+            Ok(Self::LightningAddress(target.to_string()))
+        } else {
+            Ok(Self::Invoice(target.to_string()))
+        }
+    }
+}
+
+impl From<&str> for PaymentTarget {
+    fn from(target: &str) -> Self {
+        match Self::from_str(target) {
+            Ok(target) => target,
+            Err(e) => match e {},
+        }
+    }
+}
+
+impl From<String> for PaymentTarget {
+    fn from(target: String) -> Self {
+        Self::from(target.as_str())
+    }
+}
+
+/// [`PayButton`] state
+#[derive(Debug, Clone, Default)]
+pub enum PayButtonState {
+    /// Waiting for a click
+    #[default]
+    Idle,
+    /// Asking the user permission
+    Enabling,
+    /// Waiting for the payment
+    Paying,
+    /// Paid
+    Paid,
+    /// Failed (or rejected by the user): a click retries
+    Failed(Rc<Error>),
+}
+
+impl PayButtonState {
+    fn is_busy(&self) -> bool {
+        matches!(self, Self::Enabling | Self::Paying)
+    }
+
+    /// Name, exposed as `data-state` attribute for styling
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Enabling => "enabling",
+            Self::Paying => "paying",
+            Self::Paid => "paid",
+            Self::Failed(..) => "failed",
+        }
+    }
+}
+
+/// [`PayButton`] props
+#[derive(Debug, PartialEq, Properties)]
+pub struct PayButtonProps {
+    /// What to pay
+    pub target: PaymentTarget,
+    /// Label (default: `Pay`)
+    #[prop_or_default]
+    pub children: Html,
+    /// CSS classes
+    #[prop_or_default]
+    pub class: Classes,
+    /// Paid. The response is `None` for LNURLs and lightning addresses.
+    #[prop_or_default]
+    pub on_success: Callback<Option<SendPaymentResponse>>,
+    /// Failed (or rejected by the user)
+    #[prop_or_default]
+    pub on_failed: Callback<Rc<Error>>,
+}
+
+async fn enable_and_pay(
+    webln: &WebLN,
+    target: &PaymentTarget,
+    state: &UseStateHandle<PayButtonState>,
+) -> Result<Option<SendPaymentResponse>, Error> {
+    if !webln.is_enabled().await? {
+        state.set(PayButtonState::Enabling);
+        webln.enable().await?;
+    }
+    state.set(PayButtonState::Paying);
+    target.pay(webln).await
+}
+
+/// Button handling enable → pay, with success and failure callbacks
+///
+/// Disabled until the provider is detected. Must be used inside a
+/// [`WebLNProvider`](crate::WebLNProvider).
+#[function_component(PayButton)]
+pub fn pay_button(props: &PayButtonProps) -> Html {
+    let context: WebLNContext = use_webln_context();
+    let state = use_state(PayButtonState::default);
+
+    let onclick = {
+        let context = context.clone();
+        let state = state.clone();
+        let target = props.target.clone();
+        let on_success = props.on_success.clone();
+        let on_failed = props.on_failed.clone();
+        Callback::from(move |_: MouseEvent| {
+            let webln: WebLN = match context.webln() {
+                Some(webln) if !state.is_busy() => webln.clone(),
+                _ => return,
+            };
+            let context = context.clone();
+            let state = state.clone();
+            let target = target.clone();
+            let on_success = on_success.clone();
+            let on_failed = on_failed.clone();
+            spawn_local(async move {
+                match enable_and_pay(&webln, &target, &state).await {
+                    Ok(res) => {
+                        state.set(PayButtonState::Paid);
+                        context.refresh();
+                        on_success.emit(res);
+                    }
+                    Err(e) => {
+                        let e: Rc<Error> = Rc::new(e);
+                        state.set(PayButtonState::Failed(e.clone()));
+                        on_failed.emit(e);
+                    }
+                }
+            });
+        })
+    };
+
+    let label: Html = match &*state {
+        PayButtonState::Enabling => html! { "Connecting..." },
+        PayButtonState::Paying => html! { "Paying..." },
+        PayButtonState::Paid => html! { "Paid" },
+        PayButtonState::Idle | PayButtonState::Failed(..) if props.children == html! {} => {
+            html! { "Pay" }
+        }
+        PayButtonState::Idle | PayButtonState::Failed(..) => props.children.clone(),
+    };
+
+    html! {
+        <button
+            class={props.class.clone()}
+            data-state={state.as_str()}
+            disabled={context.webln().is_none() || state.is_busy()}
+            {onclick}
+        >
+            { label }
+        </button>
+    }
+}
+
+/// [`TipButton`] props
+#[derive(Debug, PartialEq, Properties)]
+pub struct TipButtonProps {
+    /// LNURL or lightning address
+    pub address: AttrValue,
+    /// Label (default: `⚡ Tip`)
+    #[prop_or_default]
+    pub children: Html,
+    /// CSS classes
+    #[prop_or_default]
+    pub class: Classes,
+    /// Tip sent
+    #[prop_or_default]
+    pub on_success: Callback<()>,
+    /// Failed (or rejected by the user)
+    #[prop_or_default]
+    pub on_failed: Callback<Rc<Error>>,
+}
+
+/// Tip a LNURL or lightning address: the provider asks the amount
+#[function_component(TipButton)]
+pub fn tip_button(props: &TipButtonProps) -> Html {
+    let children: Html = if props.children == html! {} {
+        html! { "⚡ Tip" }
+    } else {
+        props.children.clone()
+    };
+    let on_success: Callback<Option<SendPaymentResponse>> = props.on_success.reform(|_| ());
+
+    html! {
+        <PayButton
+            target={PaymentTarget::from(props.address.as_str())}
+            class={props.class.clone()}
+            {on_success}
+            on_failed={props.on_failed.clone()}
+        >
+            { children }
+        </PayButton>
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Context provider
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use webln::{Error, Subscription, WebLN};
+use yew::platform::spawn_local;
+use yew::prelude::*;
+
+use crate::Status;
+
+/// Default time to wait for the provider injection
+pub const DETECTION_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug)]
+enum Action {
+    Detected(WebLN),
+    Failed(Error),
+    /// Account changed or refresh requested
+    Refresh,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    provider: Status<WebLN>,
+    generation: u64,
+}
+
+impl Reducible for State {
+    type Action = Action;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let provider: Status<WebLN> = match action {
+            Action::Detected(webln) => Status::Ready(webln),
+            Action::Failed(e) => Status::Failed(Rc::new(e)),
+            Action::Refresh => self.provider.clone(),
+        };
+        Rc::new(Self {
+            provider,
+            generation: self.generation + 1,
+        })
+    }
+}
+
+/// WebLN context, shared by [`WebLNProvider`] with its children
+///
+/// Two contexts are equal if they have the same generation: it is bumped when the provider is
+/// detected, when the account changes and on [`WebLNContext::refresh`].
+#[derive(Debug, Clone)]
+pub struct WebLNContext {
+    provider: Status<WebLN>,
+    generation: u64,
+    dispatcher: UseReducerDispatcher<State>,
+}
+
+impl PartialEq for WebLNContext {
+    fn eq(&self, other: &Self) -> bool {
+        self.generation == other.generation
+    }
+}
+
+impl WebLNContext {
+    /// Provider detection status
+    pub fn provider(&self) -> &Status<WebLN> {
+        &self.provider
+    }
+
+    /// Get the provider, if detected
+    pub fn webln(&self) -> Option<&WebLN> {
+        self.provider.ready()
+    }
+
+    /// Generation (usable as hook dependency)
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Bump the generation, so the hooks re-fetch their values
+    pub fn refresh(&self) {
+        self.dispatcher.dispatch(Action::Refresh);
+    }
+}
+
+/// [`WebLNProvider`] props
+#[derive(Debug, PartialEq, Properties)]
+pub struct WebLNProviderProps {
+    /// Time to wait for the provider injection
+    #[prop_or(DETECTION_TIMEOUT)]
+    pub timeout: Duration,
+    /// Children
+    #[prop_or_default]
+    pub children: Html,
+}
+
+/// Detect the WebLN provider and share it with the children
+///
+/// Children are re-rendered when the account changes (`accountChanged` event).
+#[function_component(WebLNProvider)]
+pub fn webln_provider(props: &WebLNProviderProps) -> Html {
+    let state = use_reducer(State::default);
+
+    {
+        let dispatcher = state.dispatcher();
+        use_effect_with(props.timeout, move |timeout| {
+            let timeout: Duration = *timeout;
+            let active: Rc<Cell<bool>> = Rc::new(Cell::new(true));
+            let subscription: Rc<RefCell<Option<Subscription>>> = Rc::default();
+
+            {
+                let active = active.clone();
+                let subscription = subscription.clone();
+                spawn_local(async move {
+                    let res: Result<WebLN, Error> = WebLN::wait_for_provider(timeout).await;
+                    if !active.get() {
+                        return;
+                    }
+
+                    match res {
+                        Ok(webln) => {
+                            // Not all providers emit events: ignore subscription errors
+                            let on_change = dispatcher.clone();
+                            if let Ok(sub) = webln.on_account_changed(false, move |_| {
+                                on_change.dispatch(Action::Refresh)
+                            }) {
+                                *subscription.borrow_mut() = Some(sub);
+                            }
+                            dispatcher.dispatch(Action::Detected(webln));
+                        }
+                        Err(e) => dispatcher.dispatch(Action::Failed(e)),
+                    }
+                });
+            }
+
+            move || {
+                active.set(false);
+                subscription.borrow_mut().take();
+            }
+        });
+    }
+
+    let context = WebLNContext {
+        provider: state.provider.clone(),
+        generation: state.generation,
+        dispatcher: state.dispatcher(),
+    };
+
+    html! {
+        <ContextProvider<WebLNContext> {context}>
+            { props.children.clone() }
+        </ContextProvider<WebLNContext>>
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Hooks
+
+use std::cell::Cell;
+use std::future::Future;
+use std::rc::Rc;
+
+use webln::{BalanceResponse, Error, WebLN};
+use yew::platform::spawn_local;
+use yew::prelude::*;
+
+use crate::{Status, WebLNContext};
+
+/// Get the [`WebLNContext`]
+///
+/// # Panics
+///
+/// Panics if not used inside a [`WebLNProvider`](crate::WebLNProvider).
+#[hook]
+pub fn use_webln_context() -> WebLNContext {
+    use_context::<WebLNContext>().expect("WebLN hooks must be used inside a `WebLNProvider`")
+}
+
+/// Get the provider, if detected
+#[hook]
+pub fn use_webln() -> Option<WebLN> {
+    use_webln_context().webln().cloned()
+}
+
+/// Run `request` once the provider is detected, and again every time the context changes
+///
+/// The previous value is kept while re-fetching.
+#[hook]
+fn use_request<T, F, Fut>(request: F) -> UseStateHandle<Status<T>>
+where
+    T: 'static,
+    F: Fn(WebLN) -> Fut + 'static,
+    Fut: Future<Output = Result<T, Error>> + 'static,
+{
+    let context: WebLNContext = use_webln_context();
+    let state = use_state(Status::default);
+
+    {
+        let state = state.clone();
+        use_effect_with(context, move |context| {
+            let active: Rc<Cell<bool>> = Rc::new(Cell::new(true));
+
+            match context.provider() {
+                Status::Loading => state.set(Status::Loading),
+                Status::Failed(e) => state.set(Status::Failed(e.clone())),
+                Status::Ready(webln) => {
+                    let future = request(webln.clone());
+                    let active = active.clone();
+                    spawn_local(async move {
+                        let res: Result<T, Error> = future.await;
+                        if active.get() {
+                            state.set(res.into());
+                        }
+                    });
+                }
+            }
+
+            move || active.set(false)
+        });
+    }
+
+    state
+}
+
+/// Balance of the current account
+///
+/// Re-fetched when the account changes.
+#[hook]
+pub fn use_balance() -> Status<BalanceResponse> {
+    let state = use_request(|webln: WebLN| async move { webln.get_balance().await });
+    (*state).clone()
+}
+
+/// [`use_webln_enabled`] handle
+#[derive(Debug, Clone)]
+pub struct UseWebLNEnabledHandle {
+    status: Status<bool>,
+    enable: Callback<()>,
+}
+
+impl UseWebLNEnabledHandle {
+    /// Enabled status
+    pub fn status(&self) -> &Status<bool> {
+        &self.status
+    }
+
+    /// Check if enabled (`false` while loading or if failed)
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.status, Status::Ready(true))
+    }
+
+    /// Request the user permission to use the provider
+    ///
+    /// On success, the context is refreshed, so the other hooks re-fetch their values.
+    pub fn enable(&self) {
+        self.enable.emit(());
+    }
+}
+
+/// Check if the provider is enabled, and enable it
+#[hook]
+pub fn use_webln_enabled() -> UseWebLNEnabledHandle {
+    let context: WebLNContext = use_webln_context();
+    let state = use_request(|webln: WebLN| async move { webln.is_enabled().await });
+
+    let enable: Callback<()> = {
+        let state = state.clone();
+        use_callback(context, move |(), context| {
+            let webln: WebLN = match context.webln() {
+                Some(webln) => webln.clone(),
+                None => return,
+            };
+            let context = context.clone();
+            let state = state.clone();
+            spawn_local(async move {
+                match webln.enable().await {
+                    Ok(()) => context.refresh(),
+                    Err(e) => state.set(Status::Failed(Rc::new(e))),
+                }
+            });
+        })
+    };
+
+    UseWebLNEnabledHandle {
+        status: (*state).clone(),
+        enable,
+    }
+}
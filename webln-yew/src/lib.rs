@@ -0,0 +1,50 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! WebLN hooks and context provider for Yew
+//!
+//! Wrap the app in a [`WebLNProvider`]: it waits for the provider injection and re-renders its
+//! children when the account changes. The hooks must be used inside it.
+//!
+//! ```rust,no_run
+//! use webln_yew::prelude::*;
+//! use yew::prelude::*;
+//!
+//! #[function_component(Balance)]
+//! fn balance() -> Html {
+//!     match use_balance() {
+//!         Status::Loading => html! { "Loading..." },
+//!         Status::Ready(balance) => html! { format!("{} sats", balance.balance) },
+//!         Status::Failed(e) => html! { format!("Error: {e}") },
+//!     }
+//! }
+//!
+//! #[function_component(App)]
+//! fn app() -> Html {
+//!     html! {
+//!         <WebLNProvider>
+//!             <Balance />
+//!         </WebLNProvider>
+//!     }
+//! }
+//! ```
+
+#![warn(missing_docs)]
+#![warn(rustdoc::bare_urls)]
+
+pub use webln;
+
+mod button;
+mod context;
+mod hooks;
+pub mod prelude;
+mod status;
+
+pub use self::button::{
+    PayButton, PayButtonProps, PayButtonState, PaymentTarget, TipButton, TipButtonProps,
+};
+pub use self::context::{WebLNContext, WebLNProvider, WebLNProviderProps, DETECTION_TIMEOUT};
+pub use self::hooks::{
+    use_balance, use_webln, use_webln_context, use_webln_enabled, UseWebLNEnabledHandle,
+};
+pub use self::status::Status;
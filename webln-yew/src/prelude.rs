@@ -0,0 +1,9 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Prelude
+
+pub use crate::{
+    use_balance, use_webln, use_webln_context, use_webln_enabled, PayButton, PaymentTarget, Status,
+    TipButton, UseWebLNEnabledHandle, WebLNContext, WebLNProvider,
+};
@@ -0,0 +1,57 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Async status
+
+use std::rc::Rc;
+
+use webln::Error;
+
+/// Status of an async WebLN value
+#[derive(Debug, Clone)]
+pub enum Status<T> {
+    /// Not available yet
+    Loading,
+    /// Value
+    Ready(T),
+    /// Failed
+    Failed(Rc<Error>),
+}
+
+impl<T> Default for Status<T> {
+    fn default() -> Self {
+        Self::Loading
+    }
+}
+
+impl<T> Status<T> {
+    /// Check if still loading
+    pub fn is_loading(&self) -> bool {
+        matches!(self, Self::Loading)
+    }
+
+    /// Get the value, if ready
+    pub fn ready(&self) -> Option<&T> {
+        match self {
+            Self::Ready(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the error, if failed
+    pub fn error(&self) -> Option<&Error> {
+        match self {
+            Self::Failed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<T> From<Result<T, Error>> for Status<T> {
+    fn from(res: Result<T, Error>) -> Self {
+        match res {
+            Ok(value) => Self::Ready(value),
+            Err(e) => Self::Failed(Rc::new(e)),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use webln_yew::PaymentTarget;
+
+#[test]
+fn test_payment_target() {
+    assert_eq!(
+        PaymentTarget::from("lnbc10u1pjexample"),
+        PaymentTarget::Invoice(String::from("lnbc10u1pjexample"))
+    );
+    assert_eq!(
+        PaymentTarget::from(" lightning:LNURL1DP68GURN "),
+        PaymentTarget::Lnurl(String::from("LNURL1DP68GURN"))
+    );
+    assert_eq!(
+        PaymentTarget::from("LIGHTNING:lnbc1"),
+        PaymentTarget::Invoice(String::from("lnbc1"))
+    );
+    assert_eq!(
+        PaymentTarget::from("satoshi@example.com"),
+        PaymentTarget::LightningAddress(String::from("satoshi@example.com"))
+    );
+    assert_eq!(
+        PaymentTarget::from("é"),
+        PaymentTarget::Invoice(String::from("é"))
+    );
+}
@@ -0,0 +1,174 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Alby extensions, beyond the WebLN spec
+//!
+//! <https://guides.getalby.com/developer-guide/alby-browser-extension>
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::value::{expect_object, get_optional_string, get_optional_strings};
+use crate::{
+    Deserialize, Error, GetInfoMethod, GetInfoResponse, ParseMode, ResponseWithRaw, Value, WebLN,
+};
+
+/// `window` property of the Alby object
+const NAMESPACE: &str = "alby";
+const ADD_ACCOUNT: &str = "addAccount";
+/// `getInfo` version of the Alby extension
+const ALBY_VERSION: &str = "alby";
+
+/// Alby fields of the `getInfo` response
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct AlbyInfo {
+    /// Standard WebLN fields
+    pub info: GetInfoResponse,
+    /// Extension version (i.e. `Alby`)
+    pub version: String,
+    /// Supported protocols (i.e. `lightning`, `nostr`)
+    pub supports: Vec<String>,
+}
+
+impl AlbyInfo {
+    /// Check if the extension supports `protocol` (i.e. `nostr`)
+    pub fn supports(&self, protocol: &str) -> bool {
+        self.supports
+            .iter()
+            .any(|supported| supported.eq_ignore_ascii_case(protocol))
+    }
+}
+
+/// Account to add to the Alby extension
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct AddAccountArgs {
+    /// Account name shown to the user
+    pub name: String,
+    /// Connector (i.e. `nwc`, `lnd`)
+    pub connector: String,
+    /// Connector configuration (i.e. `nostrWalletConnectUrl`)
+    pub config: BTreeMap<String, String>,
+}
+
+impl AddAccountArgs {
+    /// New account without configuration
+    pub fn new<S, C>(name: S, connector: C) -> Self
+    where
+        S: Into<String>,
+        C: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            connector: connector.into(),
+            config: BTreeMap::new(),
+        }
+    }
+
+    /// Add a configuration value
+    pub fn config<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.config.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl TryFrom<&AddAccountArgs> for Object {
+    type Error = Error;
+
+    fn try_from(args: &AddAccountArgs) -> Result<Self, Self::Error> {
+        let config = Self::new();
+        for (key, value) in args.config.iter() {
+            Reflect::set(&config, &JsValue::from_str(key), &JsValue::from_str(value))?;
+        }
+
+        let obj = Self::new();
+        Reflect::set(&obj, &JsValue::from_str("name"), &args.name.as_str().into())?;
+        Reflect::set(
+            &obj,
+            &JsValue::from_str("connector"),
+            &args.connector.as_str().into(),
+        )?;
+        Reflect::set(&obj, &JsValue::from_str("config"), &config)?;
+        Ok(obj)
+    }
+}
+
+/// Alby object injected as `window.alby`, alongside `window.webln`
+#[derive(Debug, Clone)]
+pub struct Alby {
+    provider: WebLN,
+}
+
+impl Alby {
+    /// Compose new Alby instance from `window.alby`
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            provider: WebLN::from_namespace(NAMESPACE)?,
+        })
+    }
+
+    /// Compose new Alby instance from an object obtained elsewhere than `window.alby`
+    pub fn from_provider(provider: Object) -> Self {
+        Self {
+            provider: WebLN::from_provider(provider),
+        }
+    }
+
+    /// Check if the Alby extension is installed (`window.alby` is injected), without failing
+    pub fn is_available() -> bool {
+        Self::new().is_ok()
+    }
+
+    /// Request the user permission to use the extension
+    pub async fn enable(&self) -> Result<(), Error> {
+        self.provider.enable().await
+    }
+
+    /// Request the user to add an account to the extension (i.e. a NWC connection)
+    ///
+    /// Returns `false` if the user didn't add it.
+    pub async fn add_account(&self, args: &AddAccountArgs) -> Result<bool, Error> {
+        let args: Object = args.try_into()?;
+        self.provider
+            .call(GetInfoMethod::from(ADD_ACCOUNT), &[args.into()], |result| {
+                let result: Value = Value::from_js(&result);
+                Ok(expect_object(&result)?
+                    .get("success")
+                    .as_bool()
+                    .unwrap_or_default())
+            })
+            .await
+    }
+}
+
+impl Deserialize for AlbyInfo {
+    fn from_value(value: &Value, _mode: ParseMode) -> Result<Self, Error> {
+        let info_obj: &Value = expect_object(value)?;
+        Ok(Self {
+            info: GetInfoResponse::from_value(value, ParseMode::Lenient)?,
+            version: get_optional_string(info_obj, "version", ParseMode::Lenient)?
+                .unwrap_or_default(),
+            supports: get_optional_strings(info_obj, "supports", ParseMode::Lenient)?,
+        })
+    }
+}
+
+impl WebLN {
+    /// Get the Alby fields of `getInfo`: `None` if the provider isn't the Alby extension
+    pub async fn alby_info(&self) -> Result<Option<AlbyInfo>, Error> {
+        let info: ResponseWithRaw<GetInfoResponse> = self.get_info_with_raw().await?;
+        let alby: AlbyInfo = AlbyInfo::deserialize(&info.raw)?;
+        Ok(Some(alby).filter(|alby| alby.version.to_ascii_lowercase().starts_with(ALBY_VERSION)))
+    }
+}
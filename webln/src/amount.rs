@@ -0,0 +1,155 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Amount
+
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::str::FromStr;
+
+use crate::decimal_to_units;
+
+/// Millisatoshis per satoshi
+const MSAT_PER_SAT: u64 = 1_000;
+/// Millisatoshis per bitcoin
+const MSAT_PER_BTC: u64 = 100_000_000_000;
+
+/// Amount parsing error
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseAmountError {
+    /// Empty string
+    Empty,
+    /// Expected a non-negative decimal number (i.e. `21` or `0.001`)
+    InvalidNumber,
+    /// Unknown unit: expected `sats`, `msat` or `btc`
+    UnknownUnit(String),
+    /// More decimals than millisatoshi precision allows
+    TooPrecise,
+    /// Amount too large
+    Overflow,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseAmountError {}
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Empty amount"),
+            Self::InvalidNumber => write!(f, "Invalid number"),
+            Self::UnknownUnit(unit) => {
+                write!(f, "Unknown unit `{unit}`: expected `sats`, `msat` or `btc`")
+            }
+            Self::TooPrecise => write!(f, "Too precise: max precision is 1 msat"),
+            Self::Overflow => write!(f, "Amount too large"),
+        }
+    }
+}
+
+/// Decimals of the unit, relative to msat
+fn unit_scale(unit: &str) -> Option<u32> {
+    match unit.to_ascii_lowercase().as_str() {
+        "" | "sat" | "sats" | "satoshi" | "satoshis" => Some(3),
+        "msat" | "msats" | "millisatoshi" | "millisatoshis" => Some(0),
+        "btc" | "bitcoin" => Some(11),
+        _ => None,
+    }
+}
+
+/// Lightning amount, with millisatoshi precision
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Amount(u64);
+
+impl Amount {
+    /// Zero
+    pub const ZERO: Self = Self(0);
+
+    /// New amount from millisatoshis
+    pub const fn from_msat(msat: u64) -> Self {
+        Self(msat)
+    }
+
+    /// New amount from satoshis (saturating)
+    pub const fn from_sats(sats: u64) -> Self {
+        Self(sats.saturating_mul(MSAT_PER_SAT))
+    }
+
+    /// Amount in millisatoshis
+    pub const fn msat(&self) -> u64 {
+        self.0
+    }
+
+    /// Amount in satoshis, rounded down
+    pub const fn sats(&self) -> u64 {
+        self.0 / MSAT_PER_SAT
+    }
+
+    /// Amount in bitcoin (lossy, for display)
+    pub fn btc(&self) -> f64 {
+        self.0 as f64 / MSAT_PER_BTC as f64
+    }
+
+    /// Parse a human-entered amount
+    ///
+    /// A non-negative decimal number, optionally followed by a case-insensitive unit: `sats`
+    /// (the default), `msat` or `btc`.
+    ///
+    /// ```rust
+    /// use webln::Amount;
+    ///
+    /// assert_eq!(Amount::parse("21 sats").unwrap(), Amount::from_sats(21));
+    /// assert_eq!(
+    ///     Amount::parse("0.001 BTC").unwrap(),
+    ///     Amount::from_sats(100_000)
+    /// );
+    /// assert_eq!(Amount::parse("1500").unwrap(), Amount::from_sats(1500));
+    /// ```
+    pub fn parse(amount: &str) -> Result<Self, ParseAmountError> {
+        let amount: &str = amount.trim();
+        if amount.is_empty() {
+            return Err(ParseAmountError::Empty);
+        }
+
+        let split: usize = amount
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(amount.len());
+        let (number, unit) = amount.split_at(split);
+        let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+        if int.is_empty() || frac.contains('.') {
+            return Err(ParseAmountError::InvalidNumber);
+        }
+
+        let unit: &str = unit.trim();
+        let scale: u32 =
+            unit_scale(unit).ok_or_else(|| ParseAmountError::UnknownUnit(unit.to_string()))?;
+        if frac.trim_end_matches('0').len() > scale as usize {
+            return Err(ParseAmountError::TooPrecise);
+        }
+
+        decimal_to_units(number, scale)
+            .map(Self)
+            .ok_or(ParseAmountError::Overflow)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    /// See [`Amount::parse`]
+    fn from_str(amount: &str) -> Result<Self, Self::Err> {
+        Self::parse(amount)
+    }
+}
+
+impl fmt::Display for Amount {
+    /// `21 sats`, or `21500 msat` when not a whole number of sats
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 % MSAT_PER_SAT == 0 {
+            write!(f, "{} sats", self.sats())
+        } else {
+            write!(f, "{} msat", self.0)
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Login with Lightning
+
+use alloc::string::String;
+#[cfg(feature = "secp256k1")]
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+#[cfg(feature = "secp256k1")]
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+#[cfg(feature = "secp256k1")]
+use secp256k1::hashes::{sha256d, Hash};
+#[cfg(feature = "secp256k1")]
+use secp256k1::{Message, PublicKey, Secp256k1};
+
+#[cfg(feature = "secp256k1")]
+use crate::zbase32;
+use crate::{Error, GetInfoResponse, NodeId, SignMessageResponse, WebLN};
+
+/// Prefix of the messages signed by LND `signmessage` (and most providers)
+#[cfg(feature = "secp256k1")]
+const SIGNED_MESSAGE_PREFIX: &[u8] = b"Lightning Signed Message:";
+
+/// Proof that the user controls a node, returned by [`WebLN::login`]
+///
+/// Send it to the server, which checks it with [`LoginProof::verify`] against the challenge it
+/// issued.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct LoginProof {
+    /// Node public key, from `getInfo`
+    pub pubkey: NodeId,
+    /// Signed challenge
+    pub message: String,
+    /// Signature, as returned by `signMessage` (zbase32, LND format)
+    pub signature: String,
+    /// Signing time (UNIX timestamp, secs)
+    ///
+    /// Informational only: it's not covered by the signature, so expire the challenges instead.
+    pub timestamp: u64,
+}
+
+impl LoginProof {
+    /// New login proof (i.e. received by the server)
+    pub fn new<S, T>(pubkey: NodeId, message: S, signature: T, timestamp: u64) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        Self {
+            pubkey,
+            message: message.into(),
+            signature: signature.into(),
+            timestamp,
+        }
+    }
+
+    /// Check that the proof is for `challenge` and signed by [`LoginProof::pubkey`]
+    ///
+    /// The public key is recovered from the signature, so no node lookup is needed.
+    #[cfg(feature = "secp256k1")]
+    pub fn verify(&self, challenge: &str) -> Result<(), Error> {
+        if self.message != challenge {
+            return Err(Error::InvalidSignature);
+        }
+        let recovered: PublicKey = recover(&self.message, &self.signature)?;
+        if recovered.serialize() == *self.pubkey.as_bytes() {
+            Ok(())
+        } else {
+            Err(Error::InvalidSignature)
+        }
+    }
+}
+
+/// Recover the public key of an LND `signmessage` signature
+#[cfg(feature = "secp256k1")]
+fn recover(message: &str, signature: &str) -> Result<PublicKey, Error> {
+    let signature: Vec<u8> = zbase32::decode(signature).ok_or(Error::InvalidSignature)?;
+    if signature.len() != 65 {
+        return Err(Error::InvalidSignature);
+    }
+
+    // Header byte: 27 + recovery ID (+ 4 for compressed keys)
+    let recovery_id: i32 = match signature[0] {
+        header @ 27..=34 => i32::from((header - 27) % 4),
+        _ => return Err(Error::InvalidSignature),
+    };
+    let signature =
+        RecoverableSignature::from_compact(&signature[1..], RecoveryId::from_i32(recovery_id)?)?;
+
+    let mut data: Vec<u8> = Vec::with_capacity(SIGNED_MESSAGE_PREFIX.len() + message.len());
+    data.extend_from_slice(SIGNED_MESSAGE_PREFIX);
+    data.extend_from_slice(message.as_bytes());
+    let digest = Message::from_digest(sha256d::Hash::hash(&data).to_byte_array());
+
+    Ok(Secp256k1::verification_only().recover_ecdsa(&digest, &signature)?)
+}
+
+impl WebLN {
+    /// Login with Lightning: sign a server-issued challenge, proving that the user controls the node
+    ///
+    /// The node public key is read from `getInfo`.
+    ///
+    /// ```rust,no_run
+    /// use webln::WebLN;
+    ///
+    /// # async fn run(challenge: &str) -> Result<(), webln::Error> {
+    /// let webln = WebLN::new()?;
+    /// webln.enable().await?;
+    /// let proof = webln.login(challenge).await?;
+    /// // Send the proof to the server, which calls `proof.verify(challenge)`
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn login(&self, challenge: &str) -> Result<LoginProof, Error> {
+        let info: GetInfoResponse = self.get_info().await?;
+        let pubkey: NodeId = match info.node.pubkey.as_deref() {
+            Some(pubkey) => NodeId::from_str(pubkey)?,
+            None => return Err(Error::ObjectKeyNotFound(String::from("pubkey"))),
+        };
+
+        let res: SignMessageResponse = self.sign_message(challenge).await?;
+        Ok(LoginProof::new(
+            pubkey,
+            res.message,
+            res.signature,
+            self.clock().now().as_secs(),
+        ))
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Balance watcher
+
+#[cfg(feature = "stream")]
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+use core::{fmt, future};
+
+use crate::time::Poller;
+use crate::{BalanceResponse, Error, WebLN};
+
+/// Poll `getBalance` at a fixed interval, yielding only the changes
+///
+/// The first call yields the current balance immediately. Errors are yielded too, without
+/// stopping the watcher. With the `stream` feature, it also implements `futures_core::Stream`.
+///
+/// ```rust,no_run
+/// use core::time::Duration;
+///
+/// use webln::{BalanceWatcher, WebLN};
+///
+/// # async fn run() -> Result<(), webln::Error> {
+/// let webln = WebLN::new()?;
+/// let mut watcher = BalanceWatcher::new(webln, Duration::from_secs(10));
+/// loop {
+///     let balance = watcher.next().await?;
+///     println!("Balance: {}", balance.balance);
+/// }
+/// # }
+/// ```
+pub struct BalanceWatcher {
+    webln: WebLN,
+    poller: Poller<BalanceResponse>,
+}
+
+impl fmt::Debug for BalanceWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BalanceWatcher")
+            .field("interval", &self.poller.interval)
+            .field("last", &self.poller.last())
+            .finish()
+    }
+}
+
+impl BalanceWatcher {
+    /// New balance watcher, polling every `interval`
+    pub fn new(webln: WebLN, interval: Duration) -> Self {
+        Self {
+            webln,
+            poller: Poller::new(interval),
+        }
+    }
+
+    /// Last yielded balance
+    pub fn last(&self) -> Option<&BalanceResponse> {
+        self.poller.last()
+    }
+
+    /// Wait for the next balance change
+    pub async fn next(&mut self) -> Result<BalanceResponse, Error> {
+        future::poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Result<BalanceResponse, Error>> {
+        let webln: WebLN = self.webln.clone();
+        self.poller.poll_change(cx, &self.webln, move || {
+            let webln: WebLN = webln.clone();
+            async move { webln.get_balance().await }
+        })
+    }
+}
+
+#[cfg(feature = "stream")]
+impl futures_core::Stream for BalanceWatcher {
+    type Item = Result<BalanceResponse, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_tick(cx).map(Some)
+    }
+}
+
+impl WebLN {
+    /// Watch the balance, polling every `interval`
+    ///
+    /// See [`BalanceWatcher`].
+    pub fn watch_balance(&self, interval: Duration) -> BalanceWatcher {
+        BalanceWatcher::new(self.clone(), interval)
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Base64
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes as standard, padded base64
+pub(crate) fn encode<T>(data: T) -> String
+where
+    T: AsRef<[u8]>,
+{
+    let data: &[u8] = data.as_ref();
+    let mut base64: String = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let b0: u32 = chunk[0] as u32;
+        let b1: u32 = chunk.get(1).copied().unwrap_or_default() as u32;
+        let b2: u32 = chunk.get(2).copied().unwrap_or_default() as u32;
+        let n: u32 = b0 << 16 | b1 << 8 | b2;
+
+        base64.push(CHARS[(n >> 18) as usize & 0x3f] as char);
+        base64.push(CHARS[(n >> 12) as usize & 0x3f] as char);
+        if chunk.len() > 1 {
+            base64.push(CHARS[(n >> 6) as usize & 0x3f] as char);
+        } else {
+            base64.push('=');
+        }
+        if chunk.len() > 2 {
+            base64.push(CHARS[n as usize & 0x3f] as char);
+        } else {
+            base64.push('=');
+        }
+    }
+    base64
+}
+
+fn val(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+        b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    }
+}
+
+/// Decode standard or URL-safe base64, with or without padding
+pub(crate) fn decode(base64: &str) -> Option<Vec<u8>> {
+    let base64: &[u8] = base64.trim_end_matches('=').as_bytes();
+    if base64.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut data: Vec<u8> = Vec::with_capacity(base64.len() * 3 / 4);
+    for chunk in base64.chunks(4) {
+        let mut n: u32 = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            n |= val(*c)? << (18 - 6 * i);
+        }
+        data.push((n >> 16) as u8);
+        if chunk.len() > 2 {
+            data.push((n >> 8) as u8);
+        }
+        if chunk.len() > 3 {
+            data.push(n as u8);
+        }
+    }
+    Some(data)
+}
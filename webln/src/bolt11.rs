@@ -0,0 +1,324 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Lightweight BOLT11 parser
+//!
+//! Decodes the fields useful before paying: the signature is not verified (the wallet does it).
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{hex, Amount, NodeId};
+
+/// Bech32 charset
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+/// Bech32 checksum generator
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+const CHECKSUM_WORDS: usize = 6;
+const TIMESTAMP_WORDS: usize = 7;
+/// 65 bytes recoverable signature
+const SIGNATURE_WORDS: usize = 104;
+/// Words of 32 bytes hashes (`p` and `h` fields)
+const HASH_WORDS: u16 = 52;
+/// Words of 33 bytes public keys (`n` field)
+const NODE_ID_WORDS: u16 = 53;
+/// Expiry when the invoice doesn't have the `x` field
+const DEFAULT_EXPIRY: u64 = 3600;
+/// `lightning:` URI scheme
+const LIGHTNING_SCHEME: &str = "lightning:";
+/// BOLT12 offers prefix
+const OFFER_PREFIX: &str = "lno1";
+
+const TAG_PAYMENT_HASH: u8 = 1;
+const TAG_EXPIRY: u8 = 6;
+const TAG_DESCRIPTION: u8 = 13;
+const TAG_PAYEE: u8 = 19;
+const TAG_DESCRIPTION_HASH: u8 = 23;
+
+/// BOLT11 invoice parsing error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseInvoiceError {
+    /// Invalid bech32 encoding
+    InvalidBech32,
+    /// Invalid bech32 checksum
+    InvalidChecksum,
+    /// Expected `ln` followed by a currency prefix (i.e. `lnbc`)
+    InvalidPrefix,
+    /// Invalid amount in the human-readable part
+    InvalidAmount,
+    /// Data too short or tagged field out of bounds
+    InvalidLength,
+    /// Description is not valid UTF-8
+    InvalidDescription,
+    /// Missing payment hash
+    MissingPaymentHash,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseInvoiceError {}
+
+impl fmt::Display for ParseInvoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidBech32 => write!(f, "Invalid bech32"),
+            Self::InvalidChecksum => write!(f, "Invalid checksum"),
+            Self::InvalidPrefix => write!(f, "Invalid prefix: expected `ln` + currency"),
+            Self::InvalidAmount => write!(f, "Invalid amount"),
+            Self::InvalidLength => write!(f, "Invalid length"),
+            Self::InvalidDescription => write!(f, "Invalid description: expected UTF-8"),
+            Self::MissingPaymentHash => write!(f, "Missing payment hash"),
+        }
+    }
+}
+
+/// Strip the `lightning:` URI scheme, if any
+fn strip_scheme(s: &str) -> &str {
+    let s: &str = s.trim();
+    match s.get(..LIGHTNING_SCHEME.len()) {
+        Some(scheme) if scheme.eq_ignore_ascii_case(LIGHTNING_SCHEME) => {
+            &s[LIGHTNING_SCHEME.len()..]
+        }
+        _ => s,
+    }
+}
+
+/// Check if `s` is a BOLT12 offer (`lno1...`), which `sendPayment` can't pay
+pub(crate) fn is_bolt12_offer(s: &str) -> bool {
+    strip_scheme(s)
+        .get(..OFFER_PREFIX.len())
+        .map_or(false, |prefix| prefix.eq_ignore_ascii_case(OFFER_PREFIX))
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut chk: u32 = 1;
+    for value in values.iter() {
+        let top: u32 = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ *value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+/// Decode bech32 (without the 90 chars limit), returning the HRP and the 5-bit words
+fn decode_bech32(s: &str) -> Result<(String, Vec<u8>), ParseInvoiceError> {
+    let has_lower: bool = s.bytes().any(|b| b.is_ascii_lowercase());
+    let has_upper: bool = s.bytes().any(|b| b.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(ParseInvoiceError::InvalidBech32);
+    }
+
+    let s: String = s.to_ascii_lowercase();
+    let (hrp, data) = s.rsplit_once('1').ok_or(ParseInvoiceError::InvalidBech32)?;
+    if hrp.is_empty() || data.len() < CHECKSUM_WORDS {
+        return Err(ParseInvoiceError::InvalidBech32);
+    }
+    // BIP-173: HRP characters are in the 33-126 ASCII range
+    if !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return Err(ParseInvoiceError::InvalidBech32);
+    }
+
+    let words: Vec<u8> = data
+        .bytes()
+        .map(|c| CHARSET.iter().position(|x| *x == c).map(|p| p as u8))
+        .collect::<Option<_>>()
+        .ok_or(ParseInvoiceError::InvalidBech32)?;
+
+    let mut values: Vec<u8> = Vec::with_capacity(hrp.len() * 2 + 1 + words.len());
+    values.extend(hrp.bytes().map(|b| b >> 5));
+    values.push(0);
+    values.extend(hrp.bytes().map(|b| b & 0x1f));
+    values.extend_from_slice(&words);
+    if polymod(&values) != 1 {
+        return Err(ParseInvoiceError::InvalidChecksum);
+    }
+
+    let len: usize = words.len() - CHECKSUM_WORDS;
+    Ok((hrp.to_string(), words[..len].to_vec()))
+}
+
+/// Convert 5-bit words to bytes, dropping the padding
+fn words_to_bytes(words: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(words.len() * 5 / 8);
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    for word in words.iter() {
+        acc = acc << 5 | *word as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    bytes
+}
+
+/// Big-endian integer from 5-bit words (saturating)
+fn words_to_u64(words: &[u8]) -> u64 {
+    words
+        .iter()
+        .fold(0u64, |acc, word| acc.saturating_mul(32) | *word as u64)
+}
+
+/// Parse the HRP amount (i.e. `2500u`)
+fn parse_amount(amount: &str) -> Result<Option<Amount>, ParseInvoiceError> {
+    if amount.is_empty() {
+        return Ok(None);
+    }
+
+    let mut chars = amount.chars();
+    let (digits, multiplier) = match chars.next_back() {
+        Some(m) if !m.is_ascii_digit() => (chars.as_str(), Some(m)),
+        _ => (amount, None),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseInvoiceError::InvalidAmount);
+    }
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| ParseInvoiceError::InvalidAmount)?;
+
+    let msat: Option<u64> = match multiplier {
+        None => value.checked_mul(100_000_000_000),
+        Some('m') => value.checked_mul(100_000_000),
+        Some('u') => value.checked_mul(100_000),
+        Some('n') => value.checked_mul(100),
+        // Pico-BTC: must be a whole number of msat
+        Some('p') => (value % 10 == 0).then_some(value / 10),
+        Some(..) => None,
+    };
+    msat.map(|msat| Some(Amount::from_msat(msat)))
+        .ok_or(ParseInvoiceError::InvalidAmount)
+}
+
+/// Decoded BOLT11 invoice
+///
+/// Only the fields useful before paying are decoded, and the signature is not verified.
+///
+/// ```rust
+/// use webln::Bolt11Invoice;
+///
+/// let invoice = Bolt11Invoice::parse("lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp").unwrap();
+/// assert_eq!(invoice.description.as_deref(), Some("1 cup coffee"));
+/// assert_eq!(invoice.amount.unwrap().sats(), 250_000);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Bolt11Invoice {
+    /// Currency prefix (`bc` mainnet, `tb` testnet, `tbs` signet, `bcrt` regtest)
+    pub currency: String,
+    /// Amount (`None` for zero-amount invoices)
+    pub amount: Option<Amount>,
+    /// Creation time (UNIX timestamp)
+    pub timestamp: u64,
+    /// Payment hash (hex)
+    pub payment_hash: String,
+    /// Description (memo)
+    pub description: Option<String>,
+    /// SHA256 of the description (hex), when it's too long to be embedded
+    pub description_hash: Option<String>,
+    /// Expiry, in secs after [`Bolt11Invoice::timestamp`]
+    pub expiry: u64,
+    /// Payee node ID, if explicit
+    pub payee: Option<NodeId>,
+}
+
+impl Bolt11Invoice {
+    /// Parse a BOLT11 invoice (a `lightning:` URI prefix is accepted)
+    pub fn parse(invoice: &str) -> Result<Self, ParseInvoiceError> {
+        let (hrp, words) = decode_bech32(strip_scheme(invoice))?;
+        let hrp: &str = hrp
+            .strip_prefix("ln")
+            .ok_or(ParseInvoiceError::InvalidPrefix)?;
+        let split: usize = hrp.find(|c: char| c.is_ascii_digit()).unwrap_or(hrp.len());
+        let (currency, amount) = hrp.split_at(split);
+        if currency.is_empty() {
+            return Err(ParseInvoiceError::InvalidPrefix);
+        }
+        let amount: Option<Amount> = parse_amount(amount)?;
+
+        if words.len() < TIMESTAMP_WORDS + SIGNATURE_WORDS {
+            return Err(ParseInvoiceError::InvalidLength);
+        }
+        let timestamp: u64 = words_to_u64(&words[..TIMESTAMP_WORDS]);
+        let mut fields: &[u8] = &words[TIMESTAMP_WORDS..words.len() - SIGNATURE_WORDS];
+
+        let mut payment_hash: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut description_hash: Option<String> = None;
+        let mut expiry: Option<u64> = None;
+        let mut payee: Option<NodeId> = None;
+
+        while !fields.is_empty() {
+            if fields.len() < 3 {
+                return Err(ParseInvoiceError::InvalidLength);
+            }
+            let tag: u8 = fields[0];
+            let len: u16 = (fields[1] as u16) << 5 | fields[2] as u16;
+            let data: &[u8] = fields
+                .get(3..3 + len as usize)
+                .ok_or(ParseInvoiceError::InvalidLength)?;
+            fields = &fields[3 + len as usize..];
+
+            // Readers must skip fields with unexpected lengths, and use the first known ones
+            match tag {
+                TAG_PAYMENT_HASH if len == HASH_WORDS && payment_hash.is_none() => {
+                    payment_hash = Some(hex::encode(words_to_bytes(data)));
+                }
+                TAG_DESCRIPTION if description.is_none() => {
+                    let bytes: Vec<u8> = words_to_bytes(data);
+                    description = Some(
+                        String::from_utf8(bytes)
+                            .map_err(|_| ParseInvoiceError::InvalidDescription)?,
+                    );
+                }
+                TAG_DESCRIPTION_HASH if len == HASH_WORDS && description_hash.is_none() => {
+                    description_hash = Some(hex::encode(words_to_bytes(data)));
+                }
+                TAG_EXPIRY if expiry.is_none() => expiry = Some(words_to_u64(data)),
+                TAG_PAYEE if len == NODE_ID_WORDS && payee.is_none() => {
+                    payee = NodeId::from_slice(&words_to_bytes(data)).ok();
+                }
+                _ => (),
+            }
+        }
+
+        Ok(Self {
+            currency: currency.to_string(),
+            amount,
+            timestamp,
+            payment_hash: payment_hash.ok_or(ParseInvoiceError::MissingPaymentHash)?,
+            description,
+            description_hash,
+            expiry: expiry.unwrap_or(DEFAULT_EXPIRY),
+            payee,
+        })
+    }
+
+    /// Check if the payer chooses the amount
+    ///
+    /// Wallets prompt for the amount: see [`crate::WebLN::send_payment_with_amount`].
+    pub fn is_zero_amount(&self) -> bool {
+        self.amount.is_none()
+    }
+
+    /// Expiration time (UNIX timestamp)
+    pub fn expires_at(&self) -> u64 {
+        self.timestamp.saturating_add(self.expiry)
+    }
+}
+
+impl FromStr for Bolt11Invoice {
+    type Err = ParseInvoiceError;
+
+    /// See [`Bolt11Invoice::parse`]
+    fn from_str(invoice: &str) -> Result<Self, Self::Err> {
+        Self::parse(invoice)
+    }
+}
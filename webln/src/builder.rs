@@ -0,0 +1,227 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! WebLN builder
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use js_sys::Object;
+
+#[cfg(feature = "journal")]
+use crate::Journal;
+use crate::{
+    Amount, Clock, DestinationFilter, Error, ParseMode, PromptLimit, RejectionMatcher, WebLN,
+};
+
+/// Where to find the provider
+#[derive(Debug, Clone)]
+enum Provider {
+    Namespace(String),
+    Object(Object),
+}
+
+/// Paid invoices memory
+#[derive(Debug, Clone)]
+enum PaidInvoiceMemory {
+    InMemory,
+    Persisted(String),
+}
+
+/// [`WebLN`] builder, to configure the client in one place
+///
+/// Each option matches a `WebLN::with_*` method, see their docs for the details.
+///
+/// Only the client-wide options are here. Timeouts, retries and polling intervals are set where
+/// they apply: [`MultiPaymentOptions::timeout`](crate::MultiPaymentOptions::timeout),
+/// [`Checkout::retry_delay`](crate::Checkout::retry_delay) and
+/// [`PaymentTracker::interval`](crate::PaymentTracker::interval). The provider state cache has no
+/// TTL: it's refreshed on `accountChanged` (the JS bindings have a `getInfo` TTL). There are no
+/// request interceptors nor spending budget: use [`WebLNBuilder::max_amount`] and
+/// [`WebLNBuilder::prompt_limit`] to bound the payments.
+///
+/// ```rust,no_run
+/// use webln::{Amount, PromptLimit, WebLN};
+/// # use core::time::Duration;
+///
+/// # fn run() -> Result<(), webln::Error> {
+/// let webln: WebLN = WebLN::builder()
+///     .persistence("webln")
+///     .max_amount(Amount::from_sats(10_000))
+///     .prompt_limit(PromptLimit::new(3, Duration::from_secs(60)))
+///     .keysend_fallback(true)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WebLNBuilder {
+    provider: Option<Provider>,
+    persistence: Option<String>,
+    clock: Option<Rc<dyn Clock>>,
+    prompt_limit: Option<PromptLimit>,
+    rejection_matchers: Vec<RejectionMatcher>,
+    max_amount: Option<Amount>,
+    keysend_filter: Option<DestinationFilter>,
+    keysend_fallback: bool,
+    make_invoice_fallback: bool,
+    parse_mode: ParseMode,
+    paid_invoice_memory: Option<PaidInvoiceMemory>,
+    #[cfg(feature = "journal")]
+    journal: Option<Journal>,
+}
+
+impl WebLNBuilder {
+    /// New builder, for the provider injected as `window.webln`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the provider injected as `window.<namespace>` (see [`WebLN::from_namespace`])
+    pub fn namespace<S>(mut self, namespace: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.provider = Some(Provider::Namespace(namespace.into()));
+        self
+    }
+
+    /// Use a provider object obtained elsewhere (see [`WebLN::from_provider`])
+    pub fn provider(mut self, provider: Object) -> Self {
+        self.provider = Some(Provider::Object(provider));
+        self
+    }
+
+    /// See [`WebLN::with_persistence`]
+    pub fn persistence<S>(mut self, key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.persistence = Some(key.into());
+        self
+    }
+
+    /// See [`WebLN::with_clock`]
+    pub fn clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// See [`WebLN::with_prompt_limit`]
+    pub fn prompt_limit(mut self, limit: PromptLimit) -> Self {
+        self.prompt_limit = Some(limit);
+        self
+    }
+
+    /// See [`WebLN::with_rejection_matcher`]
+    pub fn rejection_matcher(mut self, matcher: RejectionMatcher) -> Self {
+        self.rejection_matchers.push(matcher);
+        self
+    }
+
+    /// See [`WebLN::with_max_amount`]
+    pub fn max_amount(mut self, amount: Amount) -> Self {
+        self.max_amount = Some(amount);
+        self
+    }
+
+    /// See [`WebLN::with_keysend_filter`]
+    pub fn keysend_filter(mut self, filter: DestinationFilter) -> Self {
+        self.keysend_filter = Some(filter);
+        self
+    }
+
+    /// See [`WebLN::with_keysend_fallback`]
+    pub fn keysend_fallback(mut self, enable: bool) -> Self {
+        self.keysend_fallback = enable;
+        self
+    }
+
+    /// See [`WebLN::with_make_invoice_fallback`]
+    pub fn make_invoice_fallback(mut self, enable: bool) -> Self {
+        self.make_invoice_fallback = enable;
+        self
+    }
+
+    /// See [`WebLN::with_parse_mode`]
+    pub fn parse_mode(mut self, mode: ParseMode) -> Self {
+        self.parse_mode = mode;
+        self
+    }
+
+    /// See [`WebLN::with_paid_invoice_memory`]
+    pub fn paid_invoice_memory(mut self) -> Self {
+        self.paid_invoice_memory = Some(PaidInvoiceMemory::InMemory);
+        self
+    }
+
+    /// See [`WebLN::with_persisted_paid_invoice_memory`]
+    pub fn persisted_paid_invoice_memory<S>(mut self, key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.paid_invoice_memory = Some(PaidInvoiceMemory::Persisted(key.into()));
+        self
+    }
+
+    /// See [`WebLN::with_journal`]
+    #[cfg(feature = "journal")]
+    pub fn journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// Build the client
+    ///
+    /// Fails like [`WebLN::from_namespace`] if the provider is not injected.
+    pub fn build(self) -> Result<WebLN, Error> {
+        let mut webln: WebLN = match self.provider {
+            Some(Provider::Namespace(namespace)) => WebLN::from_namespace(&namespace)?,
+            Some(Provider::Object(provider)) => WebLN::from_provider(provider),
+            None => WebLN::new()?,
+        };
+
+        if let Some(key) = self.persistence {
+            webln = webln.with_persistence(key);
+        }
+        if let Some(clock) = self.clock {
+            webln = webln.with_clock(clock);
+        }
+        if let Some(limit) = self.prompt_limit {
+            webln = webln.with_prompt_limit(limit);
+        }
+        for matcher in self.rejection_matchers.into_iter() {
+            webln = webln.with_rejection_matcher(matcher);
+        }
+        if let Some(amount) = self.max_amount {
+            webln = webln.with_max_amount(amount);
+        }
+        if let Some(filter) = self.keysend_filter {
+            webln = webln.with_keysend_filter(filter);
+        }
+        match self.paid_invoice_memory {
+            Some(PaidInvoiceMemory::InMemory) => webln = webln.with_paid_invoice_memory(),
+            Some(PaidInvoiceMemory::Persisted(key)) => {
+                webln = webln.with_persisted_paid_invoice_memory(key)
+            }
+            None => {}
+        }
+        #[cfg(feature = "journal")]
+        if let Some(journal) = self.journal {
+            webln = webln.with_journal(journal);
+        }
+
+        Ok(webln
+            .with_keysend_fallback(self.keysend_fallback)
+            .with_make_invoice_fallback(self.make_invoice_fallback)
+            .with_parse_mode(self.parse_mode))
+    }
+}
+
+impl WebLN {
+    /// Configure a new instance, see [`WebLNBuilder`]
+    pub fn builder() -> WebLNBuilder {
+        WebLNBuilder::new()
+    }
+}
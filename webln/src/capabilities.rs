@@ -0,0 +1,172 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Capabilities
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+
+use crate::{GetInfoMethod, GetInfoResponse};
+
+/// Known methods, ordered by bit index
+const KNOWN: [GetInfoMethod; 15] = [
+    GetInfoMethod::IsEnabled,
+    GetInfoMethod::Enable,
+    GetInfoMethod::GetInfo,
+    GetInfoMethod::Keysend,
+    GetInfoMethod::MakeInvoice,
+    GetInfoMethod::SendPayment,
+    GetInfoMethod::SendPaymentAsync,
+    GetInfoMethod::SignMessage,
+    GetInfoMethod::VerifyMessage,
+    GetInfoMethod::Request,
+    GetInfoMethod::Lnurl,
+    GetInfoMethod::On,
+    GetInfoMethod::Off,
+    GetInfoMethod::GetBalance,
+    GetInfoMethod::SendMultiPayment,
+];
+
+fn bit(method: &GetInfoMethod) -> Option<u32> {
+    let index: u32 = match method {
+        GetInfoMethod::IsEnabled => 0,
+        GetInfoMethod::Enable => 1,
+        GetInfoMethod::GetInfo => 2,
+        GetInfoMethod::Keysend => 3,
+        GetInfoMethod::MakeInvoice => 4,
+        GetInfoMethod::SendPayment => 5,
+        GetInfoMethod::SendPaymentAsync => 6,
+        GetInfoMethod::SignMessage => 7,
+        GetInfoMethod::VerifyMessage => 8,
+        GetInfoMethod::Request => 9,
+        GetInfoMethod::Lnurl => 10,
+        GetInfoMethod::On => 11,
+        GetInfoMethod::Off => 12,
+        GetInfoMethod::GetBalance => 13,
+        GetInfoMethod::SendMultiPayment => 14,
+        GetInfoMethod::Other(..) => return None,
+    };
+    Some(1 << index)
+}
+
+/// Set of methods supported by a provider
+///
+/// Known methods are stored as bit flags, [`GetInfoMethod::Other`] in an overflow set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Capabilities {
+    known: u32,
+    other: BTreeSet<String>,
+}
+
+impl Capabilities {
+    /// New empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set of all the methods known by this crate
+    pub fn all() -> Self {
+        KNOWN.iter().cloned().collect()
+    }
+
+    /// Add a method. Returns `false` if it was already present.
+    pub fn insert(&mut self, method: GetInfoMethod) -> bool {
+        match bit(&method) {
+            Some(bit) => {
+                let inserted: bool = self.known & bit == 0;
+                self.known |= bit;
+                inserted
+            }
+            None => match method {
+                GetInfoMethod::Other(other) => self.other.insert(other),
+                _ => false,
+            },
+        }
+    }
+
+    /// Remove a method. Returns `true` if it was present.
+    pub fn remove(&mut self, method: &GetInfoMethod) -> bool {
+        match (bit(method), method) {
+            (Some(bit), _) => {
+                let removed: bool = self.known & bit != 0;
+                self.known &= !bit;
+                removed
+            }
+            (None, GetInfoMethod::Other(other)) => self.other.remove(other),
+            (None, _) => false,
+        }
+    }
+
+    /// Check if method is supported
+    pub fn contains(&self, method: &GetInfoMethod) -> bool {
+        match (bit(method), method) {
+            (Some(bit), _) => self.known & bit != 0,
+            (None, GetInfoMethod::Other(other)) => self.other.contains(other),
+            (None, _) => false,
+        }
+    }
+
+    /// Number of methods
+    pub fn len(&self) -> usize {
+        self.known.count_ones() as usize + self.other.len()
+    }
+
+    /// Check if set is empty
+    pub fn is_empty(&self) -> bool {
+        self.known == 0 && self.other.is_empty()
+    }
+
+    /// Methods supported by `self` or `other`
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            known: self.known | other.known,
+            other: self.other.union(&other.other).cloned().collect(),
+        }
+    }
+
+    /// Methods supported by both `self` and `other`
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            known: self.known & other.known,
+            other: self.other.intersection(&other.other).cloned().collect(),
+        }
+    }
+
+    /// Iterate methods: known ones first, then [`GetInfoMethod::Other`] in lexicographic order
+    pub fn iter(&self) -> impl Iterator<Item = GetInfoMethod> + '_ {
+        KNOWN
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.known & (1 << index) != 0)
+            .map(|(_, method)| method.clone())
+            .chain(self.other.iter().cloned().map(GetInfoMethod::Other))
+    }
+}
+
+impl Extend<GetInfoMethod> for Capabilities {
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = GetInfoMethod>,
+    {
+        for method in iter {
+            self.insert(method);
+        }
+    }
+}
+
+impl FromIterator<GetInfoMethod> for Capabilities {
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = GetInfoMethod>,
+    {
+        let mut capabilities = Self::new();
+        capabilities.extend(iter);
+        capabilities
+    }
+}
+
+impl From<&GetInfoResponse> for Capabilities {
+    fn from(info: &GetInfoResponse) -> Self {
+        info.methods.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,374 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Checkout state machine
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::cell::RefCell;
+use core::fmt;
+use core::future::Future;
+use core::time::Duration;
+
+use crate::{Amount, Bolt11Invoice, Error, ErrorCode, InvoiceFuture, WebLN};
+
+/// Default [`Checkout::max_attempts`]
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// Default [`Checkout::retry_delay`]
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// State of a [`Checkout`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum CheckoutState {
+    /// Waiting for the invoice
+    Created,
+    /// Invoice ready to be paid
+    InvoiceReady {
+        /// Invoice
+        invoice: String,
+        /// Failed payment attempts
+        attempts: u32,
+    },
+    /// Payment in flight
+    Paying {
+        /// Invoice
+        invoice: String,
+        /// Attempt number, starting from 1
+        attempt: u32,
+    },
+    /// Paid (final)
+    Paid {
+        /// Invoice
+        invoice: String,
+        /// Preimage
+        preimage: String,
+    },
+    /// The invoice expired before being paid (final)
+    Expired {
+        /// Invoice
+        invoice: String,
+    },
+    /// The invoice couldn't be obtained, or the last attempt failed (final)
+    Failed {
+        /// Invoice, if obtained
+        invoice: Option<String>,
+        /// Error category
+        code: ErrorCode,
+        /// Error message
+        reason: String,
+    },
+    /// Cancelled by the user or by the app (final)
+    Cancelled,
+}
+
+impl CheckoutState {
+    /// Check if the checkout is over
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Self::Paid { .. } | Self::Expired { .. } | Self::Failed { .. } | Self::Cancelled
+        )
+    }
+
+    /// Invoice, if obtained
+    pub fn invoice(&self) -> Option<&str> {
+        match self {
+            Self::InvoiceReady { invoice, .. }
+            | Self::Paying { invoice, .. }
+            | Self::Paid { invoice, .. }
+            | Self::Expired { invoice } => Some(invoice),
+            Self::Failed { invoice, .. } => invoice.as_deref(),
+            Self::Created | Self::Cancelled => None,
+        }
+    }
+
+    /// Next state after `event`, or `None` if `event` is not allowed in this state
+    pub fn apply(&self, event: &CheckoutEvent) -> Option<Self> {
+        let next: Self = match (self, event) {
+            (Self::Created, CheckoutEvent::InvoiceReceived(invoice)) => Self::InvoiceReady {
+                invoice: invoice.clone(),
+                attempts: 0,
+            },
+            (Self::InvoiceReady { invoice, attempts }, CheckoutEvent::PaymentStarted) => {
+                Self::Paying {
+                    invoice: invoice.clone(),
+                    attempt: attempts + 1,
+                }
+            }
+            // Also from `InvoiceReady`: paid out of band (i.e. reported by the backend)
+            (
+                Self::InvoiceReady { invoice, .. } | Self::Paying { invoice, .. },
+                CheckoutEvent::PaymentSucceeded { preimage },
+            ) => Self::Paid {
+                invoice: invoice.clone(),
+                preimage: preimage.clone(),
+            },
+            (
+                Self::Paying { invoice, attempt },
+                CheckoutEvent::PaymentFailed { retry: true, .. },
+            ) => Self::InvoiceReady {
+                invoice: invoice.clone(),
+                attempts: *attempt,
+            },
+            (Self::Paying { invoice, .. }, CheckoutEvent::PaymentFailed { code, reason, .. }) => {
+                Self::Failed {
+                    invoice: Some(invoice.clone()),
+                    code: *code,
+                    reason: reason.clone(),
+                }
+            }
+            (Self::Created, CheckoutEvent::PaymentFailed { code, reason, .. }) => Self::Failed {
+                invoice: None,
+                code: *code,
+                reason: reason.clone(),
+            },
+            (Self::InvoiceReady { invoice, .. }, CheckoutEvent::Expired) => Self::Expired {
+                invoice: invoice.clone(),
+            },
+            (Self::Paying { .. }, CheckoutEvent::PaymentRejected) => Self::Cancelled,
+            // A payment in flight can't be cancelled: the wallet may still complete it
+            (Self::Created | Self::InvoiceReady { .. }, CheckoutEvent::Cancelled) => {
+                Self::Cancelled
+            }
+            _ => return None,
+        };
+        Some(next)
+    }
+}
+
+/// Input of the [`CheckoutState`] machine
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum CheckoutEvent {
+    /// Invoice created by the backend
+    InvoiceReceived(String),
+    /// Payment attempt started
+    PaymentStarted,
+    /// Payment succeeded
+    PaymentSucceeded {
+        /// Preimage
+        preimage: String,
+    },
+    /// The user rejected the payment prompt
+    PaymentRejected,
+    /// Invoice creation or payment attempt failed
+    PaymentFailed {
+        /// Error category
+        code: ErrorCode,
+        /// Error message
+        reason: String,
+        /// Whether the payment is attempted again
+        retry: bool,
+    },
+    /// The invoice expired
+    Expired,
+    /// Cancelled by the user or by the app, before paying
+    Cancelled,
+}
+
+impl CheckoutEvent {
+    fn failed(e: &Error, retry: bool) -> Self {
+        Self::PaymentFailed {
+            code: e.code(),
+            reason: e.to_string(),
+            retry,
+        }
+    }
+}
+
+/// Invoice source of [`Checkout::from_source`], with the amount to pass it
+type Source = (Amount, Box<dyn Fn(Amount) -> InvoiceFuture>);
+
+/// Callback of [`Checkout::on_event`]
+type OnEvent = RefCell<Box<dyn FnMut(&CheckoutEvent, &CheckoutState)>>;
+
+/// Storefront checkout: invoice, payment attempts, retries and expiry as a [`CheckoutState`]
+/// machine
+///
+/// [`Checkout::run`] drives it to a final state. Routing and connection errors are retried up to
+/// [`Checkout::max_attempts`] times, user rejections cancel the checkout and expired invoices are
+/// never paid. Events from elsewhere (i.e. the backend reporting the payment) are applied with
+/// [`Checkout::handle`].
+///
+/// ```rust,no_run
+/// use webln::{Amount, Checkout, CheckoutState, WebLN};
+///
+/// # async fn create_invoice(amount: Amount) -> Result<String, webln::Error> { todo!() }
+/// # async fn run() -> Result<(), webln::Error> {
+/// let webln = WebLN::new()?;
+/// let checkout = Checkout::from_source(webln, Amount::from_sats(2100), create_invoice)
+///     .on_event(|event, state| println!("{event:?} -> {state:?}"));
+/// match checkout.run().await {
+///     CheckoutState::Paid { preimage, .. } => println!("Paid: {preimage}"),
+///     state => println!("Not paid: {state:?}"),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Checkout {
+    webln: WebLN,
+    source: Option<Source>,
+    max_attempts: u32,
+    retry_delay: Duration,
+    state: RefCell<CheckoutState>,
+    on_event: Option<OnEvent>,
+}
+
+impl fmt::Debug for Checkout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Checkout")
+            .field("amount", &self.source.as_ref().map(|(amount, ..)| amount))
+            .field("max_attempts", &self.max_attempts)
+            .field("retry_delay", &self.retry_delay)
+            .field("state", &self.state.borrow())
+            .finish()
+    }
+}
+
+impl Checkout {
+    fn new(webln: WebLN, source: Option<Source>, state: CheckoutState) -> Self {
+        Self {
+            webln,
+            source,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            state: RefCell::new(state),
+            on_event: None,
+        }
+    }
+
+    /// New checkout of an invoice received from the backend
+    pub fn from_invoice<S>(webln: WebLN, invoice: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::new(
+            webln,
+            None,
+            CheckoutState::InvoiceReady {
+                invoice: invoice.into(),
+                attempts: 0,
+            },
+        )
+    }
+
+    /// New checkout of `amount`, with the invoice created by `source` (i.e. a backend call)
+    pub fn from_source<F, Fut>(webln: WebLN, amount: Amount, source: F) -> Self
+    where
+        F: Fn(Amount) -> Fut + 'static,
+        Fut: Future<Output = Result<String, Error>> + 'static,
+    {
+        Self::new(
+            webln,
+            Some((amount, Box::new(move |amount| Box::pin(source(amount))))),
+            CheckoutState::Created,
+        )
+    }
+
+    /// Set the max number of payment attempts (default: 3)
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before retrying a failed payment (default: 2 secs)
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Call `callback` on every transition, with the event and the new state
+    pub fn on_event<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&CheckoutEvent, &CheckoutState) + 'static,
+    {
+        self.on_event = Some(RefCell::new(Box::new(callback)));
+        self
+    }
+
+    /// Current state
+    pub fn state(&self) -> CheckoutState {
+        self.state.borrow().clone()
+    }
+
+    /// Apply `event`, returning `false` if it's not allowed in the current state
+    pub fn handle(&self, event: CheckoutEvent) -> bool {
+        let next: CheckoutState = match self.state.borrow().apply(&event) {
+            Some(next) => next,
+            None => return false,
+        };
+        *self.state.borrow_mut() = next.clone();
+        if let Some(callback) = &self.on_event {
+            (callback.borrow_mut())(&event, &next);
+        }
+        true
+    }
+
+    /// Cancel the checkout, returning `false` if it's already over or paying
+    ///
+    /// A payment in flight can't be cancelled: the wallet may still complete it.
+    pub fn cancel(&self) -> bool {
+        self.handle(CheckoutEvent::Cancelled)
+    }
+
+    /// Drive the checkout to a final state
+    ///
+    /// Returns the current state if another call is already paying.
+    pub async fn run(&self) -> CheckoutState {
+        loop {
+            match self.state() {
+                CheckoutState::Created => {
+                    let event: CheckoutEvent = match &self.source {
+                        Some((amount, source)) => match source(*amount).await {
+                            Ok(invoice) => CheckoutEvent::InvoiceReceived(invoice),
+                            Err(e) => CheckoutEvent::failed(&e, false),
+                        },
+                        None => return self.state(),
+                    };
+                    self.handle(event);
+                }
+                CheckoutState::InvoiceReady { invoice, attempts } => {
+                    if self.is_expired(&invoice) {
+                        self.handle(CheckoutEvent::Expired);
+                        continue;
+                    }
+                    if attempts > 0 {
+                        // Without timers (no `setTimeout`), retry right away
+                        let _ = self.webln.clock().sleep(self.retry_delay).await;
+                        // Cancelled (or paid) while waiting
+                        if !matches!(self.state(), CheckoutState::InvoiceReady { .. }) {
+                            continue;
+                        }
+                    }
+
+                    if !self.handle(CheckoutEvent::PaymentStarted) {
+                        return self.state();
+                    }
+                    let event: CheckoutEvent = match self.webln.send_payment(&invoice).await {
+                        Ok(res) => CheckoutEvent::PaymentSucceeded {
+                            preimage: res.preimage,
+                        },
+                        Err(e) if e.is_user_rejected() => CheckoutEvent::PaymentRejected,
+                        Err(e) => {
+                            let retry: bool =
+                                matches!(e.code(), ErrorCode::Routing | ErrorCode::Connection)
+                                    && attempts + 1 < self.max_attempts;
+                            CheckoutEvent::failed(&e, retry)
+                        }
+                    };
+                    self.handle(event);
+                }
+                state => return state,
+            }
+        }
+    }
+
+    fn is_expired(&self, invoice: &str) -> bool {
+        Bolt11Invoice::parse(invoice).map_or(false, |invoice| {
+            self.webln.clock().now().as_secs() >= invoice.expires_at()
+        })
+    }
+}
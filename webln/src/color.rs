@@ -0,0 +1,137 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Node color
+
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
+/// Color parsing error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseRgbColorError {
+    /// Expected `#rrggbb` or `#rgb`
+    InvalidLength,
+    /// Invalid hex digit
+    InvalidHex,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseRgbColorError {}
+
+impl fmt::Display for ParseRgbColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "Invalid length: expected `#rrggbb` or `#rgb`"),
+            Self::InvalidHex => write!(f, "Invalid hex digit"),
+        }
+    }
+}
+
+/// RGB color (i.e. the node color returned by `getInfo`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RgbColor {
+    /// Red
+    pub r: u8,
+    /// Green
+    pub g: u8,
+    /// Blue
+    pub b: u8,
+}
+
+impl RgbColor {
+    /// New RGB color
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Get color as `0xRRGGBB`
+    pub fn to_u32(&self) -> u32 {
+        (self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+    }
+
+    /// Get color as `#rrggbb` (usable in CSS)
+    pub fn to_hex(&self) -> String {
+        alloc::format!("{self}")
+    }
+}
+
+fn parse_hex(digits: &[u8]) -> Result<u8, ParseRgbColorError> {
+    let mut value: u8 = 0;
+    for digit in digits.iter() {
+        let digit: u8 = (*digit as char)
+            .to_digit(16)
+            .ok_or(ParseRgbColorError::InvalidHex)? as u8;
+        value = value << 4 | digit;
+    }
+    // `#rgb` shorthand: `f` means `ff`
+    if digits.len() == 1 {
+        Ok(value * 0x11)
+    } else {
+        Ok(value)
+    }
+}
+
+impl FromStr for RgbColor {
+    type Err = ParseRgbColorError;
+
+    /// Parse `#rrggbb` or `#rgb` (the `#` is optional)
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        let color: &[u8] = color.trim().trim_start_matches('#').as_bytes();
+        let size: usize = match color.len() {
+            6 => 2,
+            3 => 1,
+            _ => return Err(ParseRgbColorError::InvalidLength),
+        };
+        Ok(Self {
+            r: parse_hex(&color[..size])?,
+            g: parse_hex(&color[size..size * 2])?,
+            b: parse_hex(&color[size * 2..])?,
+        })
+    }
+}
+
+impl fmt::Display for RgbColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl From<[u8; 3]> for RgbColor {
+    fn from([r, g, b]: [u8; 3]) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl From<(u8, u8, u8)> for RgbColor {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self { r, g, b }
+    }
+}
+
+impl From<RgbColor> for [u8; 3] {
+    fn from(color: RgbColor) -> Self {
+        [color.r, color.g, color.b]
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for RgbColor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RgbColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let color: String = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Self::from_str(&color).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Currency
+
+use alloc::string::String;
+use core::convert::Infallible;
+use core::fmt;
+use core::str::FromStr;
+
+/// Balance currency
+///
+/// Providers report it as a free-form string (`sats`, `BTC`, `EUR`, ...): this is the normalized form.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Currency {
+    /// Satoshis (the default when the provider doesn't specify a currency)
+    #[default]
+    Sats,
+    /// Bitcoin
+    Btc,
+    /// Fiat currency, with its uppercase ISO 4217 code (i.e. `USD`)
+    Fiat(String),
+}
+
+impl Currency {
+    /// Check if the currency is fiat
+    pub fn is_fiat(&self) -> bool {
+        matches!(self, Self::Fiat(..))
+    }
+
+    /// ISO 4217-like code (`BTC` or the fiat code), `None` for sats
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Self::Sats => None,
+            Self::Btc => Some("BTC"),
+            Self::Fiat(code) => Some(code),
+        }
+    }
+}
+
+impl FromStr for Currency {
+    type Err = Infallible;
+
+    /// Parse a provider currency string (case-insensitive). Empty strings are sats.
+    fn from_str(currency: &str) -> Result<Self, Self::Err> {
+        let currency: &str = currency.trim();
+        if currency.is_empty()
+            || currency.eq_ignore_ascii_case("sat")
+            || currency.eq_ignore_ascii_case("sats")
+        {
+            Ok(Self::Sats)
+        } else if currency.eq_ignore_ascii_case("btc") {
+            Ok(Self::Btc)
+        } else {
+            Ok(Self::Fiat(currency.to_ascii_uppercase()))
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sats => write!(f, "sats"),
+            Self::Btc => write!(f, "BTC"),
+            Self::Fiat(code) => write!(f, "{code}"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Currency {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Currency {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let currency: String = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(currency.parse().unwrap_or_default())
+    }
+}
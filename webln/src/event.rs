@@ -0,0 +1,279 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Events
+
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::fmt;
+use core::str::FromStr;
+
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::value::expect_object;
+use crate::{Deserialize, Error, ParseMode, Value, WebLN};
+
+const ACCOUNT_CHANGED: &str = "accountChanged";
+const PAYMENT_SUCCEEDED: &str = "sendPayment:success";
+const PAYMENT_FAILED: &str = "sendPayment:failed";
+const PAYMENT_RECEIVED: &str = "paymentReceived";
+
+/// Provider event
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Event {
+    /// The user switched account in the provider
+    AccountChanged,
+    /// A payment succeeded (`sendPayment:success`, payload: [`PaymentSuccess`])
+    PaymentSucceeded,
+    /// A payment failed (`sendPayment:failed`, payload: [`PaymentFailure`])
+    PaymentFailed,
+    /// An invoice of the node was paid (`paymentReceived`, payload: [`PaymentSuccess`])
+    ///
+    /// Not emitted by all providers: [`crate::IncomingPayment::settled`] also polls the node.
+    PaymentReceived,
+    /// Provider-specific event
+    Other(String),
+}
+
+impl From<&str> for Event {
+    fn from(event: &str) -> Self {
+        match event {
+            ACCOUNT_CHANGED => Self::AccountChanged,
+            PAYMENT_SUCCEEDED => Self::PaymentSucceeded,
+            PAYMENT_FAILED => Self::PaymentFailed,
+            PAYMENT_RECEIVED => Self::PaymentReceived,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for Event {
+    fn from(event: String) -> Self {
+        match Self::from(event.as_str()) {
+            Self::Other(..) => Self::Other(event),
+            known => known,
+        }
+    }
+}
+
+impl FromStr for Event {
+    type Err = Infallible;
+
+    fn from_str(event: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(event))
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AccountChanged => write!(f, "{ACCOUNT_CHANGED}"),
+            Self::PaymentSucceeded => write!(f, "{PAYMENT_SUCCEEDED}"),
+            Self::PaymentFailed => write!(f, "{PAYMENT_FAILED}"),
+            Self::PaymentReceived => write!(f, "{PAYMENT_RECEIVED}"),
+            Self::Other(other) => write!(f, "{other}"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Event {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Event {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let event: String = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(Self::from(event))
+    }
+}
+
+fn get_string(obj: &Value, key: &str) -> Option<String> {
+    obj.get(key).as_str().map(String::from)
+}
+
+/// [`Event::PaymentSucceeded`] payload
+///
+/// Fields missing from the provider payload are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub struct PaymentSuccess {
+    /// Preimage
+    pub preimage: Option<String>,
+    /// Payment hash
+    pub payment_hash: Option<String>,
+    /// Invoice
+    pub payment_request: Option<String>,
+}
+
+impl Deserialize for PaymentSuccess {
+    fn from_value(value: &Value, _mode: ParseMode) -> Result<Self, Error> {
+        let obj: &Value = expect_object(value)?;
+        Ok(Self {
+            preimage: get_string(obj, "preimage"),
+            payment_hash: get_string(obj, "paymentHash"),
+            payment_request: get_string(obj, "paymentRequest"),
+        })
+    }
+}
+
+/// [`Event::PaymentFailed`] payload
+///
+/// Fields missing from the provider payload are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub struct PaymentFailure {
+    /// Payment hash
+    pub payment_hash: Option<String>,
+    /// Invoice
+    pub payment_request: Option<String>,
+    /// Error message
+    pub error: Option<String>,
+}
+
+impl Deserialize for PaymentFailure {
+    fn from_value(value: &Value, _mode: ParseMode) -> Result<Self, Error> {
+        let obj: &Value = expect_object(value)?;
+        // `error` may be a message or an `Error` object
+        let error: Option<String> =
+            get_string(obj, "error").or_else(|| get_string(obj.get("error"), "message"));
+        Ok(Self {
+            payment_hash: get_string(obj, "paymentHash"),
+            payment_request: get_string(obj, "paymentRequest"),
+            error: error.or_else(|| get_string(obj, "message")),
+        })
+    }
+}
+
+impl WebLN {
+    /// Listen for [`Event::PaymentSucceeded`] with a parsed payload
+    ///
+    /// Non-object payloads are reported as an empty [`PaymentSuccess`].
+    pub fn on_payment_succeeded<F>(&self, mut callback: F) -> Result<Subscription, Error>
+    where
+        F: FnMut(PaymentSuccess) + 'static,
+    {
+        self.subscribe(Event::PaymentSucceeded, move |payload| {
+            callback(PaymentSuccess::deserialize(&payload).unwrap_or_default())
+        })
+    }
+
+    /// Listen for [`Event::PaymentFailed`] with a parsed payload
+    ///
+    /// Plain string payloads are reported as [`PaymentFailure::error`].
+    pub fn on_payment_failed<F>(&self, mut callback: F) -> Result<Subscription, Error>
+    where
+        F: FnMut(PaymentFailure) + 'static,
+    {
+        self.subscribe(Event::PaymentFailed, move |payload| {
+            let failure: PaymentFailure = match payload.as_string() {
+                Some(error) => PaymentFailure {
+                    error: Some(error),
+                    ..Default::default()
+                },
+                None => PaymentFailure::deserialize(&payload).unwrap_or_default(),
+            };
+            callback(failure)
+        })
+    }
+
+    /// Listen for `accountChanged`, keeping the cached state consistent
+    ///
+    /// On every account change the cache is cleared (see [`WebLN::invalidate`]) and, if
+    /// `reenable` is set, [`WebLN::ensure_enabled`] runs again. `callback` is called last, with
+    /// the re-enable result (always `Ok` if `reenable` is `false`), so apps can refresh their UI.
+    pub fn on_account_changed<F>(&self, reenable: bool, callback: F) -> Result<Subscription, Error>
+    where
+        F: FnMut(Result<(), Error>) + 'static,
+    {
+        let webln: WebLN = self.clone();
+        let callback: Rc<RefCell<F>> = Rc::new(RefCell::new(callback));
+        self.subscribe(Event::AccountChanged, move |_| {
+            webln.invalidate();
+
+            if !reenable {
+                (callback.borrow_mut())(Ok(()));
+                return;
+            }
+
+            let webln: WebLN = webln.clone();
+            let callback: Rc<RefCell<F>> = callback.clone();
+            spawn_local(async move {
+                let res: Result<(), Error> = webln.ensure_enabled().await;
+                (callback.borrow_mut())(res);
+            });
+        })
+    }
+}
+
+/// Event listener registered with [`WebLN::subscribe`]
+///
+/// The listener is removed from the provider when dropped.
+#[must_use = "the listener is removed when the subscription is dropped"]
+pub struct Subscription {
+    webln: WebLN,
+    event: Event,
+    closure: Option<Closure<dyn FnMut(JsValue)>>,
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("event", &self.event)
+            .finish()
+    }
+}
+
+impl Subscription {
+    pub(crate) fn new(webln: WebLN, event: Event, closure: Closure<dyn FnMut(JsValue)>) -> Self {
+        Self {
+            webln,
+            event,
+            closure: Some(closure),
+        }
+    }
+
+    /// Subscribed event
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
+
+    /// Remove the listener, reporting provider errors (dropping ignores them)
+    pub fn unsubscribe(mut self) -> Result<(), Error> {
+        self.remove()
+    }
+
+    fn remove(&mut self) -> Result<(), Error> {
+        match self.closure.take() {
+            Some(closure) => {
+                let listener: &Function = closure.as_ref().unchecked_ref();
+                self.webln.off(&self.event, listener)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let _ = self.remove();
+    }
+}
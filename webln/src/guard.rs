@@ -0,0 +1,125 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Prompt rate limit
+
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::time::Duration;
+
+use crate::{Error, GetInfoMethod};
+
+/// Limit of user-prompting calls (`enable`, `makeInvoice`, `sendPayment`, `sendPaymentAsync`,
+/// `sendMultiPayment`, `keysend`, `signMessage` and `request`, except the known lookups) per time
+/// window
+///
+/// The `request` fallbacks are counted as the method they emulate.
+///
+/// See [`WebLN::with_prompt_limit`](crate::WebLN::with_prompt_limit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PromptLimit {
+    /// Max calls in the window (`0` rejects all of them, i.e. to pause payments)
+    pub max_prompts: usize,
+    /// Sliding window
+    pub window: Duration,
+}
+
+impl PromptLimit {
+    /// At most `max_prompts` calls every `window`
+    ///
+    /// With `max_prompts == 0`, every prompting call fails with
+    /// [`Error::RateLimited`](crate::Error::RateLimited).
+    pub fn new(max_prompts: usize, window: Duration) -> Self {
+        Self {
+            max_prompts,
+            window,
+        }
+    }
+}
+
+/// `request` methods known not to prompt: read-only node lookups (LND and CLN)
+const LOOKUPS: &[&str] = &[
+    "channelbalance",
+    "decode",
+    "decodepayreq",
+    "getinfo",
+    "getnodeinfo",
+    "listchannels",
+    "listfunds",
+    "listinvoices",
+    "listnodes",
+    "listpayments",
+    "listpays",
+    "listpeers",
+    "listsendpays",
+    "lookupinvoice",
+    "walletbalance",
+];
+
+/// Check if `method` shows a wallet prompt
+fn is_prompting(method: &GetInfoMethod) -> bool {
+    matches!(
+        method,
+        GetInfoMethod::Enable
+            | GetInfoMethod::MakeInvoice
+            | GetInfoMethod::SendPayment
+            | GetInfoMethod::SendPaymentAsync
+            | GetInfoMethod::SendMultiPayment
+            | GetInfoMethod::Keysend
+            | GetInfoMethod::SignMessage
+            | GetInfoMethod::Request
+    )
+}
+
+/// Check if the `request` method is a read-only lookup, which doesn't prompt
+pub(crate) fn is_lookup(method: &str) -> bool {
+    LOOKUPS
+        .iter()
+        .any(|lookup| lookup.eq_ignore_ascii_case(method))
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PromptGuard {
+    limit: PromptLimit,
+    /// Times of the calls still in the window, oldest first, shared by the clones of the instance
+    prompts: Rc<RefCell<VecDeque<Duration>>>,
+}
+
+impl PromptGuard {
+    pub(crate) fn new(limit: PromptLimit) -> Self {
+        Self {
+            limit,
+            prompts: Rc::new(RefCell::new(VecDeque::new())),
+        }
+    }
+
+    pub(crate) fn limit(&self) -> PromptLimit {
+        self.limit
+    }
+
+    /// Count a call to `method` at `now`, if it prompts and the limit isn't reached
+    pub(crate) fn check(&self, method: &GetInfoMethod, now: Duration) -> Result<(), Error> {
+        if !is_prompting(method) {
+            return Ok(());
+        }
+
+        let mut prompts = self.prompts.borrow_mut();
+        while prompts
+            .front()
+            .map_or(false, |at| now.saturating_sub(*at) >= self.limit.window)
+        {
+            prompts.pop_front();
+        }
+
+        if prompts.len() >= self.limit.max_prompts {
+            let oldest: Duration = prompts.front().copied().unwrap_or(now);
+            return Err(Error::RateLimited {
+                retry_after: (oldest + self.limit.window).saturating_sub(now),
+            });
+        }
+
+        prompts.push_back(now);
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Hex
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const CHARS: &[u8; 16] = b"0123456789abcdef";
+
+/// Hex error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HexError {
+    /// Odd number of digits
+    OddLength,
+    /// Invalid hex digit
+    InvalidChar,
+}
+
+/// Encode bytes as lowercase hex
+pub(crate) fn encode<T>(data: T) -> String
+where
+    T: AsRef<[u8]>,
+{
+    let data: &[u8] = data.as_ref();
+    let mut hex: String = String::with_capacity(data.len() * 2);
+    for byte in data.iter() {
+        hex.push(CHARS[(byte >> 4) as usize] as char);
+        hex.push(CHARS[(byte & 0x0f) as usize] as char);
+    }
+    hex
+}
+
+fn val(c: u8) -> Result<u8, HexError> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(HexError::InvalidChar),
+    }
+}
+
+/// Decode hex string
+pub(crate) fn decode<T>(hex: T) -> Result<Vec<u8>, HexError>
+where
+    T: AsRef<[u8]>,
+{
+    let hex: &[u8] = hex.as_ref();
+
+    if hex.len() % 2 != 0 {
+        return Err(HexError::OddLength);
+    }
+
+    hex.chunks(2)
+        .map(|pair| Ok(val(pair[0])? << 4 | val(pair[1])?))
+        .collect()
+}
@@ -0,0 +1,41 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! HTTP requests with the global `fetch`
+
+use alloc::string::String;
+
+use js_sys::{Function, Object, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::Error;
+
+fn get_func(obj: &JsValue, name: &str) -> Result<Function, Error> {
+    Reflect::get(obj, &JsValue::from_str(name))
+        .ok()
+        .and_then(|f| f.dyn_into().ok())
+        .ok_or_else(|| Error::NamespaceNotFound(String::from(name)))
+}
+
+/// `GET` a JSON document
+pub(crate) async fn get_json(url: &str) -> Result<JsValue, Error> {
+    let global: Object = js_sys::global();
+    let promise: JsValue = get_func(&global, "fetch")?.call1(&global, &JsValue::from_str(url))?;
+    let response: JsValue = JsFuture::from(Promise::resolve(&promise)).await?;
+
+    let ok: bool = Reflect::get(&response, &JsValue::from_str("ok"))
+        .ok()
+        .and_then(|ok| ok.as_bool())
+        .unwrap_or_default();
+    if !ok {
+        let status: u16 = Reflect::get(&response, &JsValue::from_str("status"))
+            .ok()
+            .and_then(|status| status.as_f64())
+            .unwrap_or_default() as u16;
+        return Err(Error::Http(status));
+    }
+
+    let promise: JsValue = get_func(&response, "json")?.call0(&response)?;
+    Ok(JsFuture::from(Promise::resolve(&promise)).await?)
+}
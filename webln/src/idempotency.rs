@@ -0,0 +1,141 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Idempotency keys for payments
+
+use alloc::string::{String, ToString};
+use core::future::Future;
+
+use js_sys::{Function, Object, Promise};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{Error, KeysendArgs, SendPaymentResponse, WebLN};
+
+/// Payment made under an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Payment {
+    Invoice(String),
+    Keysend(KeysendArgs),
+}
+
+/// State of an idempotency key
+#[derive(Debug, Clone)]
+pub(crate) enum Entry {
+    /// Payment in progress: the promise resolves when it completes
+    Pending(Promise),
+    /// Successful payment
+    Paid(SendPaymentResponse),
+}
+
+/// Key reserved for a pending payment, released (unless paid) when dropped
+struct Reservation<'a> {
+    webln: &'a WebLN,
+    key: &'a str,
+    done: Promise,
+    resolve: Function,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        {
+            let mut idempotency = self.webln.inner.idempotency.borrow_mut();
+            let pending: bool = matches!(
+                idempotency.get(self.key),
+                Some((_, Entry::Pending(done))) if Object::is(done, &self.done)
+            );
+            if pending {
+                idempotency.remove(self.key);
+            }
+        }
+        let _ = self.resolve.call0(&JsValue::UNDEFINED);
+    }
+}
+
+impl WebLN {
+    /// Like [`WebLN::send_payment`], returning the recorded result if a payment already
+    /// succeeded under `key`
+    ///
+    /// Keys are kept for the lifetime of the instance (and its clones): retrying with the same
+    /// key never pays twice. Concurrent calls with the same key wait for the pending payment;
+    /// if it fails, the key is released and the next call pays. Fails with
+    /// [`Error::IdempotencyKeyReused`] if `key` was used for another payment.
+    pub async fn send_payment_idempotent(
+        &self,
+        key: &str,
+        invoice: &str,
+    ) -> Result<SendPaymentResponse, Error> {
+        self.idempotent(key, Payment::Invoice(invoice.to_string()), || {
+            self.send_payment(invoice)
+        })
+        .await
+    }
+
+    /// Like [`WebLN::keysend`], returning the recorded result if a payment already succeeded
+    /// under `key`
+    ///
+    /// See [`WebLN::send_payment_idempotent`].
+    pub async fn keysend_idempotent(
+        &self,
+        key: &str,
+        args: &KeysendArgs,
+    ) -> Result<SendPaymentResponse, Error> {
+        self.idempotent(key, Payment::Keysend(args.clone()), || self.keysend(args))
+            .await
+    }
+
+    /// Forget the result recorded under `key`
+    pub fn forget_idempotency_key(&self, key: &str) {
+        self.inner.idempotency.borrow_mut().remove(key);
+    }
+
+    async fn idempotent<F, Fut>(
+        &self,
+        key: &str,
+        payment: Payment,
+        pay: F,
+    ) -> Result<SendPaymentResponse, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<SendPaymentResponse, Error>>,
+    {
+        // Wait for the pending payment under `key`, if any
+        while let Some(entry) = self.idempotent_entry(key, &payment)? {
+            match entry {
+                Entry::Paid(res) => return Ok(res),
+                Entry::Pending(done) => {
+                    let _ = JsFuture::from(done).await;
+                }
+            }
+        }
+
+        // Reserve the key before paying
+        let mut resolve: Option<Function> = None;
+        let done = Promise::new(&mut |res, _| resolve = Some(res));
+        self.inner.idempotency.borrow_mut().insert(
+            key.to_string(),
+            (payment.clone(), Entry::Pending(done.clone())),
+        );
+        let _reservation = Reservation {
+            webln: self,
+            key,
+            done,
+            resolve: resolve.ok_or(Error::SomethingGoneWrong)?,
+        };
+
+        let res: SendPaymentResponse = pay().await?;
+        self.inner
+            .idempotency
+            .borrow_mut()
+            .insert(key.to_string(), (payment, Entry::Paid(res.clone())));
+        Ok(res)
+    }
+
+    fn idempotent_entry(&self, key: &str, payment: &Payment) -> Result<Option<Entry>, Error> {
+        match self.inner.idempotency.borrow().get(key) {
+            Some((recorded, entry)) if recorded == payment => Ok(Some(entry.clone())),
+            Some(..) => Err(Error::IdempotencyKeyReused(key.to_string())),
+            None => Ok(None),
+        }
+    }
+}
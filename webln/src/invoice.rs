@@ -0,0 +1,141 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Invoice creation and zero-amount payments through the node RPC passthrough
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::keysend::{self, SEND_PAYMENT_V2, TIMEOUT_SECS};
+use crate::value::{expect_object, get_string};
+use crate::{
+    time, Amount, Error, GetInfoMethod, RequestInvoiceArgs, RequestInvoiceResponse,
+    ResponseWithRaw, SendPaymentResponse, Value, WebLN,
+};
+
+/// LND `AddInvoice` method name, as exposed by `request`
+const LND_ADD_INVOICE: &str = "addinvoice";
+/// CLN `invoice` method name, as exposed by `request`
+const CLN_INVOICE: &str = "invoice";
+/// CLN `pay` method name, as exposed by `request`
+const CLN_PAY: &str = "pay";
+
+fn set(obj: &Object, key: &str, value: &str) -> Result<(), Error> {
+    Reflect::set(obj, &JsValue::from_str(key), &JsValue::from_str(value))?;
+    Ok(())
+}
+
+/// Get the invoice from the `key` string field
+fn parse(raw: JsValue, key: &str) -> Result<ResponseWithRaw<RequestInvoiceResponse>, Error> {
+    let value: Value = Value::from_js(&raw);
+    let invoice: String = get_string(expect_object(&value)?, key)?;
+    Ok(ResponseWithRaw {
+        response: RequestInvoiceResponse { invoice },
+        raw,
+    })
+}
+
+/// Get the preimage of a CLN `pay` result
+fn parse_cln_payment(raw: JsValue) -> Result<SendPaymentResponse, Error> {
+    let value: Value = Value::from_js(&raw);
+    let preimage: String = get_string(expect_object(&value)?, "payment_preimage")?;
+    Ok(SendPaymentResponse { preimage })
+}
+
+impl WebLN {
+    /// Create an invoice with LND `addinvoice` or CLN `invoice`, through `request`
+    ///
+    /// Uses `amount` (or `default_amount`) and `default_memo`: no amount means any amount.
+    pub(crate) async fn make_invoice_via_request(
+        &self,
+        args: &RequestInvoiceArgs,
+    ) -> Result<ResponseWithRaw<RequestInvoiceResponse>, Error> {
+        self.check_prompt(&GetInfoMethod::MakeInvoice)
+            .map_err(|e| self.call_error(GetInfoMethod::MakeInvoice, e))?;
+
+        let amount: Option<u64> = args.amount.or(args.default_amount);
+        let memo: &str = args.default_memo.as_deref().unwrap_or_default();
+
+        let params = Object::new();
+        set(&params, "value", &amount.unwrap_or_default().to_string())?;
+        set(&params, "memo", memo)?;
+        let lnd_error: Error = match self
+            .send_request(LND_ADD_INVOICE, Some(&params), false)
+            .await
+        {
+            Ok(result) => return parse(result, "payment_request"),
+            Err(e) => e,
+        };
+
+        let params = Object::new();
+        let amount_msat: String = match amount {
+            Some(amount) => amount.saturating_mul(1000).to_string(),
+            None => String::from("any"),
+        };
+        set(&params, "amount_msat", &amount_msat)?;
+        set(
+            &params,
+            "label",
+            &format!("webln-{}", time::now().as_millis()),
+        )?;
+        set(&params, "description", memo)?;
+        match self.send_request(CLN_INVOICE, Some(&params), false).await {
+            Ok(result) => parse(result, "bolt11"),
+            // Not a CLN node either: the LND error is more relevant
+            Err(..) => Err(lnd_error),
+        }
+    }
+
+    /// Pay a zero-amount invoice with LND `sendpaymentv2` or CLN `pay`, through `request`
+    pub(crate) async fn send_payment_via_request(
+        &self,
+        invoice: &str,
+        amount: Amount,
+    ) -> Result<SendPaymentResponse, Error> {
+        self.check_prompt(&GetInfoMethod::SendPayment)
+            .map_err(|e| self.call_error(GetInfoMethod::SendPayment, e))?;
+
+        let amount_msat: String = amount.msat().to_string();
+
+        let params = Object::new();
+        set(&params, "payment_request", invoice)?;
+        set(&params, "amt_msat", &amount_msat)?;
+        set(
+            &params,
+            "fee_limit_sat",
+            &keysend::fee_limit(amount.sats()).to_string(),
+        )?;
+        set(&params, "timeout_seconds", &TIMEOUT_SECS.to_string())?;
+        Reflect::set(
+            &params,
+            &JsValue::from_str("no_inflight_updates"),
+            &JsValue::TRUE,
+        )?;
+        let lnd_error: Error = match self
+            .send_request(SEND_PAYMENT_V2, Some(&params), false)
+            .await
+        {
+            Ok(result) => {
+                return keysend::check_status(result)
+                    .and_then(|payment| get_string(&payment, "payment_preimage"))
+                    .map(|preimage| SendPaymentResponse { preimage })
+                    .map_err(|e| self.call_error(GetInfoMethod::Request, e));
+            }
+            Err(e) => e,
+        };
+
+        let params = Object::new();
+        set(&params, "bolt11", invoice)?;
+        set(&params, "amount_msat", &amount_msat)?;
+        match self.send_request(CLN_PAY, Some(&params), false).await {
+            Ok(result) => {
+                parse_cln_payment(result).map_err(|e| self.call_error(GetInfoMethod::Request, e))
+            }
+            // Not a CLN node either: the LND error is more relevant
+            Err(..) => Err(lnd_error),
+        }
+    }
+}
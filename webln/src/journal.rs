@@ -0,0 +1,562 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Payment journal, stored in IndexedDB
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::future::Future;
+
+use js_sys::{Array, Function, Object, Promise, Reflect, JSON};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{Amount, Bolt11Invoice, Clock, Error, KeysendArgs, NodeId, WebLN};
+
+/// Object store name
+const STORE: &str = "receipts";
+/// Index of the object store, by timestamp
+const TIMESTAMP_INDEX: &str = "timestamp";
+/// Database schema version
+const DB_VERSION: u32 = 2;
+/// Default max number of receipts kept
+const DEFAULT_MAX_ENTRIES: usize = 1000;
+/// Version of the [`Journal::export`] format
+const EXPORT_VERSION: u32 = 1;
+
+/// Kind of payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ReceiptKind {
+    /// Invoice payment
+    Invoice,
+    /// Keysend payment
+    Keysend,
+    /// Keysend refund, see [`WebLN::refund`]
+    Refund,
+}
+
+impl ReceiptKind {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Invoice => "invoice",
+            Self::Keysend => "keysend",
+            Self::Refund => "refund",
+        }
+    }
+}
+
+/// Receipt of a successful payment
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub struct Receipt {
+    /// ID, assigned by the [`Journal`]
+    pub id: Option<u64>,
+    /// Payment time (UNIX timestamp)
+    pub timestamp: u64,
+    /// Kind of payment
+    pub kind: ReceiptKind,
+    /// Amount, if known
+    #[cfg_attr(feature = "serde", serde(rename = "amountMsat"))]
+    pub amount: Option<Amount>,
+    /// Preimage
+    pub preimage: String,
+    /// Payment hash (hex), if known
+    pub payment_hash: Option<String>,
+    /// Paid invoice
+    pub invoice: Option<String>,
+    /// Keysend destination
+    pub destination: Option<NodeId>,
+    /// Invoice description
+    pub description: Option<String>,
+    /// Refund reference
+    pub reference: Option<String>,
+}
+
+impl Receipt {
+    /// Receipt of an invoice payment (amount, payment hash and description are decoded from it),
+    /// timestamped by `clock`
+    pub fn invoice<S, P>(clock: &dyn Clock, invoice: S, preimage: P) -> Self
+    where
+        S: Into<String>,
+        P: Into<String>,
+    {
+        let invoice: String = invoice.into();
+        let decoded: Option<Bolt11Invoice> = Bolt11Invoice::parse(&invoice).ok();
+        Self {
+            id: None,
+            timestamp: clock.now().as_secs(),
+            kind: ReceiptKind::Invoice,
+            amount: decoded.as_ref().and_then(|i| i.amount),
+            preimage: preimage.into(),
+            payment_hash: decoded.as_ref().map(|i| i.payment_hash.clone()),
+            description: decoded.and_then(|i| i.description),
+            invoice: Some(invoice),
+            destination: None,
+            reference: None,
+        }
+    }
+
+    /// Receipt of a keysend payment, timestamped by `clock`
+    pub fn keysend<S>(clock: &dyn Clock, args: &KeysendArgs, preimage: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            id: None,
+            timestamp: clock.now().as_secs(),
+            kind: ReceiptKind::Keysend,
+            amount: Some(Amount::from_sats(args.amount)),
+            preimage: preimage.into(),
+            payment_hash: None,
+            invoice: None,
+            destination: Some(args.destination),
+            description: None,
+            reference: None,
+        }
+    }
+
+    /// Receipt of a keysend refund, timestamped by `clock`
+    pub fn refund<S, R>(clock: &dyn Clock, args: &KeysendArgs, preimage: S, reference: R) -> Self
+    where
+        S: Into<String>,
+        R: Into<String>,
+    {
+        Self {
+            kind: ReceiptKind::Refund,
+            reference: Some(reference.into()),
+            ..Self::keysend(clock, args, preimage)
+        }
+    }
+
+    pub(crate) fn to_js(&self) -> Result<JsValue, Error> {
+        let obj = Object::new();
+        let set = |key: &str, value: JsValue| Reflect::set(&obj, &JsValue::from_str(key), &value);
+        if let Some(id) = self.id {
+            set("id", JsValue::from(id as f64))?;
+        }
+        set("timestamp", JsValue::from(self.timestamp as f64))?;
+        set("kind", JsValue::from_str(self.kind.as_str()))?;
+        if let Some(amount) = self.amount {
+            set("amountMsat", JsValue::from(amount.msat() as f64))?;
+        }
+        set("preimage", JsValue::from_str(&self.preimage))?;
+        if let Some(payment_hash) = &self.payment_hash {
+            set("paymentHash", JsValue::from_str(payment_hash))?;
+        }
+        if let Some(invoice) = &self.invoice {
+            set("invoice", JsValue::from_str(invoice))?;
+        }
+        if let Some(destination) = &self.destination {
+            set("destination", JsValue::from_str(&destination.to_hex()))?;
+        }
+        if let Some(description) = &self.description {
+            set("description", JsValue::from_str(description))?;
+        }
+        if let Some(reference) = &self.reference {
+            set("reference", JsValue::from_str(reference))?;
+        }
+        Ok(obj.into())
+    }
+
+    /// `None` if the object is not a receipt
+    pub(crate) fn from_js(value: &JsValue) -> Option<Self> {
+        let get = |key: &str| {
+            Reflect::get(value, &JsValue::from_str(key))
+                .ok()
+                .filter(|value| !value.is_undefined() && !value.is_null())
+        };
+        let get_string = |key: &str| get(key).and_then(|value| value.as_string());
+        let get_u64 = |key: &str| get(key).and_then(|value| value.as_f64()).map(|n| n as u64);
+
+        let kind: ReceiptKind = match get_string("kind")?.as_str() {
+            "invoice" => ReceiptKind::Invoice,
+            "keysend" => ReceiptKind::Keysend,
+            "refund" => ReceiptKind::Refund,
+            _ => return None,
+        };
+        Some(Self {
+            id: get_u64("id"),
+            timestamp: get_u64("timestamp")?,
+            kind,
+            amount: get_u64("amountMsat").map(Amount::from_msat),
+            preimage: get_string("preimage")?,
+            payment_hash: get_string("paymentHash"),
+            invoice: get_string("invoice"),
+            destination: get_string("destination").and_then(|d| d.parse().ok()),
+            description: get_string("description"),
+            reference: get_string("reference"),
+        })
+    }
+}
+
+/// Filter for [`Journal::query`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JournalQuery {
+    /// Min timestamp (inclusive)
+    pub since: Option<u64>,
+    /// Max timestamp (inclusive)
+    pub until: Option<u64>,
+    /// Kind of payment
+    pub kind: Option<ReceiptKind>,
+    /// Max number of receipts
+    pub limit: Option<usize>,
+}
+
+impl JournalQuery {
+    /// New empty query (matches everything)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set min timestamp
+    pub fn since(mut self, since: u64) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Set max timestamp
+    pub fn until(mut self, until: u64) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Set kind of payment
+    pub fn kind(mut self, kind: ReceiptKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Set max number of receipts
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    fn matches(&self, receipt: &Receipt) -> bool {
+        self.since.map_or(true, |since| receipt.timestamp >= since)
+            && self.until.map_or(true, |until| receipt.timestamp <= until)
+            && self.kind.map_or(true, |kind| receipt.kind == kind)
+    }
+}
+
+fn method(obj: &JsValue, name: &str) -> Result<Function, Error> {
+    Reflect::get(obj, &JsValue::from_str(name))?
+        .dyn_into()
+        .map_err(|_| Error::NamespaceNotFound(name.to_string()))
+}
+
+/// Wait for an `IDBRequest`, returning its result
+async fn wait(request: &JsValue) -> Result<JsValue, Error> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let _ = Reflect::set(request, &JsValue::from_str("onsuccess"), &resolve);
+        let _ = Reflect::set(request, &JsValue::from_str("onerror"), &reject);
+    });
+    if JsFuture::from(promise).await.is_err() {
+        return Err(Reflect::get(request, &JsValue::from_str("error"))?.into());
+    }
+    Ok(Reflect::get(request, &JsValue::from_str("result"))?)
+}
+
+/// Wait for the transaction of `store` to commit
+///
+/// Called before the first request of the transaction completes, so that the `complete` event
+/// can't be missed.
+fn completion(store: &JsValue) -> Result<impl Future<Output = Result<(), Error>>, Error> {
+    let transaction: JsValue = Reflect::get(store, &JsValue::from_str("transaction"))?;
+    let promise = Promise::new(&mut |resolve, reject| {
+        let _ = Reflect::set(&transaction, &JsValue::from_str("oncomplete"), &resolve);
+        let _ = Reflect::set(&transaction, &JsValue::from_str("onerror"), &reject);
+        let _ = Reflect::set(&transaction, &JsValue::from_str("onabort"), &reject);
+    });
+    Ok(async move {
+        if JsFuture::from(promise).await.is_err() {
+            return Err(Reflect::get(&transaction, &JsValue::from_str("error"))?.into());
+        }
+        Ok(())
+    })
+}
+
+/// Payment journal, persisted in IndexedDB so that the history survives reloads
+///
+/// Only the newest receipts are kept (1000 by default). Register it with [`WebLN::with_journal`]
+/// to record the successful payments.
+///
+/// ```rust,no_run
+/// use webln::{Journal, JournalQuery, ReceiptKind, WebLN};
+///
+/// # async fn run() -> Result<(), webln::Error> {
+/// let journal = Journal::open("payments").await?.max_entries(500);
+/// let webln = WebLN::new()?.with_journal(journal.clone());
+/// // ...
+/// let keysends = journal
+///     .query(&JournalQuery::new().kind(ReceiptKind::Keysend).limit(10))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Journal {
+    /// `IDBDatabase`
+    db: JsValue,
+    max_entries: usize,
+}
+
+impl Journal {
+    /// Open (or create) the `name` database
+    pub async fn open(name: &str) -> Result<Self, Error> {
+        let factory: JsValue = Reflect::get(&js_sys::global(), &JsValue::from_str("indexedDB"))?;
+        if factory.is_undefined() || factory.is_null() {
+            return Err(Error::NamespaceNotFound(String::from("indexedDB")));
+        }
+
+        let request: JsValue = method(&factory, "open")?.call2(
+            &factory,
+            &JsValue::from_str(name),
+            &JsValue::from(DB_VERSION),
+        )?;
+        let upgrade: Closure<dyn FnMut(JsValue)> = {
+            let request: JsValue = request.clone();
+            Closure::once(move |event: JsValue| {
+                let _ = upgrade(&request, &event);
+            })
+        };
+        Reflect::set(
+            &request,
+            &JsValue::from_str("onupgradeneeded"),
+            upgrade.as_ref(),
+        )?;
+        let db: JsValue = wait(&request).await?;
+
+        Ok(Self {
+            db,
+            max_entries: DEFAULT_MAX_ENTRIES,
+        })
+    }
+
+    /// Set the max number of receipts kept: the oldest are dropped first
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Object store, in a new transaction
+    fn store(&self, mode: &str) -> Result<JsValue, Error> {
+        let transaction: JsValue = method(&self.db, "transaction")?.call2(
+            &self.db,
+            &JsValue::from_str(STORE),
+            &JsValue::from_str(mode),
+        )?;
+        Ok(method(&transaction, "objectStore")?.call1(&transaction, &JsValue::from_str(STORE))?)
+    }
+
+    /// Append a receipt, returning its ID
+    ///
+    /// Resolves once the receipt is committed.
+    pub async fn append(&self, receipt: &Receipt) -> Result<u64, Error> {
+        let mut receipt: Receipt = receipt.clone();
+        receipt.id = None;
+
+        let store: JsValue = self.store("readwrite")?;
+        let committed = completion(&store)?;
+        let request: JsValue = method(&store, "add")?.call1(&store, &receipt.to_js()?)?;
+        let id: u64 = wait(&request).await?.as_f64().unwrap_or_default() as u64;
+        committed.await?;
+        self.cap().await?;
+        Ok(id)
+    }
+
+    /// Drop the oldest receipts (by timestamp, then ID) beyond `max_entries`
+    ///
+    /// Not by ID alone: imported receipts get new IDs, whatever their timestamp.
+    async fn cap(&self) -> Result<(), Error> {
+        let excess: usize = self.count().await?.saturating_sub(self.max_entries);
+        if excess == 0 {
+            return Ok(());
+        }
+
+        let store: JsValue = self.store("readwrite")?;
+        let committed = completion(&store)?;
+        let index: JsValue =
+            method(&store, "index")?.call1(&store, &JsValue::from_str(TIMESTAMP_INDEX))?;
+        let request: JsValue = method(&index, "openCursor")?.call0(&index)?;
+
+        // Delete from the cursor callback: the transaction commits once it stops
+        let remaining: Rc<Cell<usize>> = Rc::new(Cell::new(excess));
+        let step: Closure<dyn FnMut(JsValue)> = {
+            let request: JsValue = request.clone();
+            Closure::wrap(Box::new(move |_: JsValue| {
+                let cursor: JsValue =
+                    Reflect::get(&request, &JsValue::from_str("result")).unwrap_or_default();
+                if cursor.is_null() || cursor.is_undefined() || remaining.get() == 0 {
+                    return;
+                }
+                remaining.set(remaining.get() - 1);
+                if let Ok(delete) = method(&cursor, "delete") {
+                    let _ = delete.call0(&cursor);
+                }
+                if let Ok(next) = method(&cursor, "continue") {
+                    let _ = next.call0(&cursor);
+                }
+            }) as Box<dyn FnMut(JsValue)>)
+        };
+        Reflect::set(&request, &JsValue::from_str("onsuccess"), step.as_ref())?;
+        committed.await
+    }
+
+    /// All the receipts, newest first (by timestamp)
+    pub async fn receipts(&self) -> Result<Vec<Receipt>, Error> {
+        self.query(&JournalQuery::default()).await
+    }
+
+    /// Receipts matching `query`, newest first
+    pub async fn query(&self, query: &JournalQuery) -> Result<Vec<Receipt>, Error> {
+        let store: JsValue = self.store("readonly")?;
+        let request: JsValue = method(&store, "getAll")?.call0(&store)?;
+        let values: Array = wait(&request).await?.unchecked_into();
+        let mut receipts: Vec<Receipt> = values
+            .iter()
+            .filter_map(|value| Receipt::from_js(&value))
+            .filter(|receipt| query.matches(receipt))
+            .collect();
+        receipts.sort_by(|a, b| (b.timestamp, b.id).cmp(&(a.timestamp, a.id)));
+        receipts.truncate(query.limit.unwrap_or(usize::MAX));
+        Ok(receipts)
+    }
+
+    /// Export the history as a JSON blob (i.e. to back it up or move it to another device)
+    pub async fn export(&self) -> Result<String, Error> {
+        let receipts = Array::new();
+        for receipt in self.receipts().await?.iter().rev() {
+            let mut receipt: Receipt = receipt.clone();
+            receipt.id = None;
+            receipts.push(&receipt.to_js()?);
+        }
+
+        let blob = Object::new();
+        Reflect::set(
+            &blob,
+            &JsValue::from_str("version"),
+            &JsValue::from(EXPORT_VERSION),
+        )?;
+        Reflect::set(&blob, &JsValue::from_str("receipts"), &receipts)?;
+        Ok(JSON::stringify(&blob)?.into())
+    }
+
+    /// Import a JSON blob created by [`Journal::export`], returning the number of new receipts
+    ///
+    /// Receipts already in the journal (same kind and preimage) are skipped.
+    pub async fn import(&self, json: &str) -> Result<usize, Error> {
+        let blob: JsValue = JSON::parse(json)
+            .map_err(|_| Error::TypeMismatch(String::from("expected a JSON journal export")))?;
+        let version: Option<f64> = Reflect::get(&blob, &JsValue::from_str("version"))?.as_f64();
+        if version != Some(EXPORT_VERSION as f64) {
+            return Err(Error::TypeMismatch(String::from(
+                "unsupported journal export version",
+            )));
+        }
+        let values: Array = Reflect::get(&blob, &JsValue::from_str("receipts"))?
+            .dyn_into()
+            .map_err(|_| Error::TypeMismatch(String::from("expected an array [receipts]")))?;
+
+        let existing: Vec<Receipt> = self.receipts().await?;
+        let mut receipts: Vec<Receipt> = Vec::new();
+        for value in values.iter() {
+            let mut receipt: Receipt = Receipt::from_js(&value)
+                .ok_or_else(|| Error::TypeMismatch(String::from("invalid receipt")))?;
+            receipt.id = None;
+            let is_known = |r: &Receipt| r.kind == receipt.kind && r.preimage == receipt.preimage;
+            if !existing.iter().any(is_known) && !receipts.iter().any(is_known) {
+                receipts.push(receipt);
+            }
+        }
+        if receipts.is_empty() {
+            return Ok(0);
+        }
+
+        // Oldest first, like the live journal
+        receipts.sort_by_key(|receipt| receipt.timestamp);
+        let store: JsValue = self.store("readwrite")?;
+        let committed = completion(&store)?;
+        let add: Function = method(&store, "add")?;
+        for receipt in receipts.iter() {
+            add.call1(&store, &receipt.to_js()?)?;
+        }
+        committed.await?;
+        self.cap().await?;
+        Ok(receipts.len())
+    }
+
+    /// Number of receipts
+    pub async fn count(&self) -> Result<usize, Error> {
+        let store: JsValue = self.store("readonly")?;
+        let request: JsValue = method(&store, "count")?.call0(&store)?;
+        Ok(wait(&request).await?.as_f64().unwrap_or_default() as usize)
+    }
+
+    /// Delete all the receipts
+    pub async fn clear(&self) -> Result<(), Error> {
+        let store: JsValue = self.store("readwrite")?;
+        let committed = completion(&store)?;
+        method(&store, "clear")?.call0(&store)?;
+        committed.await
+    }
+}
+
+/// Create the object store (version 1) and its timestamp index (version 2), on `upgradeneeded`
+fn upgrade(request: &JsValue, event: &JsValue) -> Result<(), Error> {
+    let old_version: f64 = Reflect::get(event, &JsValue::from_str("oldVersion"))?
+        .as_f64()
+        .unwrap_or_default();
+
+    let store: JsValue = if old_version < 1.0 {
+        let db: JsValue = Reflect::get(request, &JsValue::from_str("result"))?;
+        let params = Object::new();
+        Reflect::set(
+            &params,
+            &JsValue::from_str("keyPath"),
+            &JsValue::from_str("id"),
+        )?;
+        Reflect::set(&params, &JsValue::from_str("autoIncrement"), &JsValue::TRUE)?;
+        method(&db, "createObjectStore")?.call2(&db, &JsValue::from_str(STORE), &params)?
+    } else {
+        let transaction: JsValue = Reflect::get(request, &JsValue::from_str("transaction"))?;
+        method(&transaction, "objectStore")?.call1(&transaction, &JsValue::from_str(STORE))?
+    };
+
+    if old_version < 2.0 {
+        method(&store, "createIndex")?.call2(
+            &store,
+            &JsValue::from_str(TIMESTAMP_INDEX),
+            &JsValue::from_str("timestamp"),
+        )?;
+    }
+    Ok(())
+}
+
+impl WebLN {
+    /// Record the successful payments in `journal` (default: disabled)
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        Rc::make_mut(&mut self.inner).journal = Some(journal);
+        self
+    }
+
+    /// Registered journal
+    pub fn journal(&self) -> Option<&Journal> {
+        self.inner.journal.as_ref()
+    }
+
+    /// Record a receipt in the journal, if any (best effort: the payment already succeeded)
+    pub(crate) async fn record(&self, receipt: Receipt) {
+        if let Some(journal) = &self.inner.journal {
+            let _ = journal.append(&receipt).await;
+        }
+    }
+}
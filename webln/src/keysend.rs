@@ -0,0 +1,157 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Keysend emulation through the node RPC passthrough
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use js_sys::{Function, Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::value::expect_object;
+use crate::{
+    base64, hex, Error, GetInfoMethod, KeysendArgs, ProviderError, SendPaymentResponse, Value,
+    WebLN,
+};
+
+/// TLV record type carrying the keysend preimage
+pub const KEYSEND_PREIMAGE_TLV: u64 = 5482373484;
+
+/// LND `SendPaymentV2` method name, as exposed by `request`
+pub(crate) const SEND_PAYMENT_V2: &str = "sendpaymentv2";
+/// Max time LND spends looking for a route
+pub(crate) const TIMEOUT_SECS: u64 = 60;
+/// Min routing fee limit
+const MIN_FEE_LIMIT_SAT: u64 = 10;
+
+fn get_object(obj: &JsValue, key: &str) -> Result<Object, Error> {
+    Reflect::get(obj, &JsValue::from_str(key))
+        .ok()
+        .and_then(|value| value.dyn_into().ok())
+        .ok_or_else(|| Error::NamespaceNotFound(key.to_string()))
+}
+
+fn get_func(obj: &Object, name: &str) -> Result<Function, Error> {
+    get_object(obj, name)?
+        .dyn_into()
+        .map_err(|_| Error::NamespaceNotFound(name.to_string()))
+}
+
+/// Random 32 bytes preimage, from `crypto.getRandomValues`
+fn random_preimage() -> Result<[u8; 32], Error> {
+    let crypto: Object = get_object(&js_sys::global(), "crypto")?;
+    let array = Uint8Array::new_with_length(32);
+    get_func(&crypto, "getRandomValues")?.call1(&crypto, &array)?;
+    let mut preimage: [u8; 32] = [0; 32];
+    array.copy_to(&mut preimage);
+    Ok(preimage)
+}
+
+/// SHA256 digest, from `crypto.subtle`
+async fn sha256(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let crypto: Object = get_object(&js_sys::global(), "crypto")?;
+    let subtle: Object = get_object(&crypto, "subtle")?;
+    let promise: JsValue = get_func(&subtle, "digest")?.call2(
+        &subtle,
+        &JsValue::from_str("SHA-256"),
+        &Uint8Array::from(data),
+    )?;
+    let digest: JsValue = JsFuture::from(Promise::resolve(&promise)).await?;
+    Ok(Uint8Array::new(&digest).to_vec())
+}
+
+/// Routing fee limit for `amount` sats: 1%, at least [`MIN_FEE_LIMIT_SAT`]
+pub(crate) fn fee_limit(amount: u64) -> u64 {
+    (amount / 100).max(MIN_FEE_LIMIT_SAT)
+}
+
+fn set(obj: &Object, key: &str, value: &str) -> Result<(), Error> {
+    Reflect::set(obj, &JsValue::from_str(key), &JsValue::from_str(value))?;
+    Ok(())
+}
+
+/// LND `SendPaymentV2` params (bytes fields are base64 encoded)
+fn params(args: &KeysendArgs, preimage: &[u8], payment_hash: &[u8]) -> Result<Object, Error> {
+    let fee_limit: u64 = fee_limit(args.amount);
+
+    let custom_records = Object::new();
+    set(
+        &custom_records,
+        &KEYSEND_PREIMAGE_TLV.to_string(),
+        &base64::encode(preimage),
+    )?;
+    for (key, value) in args.custom_records.iter() {
+        set(&custom_records, &key.to_string(), &base64::encode(value))?;
+    }
+
+    let params = Object::new();
+    set(
+        &params,
+        "dest",
+        &base64::encode(args.destination.as_bytes()),
+    )?;
+    set(&params, "amt", &args.amount.to_string())?;
+    set(&params, "payment_hash", &base64::encode(payment_hash))?;
+    set(&params, "fee_limit_sat", &fee_limit.to_string())?;
+    set(&params, "timeout_seconds", &TIMEOUT_SECS.to_string())?;
+    Reflect::set(
+        &params,
+        &JsValue::from_str("dest_custom_records"),
+        &custom_records,
+    )?;
+    Reflect::set(
+        &params,
+        &JsValue::from_str("no_inflight_updates"),
+        &JsValue::TRUE,
+    )?;
+    Ok(params)
+}
+
+/// Check the final payment status (the REST API wraps it in `result`), returning the payment
+pub(crate) fn check_status(raw: JsValue) -> Result<Value, Error> {
+    let value: Value = Value::from_js(&raw);
+    let obj: &Value = expect_object(&value)?;
+    let payment: &Value = match obj.get("result") {
+        result if result.is_object() => result,
+        _ => obj,
+    };
+    match payment.get("status").as_str() {
+        None | Some("SUCCEEDED") => Ok(payment.clone()),
+        Some(status) => {
+            let message: String = payment
+                .get("failure_reason")
+                .as_str()
+                .unwrap_or(status)
+                .to_string();
+            Err(Error::Provider(ProviderError {
+                message,
+                raw,
+                ..Default::default()
+            }))
+        }
+    }
+}
+
+impl WebLN {
+    /// Send a keysend payment with LND `SendPaymentV2`, through `request`
+    pub(crate) async fn keysend_via_request(
+        &self,
+        args: &KeysendArgs,
+    ) -> Result<SendPaymentResponse, Error> {
+        self.check_prompt(&GetInfoMethod::Keysend)
+            .map_err(|e| self.call_error(GetInfoMethod::Keysend, e))?;
+
+        let preimage: [u8; 32] = random_preimage()?;
+        let payment_hash: Vec<u8> = sha256(&preimage).await?;
+        let params: Object = params(args, &preimage, &payment_hash)?;
+        let result: JsValue = self
+            .send_request(SEND_PAYMENT_V2, Some(&params), false)
+            .await?;
+        check_status(result).map_err(|e| self.call_error(GetInfoMethod::Request, e))?;
+        Ok(SendPaymentResponse {
+            preimage: hex::encode(preimage),
+        })
+    }
+}
@@ -4,6 +4,31 @@
 //! WebLN - Lightning Web Standard
 //!
 //! <https://webln.guide>
+//!
+//! ## Feature flags
+//!
+//! | Feature        | Default | Description                                                                   |
+//! |----------------|:-------:|-------------------------------------------------------------------------------|
+//! | `std`          |   Yes   | Implements `std::error::Error` for the error types                            |
+//! | `secp256k1`    |   Yes   | `NodeId` to `secp256k1::PublicKey` conversions, [`LoginProof::verify`]        |
+//! | `serde`        |    No   | `Serialize`/`Deserialize` for the public types                                |
+//! | `send_wrapper` |    No   | [`SendWebLN`], a `Send + Sync` handle for frameworks requiring `Send` futures |
+//! | `stream`       |    No   | `futures_core::Stream` for [`BalanceWatcher`] and [`PaymentTracker`]          |
+//! | `rates`        |    No   | `Rates`, fiat conversions of amounts and balances                             |
+//! | `journal`      |    No   | `Journal`, payment history persisted in IndexedDB                             |
+//! | `json`         |    No   | `WebLN::request_json`, with `serde_json::Value` params and results            |
+//! | `webbtc`       |    No   | `WebBTC`, companion for the on-chain provider injected as `window.webbtc`     |
+//! | `nostr`        |    No   | `Nostr`, companion for the NIP-07 signer, and NIP-57 zap receipt validation   |
+//! | `alby`         |    No   | `Alby`, extensions of the Alby browser extension beyond the WebLN spec        |
+//!
+//! ### Minimal configuration
+//!
+//! For size-sensitive WASM bundles, disable the default features: the crate stays fully functional
+//! (node IDs are validated by [`NodeId`] without pulling in libsecp256k1).
+//!
+//! ```toml
+//! webln = { version = "0.4", default-features = false }
+//! ```
 
 #![warn(missing_docs)]
 #![warn(rustdoc::bare_urls)]
@@ -11,18 +36,120 @@
 
 extern crate alloc;
 
+#[cfg(feature = "secp256k1")]
 pub extern crate secp256k1;
 
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::format;
+use alloc::rc::Rc;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::convert::Infallible;
 use core::fmt;
+use core::str::{self, FromStr};
+use core::time::Duration;
 
-use js_sys::{Array, Function, Object, Promise, Reflect};
-use secp256k1::PublicKey;
+use js_sys::{Array, Function, Object, Promise, Reflect, JSON};
 use wasm_bindgen::prelude::*;
-use wasm_bindgen_futures::JsFuture;
-use web_sys::Window;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+use self::value::{
+    expect_object, get_optional_amount, get_optional_array, get_optional_bool, get_optional_object,
+    get_optional_string, get_string,
+};
+
+#[cfg(feature = "alby")]
+mod alby;
+mod amount;
+mod auth;
+mod balance;
+mod base64;
+mod bolt11;
+mod builder;
+mod capabilities;
+mod checkout;
+mod color;
+mod currency;
+mod event;
+mod guard;
+mod hex;
+mod http;
+mod idempotency;
+mod invoice;
+#[cfg(feature = "journal")]
+mod journal;
+mod keysend;
+mod message;
+mod multi;
+mod node_id;
+#[cfg(feature = "nostr")]
+mod nostr;
+mod paid;
+mod payer;
+mod persist;
+mod policy;
+pub mod prelude;
+#[cfg(feature = "rates")]
+mod rates;
+mod raw;
+mod receive;
+mod refund;
+#[cfg(feature = "send_wrapper")]
+mod send;
+mod tab;
+mod time;
+mod tracker;
+mod value;
+#[cfg(feature = "webbtc")]
+mod webbtc;
+#[cfg(feature = "nostr")]
+mod zap;
+#[cfg(feature = "secp256k1")]
+mod zbase32;
+
+#[cfg(feature = "alby")]
+pub use self::alby::{AddAccountArgs, Alby, AlbyInfo};
+pub use self::amount::{Amount, ParseAmountError};
+pub use self::auth::LoginProof;
+pub use self::balance::BalanceWatcher;
+pub use self::bolt11::{Bolt11Invoice, ParseInvoiceError};
+pub use self::builder::WebLNBuilder;
+pub use self::capabilities::Capabilities;
+pub use self::checkout::{Checkout, CheckoutEvent, CheckoutState};
+pub use self::color::{ParseRgbColorError, RgbColor};
+pub use self::currency::Currency;
+pub use self::event::{Event, PaymentFailure, PaymentSuccess, Subscription};
+pub use self::guard::PromptLimit;
+#[cfg(feature = "journal")]
+pub use self::journal::{Journal, JournalQuery, Receipt, ReceiptKind};
+pub use self::keysend::KEYSEND_PREIMAGE_TLV;
+pub use self::message::MessageEncoding;
+pub use self::multi::{MultiPaymentOptions, MultiPaymentProgress};
+pub use self::node_id::{NodeId, ParseNodeIdError, NODE_ID_SIZE};
+#[cfg(feature = "nostr")]
+pub use self::nostr::{Nostr, SignedEvent, UnsignedEvent};
+pub use self::payer::{LightningPayer, PayFuture};
+pub use self::persist::PersistedState;
+pub use self::policy::DestinationFilter;
+#[cfg(feature = "rates")]
+pub use self::rates::{
+    HttpRateSource, RateFuture, RateParser, RateSource, Rates, DEFAULT_RATES_ENDPOINT,
+};
+pub use self::raw::ResponseWithRaw;
+pub use self::receive::IncomingPayment;
+pub use self::refund::REFUND_REFERENCE_TLV;
+#[cfg(feature = "send_wrapper")]
+pub use self::send::SendWebLN;
+pub use self::tab::{InvoiceFuture, Tab, TabStatus};
+pub use self::time::{Clock, Sleep, SystemClock};
+pub use self::tracker::{PaymentStatus, PaymentTracker};
+pub use self::value::Value;
+#[cfg(feature = "webbtc")]
+pub use self::webbtc::{GetAddressResponse, SendTransactionResponse, WebBTC, WebBTCInfo};
+#[cfg(feature = "nostr")]
+pub use self::zap::{ZapReceipt, ZAP_RECEIPT_KIND, ZAP_REQUEST_KIND};
 
 const IS_ENABLED: &str = "isEnabled";
 const ENABLE: &str = "enable";
@@ -31,6 +158,7 @@ const KEYSEND: &str = "keysend";
 const MAKE_INVOICE: &str = "makeInvoice";
 const SEND_PAYMENT: &str = "sendPayment";
 const SEND_PAYMENT_ASYNC: &str = "sendPaymentAsync";
+const SEND_MULTI_PAYMENT: &str = "sendMultiPayment";
 const SIGN_MESSAGE: &str = "signMessage";
 const VERIFY_MESSAGE: &str = "verifyMessage";
 const REQUEST: &str = "request";
@@ -39,11 +167,243 @@ const ON: &str = "on";
 const OFF: &str = "off";
 const GET_BALANCE: &str = "getBalance";
 
+/// Interval between checks in [`WebLN::wait_for_provider`]
+const PROVIDER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Error class names used by providers for user rejections
+const USER_REJECTED_NAMES: [&str; 2] = ["UserRejectedError", "RejectionError"];
+/// Error code used by providers for user rejections (same as EIP-1193)
+const USER_REJECTED_CODE: &str = "4001";
+/// Lowercase messages used by providers for user rejections
+const USER_REJECTED_MESSAGES: [&str; 5] = [
+    "user rejected",
+    "prompt was closed",
+    "user denied",
+    "user cancelled",
+    "user canceled",
+];
+
+/// Matcher used to detect user rejections not recognized by default (i.e. localized messages)
+pub type RejectionMatcher = fn(&ProviderError) -> bool;
+
+/// Error returned by the provider
+#[derive(Debug, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct ProviderError {
+    /// Error class name (i.e. `TypeError` or a provider specific class)
+    pub name: Option<String>,
+    /// Error code
+    pub code: Option<String>,
+    /// Error message
+    pub message: String,
+    /// Additional data (JSON)
+    pub data: Option<String>,
+    /// Original JS value, to inspect provider specific fields or to rethrow it
+    pub raw: JsValue,
+}
+
+impl ProviderError {
+    /// Check if the error is a user rejection, looking at the error class name, code and message
+    pub fn is_user_rejected(&self) -> bool {
+        if let Some(name) = &self.name {
+            if USER_REJECTED_NAMES.contains(&name.as_str()) {
+                return true;
+            }
+        }
+
+        if self.code.as_deref() == Some(USER_REJECTED_CODE) {
+            return true;
+        }
+
+        let message: String = self.message.to_lowercase();
+        USER_REJECTED_MESSAGES.iter().any(|m| message.contains(m))
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProviderError {}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "{name}: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(code) = &self.code {
+            write!(f, " [code={code}]")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&JsValue> for ProviderError {
+    fn from(value: &JsValue) -> Self {
+        // Plain string rejection
+        if let Some(message) = value.as_string() {
+            return Self {
+                message,
+                raw: value.clone(),
+                ..Default::default()
+            };
+        }
+
+        let obj: &Object = match value.dyn_ref() {
+            Some(obj) => obj,
+            None => {
+                return Self {
+                    message: format!("{value:?}"),
+                    raw: value.clone(),
+                    ..Default::default()
+                }
+            }
+        };
+
+        // Custom error classes don't always override `name`: fallback to constructor name
+        let name: Option<String> = match get_value_by_key(obj, "name")
+            .ok()
+            .and_then(|n| n.as_string())
+        {
+            Some(name) if name != "Error" => Some(name),
+            name => obj
+                .constructor()
+                .name()
+                .as_string()
+                .filter(|n| n != "Object")
+                .or(name),
+        };
+        let code: Option<String> = get_value_by_key(obj, "code")
+            .ok()
+            .and_then(|c| c.as_string().or_else(|| c.as_f64().map(|c| c.to_string())));
+        let message: String = get_value_by_key(obj, "message")
+            .ok()
+            .and_then(|m| m.as_string())
+            .unwrap_or_else(|| format!("{value:?}"));
+        let data: Option<String> = get_value_by_key(obj, "data")
+            .ok()
+            .filter(|d| !d.is_undefined())
+            .and_then(|d| JSON::stringify(&d).ok())
+            .and_then(|d| d.as_string());
+
+        Self {
+            name,
+            code,
+            message,
+            data,
+            raw: value.clone(),
+        }
+    }
+}
+
+/// Error category, following the WebLN error classes
+///
+/// <https://github.com/joule-extension/webln/blob/master/src/errors.ts>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "SCREAMING_SNAKE_CASE"))]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// No provider available
+    MissingProvider,
+    /// User rejected the request
+    UserRejected,
+    /// Provider can't connect to the node
+    Connection,
+    /// Method not supported by the provider
+    UnsupportedMethod,
+    /// Payment routing failed
+    Routing,
+    /// Invalid arguments or malformed response
+    InvalidData,
+    /// Invoice expired before being paid
+    Expired,
+    /// Any other error
+    Internal,
+}
+
+impl ErrorCode {
+    /// Get code as `&str` (i.e. `USER_REJECTED`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MissingProvider => "MISSING_PROVIDER",
+            Self::UserRejected => "USER_REJECTED",
+            Self::Connection => "CONNECTION",
+            Self::UnsupportedMethod => "UNSUPPORTED_METHOD",
+            Self::Routing => "ROUTING",
+            Self::InvalidData => "INVALID_DATA",
+            Self::Expired => "EXPIRED",
+            Self::Internal => "INTERNAL",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<&ProviderError> for ErrorCode {
+    fn from(e: &ProviderError) -> Self {
+        if e.is_user_rejected() {
+            return Self::UserRejected;
+        }
+
+        match e.name.as_deref() {
+            Some("MissingProviderError") => Self::MissingProvider,
+            Some("ConnectionError") => Self::Connection,
+            Some("UnsupportedMethodError") => Self::UnsupportedMethod,
+            Some("RoutingError") => Self::Routing,
+            Some("InvalidDataError") => Self::InvalidData,
+            _ => Self::Internal,
+        }
+    }
+}
+
 /// WebLN error
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    /// Generic WASM error
-    Wasm(String),
+    /// Error returned by the provider
+    Provider(ProviderError),
+    /// Secp256k1 error
+    #[cfg(feature = "secp256k1")]
+    Secp256k1(secp256k1::Error),
+    /// Invalid node ID
+    NodeId(ParseNodeIdError),
+    /// Invalid amount
+    Amount(ParseAmountError),
+    /// Invalid BOLT11 invoice
+    Invoice(ParseInvoiceError),
+    /// Invalid UTF-8 message
+    Utf8(str::Utf8Error),
+    /// Invalid signature, or signed by another key
+    InvalidSignature,
+    /// The invoice amount differs from the requested one
+    AmountMismatch {
+        /// Invoice amount
+        invoice: Amount,
+        /// Requested amount
+        requested: Amount,
+    },
+    /// The payment amount is over [`WebLN::with_max_amount`]
+    AmountExceedsLimit {
+        /// Payment amount
+        amount: Amount,
+        /// Max amount
+        limit: Amount,
+    },
+    /// The invoice doesn't set an amount (the wallet picks it), so it can't be checked against
+    /// [`WebLN::with_max_amount`]: use [`WebLN::send_payment_with_amount`]
+    UnknownAmount,
+    /// Keysend destination rejected by [`WebLN::with_keysend_filter`]
+    DestinationNotAllowed(NodeId),
+    /// Provider method call failed
+    Call {
+        /// Called method
+        method: GetInfoMethod,
+        /// Error
+        source: Box<Error>,
+    },
     /// Impossible to get window
     NoGlobalWindowObject,
     /// Impossible to get window
@@ -53,44 +413,258 @@ pub enum Error {
     /// Invalid type: expected a string
     TypeMismatch(String),
     /// User rejected
-    UserRejected,
+    UserRejected(ProviderError),
+    /// The provider answered `enable()` without enabling (i.e. `{ enabled: false }`)
+    NotEnabled,
     /// Empty invoice
     EmptyInvoice,
+    /// BOLT12 offers (`lno1...`) can't be paid with `sendPayment`
+    Bolt12Unsupported,
+    /// The provider didn't answer in time
+    Timeout,
+    /// Invoice expired before being paid
+    InvoiceExpired,
+    /// Payment failed, with the reason reported by the node
+    PaymentFailed(String),
+    /// Invoice already paid (payment hash), see [`WebLN::with_paid_invoice_memory`]
+    AlreadyPaid(String),
+    /// Idempotency key already used for another payment
+    IdempotencyKeyReused(String),
+    /// Too many prompting calls, see [`WebLN::with_prompt_limit`]
+    RateLimited {
+        /// Time until a new call is allowed
+        retry_after: Duration,
+    },
+    /// HTTP request failed with the status code
+    Http(u16),
+    /// Fiat rate not available for the currency
+    #[cfg(feature = "rates")]
+    RateUnavailable(String),
+    /// Zap receipt malformed, or not matching the zap request
+    #[cfg(feature = "nostr")]
+    InvalidZapReceipt(String),
     /// Something's gone wrong
     SomethingGoneWrong,
 }
 
+impl Error {
+    /// Get the underlying error, skipping the [`Error::Call`] context
+    pub fn inner(&self) -> &Self {
+        match self {
+            Self::Call { source, .. } => source.inner(),
+            e => e,
+        }
+    }
+
+    /// Classify the error
+    pub fn code(&self) -> ErrorCode {
+        match self.inner() {
+            Self::Provider(e) => ErrorCode::from(e),
+            Self::UserRejected(..) | Self::NotEnabled => ErrorCode::UserRejected,
+            Self::NoGlobalWindowObject => ErrorCode::MissingProvider,
+            Self::NamespaceNotFound(n)
+                if matches!(n.as_str(), "webln" | "webbtc" | "nostr" | "alby") =>
+            {
+                ErrorCode::MissingProvider
+            }
+            Self::NamespaceNotFound(..) | Self::Bolt12Unsupported => ErrorCode::UnsupportedMethod,
+            Self::Timeout | Self::Http(..) => ErrorCode::Connection,
+            Self::InvoiceExpired => ErrorCode::Expired,
+            Self::PaymentFailed(..) => ErrorCode::Routing,
+            #[cfg(feature = "rates")]
+            Self::RateUnavailable(..) => ErrorCode::Connection,
+            #[cfg(feature = "secp256k1")]
+            Self::Secp256k1(..) => ErrorCode::InvalidData,
+            #[cfg(feature = "nostr")]
+            Self::InvalidZapReceipt(..) => ErrorCode::InvalidData,
+            Self::ObjectKeyNotFound(..)
+            | Self::TypeMismatch(..)
+            | Self::NodeId(..)
+            | Self::Amount(..)
+            | Self::Invoice(..)
+            | Self::Utf8(..)
+            | Self::InvalidSignature
+            | Self::AmountMismatch { .. }
+            | Self::AmountExceedsLimit { .. }
+            | Self::UnknownAmount
+            | Self::DestinationNotAllowed(..)
+            | Self::AlreadyPaid(..)
+            | Self::IdempotencyKeyReused(..)
+            | Self::EmptyInvoice
+            | Self::SomethingGoneWrong => ErrorCode::InvalidData,
+            Self::Call { .. } | Self::RateLimited { .. } => ErrorCode::Internal,
+        }
+    }
+
+    /// Check if the user rejected the request
+    pub fn is_user_rejected(&self) -> bool {
+        matches!(self.inner(), Self::UserRejected(..) | Self::NotEnabled)
+    }
+
+    /// Get the original JS value thrown or rejected by the provider, if any
+    pub fn js_value(&self) -> Option<&JsValue> {
+        match self.inner() {
+            Self::Provider(e) | Self::UserRejected(e) => Some(&e.raw),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(feature = "std")]
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Provider(e) | Self::UserRejected(e) => Some(e),
+            #[cfg(feature = "secp256k1")]
+            Self::Secp256k1(e) => Some(e),
+            Self::NodeId(e) => Some(e),
+            Self::Amount(e) => Some(e),
+            Self::Invoice(e) => Some(e),
+            Self::Utf8(e) => Some(e),
+            Self::Call { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Wasm(e) => write!(f, "{e}"),
+            Self::Provider(e) => write!(f, "{e}"),
+            #[cfg(feature = "secp256k1")]
+            Self::Secp256k1(e) => write!(f, "{e}"),
+            Self::NodeId(e) => write!(f, "{e}"),
+            Self::Amount(e) => write!(f, "{e}"),
+            Self::Invoice(e) => write!(f, "{e}"),
+            Self::Utf8(e) => write!(f, "{e}"),
+            Self::InvalidSignature => write!(f, "Invalid signature"),
+            Self::AmountMismatch { invoice, requested } => write!(
+                f,
+                "Amount mismatch: invoice is for {invoice}, requested {requested}"
+            ),
+            Self::AmountExceedsLimit { amount, limit } => {
+                write!(f, "Amount {amount} exceeds the limit of {limit}")
+            }
+            Self::UnknownAmount => write!(f, "Invoice amount unknown, can't check the limit"),
+            Self::DestinationNotAllowed(node_id) => {
+                write!(f, "Keysend destination {node_id} not allowed")
+            }
+            Self::Call { method, source } => write!(f, "{method} failed: {source}"),
             Self::NoGlobalWindowObject => write!(f, "No global `window` object"),
             Self::NamespaceNotFound(n) => write!(f, "`{n}` namespace not found"),
             Self::ObjectKeyNotFound(n) => write!(f, "Key `{n}` not found in object"),
             Self::TypeMismatch(e) => write!(f, "Type mismatch: {e}"),
-            Self::UserRejected => write!(f, "User rejected"),
+            Self::UserRejected(..) => write!(f, "User rejected"),
+            Self::NotEnabled => write!(f, "Provider not enabled"),
             Self::EmptyInvoice => write!(f, "Empty invoice"),
+            Self::Bolt12Unsupported => write!(f, "BOLT12 offers are not supported"),
+            Self::Timeout => write!(f, "Timeout"),
+            Self::InvoiceExpired => write!(f, "Invoice expired"),
+            Self::PaymentFailed(reason) => write!(f, "Payment failed: {reason}"),
+            Self::AlreadyPaid(hash) => write!(f, "Invoice already paid (payment hash {hash})"),
+            Self::IdempotencyKeyReused(key) => {
+                write!(
+                    f,
+                    "Idempotency key `{key}` already used for another payment"
+                )
+            }
+            Self::RateLimited { retry_after } => write!(
+                f,
+                "Too many wallet prompts: retry in {} ms",
+                retry_after.as_millis()
+            ),
+            Self::Http(status) => write!(f, "HTTP error {status}"),
+            #[cfg(feature = "rates")]
+            Self::RateUnavailable(c) => write!(f, "`{c}` rate not available"),
+            #[cfg(feature = "nostr")]
+            Self::InvalidZapReceipt(e) => write!(f, "Invalid zap receipt: {e}"),
             Self::SomethingGoneWrong => write!(f, "Something's gone wrong"),
         }
     }
 }
 
+#[cfg(feature = "secp256k1")]
+impl From<secp256k1::Error> for Error {
+    fn from(e: secp256k1::Error) -> Self {
+        Self::Secp256k1(e)
+    }
+}
+
+impl From<ParseNodeIdError> for Error {
+    fn from(e: ParseNodeIdError) -> Self {
+        Self::NodeId(e)
+    }
+}
+
+impl From<ParseAmountError> for Error {
+    fn from(e: ParseAmountError) -> Self {
+        Self::Amount(e)
+    }
+}
+
+impl From<ParseInvoiceError> for Error {
+    fn from(e: ParseInvoiceError) -> Self {
+        Self::Invoice(e)
+    }
+}
+
+impl From<str::Utf8Error> for Error {
+    fn from(e: str::Utf8Error) -> Self {
+        Self::Utf8(e)
+    }
+}
+
 impl From<JsValue> for Error {
     fn from(e: JsValue) -> Self {
-        let error: String = format!("{e:?}");
-        if error.contains("User rejected") {
-            Self::UserRejected
+        let error = ProviderError::from(&e);
+        if error.is_user_rejected() {
+            Self::UserRejected(error)
         } else {
-            Self::Wasm(error)
+            Self::Provider(error)
         }
     }
 }
 
+/// How malformed optional fields of provider responses are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseMode {
+    /// Malformed optional fields are set to `None` (default)
+    #[default]
+    Lenient,
+    /// Malformed optional fields fail the response with [`Error::TypeMismatch`]
+    Strict,
+}
+
+/// Deserialize a provider response
+///
+/// Responses are converted to a [`Value`] first: implement [`Deserialize::from_value`].
+pub trait Deserialize: Sized {
+    /// Deserialize from the intermediate representation of the value resolved by the provider
+    ///
+    /// Types without optional fields ignore `mode`.
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error>;
+
+    /// Deserialize from the value resolved by the provider, in [`ParseMode::Lenient`] mode
+    fn deserialize(value: &JsValue) -> Result<Self, Error> {
+        Self::deserialize_with_mode(value, ParseMode::Lenient)
+    }
+
+    /// Deserialize from the value resolved by the provider
+    fn deserialize_with_mode(value: &JsValue, mode: ParseMode) -> Result<Self, Error> {
+        Self::from_value(&Value::from_js(value), mode)
+    }
+}
+
+/// Get value from object key
+fn get_value_by_key(obj: &Object, key: &str) -> Result<JsValue, Error> {
+    Reflect::get(obj, &JsValue::from_str(key))
+        .map_err(|_| Error::ObjectKeyNotFound(key.to_string()))
+}
+
 /// Get Info Node Response
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct GetInfoNode {
     /// Alias
     pub alias: Option<String>,
@@ -100,9 +674,19 @@ pub struct GetInfoNode {
     pub color: Option<String>,
 }
 
+impl GetInfoNode {
+    /// Parse node color
+    ///
+    /// Returns `None` if the color is missing or invalid.
+    pub fn rgb_color(&self) -> Option<RgbColor> {
+        self.color.as_deref()?.parse().ok()
+    }
+}
+
 /// Get Info Method Response
 #[allow(missing_docs)]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
 pub enum GetInfoMethod {
     IsEnabled,
     Enable,
@@ -111,6 +695,7 @@ pub enum GetInfoMethod {
     MakeInvoice,
     SendPayment,
     SendPaymentAsync,
+    SendMultiPayment,
     SignMessage,
     VerifyMessage,
     Request,
@@ -131,6 +716,7 @@ impl From<&str> for GetInfoMethod {
             MAKE_INVOICE => Self::MakeInvoice,
             SEND_PAYMENT => Self::SendPayment,
             SEND_PAYMENT_ASYNC => Self::SendPaymentAsync,
+            SEND_MULTI_PAYMENT => Self::SendMultiPayment,
             SIGN_MESSAGE => Self::SignMessage,
             VERIFY_MESSAGE => Self::VerifyMessage,
             REQUEST => Self::Request,
@@ -138,11 +724,66 @@ impl From<&str> for GetInfoMethod {
             ON => Self::On,
             OFF => Self::Off,
             GET_BALANCE => Self::GetBalance,
-            other => Self::Other(other.to_string()),
+            other => Self::from_alias(other),
+        }
+    }
+}
+
+impl From<String> for GetInfoMethod {
+    fn from(method: String) -> Self {
+        match Self::from(method.as_str()) {
+            Self::Other(other) if other == method => Self::Other(method),
+            method => method,
+        }
+    }
+}
+
+impl GetInfoMethod {
+    /// Standard method names
+    const STANDARD: [&'static str; 15] = [
+        IS_ENABLED,
+        ENABLE,
+        GET_INFO,
+        KEYSEND,
+        MAKE_INVOICE,
+        SEND_PAYMENT,
+        SEND_PAYMENT_ASYNC,
+        SEND_MULTI_PAYMENT,
+        SIGN_MESSAGE,
+        VERIFY_MESSAGE,
+        REQUEST,
+        LNURL,
+        ON,
+        OFF,
+        GET_BALANCE,
+    ];
+
+    /// Map the names reported with another casing, separators or a `webln.` prefix (i.e.
+    /// `send_payment`, `SendPayment` or `webln.keysend`) onto the standard variants
+    ///
+    /// Unknown names are only trimmed.
+    fn from_alias(method: &str) -> Self {
+        let method: &str = method.trim();
+        let name: &str = method.strip_prefix("webln.").unwrap_or(method);
+        let name: String = name.chars().filter(|c| !matches!(c, '_' | '-')).collect();
+        match Self::STANDARD
+            .iter()
+            .find(|standard| standard.eq_ignore_ascii_case(&name))
+        {
+            Some(standard) => Self::from(*standard),
+            None => Self::Other(method.to_string()),
         }
     }
 }
 
+impl FromStr for GetInfoMethod {
+    type Err = Infallible;
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(method))
+    }
+}
+
 impl fmt::Display for GetInfoMethod {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -153,6 +794,7 @@ impl fmt::Display for GetInfoMethod {
             Self::MakeInvoice => write!(f, "{MAKE_INVOICE}"),
             Self::SendPayment => write!(f, "{SEND_PAYMENT}"),
             Self::SendPaymentAsync => write!(f, "{SEND_PAYMENT_ASYNC}"),
+            Self::SendMultiPayment => write!(f, "{SEND_MULTI_PAYMENT}"),
             Self::SignMessage => write!(f, "{SIGN_MESSAGE}"),
             Self::VerifyMessage => write!(f, "{VERIFY_MESSAGE}"),
             Self::Request => write!(f, "{REQUEST}"),
@@ -165,39 +807,380 @@ impl fmt::Display for GetInfoMethod {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for GetInfoMethod {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for GetInfoMethod {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let method: String = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(Self::from(method))
+    }
+}
+
 /// Get Info Response
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct GetInfoResponse {
-    /// Node
+    /// Node (empty if not returned by the provider)
+    #[cfg_attr(feature = "serde", serde(default))]
     pub node: GetInfoNode,
-    /// Methods list
+    /// Methods list (empty if not returned by the provider)
+    #[cfg_attr(feature = "serde", serde(default))]
     pub methods: Vec<GetInfoMethod>,
 }
 
+impl GetInfoResponse {
+    /// Get supported methods as [`Capabilities`]
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::from(self)
+    }
+}
+
+impl Deserialize for GetInfoResponse {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let get_info_obj: &Value = expect_object(value)?;
+
+        // Some wallets omit `node` or `methods`: treat them as empty
+        let node: GetInfoNode = match get_optional_object(get_info_obj, "node", mode)? {
+            Some(node_obj) => GetInfoNode {
+                alias: get_optional_string(node_obj, "alias", mode)?,
+                pubkey: get_optional_string(node_obj, "pubkey", mode)?,
+                color: get_optional_string(node_obj, "color", mode)?,
+            },
+            None => GetInfoNode::default(),
+        };
+        let methods: Vec<GetInfoMethod> = get_optional_array(get_info_obj, "methods", mode)?
+            .map(|methods_array| {
+                methods_array
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(GetInfoMethod::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self { node, methods })
+    }
+}
+
+/// Enable Response
+///
+/// The WebLN spec resolves `enable()` without a value: then the provider is enabled. Some
+/// providers resolve with a bool or with `{ enabled, remember }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct EnableResponse {
+    /// Whether the provider is now enabled
+    pub enabled: bool,
+    /// Whether the permission is remembered, if reported by the provider
+    pub remember: Option<bool>,
+}
+
+impl Deserialize for EnableResponse {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let (enabled, remember): (bool, Option<bool>) = match value {
+            Value::Undefined | Value::Null => (true, None),
+            Value::Bool(enabled) => (*enabled, None),
+            value => {
+                let enable_obj: &Value = expect_object(value)?;
+                (
+                    get_optional_bool(enable_obj, "enabled", mode)?.unwrap_or(true),
+                    get_optional_bool(enable_obj, "remember", mode)?,
+                )
+            }
+        };
+        Ok(Self { enabled, remember })
+    }
+}
+
 /// Keysend args
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct KeysendArgs {
     /// Public key of the destination node.
-    pub destination: PublicKey,
+    pub destination: NodeId,
     /// Amount in SAT
     pub amount: u64,
-    // TODO: add TLVRegistry enum
-    // The key should be a stringified integer from the <https://github.com/satoshisstream/satoshis.stream/blob/main/TLV_registry.md>.
-    // The value should be an unencoded, plain string.
-    // pub custom: Option<HashMap<String, String>>,
+    /// Custom TLV records
+    ///
+    /// Keys are record types from the <https://github.com/satoshisstream/satoshis.stream/blob/main/TLV_registry.md>,
+    /// values are unencoded, plain strings.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "customRecords",
+            default,
+            skip_serializing_if = "BTreeMap::is_empty"
+        )
+    )]
+    pub custom_records: BTreeMap<u64, String>,
+}
+
+impl KeysendArgs {
+    /// New keysend args
+    pub fn new(destination: NodeId, amount: u64) -> Self {
+        Self {
+            destination,
+            amount,
+            custom_records: BTreeMap::new(),
+        }
+    }
+
+    /// Add a custom TLV record (i.e. a boostagram)
+    pub fn custom_record<S>(mut self, key: u64, value: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.custom_records.insert(key, value.into());
+        self
+    }
+
+    /// Add custom TLV records
+    pub fn custom_records<I, S>(mut self, records: I) -> Self
+    where
+        I: IntoIterator<Item = (u64, S)>,
+        S: Into<String>,
+    {
+        self.custom_records
+            .extend(records.into_iter().map(|(key, value)| (key, value.into())));
+        self
+    }
 }
 
 /// Send Payment Response
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct SendPaymentResponse {
     /// Preimage
     pub preimage: String,
 }
 
+impl Deserialize for SendPaymentResponse {
+    fn from_value(value: &Value, _mode: ParseMode) -> Result<Self, Error> {
+        let send_payment_obj: &Value = expect_object(value)?;
+        Ok(Self {
+            preimage: get_string(send_payment_obj, "preimage")?,
+        })
+    }
+}
+
+/// Send Payment Async Response
+///
+/// The provider accepted the payment, but it may still be in flight.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub struct SendPaymentAsyncResponse {
+    /// Payment hash, if returned by the provider
+    pub payment_hash: Option<String>,
+}
+
+impl Deserialize for SendPaymentAsyncResponse {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let send_payment_async_obj: &Value = expect_object(value)?;
+        Ok(Self {
+            payment_hash: get_optional_string(send_payment_async_obj, "paymentHash", mode)?,
+        })
+    }
+}
+
+/// Route of a payment, when reported by the provider (i.e. Alby `route`, from LND)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct PaymentRoute {
+    /// Amount sent, fees included
+    pub total_amount: Option<Amount>,
+    /// Fees paid
+    pub total_fees: Option<Amount>,
+}
+
+impl PaymentRoute {
+    /// Parse the `route` of a payment result, preferring the msat fields
+    fn parse(obj: &Value, mode: ParseMode) -> Result<Option<Self>, Error> {
+        let route: &Value = match get_optional_object(obj, "route", mode)? {
+            Some(route) => route,
+            None => return Ok(None),
+        };
+        let total_amount: Option<Amount> =
+            match get_optional_amount(route, "total_amt_msat", Amount::from_msat, mode)? {
+                Some(amount) => Some(amount),
+                None => get_optional_amount(route, "total_amt", Amount::from_sats, mode)?,
+            };
+        let total_fees: Option<Amount> =
+            match get_optional_amount(route, "total_fees_msat", Amount::from_msat, mode)? {
+                Some(fees) => Some(fees),
+                None => get_optional_amount(route, "total_fees", Amount::from_sats, mode)?,
+            };
+        Ok(Some(Self {
+            total_amount,
+            total_fees,
+        }))
+    }
+}
+
+/// Fee and route of a payment result: the fee is read from `feeMsat`, `fee` (sats) or the route
+pub(crate) fn parse_payment_costs(
+    obj: &Value,
+    mode: ParseMode,
+) -> Result<(Option<Amount>, Option<PaymentRoute>), Error> {
+    let route: Option<PaymentRoute> = PaymentRoute::parse(obj, mode)?;
+    let fee: Option<Amount> = match get_optional_amount(obj, "feeMsat", Amount::from_msat, mode)? {
+        Some(fee) => Some(fee),
+        None => match get_optional_amount(obj, "fee", Amount::from_sats, mode)? {
+            Some(fee) => Some(fee),
+            None => route.and_then(|route| route.total_fees),
+        },
+    };
+    Ok((fee, route))
+}
+
+/// Payment sent by `sendMultiPayment`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub struct MultiPaymentItem {
+    /// Invoice
+    pub payment_request: String,
+    /// Preimage
+    pub preimage: String,
+    /// Fee paid, if reported by the provider
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fee: Option<Amount>,
+    /// Route, if reported by the provider
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub route: Option<PaymentRoute>,
+}
+
+impl Deserialize for MultiPaymentItem {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let item_obj: &Value = expect_object(value)?;
+        let (fee, route) = parse_payment_costs(item_obj, mode)?;
+        Ok(Self {
+            payment_request: get_string(item_obj, "paymentRequest")?,
+            preimage: get_string(item_obj, "preimage")?,
+            fee,
+            route,
+        })
+    }
+}
+
+/// Payment failed in `sendMultiPayment`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub struct MultiPaymentError {
+    /// Invoice
+    pub payment_request: String,
+    /// Error message
+    pub message: String,
+}
+
+impl Deserialize for MultiPaymentError {
+    fn from_value(value: &Value, _mode: ParseMode) -> Result<Self, Error> {
+        let error_obj: &Value = expect_object(value)?;
+        Ok(Self {
+            payment_request: get_string(error_obj, "paymentRequest")?,
+            message: get_string(error_obj, "message")?,
+        })
+    }
+}
+
+/// Send multi payment response
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct SendMultiPaymentResponse {
+    /// Successful payments
+    pub payments: Vec<MultiPaymentItem>,
+    /// Failed payments
+    pub errors: Vec<MultiPaymentError>,
+    /// Invoices skipped because already in the batch (see [`MultiPaymentOptions::deduplicate`])
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub duplicates: Vec<String>,
+}
+
+/// `keysend` args object
+fn keysend_obj(args: &KeysendArgs) -> Result<Object, Error> {
+    let keysend_obj = Object::new();
+    Reflect::set(
+        &keysend_obj,
+        &JsValue::from_str("destination"),
+        &args.destination.to_string().into(),
+    )?;
+    Reflect::set(
+        &keysend_obj,
+        &JsValue::from_str("amount"),
+        &args.amount.to_string().into(),
+    )?;
+
+    if !args.custom_records.is_empty() {
+        let custom_records_obj = Object::new();
+        for (key, value) in args.custom_records.iter() {
+            Reflect::set(
+                &custom_records_obj,
+                &JsValue::from_str(&key.to_string()),
+                &JsValue::from_str(value),
+            )?;
+        }
+        Reflect::set(
+            &keysend_obj,
+            &JsValue::from_str("customRecords"),
+            &custom_records_obj.into(),
+        )?;
+    }
+
+    Ok(keysend_obj)
+}
+
+fn deserialize_array<T>(obj: &Value, key: &str, mode: ParseMode) -> Result<Vec<T>, Error>
+where
+    T: Deserialize,
+{
+    obj.get(key)
+        .as_array()
+        .ok_or_else(|| Error::TypeMismatch(format!("expected an array [{key}]")))?
+        .iter()
+        .map(|value| T::from_value(value, mode))
+        .collect()
+}
+
+impl Deserialize for SendMultiPaymentResponse {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let send_multi_payment_obj: &Value = expect_object(value)?;
+        Ok(Self {
+            payments: deserialize_array(send_multi_payment_obj, "payments", mode)?,
+            errors: deserialize_array(send_multi_payment_obj, "errors", mode)?,
+            duplicates: Vec::new(),
+        })
+    }
+}
+
 /// Request invoice args
 ///
 /// **All amounts are denominated in SAT.**
 #[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
 pub struct RequestInvoiceArgs {
     /// Amount
     pub amount: Option<u64>,
@@ -300,13 +1283,26 @@ impl TryFrom<&RequestInvoiceArgs> for Object {
 
 /// Request Invoice Response
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct RequestInvoiceResponse {
     /// BOLT-11 invoice
     pub invoice: String,
 }
 
+impl Deserialize for RequestInvoiceResponse {
+    fn from_value(value: &Value, _mode: ParseMode) -> Result<Self, Error> {
+        let request_invoice_response_obj: &Value = expect_object(value)?;
+        Ok(Self {
+            invoice: get_string(request_invoice_response_obj, "paymentRequest")?,
+        })
+    }
+}
+
 /// Sign Message Response
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct SignMessageResponse {
     /// Message
     pub message: String,
@@ -316,34 +1312,398 @@ pub struct SignMessageResponse {
 
 /// Balance Response
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct BalanceResponse {
     /// Balance
+    ///
+    /// Use [`BalanceResponse::msat`] or [`BalanceResponse::sats`] for exact amounts.
     pub balance: f64,
     /// Currency
     pub currency: Option<String>,
 }
 
+/// Parse a non-negative decimal number as an integer in units of `10^-scale`
+///
+/// `None` if it has more than `scale` significant decimals or overflows.
+fn decimal_to_units(decimal: &str, scale: u32) -> Option<u64> {
+    let (int, frac) = decimal.split_once('.').unwrap_or((decimal, ""));
+    let frac: &str = frac.trim_end_matches('0');
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if int.is_empty() || !is_digits(int) || !is_digits(frac) || frac.len() > scale as usize {
+        return None;
+    }
+
+    let int: u64 = int.parse::<u64>().ok()?.checked_mul(10u64.pow(scale))?;
+    let frac: u64 = match frac.len() {
+        0 => 0,
+        len => frac.parse::<u64>().ok()? * 10u64.pow(scale - len as u32),
+    };
+    int.checked_add(frac)
+}
+
+impl Deserialize for BalanceResponse {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let balance_response_obj: &Value = expect_object(value)?;
+
+        // Extract data
+        let balance: f64 = balance_response_obj
+            .get("balance")
+            .as_f64()
+            .ok_or_else(|| Error::TypeMismatch(String::from("expected a number [balance]")))?;
+        let currency: Option<String> = get_optional_string(balance_response_obj, "currency", mode)?;
+
+        Ok(Self { balance, currency })
+    }
+}
+
+impl BalanceResponse {
+    /// Normalized [`Currency`] (sats when the provider doesn't specify one)
+    pub fn currency_type(&self) -> Currency {
+        self.currency
+            .as_deref()
+            .map(|currency| currency.parse().unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    /// Exact balance in millisatoshis, for sats and BTC balances
+    ///
+    /// Parsed from the shortest decimal representation of the provider number, so `0.1` BTC is
+    /// exactly `10_000_000_000` msat. `None` for fiat currencies and for negative, non-finite or
+    /// sub-millisatoshi values.
+    pub fn msat(&self) -> Option<u64> {
+        let scale: u32 = match self.currency_type() {
+            Currency::Sats => 3,
+            Currency::Btc => 11,
+            Currency::Fiat(..) => return None,
+        };
+        if !self.balance.is_finite() {
+            return None;
+        }
+        decimal_to_units(&self.balance.to_string(), scale)
+    }
+
+    /// Exact balance in satoshis, for sats and BTC balances
+    ///
+    /// `None` when [`BalanceResponse::msat`] is `None` or not a whole number of sats.
+    pub fn sats(&self) -> Option<u64> {
+        let msat: u64 = self.msat()?;
+        (msat % 1000 == 0).then_some(msat / 1000)
+    }
+
+    /// Exact balance as [`Amount`], for sats and BTC balances
+    pub fn amount(&self) -> Option<Amount> {
+        self.msat().map(Amount::from_msat)
+    }
+}
+
+/// Cheap checks before calling the provider
+fn check_invoice(invoice: &str) -> Result<(), Error> {
+    // `lightning-invoice` increase too much the WASM binary size: the wallet validates invoices
+    if invoice.is_empty() {
+        return Err(Error::EmptyInvoice);
+    }
+
+    if bolt11::is_bolt12_offer(invoice) {
+        return Err(Error::Bolt12Unsupported);
+    }
+
+    Ok(())
+}
+
 /// WebLN instance
+///
+/// Wraps JS objects, so it's neither `Send` nor `Sync`: enable the `send_wrapper` feature
+/// and use `SendWebLN` when a framework requires `Send` futures.
+///
+/// Cloning is cheap: clones share the same state and configuration. The `with_*` methods
+/// configure only the instance they're called on, which keeps sharing the state (cache, pending
+/// payments, paid invoices and prompt counts) with the instance it was cloned from.
 #[derive(Debug, Clone)]
 pub struct WebLN {
-    /// `window.webln` object
-    webln_obj: Object,
+    inner: Rc<InnerWebLN>,
 }
 
-unsafe impl Send for WebLN {}
+/// Pending `sendPayment` call shared by the callers paying the same invoice, removed from
+/// [`InnerWebLN::in_flight`] when its owner completes or is dropped (i.e. on a timeout)
+struct InFlight<'a> {
+    webln: &'a WebLN,
+    invoice: &'a str,
+}
 
-unsafe impl Sync for WebLN {}
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.webln.inner.in_flight.borrow_mut().remove(self.invoice);
+    }
+}
+
+/// Last known provider state, cleared by [`WebLN::invalidate`]
+#[derive(Debug, Clone, Default)]
+struct Cache {
+    enabled: Option<bool>,
+    info: Option<GetInfoResponse>,
+    balance: Option<BalanceResponse>,
+}
+
+#[derive(Debug, Clone)]
+struct InnerWebLN {
+    /// `window.webln` object
+    webln_obj: Object,
+    /// Additional user rejection matchers
+    rejection_matchers: Vec<RejectionMatcher>,
+    /// Emulate `keysend` through `request` when unsupported
+    keysend_fallback: bool,
+    /// Emulate `makeInvoice` through `request` when unsupported
+    make_invoice_fallback: bool,
+    /// Last known state
+    cache: Rc<RefCell<Cache>>,
+    /// Response parsing mode
+    parse_mode: ParseMode,
+    /// `window` property of the provider, if any
+    namespace: Option<String>,
+    /// `localStorage` key where to save the enabled state
+    persistence: Option<String>,
+    /// Journal of the successful payments
+    #[cfg(feature = "journal")]
+    journal: Option<Journal>,
+    /// Time source for timeouts and polling
+    clock: Rc<dyn Clock>,
+    /// Rate limit of the prompting calls
+    prompt_guard: Option<guard::PromptGuard>,
+    /// Pending `sendPayment` calls, by invoice
+    in_flight: Rc<RefCell<BTreeMap<String, Promise>>>,
+    /// Pending and successful payments, by idempotency key
+    idempotency: Rc<RefCell<BTreeMap<String, (idempotency::Payment, idempotency::Entry)>>>,
+    /// Paid invoices, if remembered
+    paid: Option<paid::PaidInvoices>,
+    /// Max amount of a single payment
+    max_amount: Option<Amount>,
+    /// Keysend destinations filter
+    keysend_filter: Option<DestinationFilter>,
+}
 
 impl WebLN {
     /// Compose new WebLN instance
     pub fn new() -> Result<Self, Error> {
-        let window: Window = web_sys::window().ok_or(Error::NoGlobalWindowObject)?;
-        let namespace: JsValue = Reflect::get(&window, &JsValue::from_str("webln"))
-            .map_err(|_| Error::NamespaceNotFound(String::from("webln")))?;
-        let webln_obj: Object = namespace
+        Self::from_namespace("webln")
+    }
+
+    /// Compose new WebLN instance from a provider injected as `window.<namespace>`
+    ///
+    /// The provider is looked up on `globalThis`: in a Web Worker, it's found if the app exposes
+    /// it there (or use [`WebLN::from_provider`] with an object passed to the worker).
+    pub fn from_namespace(namespace: &str) -> Result<Self, Error> {
+        let provider: JsValue = Reflect::get(&js_sys::global(), &JsValue::from_str(namespace))
+            .map_err(|_| Error::NamespaceNotFound(namespace.to_string()))?;
+        let webln_obj: Object = provider
             .dyn_into()
-            .map_err(|_| Error::NamespaceNotFound(String::from("webln")))?;
-        Ok(Self { webln_obj })
+            .map_err(|_| Error::NamespaceNotFound(namespace.to_string()))?;
+        let mut webln: Self = Self::from_provider(webln_obj);
+        Rc::make_mut(&mut webln.inner).namespace = Some(namespace.to_string());
+        Ok(webln)
+    }
+
+    /// Compose new WebLN instance from a provider object obtained elsewhere than `window.webln`
+    /// (i.e. Bitcoin Connect's `requestProvider()`)
+    pub fn from_provider(provider: Object) -> Self {
+        Self {
+            inner: Rc::new(InnerWebLN {
+                webln_obj: provider,
+                rejection_matchers: Vec::new(),
+                keysend_fallback: false,
+                make_invoice_fallback: false,
+                cache: Rc::new(RefCell::new(Cache::default())),
+                parse_mode: ParseMode::default(),
+                namespace: None,
+                persistence: None,
+                #[cfg(feature = "journal")]
+                journal: None,
+                clock: Rc::new(SystemClock),
+                prompt_guard: None,
+                in_flight: Rc::new(RefCell::new(BTreeMap::new())),
+                idempotency: Rc::new(RefCell::new(BTreeMap::new())),
+                paid: None,
+                max_amount: None,
+                keysend_filter: None,
+            }),
+        }
+    }
+
+    /// Provider namespace, `None` if composed with [`WebLN::from_provider`]
+    pub fn namespace(&self) -> Option<&str> {
+        self.inner.namespace.as_deref()
+    }
+
+    /// Check if a provider is injected as `window.webln`, without failing
+    pub fn is_available() -> bool {
+        Self::new().is_ok()
+    }
+
+    /// Wait until a provider is injected as `window.webln`
+    ///
+    /// Extensions may inject the provider after the page scripts started. Returns the
+    /// [`WebLN::new`] error if no provider shows up within `timeout`.
+    pub async fn wait_for_provider(timeout: Duration) -> Result<Self, Error> {
+        Self::wait_for_provider_with_clock(timeout, Rc::new(SystemClock)).await
+    }
+
+    /// Like [`WebLN::wait_for_provider`], timed by `clock`, which is also set on the returned
+    /// instance (see [`WebLN::with_clock`])
+    pub async fn wait_for_provider_with_clock(
+        timeout: Duration,
+        clock: Rc<dyn Clock>,
+    ) -> Result<Self, Error> {
+        let deadline: Duration = clock.now() + timeout;
+        loop {
+            match Self::new() {
+                Ok(webln) => return Ok(webln.with_clock(clock)),
+                Err(e) if clock.now() >= deadline => return Err(e),
+                Err(..) => clock.sleep(PROVIDER_POLL_INTERVAL).await?,
+            }
+        }
+    }
+
+    /// Save the enabled state and namespace in `localStorage` under `key` (default: disabled)
+    ///
+    /// See [`WebLN::restore`] and [`WebLN::forget`].
+    pub fn with_persistence<S>(mut self, key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Rc::make_mut(&mut self.inner).persistence = Some(key.into());
+        self
+    }
+
+    /// Set the time source used for timeouts and polling (default: [`SystemClock`])
+    pub fn with_clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        Rc::make_mut(&mut self.inner).clock = clock;
+        self
+    }
+
+    /// Time source used for timeouts and polling
+    pub fn clock(&self) -> &dyn Clock {
+        self.inner.clock.as_ref()
+    }
+
+    /// Limit the user-prompting calls (i.e. when embedding third-party content that triggers
+    /// payments): calls over the limit fail with [`Error::RateLimited`], without reaching the wallet
+    ///
+    /// Windows are measured with the [`WebLN::clock`].
+    pub fn with_prompt_limit(mut self, limit: PromptLimit) -> Self {
+        Rc::make_mut(&mut self.inner).prompt_guard = Some(guard::PromptGuard::new(limit));
+        self
+    }
+
+    /// Prompt rate limit, if any
+    pub fn prompt_limit(&self) -> Option<PromptLimit> {
+        self.inner
+            .prompt_guard
+            .as_ref()
+            .map(guard::PromptGuard::limit)
+    }
+
+    /// Register an additional matcher used to detect user rejections
+    ///
+    /// Useful for providers that reject with localized or non-standard messages.
+    pub fn with_rejection_matcher(mut self, matcher: RejectionMatcher) -> Self {
+        Rc::make_mut(&mut self.inner)
+            .rejection_matchers
+            .push(matcher);
+        self
+    }
+
+    /// Reject payments over `amount` with [`Error::AmountExceedsLimit`], before prompting
+    /// (default: no limit)
+    ///
+    /// Applies to each payment: invoice amounts, explicit amounts and keysend amounts. Invoices
+    /// whose amount can't be checked are rejected too: unparsable ones with [`Error::Invoice`],
+    /// zero-amount ones with [`Error::UnknownAmount`] (unless paid with
+    /// [`WebLN::send_payment_with_amount`]).
+    pub fn with_max_amount(mut self, amount: Amount) -> Self {
+        Rc::make_mut(&mut self.inner).max_amount = Some(amount);
+        self
+    }
+
+    /// Max amount of a single payment, if any
+    pub fn max_amount(&self) -> Option<Amount> {
+        self.inner.max_amount
+    }
+
+    /// Only keysend to the destinations allowed by `filter`, rejecting the others with
+    /// [`Error::DestinationNotAllowed`] before prompting (default: no filter)
+    ///
+    /// Useful for value-4-value apps that must only pay known recipients.
+    pub fn with_keysend_filter(mut self, filter: DestinationFilter) -> Self {
+        Rc::make_mut(&mut self.inner).keysend_filter = Some(filter);
+        self
+    }
+
+    /// Keysend destinations filter, if any
+    pub fn keysend_filter(&self) -> Option<&DestinationFilter> {
+        self.inner.keysend_filter.as_ref()
+    }
+
+    /// Emulate [`WebLN::keysend`] through [`WebLN::request`] when the provider doesn't support
+    /// `keysend` (default: disabled)
+    ///
+    /// The payment is sent with LND `SendPaymentV2`, so it only works with LND backed providers.
+    /// The preimage is generated locally and added as [`KEYSEND_PREIMAGE_TLV`] record.
+    pub fn with_keysend_fallback(mut self, enable: bool) -> Self {
+        Rc::make_mut(&mut self.inner).keysend_fallback = enable;
+        self
+    }
+
+    /// Emulate [`WebLN::make_invoice`] through [`WebLN::request`] when the provider doesn't
+    /// support `makeInvoice` (default: disabled)
+    ///
+    /// The invoice is created with LND `addinvoice` or, if it fails, CLN `invoice`.
+    pub fn with_make_invoice_fallback(mut self, enable: bool) -> Self {
+        Rc::make_mut(&mut self.inner).make_invoice_fallback = enable;
+        self
+    }
+
+    /// Set how malformed optional fields of the responses are handled (default:
+    /// [`ParseMode::Lenient`])
+    pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        Rc::make_mut(&mut self.inner).parse_mode = mode;
+        self
+    }
+
+    /// Parse a provider response with the configured [`ParseMode`]
+    fn parse<T>(&self, value: &JsValue) -> Result<T, Error>
+    where
+        T: Deserialize,
+    {
+        T::deserialize_with_mode(value, self.inner.parse_mode)
+    }
+
+    /// Check if `method` must be emulated through `request`
+    ///
+    /// True when the provider supports `request` and doesn't expose `method` or doesn't list it
+    /// in `getInfo`.
+    async fn should_emulate(&self, method: GetInfoMethod) -> bool {
+        if !self.has_method(&GetInfoMethod::Request) {
+            return false;
+        }
+
+        if !self.has_method(&method) {
+            return true;
+        }
+
+        let info: GetInfoResponse = match self.cached_info() {
+            Some(info) => info,
+            None => match self.get_info().await {
+                Ok(info) => info,
+                Err(..) => return false,
+            },
+        };
+        info.methods.contains(&GetInfoMethod::Request) && !info.methods.contains(&method)
+    }
+
+    /// Check if the provider object exposes `method`
+    fn has_method(&self, method: &GetInfoMethod) -> bool {
+        self.get_func(&self.inner.webln_obj, &method.to_string())
+            .is_ok()
     }
 
     fn get_func(&self, obj: &Object, name: &str) -> Result<Function, Error> {
@@ -353,93 +1713,255 @@ impl WebLN {
             .map_err(|_| Error::NamespaceNotFound(name.to_string()))
     }
 
-    /// Get value from object key
-    fn get_value_by_key(&self, obj: &Object, key: &str) -> Result<JsValue, Error> {
-        Reflect::get(obj, &JsValue::from_str(key))
-            .map_err(|_| Error::ObjectKeyNotFound(key.to_string()))
+    /// Call a provider method, await its result and parse it.
+    ///
+    /// Any error is wrapped into [`Error::Call`].
+    async fn call<T, F>(
+        &self,
+        method: GetInfoMethod,
+        args: &[JsValue],
+        parse: F,
+    ) -> Result<T, Error>
+    where
+        F: FnOnce(JsValue) -> Result<T, Error>,
+    {
+        self.call_with(method, |method| self.start(method, args), parse)
+            .await
+    }
+
+    /// Like [`WebLN::call`], getting the promise to wait for from `start`
+    async fn call_with<T, S, F>(
+        &self,
+        method: GetInfoMethod,
+        start: S,
+        parse: F,
+    ) -> Result<T, Error>
+    where
+        S: FnOnce(&GetInfoMethod) -> Result<Promise, Error>,
+        F: FnOnce(JsValue) -> Result<T, Error>,
+    {
+        let res = async {
+            let result: JsValue = JsFuture::from(start(&method)?).await?;
+            parse(result)
+        }
+        .await;
+        res.map_err(|e| self.call_error(method, e))
+    }
+
+    /// Call a provider method, if allowed by the prompt limit
+    fn start(&self, method: &GetInfoMethod, args: &[JsValue]) -> Result<Promise, Error> {
+        self.check_prompt(method)?;
+        Ok(Promise::resolve(&self.apply(method, args)?))
+    }
+
+    /// Count a call to `method` against the prompt limit, if any
+    ///
+    /// Also called by the `request` fallbacks, which prompt like the method they emulate.
+    pub(crate) fn check_prompt(&self, method: &GetInfoMethod) -> Result<(), Error> {
+        match &self.inner.prompt_guard {
+            Some(guard) => guard.check(method, self.clock().now()),
+            None => Ok(()),
+        }
+    }
+
+    /// Call a provider method synchronously (i.e. `on`/`off`, which don't return a promise)
+    fn call_sync(&self, method: GetInfoMethod, args: &[JsValue]) -> Result<JsValue, Error> {
+        self.apply(&method, args)
+            .map_err(|e| self.call_error(method, e))
+    }
+
+    fn apply(&self, method: &GetInfoMethod, args: &[JsValue]) -> Result<JsValue, Error> {
+        let func: Function = self.get_func(&self.inner.webln_obj, &method.to_string())?;
+        let args: Array = args.iter().collect();
+        Ok(func.apply(&self.inner.webln_obj, &args)?)
+    }
+
+    fn call_error(&self, method: GetInfoMethod, e: Error) -> Error {
+        let e: Error = match e {
+            Error::Provider(e) if self.inner.rejection_matchers.iter().any(|m| m(&e)) => {
+                Error::UserRejected(e)
+            }
+            e => e,
+        };
+        Error::Call {
+            method,
+            source: Box::new(e),
+        }
     }
 
     /// Check if `webln` is enabled without explicitly enabling it through `webln.enable()`
     /// (which may cause a confirmation popup in some providers)
     pub async fn is_enabled(&self) -> Result<bool, Error> {
-        let func: Function = self.get_func(&self.webln_obj, IS_ENABLED)?;
-        let promise: Promise = Promise::resolve(&func.call0(&self.webln_obj)?);
-        let result: JsValue = JsFuture::from(promise).await?;
-        result
-            .as_bool()
-            .ok_or_else(|| Error::TypeMismatch(String::from("expected a bool")))
+        let enabled: bool = self
+            .call(GetInfoMethod::IsEnabled, &[], |result| {
+                result
+                    .as_bool()
+                    .ok_or_else(|| Error::TypeMismatch(String::from("expected a bool")))
+            })
+            .await?;
+        self.inner.cache.borrow_mut().enabled = Some(enabled);
+        Ok(enabled)
     }
 
     /// To begin interacting with WebLN APIs you'll first need to enable the provider.
     /// Calling `webln.enable()` will prompt the user for permission to use the WebLN capabilities of the browser.
     /// After that you are free to call any of the other API methods.
+    ///
+    /// Fails with [`Error::NotEnabled`] if the provider resolves without enabling.
     pub async fn enable(&self) -> Result<(), Error> {
-        let func: Function = self.get_func(&self.webln_obj, ENABLE)?;
-        let promise: Promise = Promise::resolve(&func.call0(&self.webln_obj)?);
-        JsFuture::from(promise).await?;
+        if self.enable_with_raw().await?.enabled {
+            Ok(())
+        } else {
+            Err(self.call_error(GetInfoMethod::Enable, Error::NotEnabled))
+        }
+    }
+
+    /// Like [`WebLN::enable`], returning the parsed provider response along with the raw one
+    ///
+    /// Check [`EnableResponse::enabled`]: some providers resolve even when not enabled.
+    pub async fn enable_with_raw(&self) -> Result<ResponseWithRaw<EnableResponse>, Error> {
+        let res: ResponseWithRaw<EnableResponse> = self
+            .call(GetInfoMethod::Enable, &[], |result| {
+                ResponseWithRaw::parse_with_mode(result, self.inner.parse_mode)
+            })
+            .await?;
+        self.inner.cache.borrow_mut().enabled = Some(res.enabled);
+        if res.enabled {
+            self.persist_enabled();
+        }
+        Ok(res)
+    }
+
+    /// Enable the provider, unless already enabled
+    ///
+    /// The permission popup is only shown if `isEnabled` returns `false`. Once enabled, no
+    /// provider call is made until [`WebLN::invalidate`].
+    pub async fn ensure_enabled(&self) -> Result<(), Error> {
+        if self.cached_enabled() == Some(true) {
+            return Ok(());
+        }
+
+        if !self.is_enabled().await? {
+            self.enable().await?;
+        }
         Ok(())
     }
 
     /// Get information about the connected node and what WebLN methods it supports.
     pub async fn get_info(&self) -> Result<GetInfoResponse, Error> {
-        let func: Function = self.get_func(&self.webln_obj, GET_INFO)?;
-        let promise: Promise = Promise::resolve(&func.call0(&self.webln_obj)?);
-        let result: JsValue = JsFuture::from(promise).await?;
-        let get_info_obj: Object = result.dyn_into().map_err(|_| Error::SomethingGoneWrong)?;
+        self.get_info_with_raw()
+            .await
+            .map(ResponseWithRaw::into_inner)
+    }
 
-        let node_obj: Object = self
-            .get_value_by_key(&get_info_obj, "node")?
-            .dyn_into()
-            .map_err(|_| Error::SomethingGoneWrong)?;
+    /// Like [`WebLN::get_info`], keeping the raw provider response
+    pub async fn get_info_with_raw(&self) -> Result<ResponseWithRaw<GetInfoResponse>, Error> {
+        let info: ResponseWithRaw<GetInfoResponse> = self
+            .call(GetInfoMethod::GetInfo, &[], |result| {
+                ResponseWithRaw::parse_with_mode(result, self.inner.parse_mode)
+            })
+            .await?;
+        self.inner.cache.borrow_mut().info = Some(info.response.clone());
+        Ok(info)
+    }
 
-        // Extract data
-        let alias: Option<String> = self.get_value_by_key(&node_obj, "alias")?.as_string();
-        let pubkey: Option<String> = self.get_value_by_key(&node_obj, "pubkey")?.as_string();
-        let color: Option<String> = self.get_value_by_key(&node_obj, "color")?.as_string();
-        let methods_array: Array = self.get_value_by_key(&get_info_obj, "methods")?.into();
-        let methods: Vec<GetInfoMethod> = methods_array
-            .into_iter()
-            .filter_map(|m| m.as_string())
-            .map(|m| GetInfoMethod::from(m.as_str()))
-            .collect();
+    /// Last enabled state returned by [`WebLN::is_enabled`] or [`WebLN::enable`]
+    pub fn cached_enabled(&self) -> Option<bool> {
+        self.inner.cache.borrow().enabled
+    }
 
-        Ok(GetInfoResponse {
-            node: GetInfoNode {
-                alias,
-                pubkey,
-                color,
-            },
-            methods,
-        })
+    /// Last response of [`WebLN::get_info`]
+    pub fn cached_info(&self) -> Option<GetInfoResponse> {
+        self.inner.cache.borrow().info.clone()
+    }
+
+    /// Last response of [`WebLN::get_balance`]
+    pub fn cached_balance(&self) -> Option<BalanceResponse> {
+        self.inner.cache.borrow().balance.clone()
+    }
+
+    /// Forget the cached enabled state, node info and balance
+    ///
+    /// Shared by all the clones. Called on `accountChanged` by [`WebLN::on_account_changed`].
+    pub fn invalidate(&self) {
+        *self.inner.cache.borrow_mut() = Cache::default();
     }
 
     /// Request the user to send a keysend payment.
     /// This is a spontaneous payment that does not require an invoice and only needs a destination public key and and amount.
+    ///
+    /// See [`WebLN::with_keysend_fallback`] for providers without `keysend`.
     pub async fn keysend(&self, args: &KeysendArgs) -> Result<SendPaymentResponse, Error> {
-        let func: Function = self.get_func(&self.webln_obj, KEYSEND)?;
+        let res: SendPaymentResponse = self.send_keysend(args).await?;
+        #[cfg(feature = "journal")]
+        self.record(Receipt::keysend(self.clock(), args, res.preimage.as_str()))
+            .await;
+        Ok(res)
+    }
 
-        let keysend_obj = Object::new();
-        Reflect::set(
-            &keysend_obj,
-            &JsValue::from_str("destination"),
-            &args.destination.to_string().into(),
-        )?;
-        Reflect::set(
-            &keysend_obj,
-            &JsValue::from_str("amount"),
-            &args.amount.to_string().into(),
-        )?;
+    /// Request the user to send a keysend payment, without waiting for the preimage.
+    ///
+    /// Returns once the provider accepted the call: meant for streaming payments (i.e. per minute
+    /// of a podcast), where waiting for the settlement of each one is unnecessary. There is no
+    /// guarantee that the payment will be successfully sent: later failures are dropped, while
+    /// settled payments are still recorded in the journal, if any.
+    pub async fn keysend_async(&self, args: &KeysendArgs) -> Result<(), Error> {
+        self.check_amount(Amount::from_sats(args.amount))?;
+        self.check_destination(&args.destination)?;
+
+        let webln: Self = self.clone();
+        let args: KeysendArgs = args.clone();
+
+        if self.inner.keysend_fallback && self.should_emulate(GetInfoMethod::Keysend).await {
+            spawn_local(async move {
+                let _ = webln.keysend(&args).await;
+            });
+            return Ok(());
+        }
 
-        let promise: Promise = Promise::resolve(&func.call1(&self.webln_obj, &keysend_obj.into())?);
-        let result: JsValue = JsFuture::from(promise).await?;
-        let send_payment_obj: Object = result.dyn_into().map_err(|_| Error::SomethingGoneWrong)?;
+        let promise: Promise = self
+            .start(&GetInfoMethod::Keysend, &[keysend_obj(&args)?.into()])
+            .map_err(|e| self.call_error(GetInfoMethod::Keysend, e))?;
+        spawn_local(async move {
+            let res: Result<SendPaymentResponse, Error> = webln
+                .call_with(
+                    GetInfoMethod::Keysend,
+                    |_| Ok(promise),
+                    |result| webln.parse(&result),
+                )
+                .await;
+            #[cfg(feature = "journal")]
+            if let Ok(res) = res {
+                webln
+                    .record(Receipt::keysend(
+                        webln.clock(),
+                        &args,
+                        res.preimage.as_str(),
+                    ))
+                    .await;
+            }
+            #[cfg(not(feature = "journal"))]
+            let _ = (res, args);
+        });
+        Ok(())
+    }
 
-        Ok(SendPaymentResponse {
-            preimage: self
-                .get_value_by_key(&send_payment_obj, "preimage")?
-                .as_string()
-                .ok_or_else(|| Error::TypeMismatch(String::from("expected a string [preimage]")))?,
-        })
+    /// Check the amount and the destination, then send the keysend payment (shared by
+    /// [`WebLN::keysend`] and [`WebLN::refund`])
+    async fn send_keysend(&self, args: &KeysendArgs) -> Result<SendPaymentResponse, Error> {
+        self.check_amount(Amount::from_sats(args.amount))?;
+        self.check_destination(&args.destination)?;
+
+        if self.inner.keysend_fallback && self.should_emulate(GetInfoMethod::Keysend).await {
+            return self.keysend_via_request(args).await;
+        }
+
+        self.call(
+            GetInfoMethod::Keysend,
+            &[keysend_obj(args)?.into()],
+            |result| self.parse(&result),
+        )
+        .await
     }
 
     /// Request that the user creates an invoice to be used by the web app
@@ -447,104 +1969,360 @@ impl WebLN {
         &self,
         args: &RequestInvoiceArgs,
     ) -> Result<RequestInvoiceResponse, Error> {
-        let func: Function = self.get_func(&self.webln_obj, MAKE_INVOICE)?;
+        self.make_invoice_with_raw(args)
+            .await
+            .map(ResponseWithRaw::into_inner)
+    }
 
-        let request_invoice_obj: Object = args.try_into()?;
+    /// Like [`WebLN::make_invoice`], keeping the raw provider response
+    ///
+    /// With the `request` fallback, the raw value is the node RPC response.
+    pub async fn make_invoice_with_raw(
+        &self,
+        args: &RequestInvoiceArgs,
+    ) -> Result<ResponseWithRaw<RequestInvoiceResponse>, Error> {
+        if self.inner.make_invoice_fallback && self.should_emulate(GetInfoMethod::MakeInvoice).await
+        {
+            return self.make_invoice_via_request(args).await;
+        }
 
-        let promise: Promise =
-            Promise::resolve(&func.call1(&self.webln_obj, &request_invoice_obj.into())?);
-        let result: JsValue = JsFuture::from(promise).await?;
-        let request_invoice_response_obj: Object =
-            result.dyn_into().map_err(|_| Error::SomethingGoneWrong)?;
-        Ok(RequestInvoiceResponse {
-            invoice: self
-                .get_value_by_key(&request_invoice_response_obj, "paymentRequest")?
-                .as_string()
-                .ok_or_else(|| {
-                    Error::TypeMismatch(String::from("expected a string [paymentRequest]"))
-                })?,
-        })
+        let request_invoice_obj: Object = args.try_into()?;
+        self.call(
+            GetInfoMethod::MakeInvoice,
+            &[request_invoice_obj.into()],
+            |result| ResponseWithRaw::parse_with_mode(result, self.inner.parse_mode),
+        )
+        .await
     }
 
     /// Request that the user sends a payment for an invoice.
+    ///
+    /// Concurrent calls for the same invoice (i.e. a double click on a pay button) share the
+    /// pending provider call: the user is prompted once and all the callers get its result.
     pub async fn send_payment(&self, invoice: &str) -> Result<SendPaymentResponse, Error> {
-        // `lightning-invoice` increase too much the WASM binary size
-        // For now just check if invoice is not empty
-        if invoice.is_empty() {
-            return Err(Error::EmptyInvoice);
-        }
-
-        let func: Function = self.get_func(&self.webln_obj, SEND_PAYMENT)?;
-        let promise: Promise = Promise::resolve(&func.call1(&self.webln_obj, &invoice.into())?);
-        let result: JsValue = JsFuture::from(promise).await?;
-        let send_payment_obj: Object = result.dyn_into().map_err(|_| Error::SomethingGoneWrong)?;
-        Ok(SendPaymentResponse {
-            preimage: self
-                .get_value_by_key(&send_payment_obj, "preimage")?
-                .as_string()
-                .ok_or_else(|| Error::TypeMismatch(String::from("expected a string [preimage]")))?,
-        })
+        self.send_payment_with_raw(invoice)
+            .await
+            .map(ResponseWithRaw::into_inner)
+    }
+
+    /// Like [`WebLN::send_payment`], keeping the raw provider response (i.e. to read the fees
+    /// paid, when reported)
+    pub async fn send_payment_with_raw(
+        &self,
+        invoice: &str,
+    ) -> Result<ResponseWithRaw<SendPaymentResponse>, Error> {
+        check_invoice(invoice)?;
+        self.check_not_paid(invoice)?;
+        self.check_invoice_amount(invoice)?;
+
+        let mut owner: Option<InFlight> = None;
+        let res: Result<ResponseWithRaw<SendPaymentResponse>, Error> = self
+            .call_with(
+                GetInfoMethod::SendPayment,
+                |method| {
+                    if let Some(promise) = self.inner.in_flight.borrow().get(invoice) {
+                        return Ok(promise.clone());
+                    }
+                    let promise: Promise = self.start(method, &[invoice.into()])?;
+                    self.inner
+                        .in_flight
+                        .borrow_mut()
+                        .insert(invoice.to_string(), promise.clone());
+                    owner = Some(InFlight {
+                        webln: self,
+                        invoice,
+                    });
+                    Ok(promise)
+                },
+                |result| ResponseWithRaw::parse_with_mode(result, self.inner.parse_mode),
+            )
+            .await;
+
+        // Only the first caller owns the call
+        if owner.take().is_none() {
+            return res;
+        }
+        let res: ResponseWithRaw<SendPaymentResponse> = res?;
+        self.mark_paid(invoice);
+        #[cfg(feature = "journal")]
+        self.record(Receipt::invoice(
+            self.clock(),
+            invoice,
+            res.preimage.as_str(),
+        ))
+        .await;
+        Ok(res)
+    }
+
+    /// Pay an invoice for an explicit `amount`
+    ///
+    /// Zero-amount invoices are paid through `request` (LND `sendpaymentv2` or CLN `pay`), since
+    /// `sendPayment` can't carry an amount and wallets would prompt for it. Invoices with an amount
+    /// go through [`WebLN::send_payment`], if it matches.
+    pub async fn send_payment_with_amount(
+        &self,
+        invoice: &str,
+        amount: Amount,
+    ) -> Result<SendPaymentResponse, Error> {
+        check_invoice(invoice)?;
+        self.check_not_paid(invoice)?;
+        self.check_amount(amount)?;
+
+        match Bolt11Invoice::parse(invoice)?.amount {
+            None => {
+                let res: SendPaymentResponse =
+                    self.send_payment_via_request(invoice, amount).await?;
+                self.mark_paid(invoice);
+                Ok(res)
+            }
+            Some(invoice_amount) if invoice_amount == amount => self.send_payment(invoice).await,
+            Some(invoice_amount) => Err(Error::AmountMismatch {
+                invoice: invoice_amount,
+                requested: amount,
+            }),
+        }
     }
 
     /// Request that the user sends a payment for an invoice.
     /// The payment will only be initiated and will not wait for a preimage to be returned.
     /// This is useful when paying HOLD Invoices. There is no guarantee that the payment will be successfully sent to the receiver.
     /// It's up to the receiver to check whether or not the invoice has been paid.
-    pub async fn send_payment_async(&self, invoice: &str) -> Result<(), Error> {
-        // `lightning-invoice` increase too much the WASM binary size
-        // For now just check if invoice is not empty
-        if invoice.is_empty() {
-            return Err(Error::EmptyInvoice);
-        }
+    ///
+    /// With [`WebLN::with_paid_invoice_memory`], the invoice is remembered as paid once it
+    /// settles, as reported by [`WebLN::track_payment`].
+    pub async fn send_payment_async(
+        &self,
+        invoice: &str,
+    ) -> Result<SendPaymentAsyncResponse, Error> {
+        check_invoice(invoice)?;
+        self.check_not_paid(invoice)?;
+        self.check_invoice_amount(invoice)?;
+
+        let res: SendPaymentAsyncResponse = self
+            .call(
+                GetInfoMethod::SendPaymentAsync,
+                &[invoice.into()],
+                |result| self.parse(&result),
+            )
+            .await?;
+        Ok(res)
+    }
 
-        let func: Function = self.get_func(&self.webln_obj, SEND_PAYMENT_ASYNC)?;
-        let promise: Promise = Promise::resolve(&func.call1(&self.webln_obj, &invoice.into())?);
-        let result: JsValue = JsFuture::from(promise).await?;
+    /// Request that the user sends payments for multiple invoices.
+    ///
+    /// Failed payments don't fail the whole call: they are reported in [`SendMultiPaymentResponse::errors`].
+    ///
+    /// Most providers don't implement `sendMultiPayment`: in that case the invoices are paid one
+    /// by one with `sendPayment`. Once the user rejects a payment, the remaining invoices are
+    /// reported as not attempted.
+    ///
+    /// Duplicate invoices are paid once and reported in [`SendMultiPaymentResponse::duplicates`].
+    /// Fails without paying any invoice if one is empty, a BOLT12 offer, or over
+    /// [`WebLN::with_max_amount`].
+    pub async fn send_multi_payment<S>(
+        &self,
+        invoices: &[S],
+    ) -> Result<SendMultiPaymentResponse, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.send_multi_payment_with_options(invoices, &MultiPaymentOptions::default())
+            .await
+    }
 
-        if !result.is_object() {
-            return Err(Error::SomethingGoneWrong);
+    /// Request that the user sends payments for multiple invoices, with [`MultiPaymentOptions`].
+    ///
+    /// See [`WebLN::send_multi_payment`].
+    pub async fn send_multi_payment_with_options<S>(
+        &self,
+        invoices: &[S],
+        options: &MultiPaymentOptions,
+    ) -> Result<SendMultiPaymentResponse, Error>
+    where
+        S: AsRef<str>,
+    {
+        // Like `sendPayment`, before paying any of them (natively or not)
+        for invoice in invoices.iter() {
+            check_invoice(invoice.as_ref())?;
+            self.check_invoice_amount(invoice.as_ref())?;
         }
 
-        Ok(())
+        let mut invoices: Vec<String> = invoices
+            .iter()
+            .map(|invoice| invoice.as_ref().to_string())
+            .collect();
+        let duplicates: Vec<String> = if options.deduplicate {
+            multi::dedup(&mut invoices)
+        } else {
+            Vec::new()
+        };
+
+        let mut response: SendMultiPaymentResponse =
+            if self.has_method(&GetInfoMethod::SendMultiPayment) {
+                // Already paid invoices fail like in the fallback, without reaching the provider
+                let mut paid: Vec<MultiPaymentError> = Vec::new();
+                let invoices: Array = invoices
+                    .iter()
+                    .filter(|invoice| match self.check_not_paid(invoice) {
+                        Ok(()) => true,
+                        Err(e) => {
+                            paid.push(MultiPaymentError {
+                                payment_request: invoice.to_string(),
+                                message: e.to_string(),
+                            });
+                            false
+                        }
+                    })
+                    .map(|i| JsValue::from_str(i))
+                    .collect();
+                let mut response: SendMultiPaymentResponse = if invoices.length() > 0 {
+                    self.call(
+                        GetInfoMethod::SendMultiPayment,
+                        &[invoices.into()],
+                        |result| self.parse(&result),
+                    )
+                    .await?
+                } else {
+                    SendMultiPaymentResponse::default()
+                };
+                for payment in response.payments.iter() {
+                    self.mark_paid(&payment.payment_request);
+                }
+                response.errors.extend(paid);
+                options.report_all(&response);
+                response
+            } else {
+                self.send_multi_payment_fallback(invoices, options).await?
+            };
+        response.duplicates = duplicates;
+        Ok(response)
     }
 
     /// Request that the user signs an arbitrary string message.
     pub async fn sign_message(&self, message: &str) -> Result<SignMessageResponse, Error> {
-        let func: Function = self.get_func(&self.webln_obj, SIGN_MESSAGE)?;
-        let promise: Promise = Promise::resolve(&func.call1(&self.webln_obj, &message.into())?);
-        let result: JsValue = JsFuture::from(promise).await?;
-        let sign_message_response_obj: Object =
-            result.dyn_into().map_err(|_| Error::SomethingGoneWrong)?;
+        self.call(GetInfoMethod::SignMessage, &[message.into()], |result| {
+            let sign_message_response_obj: Value = Value::from_js(&result);
 
-        // Extract data
-        let signature: String = self
-            .get_value_by_key(&sign_message_response_obj, "signature")?
-            .as_string()
-            .ok_or_else(|| Error::TypeMismatch(String::from("expected a string [signature]")))?;
-
-        Ok(SignMessageResponse {
-            message: message.to_string(),
-            signature,
+            // Extract data
+            let signature: String =
+                get_string(expect_object(&sign_message_response_obj)?, "signature")?;
+
+            Ok(SignMessageResponse {
+                message: message.to_string(),
+                signature,
+            })
         })
+        .await
+    }
+
+    /// Call a node RPC method through the provider passthrough (i.e. `getinfo` or `listchannels`).
+    ///
+    /// The result is returned as-is, since it depends on the node implementation. Counted against
+    /// the [`WebLN::with_prompt_limit`], unless a known read-only lookup (i.e. `getinfo`).
+    pub async fn request(&self, method: &str, params: Option<&JsValue>) -> Result<JsValue, Error> {
+        self.send_request(method, params, !guard::is_lookup(method))
+            .await
+    }
+
+    /// Like [`WebLN::request`], counting the call against the prompt limit only if `prompting`
+    ///
+    /// The `request` fallbacks pass `false`: they're counted as the method they emulate.
+    pub(crate) async fn send_request(
+        &self,
+        method: &str,
+        params: Option<&JsValue>,
+        prompting: bool,
+    ) -> Result<JsValue, Error> {
+        let mut args: Vec<JsValue> = Vec::with_capacity(2);
+        args.push(JsValue::from_str(method));
+        if let Some(params) = params {
+            args.push(params.clone());
+        }
+        self.call_with(
+            GetInfoMethod::Request,
+            |method| {
+                if prompting {
+                    self.check_prompt(method)?;
+                }
+                Ok(Promise::resolve(&self.apply(method, &args)?))
+            },
+            Ok,
+        )
+        .await
+    }
+
+    /// Like [`WebLN::request`], with JSON params and result
+    ///
+    /// `Value::Null` params are omitted.
+    #[cfg(feature = "json")]
+    pub async fn request_json(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        use serde::Serialize;
+
+        let params: Option<JsValue> = if params.is_null() {
+            None
+        } else {
+            let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+            Some(
+                params
+                    .serialize(&serializer)
+                    .map_err(|e| Error::TypeMismatch(e.to_string()))?,
+            )
+        };
+        let result: JsValue = self.request(method, params.as_ref()).await?;
+        serde_wasm_bindgen::from_value(result).map_err(|e| Error::TypeMismatch(e.to_string()))
+    }
+
+    /// Register a JS `listener` for `event`
+    ///
+    /// Use [`WebLN::subscribe`] to listen with a Rust closure.
+    pub fn on(&self, event: &Event, listener: &Function) -> Result<(), Error> {
+        self.call_sync(
+            GetInfoMethod::On,
+            &[JsValue::from_str(&event.to_string()), listener.into()],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a JS `listener` previously registered with [`WebLN::on`]
+    pub fn off(&self, event: &Event, listener: &Function) -> Result<(), Error> {
+        self.call_sync(
+            GetInfoMethod::Off,
+            &[JsValue::from_str(&event.to_string()), listener.into()],
+        )?;
+        Ok(())
+    }
+
+    /// Listen for `event` with a Rust closure
+    ///
+    /// The listener stays registered until the returned [`Subscription`] is dropped.
+    pub fn subscribe<F>(&self, event: Event, callback: F) -> Result<Subscription, Error>
+    where
+        F: FnMut(JsValue) + 'static,
+    {
+        let closure: Closure<dyn FnMut(JsValue)> = Closure::new(callback);
+        self.on(&event, closure.as_ref().unchecked_ref())?;
+        Ok(Subscription::new(self.clone(), event, closure))
     }
 
     /// Fetch the balance of the current account.
     pub async fn get_balance(&self) -> Result<BalanceResponse, Error> {
-        let func: Function = self.get_func(&self.webln_obj, GET_BALANCE)?;
-        let promise: Promise = Promise::resolve(&func.call0(&self.webln_obj)?);
-        let result: JsValue = JsFuture::from(promise).await?;
-        let balance_response_obj: Object =
-            result.dyn_into().map_err(|_| Error::SomethingGoneWrong)?;
-
-        // Extract data
-        let balance: f64 = self
-            .get_value_by_key(&balance_response_obj, "balance")?
-            .as_f64()
-            .ok_or_else(|| Error::TypeMismatch(String::from("expected a number [balance]")))?;
-        let currency: Option<String> = self
-            .get_value_by_key(&balance_response_obj, "currency")?
-            .as_string();
+        self.get_balance_with_raw()
+            .await
+            .map(ResponseWithRaw::into_inner)
+    }
 
-        Ok(BalanceResponse { balance, currency })
+    /// Like [`WebLN::get_balance`], keeping the raw provider response
+    pub async fn get_balance_with_raw(&self) -> Result<ResponseWithRaw<BalanceResponse>, Error> {
+        let balance: ResponseWithRaw<BalanceResponse> = self
+            .call(GetInfoMethod::GetBalance, &[], |result| {
+                ResponseWithRaw::parse_with_mode(result, self.inner.parse_mode)
+            })
+            .await?;
+        self.inner.cache.borrow_mut().balance = Some(balance.response.clone());
+        Ok(balance)
     }
 }
@@ -0,0 +1,61 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Binary messages signing
+
+use alloc::string::{String, ToString};
+use core::{fmt, str};
+
+use crate::{base64, hex, Error, SignMessageResponse, WebLN};
+
+/// Encoding of the binary messages given to [`WebLN::sign_message_bytes`]
+///
+/// `signMessage` only takes strings: the bytes are encoded first, and the signature covers the
+/// encoded string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MessageEncoding {
+    /// UTF-8 text, as-is (fails for invalid UTF-8)
+    #[default]
+    Utf8,
+    /// Lowercase hex
+    Hex,
+    /// Standard, padded base64
+    Base64,
+}
+
+impl fmt::Display for MessageEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Utf8 => write!(f, "utf8"),
+            Self::Hex => write!(f, "hex"),
+            Self::Base64 => write!(f, "base64"),
+        }
+    }
+}
+
+impl MessageEncoding {
+    /// Encode `message` as the string to sign
+    pub fn encode(&self, message: &[u8]) -> Result<String, Error> {
+        match self {
+            Self::Utf8 => Ok(str::from_utf8(message)?.to_string()),
+            Self::Hex => Ok(hex::encode(message)),
+            Self::Base64 => Ok(base64::encode(message)),
+        }
+    }
+}
+
+impl WebLN {
+    /// Request that the user signs a binary message (i.e. an auth challenge), encoded as `encoding`
+    ///
+    /// [`SignMessageResponse::message`] is the encoded message, as signed by the provider.
+    pub async fn sign_message_bytes(
+        &self,
+        message: &[u8],
+        encoding: MessageEncoding,
+    ) -> Result<SignMessageResponse, Error> {
+        let message: String = encoding.encode(message)?;
+        self.sign_message(&message).await
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Multi payments
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::fmt;
+use core::future::{self, Future};
+use core::pin::Pin;
+use core::task::Poll;
+use core::time::Duration;
+
+use crate::{
+    parse_payment_costs, time, Error, MultiPaymentError, MultiPaymentItem, ParseMode,
+    ResponseWithRaw, SendMultiPaymentResponse, SendPaymentResponse, Value, WebLN,
+};
+
+/// Multi payment progress, reported to [`MultiPaymentOptions::on_progress`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MultiPaymentProgress {
+    /// Number of invoices paid or failed so far, including this one
+    pub completed: usize,
+    /// Number of invoices in the batch
+    pub total: usize,
+    /// Outcome of the invoice
+    pub result: Result<MultiPaymentItem, MultiPaymentError>,
+}
+
+/// Progress callback
+type OnProgress = Rc<dyn Fn(&MultiPaymentProgress)>;
+
+/// Multi payment options
+///
+/// `concurrency` and `timeout` are only used when the provider doesn't implement
+/// `sendMultiPayment` and the invoices are paid with `sendPayment`.
+#[derive(Clone)]
+pub struct MultiPaymentOptions {
+    /// Max number of invoices paid in parallel (default: 1, one by one)
+    pub concurrency: usize,
+    /// Max time to wait for each payment (default: no timeout)
+    ///
+    /// Timed out payments are reported in [`SendMultiPaymentResponse::errors`] with a `Timeout`
+    /// message. The provider may still complete them later.
+    pub timeout: Option<Duration>,
+    /// Called every time an invoice is paid or failed
+    ///
+    /// With a native `sendMultiPayment`, the provider only answers at the end of the batch: the
+    /// progress is reported for all the invoices at once.
+    pub on_progress: Option<OnProgress>,
+    /// Pay duplicate invoices only once (default: `true`)
+    ///
+    /// Invoices are compared case-insensitively. The skipped ones are reported in
+    /// [`SendMultiPaymentResponse::duplicates`].
+    pub deduplicate: bool,
+}
+
+impl fmt::Debug for MultiPaymentOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiPaymentOptions")
+            .field("concurrency", &self.concurrency)
+            .field("timeout", &self.timeout)
+            .field("on_progress", &self.on_progress.is_some())
+            .field("deduplicate", &self.deduplicate)
+            .finish()
+    }
+}
+
+impl Default for MultiPaymentOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 1,
+            timeout: None,
+            on_progress: None,
+            deduplicate: true,
+        }
+    }
+}
+
+impl MultiPaymentOptions {
+    /// New default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set max number of invoices paid in parallel (`0` is treated as `1`)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Set max time to wait for each payment
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set whether duplicate invoices are paid only once
+    pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Set progress callback
+    pub fn on_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: Fn(&MultiPaymentProgress) + 'static,
+    {
+        self.on_progress = Some(Rc::new(on_progress));
+        self
+    }
+
+    /// Report the outcome of an invoice
+    pub(crate) fn report(
+        &self,
+        completed: usize,
+        total: usize,
+        result: Result<MultiPaymentItem, MultiPaymentError>,
+    ) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(&MultiPaymentProgress {
+                completed,
+                total,
+                result,
+            });
+        }
+    }
+
+    /// Report the outcome of a whole batch
+    pub(crate) fn report_all(&self, response: &SendMultiPaymentResponse) {
+        if self.on_progress.is_none() {
+            return;
+        }
+
+        let total: usize = response.payments.len() + response.errors.len();
+        let results = response
+            .payments
+            .iter()
+            .cloned()
+            .map(Ok)
+            .chain(response.errors.iter().cloned().map(Err));
+        for (index, result) in results.enumerate() {
+            self.report(index + 1, total, result);
+        }
+    }
+}
+
+/// Remove the duplicate invoices, keeping the first occurrence
+///
+/// Returns the removed ones.
+pub(crate) fn dedup(invoices: &mut Vec<String>) -> Vec<String> {
+    let mut seen: BTreeSet<String> = BTreeSet::new();
+    let mut duplicates: Vec<String> = Vec::new();
+    invoices.retain(|invoice| {
+        if seen.insert(invoice.to_lowercase()) {
+            true
+        } else {
+            duplicates.push(invoice.clone());
+            false
+        }
+    });
+    duplicates
+}
+
+/// Outcome of an invoice
+type Outcome = Result<MultiPaymentItem, MultiPaymentError>;
+
+/// Message of the invoices left unpaid after the user rejected a payment
+const NOT_ATTEMPTED: &str = "Not attempted: the user rejected a previous payment";
+
+/// Invoices shared by the workers
+struct Pool {
+    invoices: Vec<String>,
+    next: Cell<usize>,
+    completed: Cell<usize>,
+    /// The user rejected a payment: don't take the remaining invoices
+    stopped: Cell<bool>,
+    results: RefCell<Vec<Option<Outcome>>>,
+}
+
+impl Pool {
+    fn take(&self) -> Option<usize> {
+        let index: usize = self.next.get();
+        if index < self.invoices.len() && !self.stopped.get() {
+            self.next.set(index + 1);
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Record the outcome of the invoice at `index`
+    fn complete(&self, index: usize, res: Outcome, options: &MultiPaymentOptions) {
+        let completed: usize = self.completed.get() + 1;
+        self.completed.set(completed);
+        options.report(completed, self.invoices.len(), res.clone());
+        self.results.borrow_mut()[index] = Some(res);
+    }
+}
+
+/// Poll `futures` to completion, in the calling task
+///
+/// Unlike spawned tasks, they are dropped with the returned future.
+async fn join_all<F>(futures: Vec<F>)
+where
+    F: Future<Output = ()>,
+{
+    let mut futures: Vec<Option<Pin<Box<F>>>> =
+        futures.into_iter().map(|f| Some(Box::pin(f))).collect();
+    future::poll_fn(|cx| {
+        let mut pending: bool = false;
+        for slot in futures.iter_mut() {
+            if let Some(future) = slot {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(()) => *slot = None,
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    })
+    .await
+}
+
+impl WebLN {
+    /// Pay the invoices with `sendPayment`, for providers without `sendMultiPayment`
+    ///
+    /// Each worker pays one invoice at a time: results keep the order of `invoices`. The workers
+    /// run in the returned future: dropping it (i.e. on abort) stops them, without starting the
+    /// remaining payments. After a user rejection, the remaining invoices are not attempted.
+    pub(crate) async fn send_multi_payment_fallback(
+        &self,
+        invoices: Vec<String>,
+        options: &MultiPaymentOptions,
+    ) -> Result<SendMultiPaymentResponse, Error> {
+        let workers: usize = options.concurrency.clamp(1, invoices.len().max(1));
+        let pool = Pool {
+            results: RefCell::new(invoices.iter().map(|_| None).collect()),
+            invoices,
+            next: Cell::new(0),
+            completed: Cell::new(0),
+            stopped: Cell::new(false),
+        };
+
+        join_all(
+            (0..workers)
+                .map(|_| self.multi_payment_worker(&pool, options))
+                .collect(),
+        )
+        .await;
+
+        let mut response = SendMultiPaymentResponse::default();
+        let results = pool.results.take();
+        for (index, res) in results.into_iter().enumerate() {
+            match res {
+                Some(Ok(item)) => response.payments.push(item),
+                Some(Err(e)) => response.errors.push(e),
+                None => {
+                    let error = MultiPaymentError {
+                        payment_request: pool.invoices[index].clone(),
+                        message: String::from(NOT_ATTEMPTED),
+                    };
+                    pool.complete(index, Err(error.clone()), options);
+                    response.errors.push(error);
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    /// Pay the invoices of `pool`, one at a time
+    async fn multi_payment_worker(&self, pool: &Pool, options: &MultiPaymentOptions) {
+        while let Some(index) = pool.take() {
+            let payment_request: String = pool.invoices[index].clone();
+            let payment = self.send_payment_with_raw(&payment_request);
+            let res: Result<ResponseWithRaw<SendPaymentResponse>, Error> = match options.timeout {
+                Some(timeout) => time::timeout(self.clock(), timeout, payment)
+                    .await
+                    .unwrap_or(Err(Error::Timeout)),
+                None => payment.await,
+            };
+            let res: Outcome = match res {
+                Ok(res) => {
+                    let (fee, route) =
+                        parse_payment_costs(&Value::from_js(&res.raw), ParseMode::Lenient)
+                            .unwrap_or_default();
+                    Ok(MultiPaymentItem {
+                        payment_request,
+                        preimage: res.response.preimage,
+                        fee,
+                        route,
+                    })
+                }
+                Err(e) => {
+                    if e.is_user_rejected() {
+                        pool.stopped.set(true);
+                    }
+                    Err(MultiPaymentError {
+                        payment_request,
+                        message: e.inner().to_string(),
+                    })
+                }
+            };
+            pool.complete(index, res, options);
+        }
+    }
+}
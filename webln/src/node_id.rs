@@ -0,0 +1,147 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Node ID
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(feature = "secp256k1")]
+use secp256k1::PublicKey;
+
+use crate::hex::{self, HexError};
+
+/// Compressed public key size
+pub const NODE_ID_SIZE: usize = 33;
+
+/// Node ID parsing error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseNodeIdError {
+    /// Expected 33 bytes (66 hex chars)
+    InvalidLength,
+    /// Invalid hex
+    InvalidHex,
+    /// Compressed public keys start with `02` or `03`
+    InvalidPrefix,
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseNodeIdError {}
+
+impl fmt::Display for ParseNodeIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength => write!(f, "Invalid length: expected {NODE_ID_SIZE} bytes"),
+            Self::InvalidHex => write!(f, "Invalid hex"),
+            Self::InvalidPrefix => write!(f, "Invalid prefix: expected `02` or `03`"),
+        }
+    }
+}
+
+impl From<HexError> for ParseNodeIdError {
+    fn from(_: HexError) -> Self {
+        Self::InvalidHex
+    }
+}
+
+/// Node ID (33-byte compressed public key)
+///
+/// Only the encoding is validated: checking that the key is a valid curve point requires `secp256k1`
+/// (see [`NodeId::to_public_key`]).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId([u8; NODE_ID_SIZE]);
+
+impl fmt::Debug for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NodeId({self})")
+    }
+}
+
+impl NodeId {
+    /// Construct from bytes
+    pub fn from_slice(slice: &[u8]) -> Result<Self, ParseNodeIdError> {
+        let bytes: [u8; NODE_ID_SIZE] = slice
+            .try_into()
+            .map_err(|_| ParseNodeIdError::InvalidLength)?;
+
+        if bytes[0] != 0x02 && bytes[0] != 0x03 {
+            return Err(ParseNodeIdError::InvalidPrefix);
+        }
+
+        Ok(Self(bytes))
+    }
+
+    /// Get as bytes
+    pub fn as_bytes(&self) -> &[u8; NODE_ID_SIZE] {
+        &self.0
+    }
+
+    /// Get as lowercase hex
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Convert to [`PublicKey`], checking that the key is a valid curve point
+    #[cfg(feature = "secp256k1")]
+    pub fn to_public_key(&self) -> Result<PublicKey, secp256k1::Error> {
+        PublicKey::from_slice(&self.0)
+    }
+}
+
+impl FromStr for NodeId {
+    type Err = ParseNodeIdError;
+
+    fn from_str(node_id: &str) -> Result<Self, Self::Err> {
+        if node_id.len() != NODE_ID_SIZE * 2 {
+            return Err(ParseNodeIdError::InvalidLength);
+        }
+
+        let bytes: Vec<u8> = hex::decode(node_id)?;
+        Self::from_slice(&bytes)
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+impl From<PublicKey> for NodeId {
+    fn from(public_key: PublicKey) -> Self {
+        Self(public_key.serialize())
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+impl TryFrom<NodeId> for PublicKey {
+    type Error = secp256k1::Error;
+
+    fn try_from(node_id: NodeId) -> Result<Self, Self::Error> {
+        node_id.to_public_key()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for NodeId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NodeId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let node_id: String = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Self::from_str(&node_id).map_err(serde::de::Error::custom)
+    }
+}
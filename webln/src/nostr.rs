@@ -0,0 +1,327 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Nostr companion (`window.nostr`, NIP-07)
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/07.md>
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "secp256k1")]
+use core::fmt::Write;
+
+use js_sys::{Array, Object, Reflect};
+#[cfg(feature = "secp256k1")]
+use secp256k1::hashes::{sha256, Hash};
+#[cfg(feature = "secp256k1")]
+use secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use wasm_bindgen::{JsCast, JsValue};
+
+#[cfg(feature = "secp256k1")]
+use crate::hex;
+use crate::value::{expect_object, get_string};
+use crate::{time, Deserialize, Error, GetInfoMethod, ParseMode, Value, WebLN};
+
+/// `window` property of the provider
+const NAMESPACE: &str = "nostr";
+const GET_PUBLIC_KEY: &str = "getPublicKey";
+const SIGN_EVENT: &str = "signEvent";
+const NIP04: &str = "nip04";
+const ENCRYPT: &str = "encrypt";
+const DECRYPT: &str = "decrypt";
+
+fn get_u64(obj: &Value, key: &str) -> Result<u64, Error> {
+    obj.get(key)
+        .as_f64()
+        .map(|value| value as u64)
+        .ok_or_else(|| Error::TypeMismatch(alloc::format!("expected a number [{key}]")))
+}
+
+/// Parse a string returned by the provider
+fn parse_string(value: JsValue) -> Result<String, Error> {
+    value
+        .as_string()
+        .ok_or_else(|| Error::TypeMismatch(String::from("expected a string")))
+}
+
+/// Nostr event to sign
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct UnsignedEvent {
+    /// UNIX timestamp, in seconds
+    pub created_at: u64,
+    /// Event kind
+    pub kind: u16,
+    /// Tags
+    pub tags: Vec<Vec<String>>,
+    /// Content
+    pub content: String,
+}
+
+impl UnsignedEvent {
+    /// New event created now, without tags
+    pub fn new<S>(kind: u16, content: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            created_at: time::now().as_secs(),
+            kind,
+            tags: Vec::new(),
+            content: content.into(),
+        }
+    }
+
+    /// Set the creation timestamp
+    pub fn created_at(mut self, created_at: u64) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    /// Add a tag (i.e. `["p", "<pubkey>"]`)
+    pub fn tag<I, S>(mut self, tag: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags.push(tag.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+impl TryFrom<&UnsignedEvent> for Object {
+    type Error = Error;
+
+    fn try_from(event: &UnsignedEvent) -> Result<Self, Self::Error> {
+        let obj = Self::new();
+        Reflect::set(
+            &obj,
+            &JsValue::from_str("created_at"),
+            &JsValue::from_f64(event.created_at as f64),
+        )?;
+        Reflect::set(
+            &obj,
+            &JsValue::from_str("kind"),
+            &JsValue::from_f64(event.kind as f64),
+        )?;
+        let tags: Array = event
+            .tags
+            .iter()
+            .map(|tag| {
+                tag.iter()
+                    .map(|value| JsValue::from_str(value))
+                    .collect::<Array>()
+            })
+            .collect();
+        Reflect::set(&obj, &JsValue::from_str("tags"), &tags)?;
+        Reflect::set(
+            &obj,
+            &JsValue::from_str("content"),
+            &JsValue::from_str(&event.content),
+        )?;
+        Ok(obj)
+    }
+}
+
+/// Nostr event signed by the provider
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct SignedEvent {
+    /// Hex encoded event ID
+    pub id: String,
+    /// Hex encoded public key of the author
+    pub pubkey: String,
+    /// UNIX timestamp, in seconds
+    pub created_at: u64,
+    /// Event kind
+    pub kind: u16,
+    /// Tags
+    pub tags: Vec<Vec<String>>,
+    /// Content
+    pub content: String,
+    /// Hex encoded Schnorr signature
+    pub sig: String,
+}
+
+impl Deserialize for SignedEvent {
+    fn from_value(value: &Value, _mode: ParseMode) -> Result<Self, Error> {
+        let event_obj: &Value = expect_object(value)?;
+        let tags_error = || Error::TypeMismatch(String::from("expected an array [tags]"));
+        let tags: Vec<Vec<String>> = event_obj
+            .get("tags")
+            .as_array()
+            .ok_or_else(tags_error)?
+            .iter()
+            .map(|tag| {
+                tag.as_array()
+                    .map(|tag| {
+                        tag.iter()
+                            .filter_map(Value::as_str)
+                            .map(String::from)
+                            .collect()
+                    })
+                    .ok_or_else(tags_error)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            id: get_string(event_obj, "id")?,
+            pubkey: get_string(event_obj, "pubkey")?,
+            created_at: get_u64(event_obj, "created_at")?,
+            kind: get_u64(event_obj, "kind")? as u16,
+            tags,
+            content: get_string(event_obj, "content")?,
+            sig: get_string(event_obj, "sig")?,
+        })
+    }
+}
+
+#[cfg(feature = "secp256k1")]
+impl SignedEvent {
+    /// Check that [`SignedEvent::id`] is the hash of the event, signed by [`SignedEvent::pubkey`]
+    pub fn verify(&self) -> Result<(), Error> {
+        let id: [u8; 32] = sha256::Hash::hash(self.serialize().as_bytes()).to_byte_array();
+        let decode = |value: &str| hex::decode(value).map_err(|_| Error::InvalidSignature);
+        if decode(&self.id)? != id {
+            return Err(Error::InvalidSignature);
+        }
+
+        let pubkey = XOnlyPublicKey::from_slice(&decode(&self.pubkey)?)?;
+        let sig = schnorr::Signature::from_slice(&decode(&self.sig)?)?;
+        Secp256k1::verification_only()
+            .verify_schnorr(&sig, &Message::from_digest(id), &pubkey)
+            .map_err(|_| Error::InvalidSignature)
+    }
+
+    /// Serialization hashed into the event ID (NIP-01)
+    fn serialize(&self) -> String {
+        let mut json: String = alloc::format!(
+            "[0,{},{},{},[",
+            json_string(&self.pubkey),
+            self.created_at,
+            self.kind
+        );
+        for (i, tag) in self.tags.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push('[');
+            for (j, value) in tag.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                json.push_str(&json_string(value));
+            }
+            json.push(']');
+        }
+        json.push_str("],");
+        json.push_str(&json_string(&self.content));
+        json.push(']');
+        json
+    }
+}
+
+/// JSON string, escaped as required by NIP-01
+#[cfg(feature = "secp256k1")]
+fn json_string(value: &str) -> String {
+    let mut json: String = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            '\u{8}' => json.push_str("\\b"),
+            '\u{c}' => json.push_str("\\f"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Nostr signer injected as `window.nostr` (NIP-07), often alongside WebLN
+///
+/// Calls go through the same machinery as [`WebLN`]: errors, user rejections and parsing are
+/// handled the same way.
+#[derive(Debug, Clone)]
+pub struct Nostr {
+    provider: WebLN,
+}
+
+impl Nostr {
+    /// Compose new Nostr instance from `window.nostr`
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            provider: WebLN::from_namespace(NAMESPACE)?,
+        })
+    }
+
+    /// Compose new Nostr instance from a provider object obtained elsewhere than `window.nostr`
+    pub fn from_provider(provider: Object) -> Self {
+        Self {
+            provider: WebLN::from_provider(provider),
+        }
+    }
+
+    /// Check if a provider is injected as `window.nostr`, without failing
+    pub fn is_available() -> bool {
+        Self::new().is_ok()
+    }
+
+    /// Get the hex encoded public key of the user
+    pub async fn get_public_key(&self) -> Result<String, Error> {
+        self.provider
+            .call(GetInfoMethod::from(GET_PUBLIC_KEY), &[], parse_string)
+            .await
+    }
+
+    /// Request the user to sign `event`
+    pub async fn sign_event(&self, event: &UnsignedEvent) -> Result<SignedEvent, Error> {
+        let event_obj: Object = event.try_into()?;
+        self.provider
+            .call(
+                GetInfoMethod::from(SIGN_EVENT),
+                &[event_obj.into()],
+                |result| SignedEvent::deserialize(&result),
+            )
+            .await
+    }
+
+    /// Encrypt `plaintext` for `pubkey` (hex encoded) with NIP-04
+    pub async fn nip04_encrypt(&self, pubkey: &str, plaintext: &str) -> Result<String, Error> {
+        self.nip04()?
+            .call(
+                GetInfoMethod::from(ENCRYPT),
+                &[pubkey.into(), plaintext.into()],
+                parse_string,
+            )
+            .await
+    }
+
+    /// Decrypt `ciphertext` from `pubkey` (hex encoded) with NIP-04
+    pub async fn nip04_decrypt(&self, pubkey: &str, ciphertext: &str) -> Result<String, Error> {
+        self.nip04()?
+            .call(
+                GetInfoMethod::from(DECRYPT),
+                &[pubkey.into(), ciphertext.into()],
+                parse_string,
+            )
+            .await
+    }
+
+    /// `nip04` object of the provider
+    fn nip04(&self) -> Result<WebLN, Error> {
+        let nip04: Object = Reflect::get(&self.provider.inner.webln_obj, &JsValue::from_str(NIP04))
+            .ok()
+            .and_then(|nip04| nip04.dyn_into().ok())
+            .ok_or_else(|| Error::NamespaceNotFound(NIP04.to_string()))?;
+        Ok(WebLN::from_provider(nip04))
+    }
+}
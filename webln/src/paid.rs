@@ -0,0 +1,139 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Paid invoices memory
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use js_sys::Array;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{persist, Bolt11Invoice, Error, WebLN};
+
+/// Payment hashes kept, the oldest are dropped first
+const MAX_PAID_INVOICES: usize = 1000;
+
+/// Payment hashes of the invoices paid through the instance
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PaidInvoices {
+    /// Oldest first, shared by the clones of the instance
+    hashes: Rc<RefCell<Vec<String>>>,
+    /// `localStorage` key where to save the hashes
+    storage_key: Option<String>,
+}
+
+impl PaidInvoices {
+    /// Load the hashes saved under `key`, if any
+    fn persisted(key: String) -> Self {
+        let hashes: Vec<String> = persist::get_json(&key)
+            .ok()
+            .flatten()
+            .and_then(|value| value.dyn_into::<Array>().ok())
+            .map(|hashes| hashes.iter().filter_map(|hash| hash.as_string()).collect())
+            .unwrap_or_default();
+        Self {
+            hashes: Rc::new(RefCell::new(hashes)),
+            storage_key: Some(key),
+        }
+    }
+
+    fn contains(&self, payment_hash: &str) -> bool {
+        self.hashes.borrow().iter().any(|hash| hash == payment_hash)
+    }
+
+    fn insert(&self, payment_hash: String) {
+        let mut hashes = self.hashes.borrow_mut();
+        if hashes.contains(&payment_hash) {
+            return;
+        }
+        hashes.push(payment_hash);
+        if hashes.len() > MAX_PAID_INVOICES {
+            let excess: usize = hashes.len() - MAX_PAID_INVOICES;
+            hashes.drain(..excess);
+        }
+
+        // Best effort
+        if let Some(key) = &self.storage_key {
+            let array: Array = hashes.iter().map(|hash| JsValue::from_str(hash)).collect();
+            let _ = persist::set_json(key, &array);
+        }
+    }
+
+    fn clear(&self) {
+        self.hashes.borrow_mut().clear();
+        if let Some(key) = &self.storage_key {
+            let _ = persist::remove(key);
+        }
+    }
+}
+
+/// Payment hash of `invoice`, if it's a valid BOLT11 invoice
+fn payment_hash(invoice: &str) -> Option<String> {
+    Bolt11Invoice::parse(invoice)
+        .ok()
+        .map(|invoice| invoice.payment_hash)
+}
+
+impl WebLN {
+    /// Remember the paid invoices, rejecting new payments of them with [`Error::AlreadyPaid`]
+    /// (default: disabled)
+    ///
+    /// Providers may let users pay an invoice twice. Invoices are identified by payment hash, for
+    /// the lifetime of the instance (and its clones).
+    pub fn with_paid_invoice_memory(mut self) -> Self {
+        Rc::make_mut(&mut self.inner).paid = Some(PaidInvoices::default());
+        self
+    }
+
+    /// Like [`WebLN::with_paid_invoice_memory`], saving the payment hashes in `localStorage`
+    /// under `key`, to survive page loads
+    pub fn with_persisted_paid_invoice_memory<S>(mut self, key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Rc::make_mut(&mut self.inner).paid = Some(PaidInvoices::persisted(key.into()));
+        self
+    }
+
+    /// Check if `invoice` was paid (only with [`WebLN::with_paid_invoice_memory`])
+    pub fn is_invoice_paid(&self, invoice: &str) -> bool {
+        match (&self.inner.paid, payment_hash(invoice)) {
+            (Some(paid), Some(hash)) => paid.contains(&hash),
+            _ => false,
+        }
+    }
+
+    /// Forget the paid invoices
+    pub fn forget_paid_invoices(&self) {
+        if let Some(paid) = &self.inner.paid {
+            paid.clear();
+        }
+    }
+
+    /// Fail with [`Error::AlreadyPaid`] if `invoice` was paid
+    pub(crate) fn check_not_paid(&self, invoice: &str) -> Result<(), Error> {
+        if let (Some(paid), Some(hash)) = (&self.inner.paid, payment_hash(invoice)) {
+            if paid.contains(&hash) {
+                return Err(Error::AlreadyPaid(hash));
+            }
+        }
+        Ok(())
+    }
+
+    /// Remember `invoice` as paid
+    pub(crate) fn mark_paid(&self, invoice: &str) {
+        if let Some(hash) = payment_hash(invoice) {
+            self.mark_hash_paid(hash);
+        }
+    }
+
+    /// Remember the invoice with `payment_hash` as paid
+    pub(crate) fn mark_hash_paid(&self, payment_hash: String) {
+        if let Some(paid) = &self.inner.paid {
+            paid.insert(payment_hash);
+        }
+    }
+}
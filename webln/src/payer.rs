@@ -0,0 +1,71 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Payer backend
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::future::Future;
+use core::pin::Pin;
+use core::str::FromStr;
+
+use crate::{Error, KeysendArgs, NodeId, WebLN};
+
+/// Future returned by [`LightningPayer`] methods: the preimage of the payment
+pub type PayFuture<'a, E> = Pin<Box<dyn Future<Output = Result<String, E>> + 'a>>;
+
+/// Minimal Lightning payer
+///
+/// Depend on it (i.e. in a payment SDK or a nostr library) to pay without knowing where the
+/// payments come from: [`WebLN`] implements it, as the payer backend of browser apps.
+///
+/// ```rust,no_run
+/// use webln::LightningPayer;
+///
+/// async fn zap<P>(payer: &P, invoice: &str) -> Result<String, P::Error>
+/// where
+///     P: LightningPayer,
+/// {
+///     payer.pay_invoice(invoice).await
+/// }
+/// ```
+pub trait LightningPayer {
+    /// Error
+    type Error;
+
+    /// Pay a BOLT11 invoice, returning the preimage
+    fn pay_invoice<'a>(&'a self, invoice: &'a str) -> PayFuture<'a, Self::Error>;
+
+    /// Send `amount` SAT to a node (hex public key) with keysend, returning the preimage
+    fn pay_keysend<'a>(&'a self, destination: &'a str, amount: u64) -> PayFuture<'a, Self::Error>;
+}
+
+impl<T> LightningPayer for &T
+where
+    T: LightningPayer + ?Sized,
+{
+    type Error = T::Error;
+
+    fn pay_invoice<'a>(&'a self, invoice: &'a str) -> PayFuture<'a, Self::Error> {
+        (**self).pay_invoice(invoice)
+    }
+
+    fn pay_keysend<'a>(&'a self, destination: &'a str, amount: u64) -> PayFuture<'a, Self::Error> {
+        (**self).pay_keysend(destination, amount)
+    }
+}
+
+impl LightningPayer for WebLN {
+    type Error = Error;
+
+    fn pay_invoice<'a>(&'a self, invoice: &'a str) -> PayFuture<'a, Self::Error> {
+        Box::pin(async move { Ok(self.send_payment(invoice).await?.preimage) })
+    }
+
+    fn pay_keysend<'a>(&'a self, destination: &'a str, amount: u64) -> PayFuture<'a, Self::Error> {
+        Box::pin(async move {
+            let args = KeysendArgs::new(NodeId::from_str(destination)?, amount);
+            Ok(self.keysend(&args).await?.preimage)
+        })
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Enable state persistence in `localStorage`
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use js_sys::{Function, Object, Reflect, JSON};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{Error, Value, WebLN};
+
+/// State saved by [`WebLN::with_persistence`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PersistedState {
+    /// The user enabled the provider on this site
+    pub enabled: bool,
+    /// Provider namespace (i.e. `webln`), `None` for providers given to [`WebLN::from_provider`]
+    pub namespace: Option<String>,
+}
+
+/// `localStorage`, if available (it throws in some privacy modes)
+fn storage() -> Result<Object, Error> {
+    Reflect::get(&js_sys::global(), &JsValue::from_str("localStorage"))
+        .ok()
+        .and_then(|storage| storage.dyn_into().ok())
+        .ok_or_else(|| Error::NamespaceNotFound(String::from("localStorage")))
+}
+
+/// Call a `Storage` method
+fn call(method: &str, args: &[&str]) -> Result<JsValue, Error> {
+    let storage: Object = storage()?;
+    let func: Function = Reflect::get(&storage, &JsValue::from_str(method))?
+        .dyn_into()
+        .map_err(|_| Error::NamespaceNotFound(String::from(method)))?;
+    let args: Vec<JsValue> = args.iter().map(|arg| JsValue::from_str(arg)).collect();
+    Ok(func.apply(&storage, &args.into_iter().collect())?)
+}
+
+/// Read the JSON value saved under `key`
+pub(crate) fn get_json(key: &str) -> Result<Option<JsValue>, Error> {
+    match call("getItem", &[key])?.as_string() {
+        Some(value) => Ok(Some(JSON::parse(&value)?)),
+        None => Ok(None),
+    }
+}
+
+/// Save `value` as JSON under `key`
+pub(crate) fn set_json(key: &str, value: &JsValue) -> Result<(), Error> {
+    let value: String = JSON::stringify(value)?.into();
+    call("setItem", &[key, &value])?;
+    Ok(())
+}
+
+pub(crate) fn load(key: &str) -> Result<Option<PersistedState>, Error> {
+    let value: Value = match get_json(key)? {
+        Some(value) => Value::from_js(&value),
+        None => return Ok(None),
+    };
+    let enabled: bool = value.get("enabled").as_bool().unwrap_or_default();
+    let namespace: Option<String> = value.get("namespace").as_str().map(String::from);
+    Ok(Some(PersistedState { enabled, namespace }))
+}
+
+pub(crate) fn save(key: &str, state: &PersistedState) -> Result<(), Error> {
+    let obj = Object::new();
+    Reflect::set(
+        &obj,
+        &JsValue::from_str("enabled"),
+        &JsValue::from_bool(state.enabled),
+    )?;
+    if let Some(namespace) = &state.namespace {
+        Reflect::set(
+            &obj,
+            &JsValue::from_str("namespace"),
+            &JsValue::from_str(namespace),
+        )?;
+    }
+    set_json(key, &obj)
+}
+
+pub(crate) fn remove(key: &str) -> Result<(), Error> {
+    call("removeItem", &[key])?;
+    Ok(())
+}
+
+impl WebLN {
+    /// Read the state saved by [`WebLN::with_persistence`] under `key`
+    pub fn persisted_state(key: &str) -> Result<Option<PersistedState>, Error> {
+        load(key)
+    }
+
+    /// Restore the instance enabled on a previous page load, if any
+    ///
+    /// Uses the saved namespace and calls `enable()` again: providers don't prompt for sites
+    /// already authorized, so the app can skip its own "connect" step. If the provider refuses,
+    /// the saved state is forgotten and the error returned.
+    pub async fn restore(key: &str) -> Result<Option<Self>, Error> {
+        let state: PersistedState = match load(key)? {
+            Some(state) if state.enabled => state,
+            _ => return Ok(None),
+        };
+        let namespace: &str = state.namespace.as_deref().unwrap_or("webln");
+        let webln: Self = Self::from_namespace(namespace)?.with_persistence(key);
+        match webln.enable().await {
+            Ok(()) => Ok(Some(webln)),
+            Err(e) => {
+                webln.forget();
+                Err(e)
+            }
+        }
+    }
+
+    /// Forget the saved state (i.e. on "disconnect"), and the cached one
+    pub fn forget(&self) {
+        if let Some(key) = &self.inner.persistence {
+            let _ = remove(key);
+        }
+        self.invalidate();
+    }
+
+    /// Save the enabled state, if persistence is on (best effort)
+    pub(crate) fn persist_enabled(&self) {
+        if let Some(key) = &self.inner.persistence {
+            let state = PersistedState {
+                enabled: true,
+                namespace: self.inner.namespace.clone(),
+            };
+            let _ = save(key, &state);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Client-side payment policy, checked before prompting
+
+use alloc::collections::BTreeSet;
+
+use crate::{Amount, Bolt11Invoice, Error, NodeId, WebLN};
+
+/// Keysend destination filter, see [`WebLN::with_keysend_filter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DestinationFilter {
+    /// Only pay these nodes
+    Allow(BTreeSet<NodeId>),
+    /// Never pay these nodes
+    Deny(BTreeSet<NodeId>),
+}
+
+impl DestinationFilter {
+    /// Only pay `destinations`
+    pub fn allow<I>(destinations: I) -> Self
+    where
+        I: IntoIterator<Item = NodeId>,
+    {
+        Self::Allow(destinations.into_iter().collect())
+    }
+
+    /// Never pay `destinations`
+    pub fn deny<I>(destinations: I) -> Self
+    where
+        I: IntoIterator<Item = NodeId>,
+    {
+        Self::Deny(destinations.into_iter().collect())
+    }
+
+    /// Check if `destination` can be paid
+    pub fn is_allowed(&self, destination: &NodeId) -> bool {
+        match self {
+            Self::Allow(allowed) => allowed.contains(destination),
+            Self::Deny(denied) => !denied.contains(destination),
+        }
+    }
+}
+
+impl WebLN {
+    /// Fail with [`Error::AmountExceedsLimit`] if `amount` is over the max amount
+    pub(crate) fn check_amount(&self, amount: Amount) -> Result<(), Error> {
+        match self.inner.max_amount {
+            Some(limit) if amount > limit => Err(Error::AmountExceedsLimit { amount, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail with [`Error::DestinationNotAllowed`] if the keysend filter rejects `destination`
+    pub(crate) fn check_destination(&self, destination: &NodeId) -> Result<(), Error> {
+        match &self.inner.keysend_filter {
+            Some(filter) if !filter.is_allowed(destination) => {
+                Err(Error::DestinationNotAllowed(*destination))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the invoice amount against the max amount
+    ///
+    /// With a max amount, invoices whose amount is unknown (unparsable or zero-amount) fail.
+    pub(crate) fn check_invoice_amount(&self, invoice: &str) -> Result<(), Error> {
+        if self.inner.max_amount.is_none() {
+            return Ok(());
+        }
+
+        match Bolt11Invoice::parse(invoice)?.amount {
+            Some(amount) => self.check_amount(amount),
+            None => Err(Error::UnknownAmount),
+        }
+    }
+}
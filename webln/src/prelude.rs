@@ -0,0 +1,32 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Prelude
+//!
+//! ```rust,no_run
+//! use webln::prelude::*;
+//! ```
+
+#[cfg(feature = "send_wrapper")]
+pub use crate::SendWebLN;
+#[cfg(feature = "alby")]
+pub use crate::{AddAccountArgs, Alby, AlbyInfo};
+pub use crate::{
+    Amount, BalanceResponse, BalanceWatcher, Bolt11Invoice, Capabilities, Checkout, CheckoutEvent,
+    CheckoutState, Clock, Currency, Deserialize as _, DestinationFilter, EnableResponse, Error,
+    ErrorCode, Event, GetInfoMethod, GetInfoNode, GetInfoResponse, IncomingPayment, KeysendArgs,
+    LightningPayer, LoginProof, MessageEncoding, MultiPaymentError, MultiPaymentItem,
+    MultiPaymentOptions, MultiPaymentProgress, NodeId, ParseMode, PaymentFailure, PaymentRoute,
+    PaymentStatus, PaymentSuccess, PaymentTracker, PersistedState, PromptLimit, ProviderError,
+    RequestInvoiceArgs, RequestInvoiceResponse, ResponseWithRaw, RgbColor,
+    SendMultiPaymentResponse, SendPaymentAsyncResponse, SendPaymentResponse, SignMessageResponse,
+    Subscription, SystemClock, Tab, TabStatus, WebLN, WebLNBuilder,
+};
+#[cfg(feature = "webbtc")]
+pub use crate::{GetAddressResponse, SendTransactionResponse, WebBTC, WebBTCInfo};
+#[cfg(feature = "rates")]
+pub use crate::{HttpRateSource, RateSource, Rates};
+#[cfg(feature = "journal")]
+pub use crate::{Journal, JournalQuery, Receipt, ReceiptKind};
+#[cfg(feature = "nostr")]
+pub use crate::{Nostr, SignedEvent, UnsignedEvent, ZapReceipt};
@@ -0,0 +1,229 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Fiat rates
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use core::cell::RefCell;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::time::Duration;
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+
+use crate::{http, Amount, BalanceResponse, Clock, Currency, Error, SystemClock};
+
+/// Default [`HttpRateSource`] endpoint (CoinGecko simple price API)
+pub const DEFAULT_RATES_ENDPOINT: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies={currency}";
+
+/// Default [`Rates`] cache TTL
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+/// Satoshis per bitcoin
+const SATS_PER_BTC: f64 = 100_000_000.0;
+
+/// Future returned by [`RateSource::btc_price`]
+pub type RateFuture<'a> = Pin<Box<dyn Future<Output = Result<f64, Error>> + 'a>>;
+
+/// Source of BTC fiat rates
+///
+/// Implement it to plug a custom source (i.e. an app backend) into [`Rates`].
+pub trait RateSource {
+    /// Price of 1 BTC in `currency` (uppercase ISO 4217 code, i.e. `USD`)
+    fn btc_price<'a>(&'a self, currency: &'a str) -> RateFuture<'a>;
+}
+
+/// Extract the price from the JSON response of an [`HttpRateSource`] endpoint
+pub type RateParser = fn(&JsValue, &str) -> Option<f64>;
+
+/// Parse CoinGecko `simple/price` responses (`{"bitcoin":{"usd":12345.6}}`)
+fn parse_coingecko(value: &JsValue, currency: &str) -> Option<f64> {
+    let bitcoin: JsValue = Reflect::get(value, &JsValue::from_str("bitcoin")).ok()?;
+    let key: String = currency.to_ascii_lowercase();
+    Reflect::get(&bitcoin, &JsValue::from_str(&key))
+        .ok()?
+        .as_f64()
+}
+
+/// Rates fetched over HTTP with the global `fetch`
+///
+/// `{currency}` in the endpoint is replaced with the lowercase currency code.
+#[derive(Debug, Clone)]
+pub struct HttpRateSource {
+    endpoint: String,
+    parser: RateParser,
+}
+
+impl Default for HttpRateSource {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_RATES_ENDPOINT.to_string(),
+            parser: parse_coingecko,
+        }
+    }
+}
+
+impl HttpRateSource {
+    /// New HTTP rate source, parsing CoinGecko-like responses
+    ///
+    /// Use [`HttpRateSource::parser`] for endpoints with a different response format.
+    pub fn new<S>(endpoint: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            endpoint: endpoint.into(),
+            parser: parse_coingecko,
+        }
+    }
+
+    /// Set the response parser
+    pub fn parser(mut self, parser: RateParser) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    async fn fetch(&self, currency: &str) -> Result<f64, Error> {
+        let unavailable = || Error::RateUnavailable(currency.to_string());
+
+        let url: String = self
+            .endpoint
+            .replace("{currency}", &currency.to_ascii_lowercase());
+        let body: JsValue = http::get_json(&url).await.map_err(|e| match e {
+            Error::NamespaceNotFound(..) => e,
+            _ => unavailable(),
+        })?;
+
+        (self.parser)(&body, currency)
+            .filter(|price| price.is_finite() && *price > 0.0)
+            .ok_or_else(unavailable)
+    }
+}
+
+impl RateSource for HttpRateSource {
+    fn btc_price<'a>(&'a self, currency: &'a str) -> RateFuture<'a> {
+        Box::pin(self.fetch(currency))
+    }
+}
+
+/// Cached fiat conversions, backed by a [`RateSource`]
+///
+/// Prices are kept for the cache TTL (60 secs by default), per currency.
+///
+/// ```rust,no_run
+/// use webln::{Amount, Rates};
+///
+/// # async fn run() -> Result<(), webln::Error> {
+/// let rates = Rates::default();
+/// let usd: f64 = rates.to_fiat(Amount::from_sats(21_000), "USD").await?;
+/// println!("{usd:.2} USD");
+/// # Ok(())
+/// # }
+/// ```
+pub struct Rates<S = HttpRateSource> {
+    source: S,
+    ttl: Duration,
+    clock: Rc<dyn Clock>,
+    /// Uppercase currency code → (fetch time, price)
+    cache: RefCell<BTreeMap<String, (Duration, f64)>>,
+}
+
+impl<S> fmt::Debug for Rates<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rates")
+            .field("ttl", &self.ttl)
+            .field("clock", &self.clock)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+impl Default for Rates {
+    fn default() -> Self {
+        Self::new(HttpRateSource::default())
+    }
+}
+
+impl<S> Rates<S>
+where
+    S: RateSource,
+{
+    /// New rates cache
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            ttl: DEFAULT_TTL,
+            clock: Rc::new(SystemClock),
+            cache: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Set the cache TTL
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the time source used for the cache TTL (default: [`SystemClock`])
+    pub fn clock(mut self, clock: Rc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Rate source
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Drop the cached prices
+    pub fn invalidate(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    /// Price of 1 BTC in `currency`, from the cache if not expired
+    pub async fn btc_price(&self, currency: &str) -> Result<f64, Error> {
+        let currency: String = currency.trim().to_ascii_uppercase();
+        let now: Duration = self.clock.now();
+
+        if let Some((fetched_at, price)) = self.cache.borrow().get(&currency) {
+            if now.saturating_sub(*fetched_at) < self.ttl {
+                return Ok(*price);
+            }
+        }
+
+        let price: f64 = self.source.btc_price(&currency).await?;
+        self.cache.borrow_mut().insert(currency, (now, price));
+        Ok(price)
+    }
+
+    /// Convert an amount to `currency`
+    pub async fn to_fiat(&self, amount: Amount, currency: &str) -> Result<f64, Error> {
+        let price: f64 = self.btc_price(currency).await?;
+        Ok(amount.btc() * price)
+    }
+
+    /// Convert a balance to `currency`
+    ///
+    /// Fiat balances in another currency are converted through their BTC price.
+    pub async fn balance_to_fiat(
+        &self,
+        balance: &BalanceResponse,
+        currency: &str,
+    ) -> Result<f64, Error> {
+        let btc: f64 = match balance.currency_type() {
+            Currency::Sats => balance.balance / SATS_PER_BTC,
+            Currency::Btc => balance.balance,
+            Currency::Fiat(code) if code.eq_ignore_ascii_case(currency.trim()) => {
+                return Ok(balance.balance)
+            }
+            Currency::Fiat(code) => balance.balance / self.btc_price(&code).await?,
+        };
+        let price: f64 = self.btc_price(currency).await?;
+        Ok(btc * price)
+    }
+}
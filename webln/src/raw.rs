@@ -0,0 +1,56 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Raw responses
+
+use core::ops::Deref;
+
+use wasm_bindgen::JsValue;
+
+use crate::{Deserialize, Error, ParseMode};
+
+/// Parsed response along with the original value returned by the provider
+///
+/// Use it to read provider specific fields not modeled by the crate. Derefs to the parsed
+/// response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseWithRaw<T> {
+    /// Parsed response
+    pub response: T,
+    /// Value resolved by the provider
+    pub raw: JsValue,
+}
+
+impl<T> ResponseWithRaw<T> {
+    /// Parse `raw`, in [`ParseMode::Lenient`] mode
+    pub fn parse(raw: JsValue) -> Result<Self, Error>
+    where
+        T: Deserialize,
+    {
+        Self::parse_with_mode(raw, ParseMode::Lenient)
+    }
+
+    /// Parse `raw`
+    pub fn parse_with_mode(raw: JsValue, mode: ParseMode) -> Result<Self, Error>
+    where
+        T: Deserialize,
+    {
+        Ok(Self {
+            response: T::deserialize_with_mode(&raw, mode)?,
+            raw,
+        })
+    }
+
+    /// Get the parsed response, dropping the raw value
+    pub fn into_inner(self) -> T {
+        self.response
+    }
+}
+
+impl<T> Deref for ResponseWithRaw<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.response
+    }
+}
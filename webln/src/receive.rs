@@ -0,0 +1,228 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Receive payments
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use core::cell::RefCell;
+use core::future::{self, Future};
+use core::pin::Pin;
+use core::task::{Poll, Waker};
+use core::time::Duration;
+
+use crate::{
+    Bolt11Invoice, Deserialize, Error, ErrorCode, Event, PaymentStatus, PaymentSuccess,
+    PaymentTracker, RequestInvoiceArgs, Sleep, Subscription, WebLN,
+};
+
+/// First delay before polling again after an error
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+/// Max delay before polling again after an error
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Pending node (or LUD-21) polling: the final status
+type Polling = Pin<Box<dyn Future<Output = Result<PaymentStatus, Error>>>>;
+
+/// Poll until the final status
+///
+/// With `retry`, polling errors are retried with an exponential backoff, except user rejections
+/// and unsupported lookups.
+async fn final_status(
+    webln: WebLN,
+    mut tracker: PaymentTracker,
+    retry: bool,
+) -> Result<PaymentStatus, Error> {
+    let mut backoff: Duration = MIN_BACKOFF;
+    loop {
+        match tracker.next().await {
+            Some(Ok(PaymentStatus::Pending)) => backoff = MIN_BACKOFF,
+            Some(Err(e))
+                if retry && !e.is_user_rejected() && e.code() != ErrorCode::UnsupportedMethod =>
+            {
+                webln.clock().sleep(backoff).await?;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+            Some(res) => return res,
+            None => return Err(Error::SomethingGoneWrong),
+        }
+    }
+}
+
+/// Preimage reported by the `paymentReceived` event, and the waker of the pending wait
+#[derive(Default)]
+struct Received {
+    preimage: Option<String>,
+    waker: Option<Waker>,
+}
+
+/// Invoice created by [`WebLN::receive`], waiting to be paid
+///
+/// Show [`IncomingPayment::invoice`] to the payer, then wait for [`IncomingPayment::settled`].
+#[derive(Debug)]
+pub struct IncomingPayment {
+    webln: WebLN,
+    invoice: Bolt11Invoice,
+    payment_request: String,
+    tracker: PaymentTracker,
+}
+
+impl IncomingPayment {
+    /// BOLT11 invoice to pay
+    pub fn invoice(&self) -> &str {
+        &self.payment_request
+    }
+
+    /// Payment hash (hex)
+    pub fn payment_hash(&self) -> &str {
+        &self.invoice.payment_hash
+    }
+
+    /// Decoded invoice
+    pub fn decoded(&self) -> &Bolt11Invoice {
+        &self.invoice
+    }
+
+    /// Poll a LUD-21 verify URL instead of the node
+    pub fn verify_url<S>(mut self, url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.tracker = self.tracker.verify_url(url);
+        self
+    }
+
+    /// Set the polling interval
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.tracker = self.tracker.interval(interval);
+        self
+    }
+
+    /// Wait for the payment, returning the preimage
+    ///
+    /// Listens for the `paymentReceived` provider event and, at the same time, polls the LUD-21
+    /// verify URL (if set) or the node (LND `lookupinvoice` or CLN `listinvoices`). If polling isn't
+    /// supported, only the event is waited for. Polling errors are retried with a backoff while the
+    /// event is listened for. Fails if the invoice is canceled or expires (by the
+    /// [`Clock`](crate::Clock) of the instance).
+    pub async fn settled(self) -> Result<String, Error> {
+        let now: u64 = self.webln.clock().now().as_secs();
+        let expires_at: u64 = self.invoice.expires_at();
+        if now >= expires_at {
+            return Err(Error::InvoiceExpired);
+        }
+
+        let received: Rc<RefCell<Received>> = Rc::new(RefCell::new(Received::default()));
+        let subscription: Option<Subscription> = {
+            let received = received.clone();
+            let payment_hash: String = self.invoice.payment_hash.clone();
+            let payment_request: String = self.payment_request.clone();
+            self.webln
+                .subscribe(Event::PaymentReceived, move |payload| {
+                    let payment = PaymentSuccess::deserialize(&payload).unwrap_or_default();
+                    let matches: bool = payment
+                        .payment_hash
+                        .as_deref()
+                        .map_or(false, |hash| hash.eq_ignore_ascii_case(&payment_hash))
+                        || payment.payment_request.as_deref() == Some(payment_request.as_str());
+                    if let (true, Some(preimage)) = (matches, payment.preimage) {
+                        let mut received = received.borrow_mut();
+                        received.preimage = Some(preimage);
+                        if let Some(waker) = received.waker.take() {
+                            waker.wake();
+                        }
+                    }
+                })
+                .ok()
+        };
+
+        let mut polling: Option<Polling> = Some(Box::pin(final_status(
+            self.webln.clone(),
+            self.tracker,
+            subscription.is_some(),
+        )));
+        let mut expiry: Option<Sleep> = Some(
+            self.webln
+                .clock()
+                .sleep(Duration::from_secs(expires_at - now)),
+        );
+        let res: Result<String, Error> = future::poll_fn(|cx| {
+            {
+                let mut received = received.borrow_mut();
+                if let Some(preimage) = received.preimage.take() {
+                    return Poll::Ready(Ok(preimage));
+                }
+                received.waker = Some(cx.waker().clone());
+            }
+
+            if let Some(tick) = polling.as_mut() {
+                match tick.as_mut().poll(cx) {
+                    Poll::Ready(Ok(PaymentStatus::Settled { preimage })) => {
+                        return Poll::Ready(
+                            preimage
+                                .ok_or_else(|| Error::ObjectKeyNotFound(String::from("preimage"))),
+                        )
+                    }
+                    Poll::Ready(Ok(PaymentStatus::Failed { reason })) => {
+                        return Poll::Ready(Err(Error::PaymentFailed(
+                            reason.unwrap_or_else(|| String::from("unknown reason")),
+                        )))
+                    }
+                    Poll::Ready(Ok(PaymentStatus::Pending)) => polling = None,
+                    // Node lookups not supported: wait for the event only
+                    Poll::Ready(Err(e)) if subscription.is_none() || e.is_user_rejected() => {
+                        return Poll::Ready(Err(e))
+                    }
+                    Poll::Ready(Err(..)) => polling = None,
+                    Poll::Pending => (),
+                }
+            }
+
+            if let Some(timer) = expiry.as_mut() {
+                match timer.as_mut().poll(cx) {
+                    Poll::Ready(Ok(())) => return Poll::Ready(Err(Error::InvoiceExpired)),
+                    // No timers: rely on the node reporting the expiry
+                    Poll::Ready(Err(..)) => expiry = None,
+                    Poll::Pending => (),
+                }
+            }
+            Poll::Pending
+        })
+        .await;
+
+        drop(subscription);
+        res
+    }
+}
+
+impl WebLN {
+    /// Create an invoice for `amount` SAT, to wait for its payment
+    ///
+    /// The "show invoice, wait for payment" flow: see [`IncomingPayment`].
+    ///
+    /// ```rust,no_run
+    /// use webln::WebLN;
+    ///
+    /// # async fn run() -> Result<(), webln::Error> {
+    /// let webln = WebLN::new()?;
+    /// let incoming = webln.receive(2100, "Coffee").await?;
+    /// println!("Pay {}", incoming.invoice());
+    /// let preimage = incoming.settled().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn receive(&self, amount: u64, memo: &str) -> Result<IncomingPayment, Error> {
+        let args = RequestInvoiceArgs::new()
+            .amount(amount)
+            .default_memo(memo.to_string());
+        let payment_request: String = self.make_invoice(&args).await?.invoice;
+        let invoice: Bolt11Invoice = Bolt11Invoice::parse(&payment_request)?;
+        Ok(IncomingPayment {
+            webln: self.clone(),
+            tracker: self.track_invoice(invoice.payment_hash.clone()),
+            invoice,
+            payment_request,
+        })
+    }
+}
@@ -0,0 +1,38 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Keysend refunds
+
+#[cfg(feature = "journal")]
+use crate::Receipt;
+use crate::{Error, KeysendArgs, NodeId, SendPaymentResponse, WebLN};
+
+/// TLV record type carrying the refund reference (odd: ignored by recipients that don't know it)
+pub const REFUND_REFERENCE_TLV: u64 = 733863;
+
+impl WebLN {
+    /// Refund `amount` SAT to `destination` with a keysend payment
+    ///
+    /// `reference` (i.e. the order ID) is added as [`REFUND_REFERENCE_TLV`] record, so the
+    /// recipient can match the refund. The payment is recorded in the journal, if any, as a refund
+    /// receipt carrying the reference.
+    pub async fn refund(
+        &self,
+        destination: NodeId,
+        amount: u64,
+        reference: &str,
+    ) -> Result<SendPaymentResponse, Error> {
+        let args =
+            KeysendArgs::new(destination, amount).custom_record(REFUND_REFERENCE_TLV, reference);
+        let res: SendPaymentResponse = self.send_keysend(&args).await?;
+        #[cfg(feature = "journal")]
+        self.record(Receipt::refund(
+            self.clock(),
+            &args,
+            res.preimage.as_str(),
+            reference,
+        ))
+        .await;
+        Ok(res)
+    }
+}
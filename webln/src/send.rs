@@ -0,0 +1,68 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! `Send` wrapper
+
+use core::future::Future;
+use core::ops::Deref;
+
+use send_wrapper::SendWrapper;
+
+use crate::{Error, WebLN};
+
+/// `Send + Sync` WebLN handle
+///
+/// JS objects can't leave the thread that created them: accessing the inner [`WebLN`] (or polling
+/// a future returned by [`SendWebLN::send`]) from another thread **panics** instead of being UB.
+///
+/// ```rust,no_run
+/// # async fn example() -> Result<(), webln::Error> {
+/// use webln::SendWebLN;
+///
+/// let webln = SendWebLN::new()?;
+/// let info = webln.send(webln.get_info()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SendWebLN {
+    inner: SendWrapper<WebLN>,
+}
+
+impl Deref for SendWebLN {
+    type Target = WebLN;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl From<WebLN> for SendWebLN {
+    fn from(webln: WebLN) -> Self {
+        Self {
+            inner: SendWrapper::new(webln),
+        }
+    }
+}
+
+impl SendWebLN {
+    /// Compose new WebLN instance
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self::from(WebLN::new()?))
+    }
+
+    /// Make a [`WebLN`] future `Send`
+    pub fn send<F>(&self, future: F) -> SendWrapper<F>
+    where
+        F: Future,
+    {
+        SendWrapper::new(future)
+    }
+
+    /// Get inner [`WebLN`]
+    ///
+    /// Panics if called from a thread other than the one that created the handle.
+    pub fn into_inner(self) -> WebLN {
+        self.inner.take()
+    }
+}
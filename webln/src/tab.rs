@@ -0,0 +1,256 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Micro-payment tab
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::cell::{Cell, RefCell};
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::time::Duration;
+
+use crate::{Amount, Error, KeysendArgs, NodeId, SendPaymentResponse, WebLN};
+
+/// Future returned by the invoice callback of [`Tab::invoice`]
+pub type InvoiceFuture = Pin<Box<dyn Future<Output = Result<String, Error>>>>;
+
+/// Callback of [`Tab::on_change`]
+type OnChange = RefCell<Box<dyn FnMut(&TabStatus)>>;
+
+/// Where the tab is settled
+enum Payee {
+    Keysend(NodeId),
+    Invoice(Box<dyn Fn(Amount) -> InvoiceFuture>),
+}
+
+impl fmt::Debug for Payee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keysend(node_id) => f.debug_tuple("Keysend").field(node_id).finish(),
+            Self::Invoice(..) => f.debug_tuple("Invoice").finish(),
+        }
+    }
+}
+
+/// Snapshot of a [`Tab`], passed to [`Tab::on_change`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct TabStatus {
+    /// Owed and not paid yet
+    pub pending: Amount,
+    /// Being paid by the current settlement
+    pub settling: Amount,
+    /// Paid since the tab was opened
+    pub settled: Amount,
+    /// Charges since the last settlement
+    pub charges: u32,
+    /// Consecutive failed settlements
+    pub failures: u32,
+}
+
+/// Tab of tiny owed amounts, settled with a single payment
+///
+/// For games and pay-per-action apps: [`Tab::charge`] tallies the amounts locally (with
+/// millisatoshi precision), and the tab is settled at the [`Tab::threshold`], periodically with
+/// [`Tab::run`], or on demand with [`Tab::settle`]. Only whole sats are paid: the remainder stays
+/// on the tab.
+///
+/// ```rust,no_run
+/// use core::time::Duration;
+///
+/// use webln::{Amount, NodeId, Tab, WebLN};
+///
+/// # async fn run(node_id: NodeId) -> Result<(), webln::Error> {
+/// let webln = WebLN::new()?;
+/// let tab = Tab::keysend(webln, node_id)
+///     .threshold(Amount::from_sats(100))
+///     .on_change(|status| println!("Pending: {}", status.pending));
+///
+/// // On every action
+/// tab.charge(Amount::from_msat(250)).await?;
+///
+/// // Or settle every minute (until `tab.close()`)
+/// tab.run(Duration::from_secs(60)).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Tab {
+    webln: WebLN,
+    payee: Payee,
+    threshold: Option<Amount>,
+    status: Cell<TabStatus>,
+    closed: Cell<bool>,
+    on_change: Option<OnChange>,
+}
+
+impl fmt::Debug for Tab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tab")
+            .field("payee", &self.payee)
+            .field("threshold", &self.threshold)
+            .field("status", &self.status.get())
+            .field("closed", &self.closed.get())
+            .finish()
+    }
+}
+
+impl Tab {
+    fn new(webln: WebLN, payee: Payee) -> Self {
+        Self {
+            webln,
+            payee,
+            threshold: None,
+            status: Cell::new(TabStatus::default()),
+            closed: Cell::new(false),
+            on_change: None,
+        }
+    }
+
+    /// New tab, settled with keysend payments to `destination`
+    pub fn keysend(webln: WebLN, destination: NodeId) -> Self {
+        Self::new(webln, Payee::Keysend(destination))
+    }
+
+    /// New tab, settled by paying the invoices returned by `invoice` for the settled amount
+    ///
+    /// Invoices for another amount are rejected (see [`WebLN::send_payment_with_amount`]).
+    pub fn invoice<F, Fut>(webln: WebLN, invoice: F) -> Self
+    where
+        F: Fn(Amount) -> Fut + 'static,
+        Fut: Future<Output = Result<String, Error>> + 'static,
+    {
+        Self::new(
+            webln,
+            Payee::Invoice(Box::new(move |amount| Box::pin(invoice(amount)))),
+        )
+    }
+
+    /// Settle as soon as the pending amount reaches `threshold`
+    pub fn threshold(mut self, threshold: Amount) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Call `callback` on every change of the tab (i.e. to display the pending amount)
+    pub fn on_change<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&TabStatus) + 'static,
+    {
+        self.on_change = Some(RefCell::new(Box::new(callback)));
+        self
+    }
+
+    /// Current status
+    pub fn status(&self) -> TabStatus {
+        self.status.get()
+    }
+
+    /// Owed and not paid yet
+    pub fn pending(&self) -> Amount {
+        self.status.get().pending
+    }
+
+    fn update<F>(&self, f: F)
+    where
+        F: FnOnce(&mut TabStatus),
+    {
+        let mut status: TabStatus = self.status.get();
+        f(&mut status);
+        self.status.set(status);
+        if let Some(callback) = &self.on_change {
+            (callback.borrow_mut())(&status);
+        }
+    }
+
+    /// Add `amount` to the tab, settling it if the threshold is reached
+    ///
+    /// Returns the settlement payment, if any.
+    pub async fn charge(&self, amount: Amount) -> Result<Option<SendPaymentResponse>, Error> {
+        self.update(|status| {
+            status.pending = Amount::from_msat(status.pending.msat().saturating_add(amount.msat()));
+            status.charges = status.charges.saturating_add(1);
+        });
+
+        match self.threshold {
+            Some(threshold) if self.pending() >= threshold => self.settle().await,
+            _ => Ok(None),
+        }
+    }
+
+    /// Pay the whole sats of the pending amount
+    ///
+    /// Returns `None` if there's less than 1 sat to pay, or if a settlement is already running.
+    /// On failure the amount stays on the tab.
+    pub async fn settle(&self) -> Result<Option<SendPaymentResponse>, Error> {
+        let status: TabStatus = self.status.get();
+        let amount: Amount = Amount::from_sats(status.pending.sats());
+        if status.settling != Amount::ZERO || amount == Amount::ZERO {
+            return Ok(None);
+        }
+
+        self.update(|status| {
+            status.pending = Amount::from_msat(status.pending.msat() - amount.msat());
+            status.settling = amount;
+        });
+
+        let res: Result<SendPaymentResponse, Error> = match &self.payee {
+            Payee::Keysend(destination) => {
+                let args = KeysendArgs::new(*destination, amount.sats());
+                self.webln.keysend(&args).await
+            }
+            Payee::Invoice(invoice) => match invoice(amount).await {
+                Ok(invoice) => self.webln.send_payment_with_amount(&invoice, amount).await,
+                Err(e) => Err(e),
+            },
+        };
+
+        match res {
+            Ok(res) => {
+                self.update(|status| {
+                    status.settling = Amount::ZERO;
+                    status.settled =
+                        Amount::from_msat(status.settled.msat().saturating_add(amount.msat()));
+                    status.charges = 0;
+                    status.failures = 0;
+                });
+                Ok(Some(res))
+            }
+            Err(e) => {
+                self.update(|status| {
+                    status.settling = Amount::ZERO;
+                    status.pending =
+                        Amount::from_msat(status.pending.msat().saturating_add(amount.msat()));
+                    status.failures = status.failures.saturating_add(1);
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Settle every `interval`, until [`Tab::close`]
+    ///
+    /// Failed settlements are retried at the next tick (see [`TabStatus::failures`]). Once
+    /// closed, the tab is settled a last time and the result is returned.
+    pub async fn run(&self, interval: Duration) -> Result<(), Error> {
+        while !self.closed.get() {
+            self.webln.clock().sleep(interval).await?;
+            if !self.closed.get() {
+                let _ = self.settle().await;
+            }
+        }
+        self.settle().await.map(|_| ())
+    }
+
+    /// Stop [`Tab::run`] at its next tick
+    pub fn close(&self) {
+        self.closed.set(true);
+    }
+
+    /// Check if the tab was closed
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Time
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
+use core::future::{self, Future};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use js_sys::{Date, Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{Error, WebLN};
+
+/// Future returned by [`Clock::sleep`]
+pub type Sleep = Pin<Box<dyn Future<Output = Result<(), Error>>>>;
+
+/// Source of time for timeouts, TTL caches and polling
+///
+/// [`SystemClock`] is used by default: implement it to control time in tests.
+pub trait Clock: fmt::Debug {
+    /// Time elapsed since the UNIX epoch
+    fn now(&self) -> Duration;
+
+    /// Resolve after `duration`
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// Wall clock: `Date.now()` and `setTimeout`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(sleep(duration))
+    }
+}
+
+/// Time elapsed since the UNIX epoch
+pub(crate) fn now() -> Duration {
+    Duration::from_secs_f64(Date::now() / 1000.0)
+}
+
+/// Sleep using the global `setTimeout` (of the window or of the worker)
+pub(crate) async fn sleep(duration: Duration) -> Result<(), Error> {
+    let set_timeout: Function = Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+        .ok()
+        .and_then(|func| func.dyn_into().ok())
+        .ok_or_else(|| Error::NamespaceNotFound(String::from("setTimeout")))?;
+    let millis: i32 = duration.as_millis().try_into().unwrap_or(i32::MAX);
+    let promise = Promise::new(&mut |resolve, reject| {
+        if let Err(e) = set_timeout.call2(&JsValue::UNDEFINED, &resolve, &JsValue::from(millis)) {
+            let _ = reject.call1(&JsValue::NULL, &e);
+        }
+    });
+    JsFuture::from(promise).await?;
+    Ok(())
+}
+
+/// Wait for `future` up to `duration` of `clock`: `None` if it didn't complete in time
+///
+/// If the timer can't be set (no `setTimeout`), `future` is awaited without deadline.
+pub(crate) async fn timeout<F>(
+    clock: &dyn Clock,
+    duration: Duration,
+    future: F,
+) -> Option<F::Output>
+where
+    F: Future,
+{
+    let mut future: Pin<Box<F>> = Box::pin(future);
+    let mut timer: Option<Sleep> = Some(clock.sleep(duration));
+    future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+
+        if let Some(t) = timer.as_mut() {
+            match t.as_mut().poll(cx) {
+                Poll::Ready(Ok(())) => return Poll::Ready(None),
+                Poll::Ready(Err(..)) => timer = None,
+                Poll::Pending => (),
+            }
+        }
+
+        Poll::Pending
+    })
+    .await
+}
+
+/// Pending poll of a [`Poller`]: the next change
+type Tick<T> = Pin<Box<dyn Future<Output = Result<T, Error>>>>;
+
+/// Run `check` every `interval` until the result differs from `last`
+async fn wait_for_change<T, F, Fut>(
+    webln: WebLN,
+    check: F,
+    mut delay: Option<Duration>,
+    interval: Duration,
+    last: Option<T>,
+) -> Result<T, Error>
+where
+    T: PartialEq,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    loop {
+        if let Some(delay) = delay {
+            webln.clock().sleep(delay).await?;
+        }
+        delay = Some(interval);
+
+        match check().await {
+            Ok(value) if last.as_ref() == Some(&value) => continue,
+            res => return res,
+        }
+    }
+}
+
+/// Polling at a fixed interval (by the [`Clock`] of the instance), yielding only the changes
+///
+/// The first poll yields the current value immediately. Errors are yielded too, keeping the last
+/// value.
+pub(crate) struct Poller<T> {
+    pub(crate) interval: Duration,
+    last: Option<T>,
+    started: bool,
+    tick: Option<Tick<T>>,
+}
+
+impl<T> Poller<T>
+where
+    T: Clone + PartialEq + 'static,
+{
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: None,
+            started: false,
+            tick: None,
+        }
+    }
+
+    /// Last yielded value
+    pub(crate) fn last(&self) -> Option<&T> {
+        self.last.as_ref()
+    }
+
+    /// Poll the next change, running `check` on each tick
+    pub(crate) fn poll_change<F, Fut>(
+        &mut self,
+        cx: &mut Context<'_>,
+        webln: &WebLN,
+        check: F,
+    ) -> Poll<Result<T, Error>>
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<T, Error>> + 'static,
+    {
+        let tick: &mut Tick<T> = self.tick.get_or_insert_with(|| {
+            // Don't wait before the first check
+            let delay: Option<Duration> = self.started.then_some(self.interval);
+            Box::pin(wait_for_change(
+                webln.clone(),
+                check,
+                delay,
+                self.interval,
+                self.last.clone(),
+            ))
+        });
+
+        match tick.as_mut().poll(cx) {
+            Poll::Ready(res) => {
+                self.tick = None;
+                self.started = true;
+                if let Ok(value) = &res {
+                    self.last = Some(value.clone());
+                }
+                Poll::Ready(res)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,384 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Payment status tracker
+
+use alloc::string::String;
+#[cfg(feature = "stream")]
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+use core::{fmt, future};
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::time::Poller;
+use crate::value::expect_object;
+use crate::{base64, hex, http, Error, ProviderError, Value, WebLN};
+
+/// LND `ListPayments` method name, as exposed by `request`
+const LND_LIST_PAYMENTS: &str = "listpayments";
+/// Recent LND payments to look the hash up in
+const LND_MAX_PAYMENTS: &str = "100";
+/// CLN `listpays` method name, as exposed by `request`
+const CLN_LIST_PAYS: &str = "listpays";
+/// LND `LookupInvoice` method name, as exposed by `request`
+const LND_LOOKUP_INVOICE: &str = "lookupinvoice";
+/// CLN `listinvoices` method name, as exposed by `request`
+const CLN_LIST_INVOICES: &str = "listinvoices";
+/// Default polling interval
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Payment status
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PaymentStatus {
+    /// Not settled yet (or not known by the node yet)
+    Pending,
+    /// Settled
+    Settled {
+        /// Preimage, if reported
+        preimage: Option<String>,
+    },
+    /// Failed
+    Failed {
+        /// Failure reason, if reported
+        reason: Option<String>,
+    },
+}
+
+impl PaymentStatus {
+    /// Check if the status can't change anymore
+    pub fn is_final(&self) -> bool {
+        !matches!(self, Self::Pending)
+    }
+}
+
+/// Non-empty string (nodes report missing values as `""`)
+fn get_string(obj: &Value, key: &str) -> Option<String> {
+    obj.get(key)
+        .as_str()
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+}
+
+/// LUD-21 verify response: `{"status":"OK","settled":true,"preimage":"..."}`
+fn parse_lud21(raw: JsValue) -> Result<PaymentStatus, Error> {
+    let value: Value = Value::from_js(&raw);
+    if get_string(&value, "status").as_deref() == Some("ERROR") {
+        return Err(Error::Provider(ProviderError {
+            message: get_string(&value, "reason").unwrap_or_default(),
+            raw,
+            ..Default::default()
+        }));
+    }
+
+    let settled: bool = value
+        .get("settled")
+        .as_bool()
+        .ok_or_else(|| Error::TypeMismatch(String::from("expected a bool [settled]")))?;
+    if settled {
+        Ok(PaymentStatus::Settled {
+            preimage: get_string(&value, "preimage"),
+        })
+    } else {
+        Ok(PaymentStatus::Pending)
+    }
+}
+
+/// Find the payment in LND `listpayments`, CLN `listpays` or CLN `listinvoices` results
+fn parse_payments(value: &Value, key: &str, payment_hash: &str) -> Result<PaymentStatus, Error> {
+    let payment: Option<&Value> = expect_object(value)?
+        .get(key)
+        .as_array()
+        .ok_or_else(|| Error::TypeMismatch(alloc::format!("expected an array [{key}]")))?
+        .iter()
+        .find(|payment| {
+            get_string(payment, "payment_hash")
+                .map_or(false, |hash| hash.eq_ignore_ascii_case(payment_hash))
+        });
+    let payment: &Value = match payment {
+        Some(payment) => payment,
+        None => return Ok(PaymentStatus::Pending),
+    };
+
+    let status: Option<String> = get_string(payment, "status");
+    match status.as_deref() {
+        Some("SUCCEEDED") | Some("complete") | Some("paid") => Ok(PaymentStatus::Settled {
+            preimage: get_string(payment, "payment_preimage")
+                .or_else(|| get_string(payment, "preimage")),
+        }),
+        Some("FAILED") | Some("failed") => Ok(PaymentStatus::Failed {
+            reason: get_string(payment, "failure_reason"),
+        }),
+        Some("expired") => Ok(PaymentStatus::Failed {
+            reason: Some(String::from("Invoice expired")),
+        }),
+        _ => Ok(PaymentStatus::Pending),
+    }
+}
+
+/// LND `lookupinvoice` result (`r_preimage` is base64 in the REST API)
+fn parse_lnd_invoice(value: &Value) -> PaymentStatus {
+    match get_string(value, "state").as_deref() {
+        Some("SETTLED") => PaymentStatus::Settled {
+            preimage: get_string(value, "r_preimage").map(|preimage| {
+                match (hex::decode(&preimage), base64::decode(&preimage)) {
+                    (Ok(..), ..) | (.., None) => preimage,
+                    (.., Some(bytes)) => hex::encode(bytes),
+                }
+            }),
+        },
+        Some("CANCELED") => PaymentStatus::Failed {
+            reason: Some(String::from("Invoice canceled")),
+        },
+        _ => PaymentStatus::Pending,
+    }
+}
+
+/// Where to look the payment up
+#[derive(Debug, Clone)]
+struct Checker {
+    webln: WebLN,
+    payment_hash: String,
+    verify_url: Option<String>,
+    /// Look up an invoice of the node, instead of an outgoing payment
+    incoming: bool,
+}
+
+impl Checker {
+    async fn check(&self) -> Result<PaymentStatus, Error> {
+        if let Some(url) = &self.verify_url {
+            return parse_lud21(http::get_json(url).await?);
+        }
+        if self.incoming {
+            return self.check_invoice().await;
+        }
+
+        let params = Object::new();
+        Reflect::set(
+            &params,
+            &JsValue::from_str("include_incomplete"),
+            &JsValue::TRUE,
+        )?;
+        Reflect::set(&params, &JsValue::from_str("reversed"), &JsValue::TRUE)?;
+        Reflect::set(
+            &params,
+            &JsValue::from_str("max_payments"),
+            &JsValue::from_str(LND_MAX_PAYMENTS),
+        )?;
+        let lnd_error: Error = match self.webln.request(LND_LIST_PAYMENTS, Some(&params)).await {
+            Ok(result) => {
+                return parse_payments(&Value::from_js(&result), "payments", &self.payment_hash)
+            }
+            Err(e) => e,
+        };
+
+        let params = Object::new();
+        Reflect::set(
+            &params,
+            &JsValue::from_str("payment_hash"),
+            &JsValue::from_str(&self.payment_hash),
+        )?;
+        match self.webln.request(CLN_LIST_PAYS, Some(&params)).await {
+            Ok(result) => parse_payments(&Value::from_js(&result), "pays", &self.payment_hash),
+            // Not a CLN node either: the LND error is more relevant
+            Err(..) => Err(lnd_error),
+        }
+    }
+
+    async fn check_invoice(&self) -> Result<PaymentStatus, Error> {
+        let params = Object::new();
+        Reflect::set(
+            &params,
+            &JsValue::from_str("r_hash_str"),
+            &JsValue::from_str(&self.payment_hash),
+        )?;
+        let lnd_error: Error = match self.webln.request(LND_LOOKUP_INVOICE, Some(&params)).await {
+            Ok(result) => return Ok(parse_lnd_invoice(&Value::from_js(&result))),
+            Err(e) => e,
+        };
+
+        let params = Object::new();
+        Reflect::set(
+            &params,
+            &JsValue::from_str("payment_hash"),
+            &JsValue::from_str(&self.payment_hash),
+        )?;
+        match self.webln.request(CLN_LIST_INVOICES, Some(&params)).await {
+            Ok(result) => parse_payments(&Value::from_js(&result), "invoices", &self.payment_hash),
+            Err(..) => Err(lnd_error),
+        }
+    }
+}
+
+/// Track the settlement of a payment (i.e. one initiated with [`WebLN::send_payment_async`])
+///
+/// Polls the LUD-21 verify URL, if set, or the node through `request` (LND `listpayments` or CLN
+/// `listpays`, or LND `lookupinvoice` or CLN `listinvoices` for [`PaymentTracker::incoming`]).
+/// With the `stream` feature, it also implements `futures_core::Stream`, ending after the final
+/// status.
+///
+/// ```rust,no_run
+/// use webln::WebLN;
+///
+/// # async fn run(payment_hash: &str) -> Result<(), webln::Error> {
+/// let webln = WebLN::new()?;
+/// let preimage = webln.track_payment(payment_hash).settled().await?;
+/// println!("Settled: {preimage:?}");
+/// # Ok(())
+/// # }
+/// ```
+pub struct PaymentTracker {
+    checker: Checker,
+    poller: Poller<PaymentStatus>,
+}
+
+impl fmt::Debug for PaymentTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PaymentTracker")
+            .field("payment_hash", &self.checker.payment_hash)
+            .field("verify_url", &self.checker.verify_url)
+            .field("incoming", &self.checker.incoming)
+            .field("interval", &self.poller.interval)
+            .field("last", &self.poller.last())
+            .finish()
+    }
+}
+
+impl PaymentTracker {
+    /// New payment tracker, polling the node every 3 secs
+    pub fn new<S>(webln: WebLN, payment_hash: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            checker: Checker {
+                webln,
+                payment_hash: payment_hash.into(),
+                verify_url: None,
+                incoming: false,
+            },
+            poller: Poller::new(DEFAULT_INTERVAL),
+        }
+    }
+
+    /// Poll a LUD-21 verify URL instead of the node
+    pub fn verify_url<S>(mut self, url: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.checker.verify_url = Some(url.into());
+        self
+    }
+
+    /// Track an invoice of the node (i.e. created with [`WebLN::make_invoice`]) instead of an
+    /// outgoing payment
+    pub fn incoming(mut self) -> Self {
+        self.checker.incoming = true;
+        self
+    }
+
+    /// Set the polling interval
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.poller.interval = interval;
+        self
+    }
+
+    /// Payment hash
+    pub fn payment_hash(&self) -> &str {
+        &self.checker.payment_hash
+    }
+
+    /// Last yielded status
+    pub fn last(&self) -> Option<&PaymentStatus> {
+        self.poller.last()
+    }
+
+    /// Check the current status once
+    pub async fn check(&self) -> Result<PaymentStatus, Error> {
+        self.checker.check().await
+    }
+
+    /// Wait for the next status change
+    ///
+    /// The first call yields the current status immediately. `None` after the final status.
+    pub async fn next(&mut self) -> Option<Result<PaymentStatus, Error>> {
+        future::poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    /// Wait for the settlement, returning the preimage (if reported)
+    ///
+    /// Fails with [`Error::PaymentFailed`] if the payment fails, or at the first polling error.
+    pub async fn settled(mut self) -> Result<Option<String>, Error> {
+        loop {
+            match self.next().await {
+                Some(Ok(PaymentStatus::Settled { preimage })) => return Ok(preimage),
+                Some(Ok(PaymentStatus::Failed { reason })) => {
+                    return Err(Error::PaymentFailed(
+                        reason.unwrap_or_else(|| String::from("unknown reason")),
+                    ))
+                }
+                Some(Ok(PaymentStatus::Pending)) => continue,
+                Some(Err(e)) => return Err(e),
+                None => return Err(Error::SomethingGoneWrong),
+            }
+        }
+    }
+
+    fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<PaymentStatus, Error>>> {
+        if self.poller.last().map_or(false, PaymentStatus::is_final) {
+            return Poll::Ready(None);
+        }
+
+        let checker: Checker = self.checker.clone();
+        let check = move || {
+            let checker: Checker = checker.clone();
+            async move { checker.check().await }
+        };
+        match self.poller.poll_change(cx, &self.checker.webln, check) {
+            Poll::Ready(res) => {
+                // Outgoing payments made with `sendPaymentAsync` are only known paid now
+                if !self.checker.incoming && matches!(res, Ok(PaymentStatus::Settled { .. })) {
+                    self.checker
+                        .webln
+                        .mark_hash_paid(self.checker.payment_hash.clone());
+                }
+                Poll::Ready(Some(res))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl futures_core::Stream for PaymentTracker {
+    type Item = Result<PaymentStatus, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_tick(cx)
+    }
+}
+
+impl WebLN {
+    /// Track the settlement of a payment, given its payment hash
+    ///
+    /// See [`PaymentTracker`].
+    pub fn track_payment<S>(&self, payment_hash: S) -> PaymentTracker
+    where
+        S: Into<String>,
+    {
+        PaymentTracker::new(self.clone(), payment_hash)
+    }
+
+    /// Track the settlement of an invoice of the node, given its payment hash
+    ///
+    /// See [`PaymentTracker::incoming`].
+    pub fn track_invoice<S>(&self, payment_hash: S) -> PaymentTracker
+    where
+        S: Into<String>,
+    {
+        PaymentTracker::new(self.clone(), payment_hash).incoming()
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Intermediate representation of the provider responses
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use js_sys::{Array, Object, Reflect, Set};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{Amount, Error, ParseMode};
+
+/// Max nesting of the converted values: deeper ones are dropped
+const MAX_DEPTH: usize = 32;
+
+static UNDEFINED: Value = Value::Undefined;
+
+/// JS value, converted once from the provider response
+///
+/// The [`Deserialize`](crate::Deserialize) implementations parse this representation, not the
+/// JS objects: the parsing logic doesn't need a JS runtime, so it's testable with `cargo test`.
+///
+/// Functions are dropped. Objects include the properties inherited from their class (i.e.
+/// getters), like `Reflect.get` would see them. Cyclic references (an object nested in itself)
+/// are dropped; objects referenced several times are converted at each place.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Value {
+    /// `undefined` (and dropped values)
+    #[default]
+    Undefined,
+    /// `null`
+    Null,
+    /// Boolean
+    Bool(bool),
+    /// Number
+    Number(f64),
+    /// String
+    String(String),
+    /// Array
+    Array(Vec<Value>),
+    /// Object
+    Object(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Convert a JS value
+    pub fn from_js(value: &JsValue) -> Self {
+        Self::convert(value, 0, &Set::new(&JsValue::UNDEFINED))
+    }
+
+    /// Convert `value`, nested in the objects and arrays of `ancestors`
+    fn convert(value: &JsValue, depth: usize, ancestors: &Set) -> Self {
+        if value.is_undefined() || value.is_function() || depth > MAX_DEPTH {
+            return Self::Undefined;
+        }
+        if value.is_null() {
+            return Self::Null;
+        }
+        if let Some(value) = value.as_bool() {
+            return Self::Bool(value);
+        }
+        if let Some(value) = value.as_f64() {
+            return Self::Number(value);
+        }
+        if let Some(value) = value.as_string() {
+            return Self::String(value);
+        }
+        // Cycle
+        if ancestors.has(value) {
+            return Self::Undefined;
+        }
+        if Array::is_array(value) {
+            ancestors.add(value);
+            let array: &Array = value.unchecked_ref();
+            let items: Vec<Value> = array
+                .iter()
+                .map(|item| Self::convert(&item, depth + 1, ancestors))
+                .collect();
+            ancestors.delete(value);
+            return Self::Array(items);
+        }
+        match value.dyn_ref::<Object>() {
+            Some(obj) => {
+                ancestors.add(value);
+                let map: BTreeMap<String, Value> = Self::convert_object(obj, depth, ancestors);
+                ancestors.delete(value);
+                Self::Object(map)
+            }
+            None => Self::Undefined,
+        }
+    }
+
+    /// Own and inherited properties, up to `Object.prototype`
+    fn convert_object(obj: &Object, depth: usize, ancestors: &Set) -> BTreeMap<String, Value> {
+        let object_prototype: JsValue = Object::get_prototype_of(&Object::new().into()).into();
+        let mut map: BTreeMap<String, Value> = BTreeMap::new();
+        let mut current: JsValue = obj.into();
+        while current.is_object() && current != object_prototype {
+            let current_obj: &Object = current.unchecked_ref();
+            for key in Object::get_own_property_names(current_obj).iter() {
+                let key: String = match key.as_string() {
+                    Some(key) if !map.contains_key(&key) => key,
+                    _ => continue,
+                };
+                // Read from `obj`, so inherited getters see the right receiver
+                let value: Value = match Reflect::get(obj, &JsValue::from_str(&key)) {
+                    Ok(value) => Self::convert(&value, depth + 1, ancestors),
+                    Err(..) => continue,
+                };
+                if value != Self::Undefined {
+                    map.insert(key, value);
+                }
+            }
+            current = Object::get_prototype_of(&current).into();
+        }
+        map
+    }
+
+    /// Get the value of an object key: [`Value::Undefined`] if missing or not an object
+    pub fn get(&self, key: &str) -> &Value {
+        match self {
+            Self::Object(map) => map.get(key).unwrap_or(&UNDEFINED),
+            _ => &UNDEFINED,
+        }
+    }
+
+    /// Check if `undefined` or `null`
+    pub fn is_nullish(&self) -> bool {
+        matches!(self, Self::Undefined | Self::Null)
+    }
+
+    /// Check if an object or an array (like `typeof value === "object"`)
+    pub fn is_object(&self) -> bool {
+        matches!(self, Self::Object(..) | Self::Array(..))
+    }
+
+    /// Get as a bool
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get as a number
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get as a string
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get as an array
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+}
+
+impl From<&JsValue> for Value {
+    fn from(value: &JsValue) -> Self {
+        Self::from_js(value)
+    }
+}
+
+/// Check that a response is an object
+pub(crate) fn expect_object(value: &Value) -> Result<&Value, Error> {
+    if value.is_object() {
+        Ok(value)
+    } else {
+        Err(Error::SomethingGoneWrong)
+    }
+}
+
+/// Get a required string
+pub(crate) fn get_string(obj: &Value, key: &str) -> Result<String, Error> {
+    obj.get(key)
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| Error::TypeMismatch(format!("expected a string [{key}]")))
+}
+
+/// Get an optional value, checked by `check` (described by `expected`, i.e. "an array"):
+/// missing and `null` values are `None`
+pub(crate) fn get_optional<'a>(
+    obj: &'a Value,
+    key: &str,
+    expected: &str,
+    check: fn(&Value) -> bool,
+    mode: ParseMode,
+) -> Result<Option<&'a Value>, Error> {
+    let value: &Value = obj.get(key);
+    if value.is_nullish() {
+        return Ok(None);
+    }
+
+    match (check(value), mode) {
+        (true, ..) => Ok(Some(value)),
+        (false, ParseMode::Lenient) => Ok(None),
+        (false, ParseMode::Strict) => {
+            Err(Error::TypeMismatch(format!("expected {expected} [{key}]")))
+        }
+    }
+}
+
+/// Get an optional object
+pub(crate) fn get_optional_object<'a>(
+    obj: &'a Value,
+    key: &str,
+    mode: ParseMode,
+) -> Result<Option<&'a Value>, Error> {
+    get_optional(obj, key, "an object", Value::is_object, mode)
+}
+
+/// Get an optional array
+pub(crate) fn get_optional_array<'a>(
+    obj: &'a Value,
+    key: &str,
+    mode: ParseMode,
+) -> Result<Option<&'a [Value]>, Error> {
+    Ok(get_optional(
+        obj,
+        key,
+        "an array",
+        |value| value.as_array().is_some(),
+        mode,
+    )?
+    .and_then(Value::as_array))
+}
+
+/// Get an optional bool: missing and `null` values are `None`
+pub(crate) fn get_optional_bool(
+    obj: &Value,
+    key: &str,
+    mode: ParseMode,
+) -> Result<Option<bool>, Error> {
+    Ok(
+        get_optional(obj, key, "a bool", |value| value.as_bool().is_some(), mode)?
+            .and_then(Value::as_bool),
+    )
+}
+
+/// Get an optional string: missing and `null` values are `None`
+pub(crate) fn get_optional_string(
+    obj: &Value,
+    key: &str,
+    mode: ParseMode,
+) -> Result<Option<String>, Error> {
+    Ok(
+        get_optional(obj, key, "a string", |value| value.as_str().is_some(), mode)?
+            .and_then(Value::as_str)
+            .map(String::from),
+    )
+}
+
+/// Get an optional array of strings: missing and `null` values are empty
+#[cfg(any(feature = "webbtc", feature = "alby"))]
+pub(crate) fn get_optional_strings(
+    obj: &Value,
+    key: &str,
+    mode: ParseMode,
+) -> Result<Vec<String>, Error> {
+    Ok(get_optional_array(obj, key, mode)?
+        .map(|array| {
+            array
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Parse a non-negative integer, reported as a number or a numeric string
+///
+/// Fractional and out of range numbers are rejected, not rounded.
+fn parse_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(value) => value.trim().parse().ok(),
+        // `u64::MAX as f64` rounds up to 2^64
+        Value::Number(value) if *value >= 0.0 && *value < u64::MAX as f64 => {
+            // No `f64::fract` without `std`
+            let int: u64 = *value as u64;
+            (int as f64 == *value).then_some(int)
+        }
+        _ => None,
+    }
+}
+
+/// Get an optional amount in `unit` (msat or sat), reported as a number or a numeric string:
+/// missing and `null` values are `None`
+pub(crate) fn get_optional_amount(
+    obj: &Value,
+    key: &str,
+    unit: fn(u64) -> Amount,
+    mode: ParseMode,
+) -> Result<Option<Amount>, Error> {
+    Ok(get_optional(
+        obj,
+        key,
+        "an amount",
+        |value| parse_u64(value).is_some(),
+        mode,
+    )?
+    .and_then(parse_u64)
+    .map(unit))
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! WebBTC companion (`window.webbtc`)
+//!
+//! <https://github.com/getAlby/webbtc>
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use js_sys::Object;
+use wasm_bindgen::JsValue;
+
+use crate::value::{expect_object, get_optional_string, get_optional_strings, get_string};
+use crate::{Amount, Deserialize, Error, GetInfoMethod, ParseMode, Value, WebLN};
+
+/// `window` property of the provider
+const NAMESPACE: &str = "webbtc";
+const GET_ADDRESS: &str = "getAddress";
+const SEND_TRANSACTION: &str = "sendTransaction";
+
+/// WebBTC `getInfo` response
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct WebBTCInfo {
+    /// Provider version
+    pub version: Option<String>,
+    /// Supported features (i.e. `lightning`, `onchain`)
+    pub supports: Vec<String>,
+    /// Supported methods
+    pub methods: Vec<String>,
+}
+
+impl Deserialize for WebBTCInfo {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let info_obj: &Value = expect_object(value)?;
+        Ok(Self {
+            version: get_optional_string(info_obj, "version", mode)?,
+            supports: get_optional_strings(info_obj, "supports", mode)?,
+            methods: get_optional_strings(info_obj, "methods", mode)?,
+        })
+    }
+}
+
+/// WebBTC `getAddress` response
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub struct GetAddressResponse {
+    /// On-chain address
+    pub address: String,
+    /// Derivation path, if returned by the provider
+    pub derivation_path: Option<String>,
+    /// Derivation index, if returned by the provider
+    pub index: Option<u32>,
+    /// Hex encoded public key, if returned by the provider
+    pub public_key: Option<String>,
+}
+
+impl Deserialize for GetAddressResponse {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let address_obj: &Value = expect_object(value)?;
+        Ok(Self {
+            address: get_string(address_obj, "address")?,
+            derivation_path: get_optional_string(address_obj, "derivationPath", mode)?,
+            index: address_obj.get("index").as_f64().map(|index| index as u32),
+            public_key: get_optional_string(address_obj, "publicKey", mode)?,
+        })
+    }
+}
+
+/// WebBTC `sendTransaction` response
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct SendTransactionResponse {
+    /// Transaction ID, if returned by the provider
+    pub txid: Option<String>,
+}
+
+impl Deserialize for SendTransactionResponse {
+    fn from_value(value: &Value, mode: ParseMode) -> Result<Self, Error> {
+        let tx_obj: &Value = expect_object(value)?;
+        let txid: Option<String> = match get_optional_string(tx_obj, "txId", mode)? {
+            Some(txid) => Some(txid),
+            None => get_optional_string(tx_obj, "txid", mode)?,
+        };
+        Ok(Self { txid })
+    }
+}
+
+/// WebBTC provider, for the on-chain features injected alongside WebLN (`window.webbtc`)
+///
+/// Calls go through the same machinery as [`WebLN`]: errors, user rejections and parsing are
+/// handled the same way.
+#[derive(Debug, Clone)]
+pub struct WebBTC {
+    provider: WebLN,
+}
+
+impl WebBTC {
+    /// Compose new WebBTC instance from `window.webbtc`
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            provider: WebLN::from_namespace(NAMESPACE)?,
+        })
+    }
+
+    /// Compose new WebBTC instance from a provider object obtained elsewhere than `window.webbtc`
+    pub fn from_provider(provider: Object) -> Self {
+        Self {
+            provider: WebLN::from_provider(provider),
+        }
+    }
+
+    /// Check if a provider is injected as `window.webbtc`, without failing
+    pub fn is_available() -> bool {
+        Self::new().is_ok()
+    }
+
+    /// Request the user permission to use the provider
+    pub async fn enable(&self) -> Result<(), Error> {
+        self.provider.enable().await
+    }
+
+    /// Get the provider version and supported methods
+    pub async fn get_info(&self) -> Result<WebBTCInfo, Error> {
+        self.provider
+            .call(GetInfoMethod::GetInfo, &[], |result| {
+                self.provider.parse(&result)
+            })
+            .await
+    }
+
+    /// Request an on-chain address to receive funds
+    pub async fn get_address(&self) -> Result<GetAddressResponse, Error> {
+        self.provider
+            .call(GetInfoMethod::from(GET_ADDRESS), &[], |result| {
+                self.provider.parse(&result)
+            })
+            .await
+    }
+
+    /// Request the user to send `amount` on-chain to `address`
+    ///
+    /// On-chain amounts are whole sats: millisats are dropped.
+    pub async fn send_transaction(
+        &self,
+        address: &str,
+        amount: Amount,
+    ) -> Result<SendTransactionResponse, Error> {
+        let args: [JsValue; 2] = [address.into(), amount.sats().to_string().into()];
+        self.provider
+            .call(GetInfoMethod::from(SEND_TRANSACTION), &args, |result| {
+                self.provider.parse(&result)
+            })
+            .await
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Zap receipts (NIP-57)
+//!
+//! <https://github.com/nostr-protocol/nips/blob/master/57.md>
+
+use alloc::string::String;
+#[cfg(feature = "secp256k1")]
+use alloc::vec::Vec;
+
+use js_sys::JSON;
+#[cfg(feature = "secp256k1")]
+use secp256k1::hashes::{sha256, Hash};
+use wasm_bindgen::JsValue;
+
+#[cfg(feature = "secp256k1")]
+use crate::hex;
+use crate::{Amount, Bolt11Invoice, Deserialize, Error, SignedEvent};
+
+/// Zap request kind
+pub const ZAP_REQUEST_KIND: u16 = 9734;
+/// Zap receipt kind
+pub const ZAP_RECEIPT_KIND: u16 = 9735;
+
+/// First value of the `name` tag
+fn tag<'a>(event: &'a SignedEvent, name: &str) -> Option<&'a str> {
+    event
+        .tags
+        .iter()
+        .find(|tag| tag.first().map(String::as_str) == Some(name))
+        .and_then(|tag| tag.get(1))
+        .map(String::as_str)
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidZapReceipt(String::from(reason))
+}
+
+/// Zap receipt (kind `9735`), published by the recipient's LNURL server once the invoice is paid
+///
+/// Parse it with [`ZapReceipt::from_event`], then check it with [`ZapReceipt::verify`] before
+/// showing the zap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ZapReceipt {
+    /// Receipt event
+    pub event: SignedEvent,
+    /// Paid invoice (`bolt11` tag)
+    pub invoice: Bolt11Invoice,
+    /// Zap request (kind `9734`), embedded in the `description` tag
+    pub request: SignedEvent,
+    /// Raw `description` tag, hashed into the invoice
+    pub description: String,
+    /// Payment preimage (`preimage` tag), if published
+    pub preimage: Option<String>,
+}
+
+impl ZapReceipt {
+    /// Parse a zap receipt event
+    ///
+    /// Only the structure is checked: see [`ZapReceipt::verify`].
+    pub fn from_event(event: SignedEvent) -> Result<Self, Error> {
+        if event.kind != ZAP_RECEIPT_KIND {
+            return Err(invalid("not a zap receipt"));
+        }
+
+        let invoice: &str = tag(&event, "bolt11").ok_or_else(|| invalid("missing bolt11 tag"))?;
+        let invoice: Bolt11Invoice = Bolt11Invoice::parse(invoice)?;
+        let description: String = tag(&event, "description")
+            .ok_or_else(|| invalid("missing description tag"))?
+            .into();
+        let request: JsValue =
+            JSON::parse(&description).map_err(|_| invalid("description is not a zap request"))?;
+        let request: SignedEvent = SignedEvent::deserialize(&request)?;
+        if request.kind != ZAP_REQUEST_KIND {
+            return Err(invalid("description is not a zap request"));
+        }
+
+        Ok(Self {
+            preimage: tag(&event, "preimage").map(String::from),
+            event,
+            invoice,
+            request,
+            description,
+        })
+    }
+
+    /// Zapped amount
+    pub fn amount(&self) -> Option<Amount> {
+        self.invoice.amount
+    }
+
+    /// Hex encoded public key of the sender (author of the zap request)
+    pub fn sender(&self) -> &str {
+        &self.request.pubkey
+    }
+
+    /// Check that the receipt corresponds to a payment of the zap request
+    ///
+    /// `nostr_pubkey` is the `nostrPubkey` of the recipient's LNURL-pay endpoint, which signs the
+    /// receipts. Checks the signatures of the receipt and of the zap request, that the invoice
+    /// commits to the zap request and is for the requested amount, that the zapped profile and
+    /// event match, and the preimage (if any).
+    #[cfg(feature = "secp256k1")]
+    pub fn verify(&self, nostr_pubkey: &str) -> Result<(), Error> {
+        if !self.event.pubkey.eq_ignore_ascii_case(nostr_pubkey) {
+            return Err(Error::InvalidSignature);
+        }
+        self.event.verify()?;
+        self.request.verify()?;
+
+        let description_hash: String =
+            hex::encode(sha256::Hash::hash(self.description.as_bytes()).to_byte_array());
+        if self.invoice.description_hash.as_deref() != Some(description_hash.as_str()) {
+            return Err(invalid("invoice doesn't commit to the zap request"));
+        }
+
+        let amount: Amount = self
+            .invoice
+            .amount
+            .ok_or_else(|| invalid("zero-amount invoice"))?;
+        if let Some(requested) = tag(&self.request, "amount") {
+            let requested: u64 = requested
+                .parse()
+                .map_err(|_| invalid("invalid amount tag"))?;
+            if amount.msat() != requested {
+                return Err(Error::AmountMismatch {
+                    invoice: amount,
+                    requested: Amount::from_msat(requested),
+                });
+            }
+        }
+
+        if tag(&self.event, "p").is_none() {
+            return Err(invalid("missing p tag"));
+        }
+        for name in ["p", "e", "a"] {
+            if tag(&self.request, name) != tag(&self.event, name) {
+                return Err(Error::InvalidZapReceipt(alloc::format!(
+                    "{name} tag differs from the zap request"
+                )));
+            }
+        }
+
+        if let Some(preimage) = &self.preimage {
+            let preimage: Vec<u8> =
+                hex::decode(preimage).map_err(|_| invalid("invalid preimage"))?;
+            let payment_hash: String = hex::encode(sha256::Hash::hash(&preimage).to_byte_array());
+            if payment_hash != self.invoice.payment_hash {
+                return Err(invalid("preimage doesn't match the payment hash"));
+            }
+        }
+
+        Ok(())
+    }
+}
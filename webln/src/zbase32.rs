@@ -0,0 +1,29 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! z-base-32 (used by LND `signmessage` signatures)
+
+use alloc::vec::Vec;
+
+const CHARS: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+/// Decode a z-base-32 string: trailing bits that don't fill a byte are dropped
+pub(crate) fn decode<T>(data: T) -> Option<Vec<u8>>
+where
+    T: AsRef<[u8]>,
+{
+    let data: &[u8] = data.as_ref();
+    let mut bytes: Vec<u8> = Vec::with_capacity(data.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits: u32 = 0;
+    for c in data.iter() {
+        let value: u32 = CHARS.iter().position(|x| x == c)? as u32;
+        buffer = (buffer << 5 | value) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
@@ -0,0 +1,126 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Per-call overhead of the binding layer, against the mock provider
+//!
+//! Ignored by default, run with `just bench`.
+
+#![cfg(target_arch = "wasm32")]
+
+use core::future::Future;
+
+use js_sys::{Function, Object, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use webln::{Deserialize, GetInfoResponse, SendPaymentResponse, WebLN};
+
+mod common;
+
+use self::common::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const ITERATIONS: u32 = 10_000;
+
+/// `performance.now()`, in milliseconds
+fn now() -> f64 {
+    let performance: JsValue = Reflect::get(&js_sys::global(), &"performance".into()).unwrap();
+    let now: Function = Reflect::get(&performance, &"now".into())
+        .unwrap()
+        .unchecked_into();
+    now.call0(&performance).unwrap().as_f64().unwrap()
+}
+
+/// Run `f` [`ITERATIONS`] times and log the mean time per call
+async fn bench<F, Fut>(name: &str, f: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    // Warm up
+    for _ in 0..ITERATIONS / 10 {
+        f().await;
+    }
+
+    let start: f64 = now();
+    for _ in 0..ITERATIONS {
+        f().await;
+    }
+    let micros: f64 = (now() - start) * 1000.0 / f64::from(ITERATIONS);
+    console_log!("{name:<36} {micros:>8.2} µs/call");
+}
+
+/// Call the mock method directly: the baseline of the provider calls
+async fn raw_call(provider: &Object, method: &str, arg: &JsValue) -> JsValue {
+    let func: Function = Reflect::get(provider, &method.into())
+        .unwrap()
+        .unchecked_into();
+    let promise: Promise = func.call1(provider, arg).unwrap().unchecked_into();
+    JsFuture::from(promise).await.unwrap()
+}
+
+#[wasm_bindgen_test]
+#[ignore = "benchmark"]
+async fn bench_get_info() {
+    let mock = MockProvider::new();
+    let provider: Object = mock.object();
+    let webln = WebLN::from_provider(mock.object());
+
+    bench("getInfo: raw JS call", || async {
+        raw_call(&provider, "getInfo", &JsValue::UNDEFINED).await;
+    })
+    .await;
+    bench("getInfo: WebLN::get_info", || async {
+        webln.get_info().await.unwrap();
+    })
+    .await;
+
+    let info: JsValue = raw_call(&provider, "getInfo", &JsValue::UNDEFINED).await;
+    bench("getInfo: deserialization only", || async {
+        GetInfoResponse::deserialize(&info).unwrap();
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+#[ignore = "benchmark"]
+async fn bench_send_payment() {
+    let mock = MockProvider::new();
+    let provider: Object = mock.object();
+    let webln = WebLN::from_provider(mock.object());
+    let invoice: JsValue = JsValue::from_str(COFFEE_INVOICE);
+
+    bench("sendPayment: raw JS call", || async {
+        raw_call(&provider, "sendPayment", &invoice).await;
+    })
+    .await;
+    bench("sendPayment: WebLN::send_payment", || async {
+        webln.send_payment(COFFEE_INVOICE).await.unwrap();
+    })
+    .await;
+
+    let res: JsValue = raw_call(&provider, "sendPayment", &invoice).await;
+    bench("sendPayment: deserialization only", || async {
+        SendPaymentResponse::deserialize(&res).unwrap();
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+#[ignore = "benchmark"]
+async fn bench_lookups() {
+    let mock = MockProvider::new();
+    mock.install();
+
+    bench("WebLN::new (namespace lookup)", || async {
+        WebLN::new().unwrap();
+    })
+    .await;
+
+    let webln = WebLN::new().unwrap();
+    bench("WebLN::is_enabled (bool result)", || async {
+        webln.is_enabled().await.unwrap();
+    })
+    .await;
+}
@@ -0,0 +1,421 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Mock `window.webln` provider and fake timers
+
+#![allow(dead_code)]
+
+use core::time::Duration;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use js_sys::{Function, Object, Promise, Reflect, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+use webln::{Clock, Error, Sleep};
+
+pub const PUBKEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+pub const PREIMAGE: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+pub const INVOICE: &str = "lnbc10n1mock";
+/// BOLT11 test vector without amount
+pub const ZERO_AMOUNT_INVOICE: &str = "lnbc1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdpl2pkx2ctnv5sxxmmwwd5kgetjypeh2ursdae8g6twvus8g6rfwvs8qun0dfjkxaq8rkx3yf5tcsyz3d73gafnh3cax9rn449d9p5uxz9ezhhypd0elx87sjle52x86fux2ypatgddc6k63n7erqz25le42c4u4ecky03ylcqca784w";
+/// BOLT11 test vector for 250000 sats
+pub const COFFEE_INVOICE: &str = "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp";
+pub const SIGNATURE: &str = "d9h1ecqhbwwbx3i1mwuycc1dssxbyrx3bh56kn3b6x8ymmkbp7yjbfxzczb6nxrbrq9wpdyq7xuhe9xb9wq1uufjsozfnxrc5erdd7i";
+
+/// Parse a JSON string into a JS value
+pub fn json(s: &str) -> JsValue {
+    JSON::parse(s).unwrap()
+}
+
+/// Promise resolved with `value` after `millis`, calling `on_resolve` first
+pub fn resolve_after<F>(millis: i32, value: JsValue, on_resolve: F) -> Promise
+where
+    F: FnOnce() + 'static,
+{
+    let mut on_resolve = Some(on_resolve);
+    Promise::new(&mut |resolve, _| {
+        let on_resolve = on_resolve.take();
+        let value = value.clone();
+        let done = Closure::once_into_js(move || {
+            if let Some(on_resolve) = on_resolve {
+                on_resolve();
+            }
+            resolve.call1(&JsValue::NULL, &value).unwrap();
+        });
+        web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(done.unchecked_ref(), millis)
+            .unwrap();
+    })
+}
+
+/// Let the pending promises and futures run (i.e. after [`FakeClock::advance`])
+pub async fn settle() {
+    JsFuture::from(resolve_after(0, JsValue::NULL, || ()))
+        .await
+        .unwrap();
+}
+
+/// Clock advanced by hand: sleeps resolve once [`FakeClock::advance`] reaches their deadline
+#[derive(Debug, Default)]
+pub struct FakeClock {
+    now: Cell<Duration>,
+    /// Deadline and `resolve` function of the pending sleeps
+    timers: RefCell<Vec<(Duration, Function)>>,
+    /// Requested sleep durations
+    sleeps: RefCell<Vec<Duration>>,
+}
+
+impl FakeClock {
+    pub fn new() -> Rc<Self> {
+        Rc::new(Self::default())
+    }
+
+    /// Move the time forward, resolving the expired sleeps
+    pub fn advance(&self, duration: Duration) {
+        let now: Duration = self.now.get() + duration;
+        self.now.set(now);
+        let expired: Vec<Function> = {
+            let mut timers = self.timers.borrow_mut();
+            let (expired, pending) = timers.drain(..).partition(|(deadline, _)| *deadline <= now);
+            *timers = pending;
+            expired.into_iter().map(|(_, resolve)| resolve).collect()
+        };
+        for resolve in expired {
+            resolve.call0(&JsValue::NULL).unwrap();
+        }
+    }
+
+    /// Number of sleeps not expired yet
+    pub fn pending_timers(&self) -> usize {
+        self.timers.borrow().len()
+    }
+
+    /// Durations of all the requested sleeps
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.sleeps.borrow().clone()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        self.sleeps.borrow_mut().push(duration);
+        let deadline: Duration = self.now.get() + duration;
+        let promise = Promise::new(&mut |resolve, _| {
+            self.timers.borrow_mut().push((deadline, resolve));
+        });
+        Box::pin(async move {
+            JsFuture::from(promise).await.map_err(Error::from)?;
+            Ok(())
+        })
+    }
+}
+
+/// Provider calls held until settled by hand (see [`MockProvider::defer`])
+#[derive(Debug, Clone, Default)]
+pub struct Deferred {
+    /// `resolve` and `reject` functions of the held calls, oldest first
+    pending: Rc<RefCell<VecDeque<(Function, Function)>>>,
+}
+
+impl Deferred {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of held calls
+    pub fn pending(&self) -> usize {
+        self.pending.borrow().len()
+    }
+
+    /// Resolve the oldest held call with `value`
+    pub fn resolve_next(&self, value: &JsValue) {
+        let (resolve, _) = self.take();
+        resolve.call1(&JsValue::NULL, value).unwrap();
+    }
+
+    /// Reject the oldest held call with `error`
+    pub fn reject_next(&self, error: &JsValue) {
+        let (_, reject) = self.take();
+        reject.call1(&JsValue::NULL, error).unwrap();
+    }
+
+    fn take(&self) -> (Function, Function) {
+        self.pending.borrow_mut().pop_front().expect("no held call")
+    }
+
+    fn hold(&self) -> Promise {
+        Promise::new(&mut |resolve, reject| {
+            self.pending.borrow_mut().push_back((resolve, reject));
+        })
+    }
+}
+
+/// Remove `window.webln`
+pub fn uninstall() {
+    Reflect::delete_property(&js_sys::global(), &JsValue::from_str("webln")).unwrap();
+}
+
+/// Scripted answer of a mocked method (see [`MockProvider::script`])
+#[derive(Debug, Clone)]
+pub enum Behavior {
+    /// Resolve with the value
+    Resolve(JsValue),
+    /// Resolve with the value after some millis
+    ResolveAfter(i32, JsValue),
+    /// Reject like a provider whose prompt was dismissed by the user
+    RejectUser,
+    /// Reject with a custom error (i.e. `{ code, message }` objects)
+    Reject(JsValue),
+    /// Never settle (hung wallet)
+    Never,
+}
+
+impl Behavior {
+    fn promise(&self) -> Promise {
+        match self {
+            Self::Resolve(value) => Promise::resolve(value),
+            Self::ResolveAfter(millis, value) => resolve_after(*millis, value.clone(), || ()),
+            Self::RejectUser => Promise::reject(&js_sys::Error::new("User rejected").into()),
+            Self::Reject(error) => Promise::reject(error),
+            Self::Never => Promise::new(&mut |_, _| ()),
+        }
+    }
+}
+
+/// Method name and arguments of a provider call
+type Call = (String, Vec<JsValue>);
+
+/// Mock WebLN provider
+///
+/// Every method is backed by a closure returning a promise, and every call is recorded.
+#[derive(Debug, Clone)]
+pub struct MockProvider {
+    obj: Object,
+    calls: Rc<RefCell<Vec<Call>>>,
+    listeners: Rc<RefCell<Vec<(String, Function)>>>,
+}
+
+impl MockProvider {
+    /// Provider implementing every method with a successful response
+    pub fn new() -> Self {
+        let mock = Self {
+            obj: Object::new(),
+            calls: Rc::default(),
+            listeners: Rc::default(),
+        };
+        mock.events()
+            .resolve("isEnabled", JsValue::TRUE)
+            .resolve("enable", json(r#"{"enabled":true}"#))
+            .resolve(
+                "getInfo",
+                json(&format!(
+                    r##"{{"node":{{"alias":"mock","pubkey":"{PUBKEY}","color":"#3399ff"}},"methods":["getInfo","keysend","makeInvoice","sendPayment","sendPaymentAsync","signMessage","getBalance","customMethod"]}}"##
+                )),
+            )
+            .resolve("keysend", json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)))
+            .resolve(
+                "makeInvoice",
+                json(&format!(r#"{{"paymentRequest":"{INVOICE}"}}"#)),
+            )
+            .resolve(
+                "sendPayment",
+                json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)),
+            )
+            .resolve("sendPaymentAsync", json("{}"))
+            .resolve(
+                "sendMultiPayment",
+                json(&format!(
+                    r#"{{"payments":[{{"paymentRequest":"{INVOICE}","preimage":"{PREIMAGE}"}}],"errors":[{{"paymentRequest":"lnbc1expired","message":"invoice expired"}}]}}"#
+                )),
+            )
+            .resolve(
+                "signMessage",
+                json(&format!(
+                    r#"{{"message":"hello","signature":"{SIGNATURE}"}}"#
+                )),
+            )
+            .resolve(
+                "getBalance",
+                json(r#"{"balance":21000,"currency":"sats"}"#),
+            )
+            .resolve("request", json(r#"{"alias":"mock","num_peers":3}"#))
+    }
+
+    /// Make `method` resolve with `value`
+    pub fn resolve(self, method: &str, value: JsValue) -> Self {
+        self.set(method, move |_| Promise::resolve(&value))
+    }
+
+    /// Make `method` reject with `error`
+    pub fn reject(self, method: &str, error: JsValue) -> Self {
+        self.set(method, move |_| Promise::reject(&error))
+    }
+
+    /// Make `method` reject like a provider whose prompt was dismissed by the user
+    pub fn reject_user(self, method: &str) -> Self {
+        self.behave(method, Behavior::RejectUser)
+    }
+
+    /// Make `method` resolve with `value` after `millis`
+    pub fn resolve_after(self, method: &str, millis: i32, value: JsValue) -> Self {
+        self.behave(method, Behavior::ResolveAfter(millis, value))
+    }
+
+    /// Make `method` never settle
+    pub fn never(self, method: &str) -> Self {
+        self.behave(method, Behavior::Never)
+    }
+
+    /// Make every call to `method` answer with `behavior`
+    pub fn behave(self, method: &str, behavior: Behavior) -> Self {
+        self.set(method, move |_| behavior.promise())
+    }
+
+    /// Answer the calls to `method` with `behaviors`, in order: the last one is repeated
+    pub fn script<I>(self, method: &str, behaviors: I) -> Self
+    where
+        I: IntoIterator<Item = Behavior>,
+    {
+        let behaviors: RefCell<VecDeque<Behavior>> = RefCell::new(behaviors.into_iter().collect());
+        self.set(method, move |_| {
+            let mut behaviors = behaviors.borrow_mut();
+            let behavior: Behavior = match behaviors.len() {
+                0 => panic!("empty script"),
+                1 => behaviors[0].clone(),
+                _ => behaviors.pop_front().unwrap(),
+            };
+            behavior.promise()
+        })
+    }
+
+    /// Make `method` answer with `f(first argument)`
+    pub fn respond<F>(self, method: &str, f: F) -> Self
+    where
+        F: Fn(JsValue) -> Promise + 'static,
+    {
+        self.set(method, f)
+    }
+
+    /// Hold the calls to `method` until settled through `deferred`
+    pub fn defer(self, method: &str, deferred: &Deferred) -> Self {
+        let deferred: Deferred = deferred.clone();
+        self.set(method, move |_| deferred.hold())
+    }
+
+    /// Number of calls to `method`
+    pub fn call_count(&self, method: &str) -> usize {
+        self.calls
+            .borrow()
+            .iter()
+            .filter(|(name, _)| name == method)
+            .count()
+    }
+
+    /// Remove `method` from the provider
+    pub fn remove(self, method: &str) -> Self {
+        Reflect::delete_property(&self.obj, &JsValue::from_str(method)).unwrap();
+        self
+    }
+
+    /// Call the listeners registered for `event`
+    pub fn emit(&self, event: &str, payload: &JsValue) {
+        let listeners: Vec<Function> = self
+            .listeners
+            .borrow()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, listener)| listener.clone())
+            .collect();
+        for listener in listeners {
+            listener.call1(&JsValue::NULL, payload).unwrap();
+        }
+    }
+
+    /// Number of listeners registered for `event`
+    pub fn listener_count(&self, event: &str) -> usize {
+        self.listeners
+            .borrow()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .count()
+    }
+
+    /// Synchronous `on`/`off`, keeping track of the listeners
+    fn events(self) -> Self {
+        for (method, add) in [("on", true), ("off", false)] {
+            let listeners = self.listeners.clone();
+            let closure = Closure::<dyn Fn(String, Function)>::new(
+                move |event: String, listener: Function| {
+                    let mut listeners = listeners.borrow_mut();
+                    if add {
+                        listeners.push((event, listener));
+                    } else {
+                        listeners.retain(|(e, l)| !(e == &event && l == &listener));
+                    }
+                },
+            );
+            Reflect::set(
+                &self.obj,
+                &JsValue::from_str(method),
+                &closure.into_js_value(),
+            )
+            .unwrap();
+        }
+        self
+    }
+
+    /// Provider object
+    pub fn object(&self) -> Object {
+        self.obj.clone()
+    }
+
+    /// Install the provider as `window.webln`
+    pub fn install(&self) {
+        Reflect::set(&js_sys::global(), &JsValue::from_str("webln"), &self.obj).unwrap();
+    }
+
+    /// Get the first argument of the last call to `method`
+    pub fn last_call(&self, method: &str) -> Option<JsValue> {
+        self.last_call_args(method)?.into_iter().next()
+    }
+
+    /// Get the arguments (the first two) of the last call to `method`
+    pub fn last_call_args(&self, method: &str) -> Option<Vec<JsValue>> {
+        self.calls
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(name, _)| name == method)
+            .map(|(_, args)| args.clone())
+    }
+
+    fn set<F>(self, method: &str, f: F) -> Self
+    where
+        F: Fn(JsValue) -> Promise + 'static,
+    {
+        let calls = self.calls.clone();
+        let name: String = method.to_string();
+        let closure = Closure::<dyn Fn(JsValue, JsValue) -> Promise>::new(
+            move |arg: JsValue, arg2: JsValue| {
+                calls
+                    .borrow_mut()
+                    .push((name.clone(), vec![arg.clone(), arg2]));
+                f(arg)
+            },
+        );
+        Reflect::set(
+            &self.obj,
+            &JsValue::from_str(method),
+            &closure.into_js_value(),
+        )
+        .unwrap();
+        self
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Contract tests against the WebLN TypeScript types (vendored in `contract/webln-types.json`)
+//!
+//! Checks that the args passed to the provider match the spec field names and types, and that
+//! spec-conforming responses (also without their optional fields) are accepted.
+
+#![cfg(target_arch = "wasm32")]
+
+use core::str::FromStr;
+
+use serde_json::Value as Schema;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use webln::{KeysendArgs, NodeId, RequestInvoiceArgs, Value, WebLN};
+
+mod common;
+
+use self::common::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const ALL_METHODS: &str = r#"{"node":{},"methods":["getInfo","keysend","makeInvoice","sendPayment","sendPaymentAsync","sendMultiPayment","signMessage","getBalance"]}"#;
+
+fn spec() -> Schema {
+    serde_json::from_str(include_str!("contract/webln-types.json")).unwrap()
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Undefined => "undefined",
+        Value::Null => "null",
+        Value::Bool(..) => "boolean",
+        Value::Number(..) => "number",
+        Value::String(..) => "string",
+        Value::Array(..) => "array",
+        Value::Object(..) => "object",
+    }
+}
+
+/// Check `value` against `schema`, returning the mismatches
+fn validate(value: &Value, schema: &Schema, path: &str) -> Vec<String> {
+    if let Some(any_of) = schema["anyOf"].as_array() {
+        return if any_of.iter().any(|s| validate(value, s, path).is_empty()) {
+            Vec::new()
+        } else {
+            vec![format!("{path}: {} matches none of anyOf", type_of(value))]
+        };
+    }
+
+    let expected: &str = schema["type"].as_str().unwrap();
+    if type_of(value) != expected {
+        return vec![format!(
+            "{path}: expected {expected}, got {}",
+            type_of(value)
+        )];
+    }
+
+    let mut errors: Vec<String> = Vec::new();
+    match value {
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                errors.extend(validate(item, &schema["items"], &format!("{path}[{i}]")));
+            }
+        }
+        Value::Object(map) => {
+            for key in schema["required"].as_array().into_iter().flatten() {
+                let key: &str = key.as_str().unwrap();
+                if !map.contains_key(key) {
+                    errors.push(format!("{path}.{key}: missing"));
+                }
+            }
+            for (key, field) in map.iter() {
+                let path: String = format!("{path}.{key}");
+                match (
+                    schema["properties"].get(key),
+                    &schema["additionalProperties"],
+                ) {
+                    (Some(field_schema), ..) => errors.extend(validate(field, field_schema, &path)),
+                    (None, Schema::Bool(false)) => errors.push(format!("{path}: not in the spec")),
+                    (None, Schema::Object(..)) => {
+                        errors.extend(validate(field, &schema["additionalProperties"], &path))
+                    }
+                    (None, ..) => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    errors
+}
+
+/// The example, and one copy for each optional field left out
+fn variants(example: &Schema, schema: &Schema) -> Vec<Schema> {
+    let mut res: Vec<Schema> = vec![example.clone()];
+    let (map, properties) = match (example.as_object(), schema["properties"].as_object()) {
+        (Some(map), Some(properties)) => (map, properties),
+        _ => return res,
+    };
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Schema::as_str)
+        .collect();
+    for (key, field) in map.iter() {
+        if !required.contains(&key.as_str()) {
+            let mut variant = map.clone();
+            variant.remove(key);
+            res.push(Schema::Object(variant));
+        }
+        if let Some(field_schema) = properties.get(key) {
+            for nested in variants(field, field_schema).into_iter().skip(1) {
+                let mut variant = map.clone();
+                variant.insert(key.clone(), nested);
+                res.push(Schema::Object(variant));
+            }
+        }
+    }
+    res
+}
+
+fn check_arg(mock: &MockProvider, method: &str) {
+    let arg: JsValue = mock.last_call(method).unwrap();
+    let errors = validate(&Value::from_js(&arg), &spec()["args"][method], method);
+    assert!(errors.is_empty(), "{errors:?}");
+}
+
+#[wasm_bindgen_test]
+fn test_spec_examples() {
+    let spec = spec();
+    for (method, response) in spec["responses"].as_object().unwrap() {
+        let value = Value::from_js(&json(&response["example"].to_string()));
+        let errors = validate(&value, &response["schema"], method);
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_args() {
+    let mock = MockProvider::new().resolve("getInfo", json(ALL_METHODS));
+    mock.install();
+    let webln = WebLN::new().unwrap();
+
+    let args = KeysendArgs::new(NodeId::from_str(PUBKEY).unwrap(), 21)
+        .custom_record(7629169, r#"{"action":"boost"}"#);
+    webln.keysend(&args).await.unwrap();
+    check_arg(&mock, "keysend");
+
+    let args = RequestInvoiceArgs::new()
+        .amount(21)
+        .default_amount(21)
+        .minimum_amount(1)
+        .maximum_amount(100)
+        .default_memo(String::from("contract"));
+    webln.make_invoice(&args).await.unwrap();
+    check_arg(&mock, "makeInvoice");
+
+    webln.send_payment(INVOICE).await.unwrap();
+    check_arg(&mock, "sendPayment");
+
+    webln.send_payment_async(INVOICE).await.unwrap();
+    check_arg(&mock, "sendPaymentAsync");
+
+    webln.sign_message("hello").await.unwrap();
+    check_arg(&mock, "signMessage");
+
+    webln.send_multi_payment(&[INVOICE]).await.unwrap();
+    check_arg(&mock, "sendMultiPayment");
+}
+
+#[wasm_bindgen_test]
+async fn test_responses() {
+    let spec = spec();
+    for (method, response) in spec["responses"].as_object().unwrap() {
+        for example in variants(&response["example"], &response["schema"]) {
+            let mock = MockProvider::new()
+                .resolve("getInfo", json(ALL_METHODS))
+                .resolve(method, json(&example.to_string()));
+            mock.install();
+            let webln = WebLN::new().unwrap();
+
+            let ok: bool = match method.as_str() {
+                "getInfo" => webln.get_info().await.is_ok(),
+                "keysend" => {
+                    let args = KeysendArgs::new(NodeId::from_str(PUBKEY).unwrap(), 21);
+                    webln.keysend(&args).await.is_ok()
+                }
+                "makeInvoice" => webln
+                    .make_invoice(&RequestInvoiceArgs::new().amount(21))
+                    .await
+                    .is_ok(),
+                "sendPayment" => webln.send_payment(INVOICE).await.is_ok(),
+                "sendPaymentAsync" => webln.send_payment_async(INVOICE).await.is_ok(),
+                "signMessage" => webln.sign_message("hello").await.is_ok(),
+                "getBalance" => webln.get_balance().await.is_ok(),
+                "sendMultiPayment" => webln.send_multi_payment(&[INVOICE]).await.is_ok(),
+                other => panic!("no call for {other}"),
+            };
+            assert!(
+                ok,
+                "{method} rejected a spec-conforming response: {example}"
+            );
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Property tests: arbitrary and near-miss values must produce typed errors, never panics
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use webln::{
+    BalanceResponse, Deserialize, Error, GetInfoResponse, ParseMode, RequestInvoiceResponse,
+    SendMultiPaymentResponse, SendPaymentResponse, Value, WebLN,
+};
+
+mod common;
+
+use self::common::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const ITERATIONS: usize = 500;
+
+/// Keys used by the responses, so generated objects often look almost valid
+const KEYS: [&str; 14] = [
+    "node",
+    "alias",
+    "pubkey",
+    "color",
+    "methods",
+    "preimage",
+    "payments",
+    "errors",
+    "paymentRequest",
+    "balance",
+    "currency",
+    "signature",
+    "message",
+    "length",
+];
+
+/// Xorshift PRNG: deterministic, so failures are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn key(&mut self) -> &'static str {
+        KEYS[self.below(KEYS.len() as u64) as usize]
+    }
+
+    fn number(&mut self) -> f64 {
+        match self.below(6) {
+            0 => f64::NAN,
+            1 => f64::INFINITY,
+            2 => -1.0,
+            3 => 4_294_967_296.0,
+            4 => 0.5,
+            _ => self.below(1_000_000) as f64,
+        }
+    }
+
+    fn string(&mut self) -> JsValue {
+        match self.below(4) {
+            0 => JsValue::from_str(""),
+            1 => JsValue::from_str(PREIMAGE),
+            2 => JsValue::from_str("getInfo"),
+            _ => JsValue::from_str("\u{0}\u{fffd}"),
+        }
+    }
+
+    fn value(&mut self, depth: u8) -> JsValue {
+        let max: u64 = if depth == 0 { 5 } else { 7 };
+        match self.below(max) {
+            0 => JsValue::UNDEFINED,
+            1 => JsValue::NULL,
+            2 => JsValue::from_bool(self.below(2) == 0),
+            3 => JsValue::from_f64(self.number()),
+            4 => self.string(),
+            5 => {
+                let array = Array::new();
+                for _ in 0..self.below(4) {
+                    array.push(&self.value(depth - 1));
+                }
+                array.into()
+            }
+            _ => {
+                let obj = Object::new();
+                for _ in 0..self.below(5) {
+                    Reflect::set(&obj, &JsValue::from_str(self.key()), &self.value(depth - 1))
+                        .unwrap();
+                }
+                obj.into()
+            }
+        }
+    }
+
+    /// Take a valid response and break one of its keys
+    fn near_miss(&mut self, valid: &str) -> JsValue {
+        let value: JsValue = json(valid);
+        let target: JsValue = match Reflect::get(&value, &JsValue::from_str("node")) {
+            Ok(node) if node.is_object() && self.below(2) == 0 => node,
+            _ => value.clone(),
+        };
+        let keys: Array = Object::keys(&target.clone().into());
+        let key: JsValue = keys.get(self.below(keys.length() as u64) as u32);
+        if self.below(3) == 0 {
+            Reflect::delete_property(&target.into(), &key).unwrap();
+        } else {
+            Reflect::set(&target, &key, &self.value(2)).unwrap();
+        }
+        value
+    }
+}
+
+fn check<T>(value: &JsValue)
+where
+    T: Deserialize,
+{
+    for res in [
+        T::deserialize(value),
+        T::deserialize_with_mode(value, ParseMode::Strict),
+    ] {
+        match res {
+            Ok(_) => (),
+            Err(Error::Provider(e)) => panic!("untyped error for {value:?}: {e}"),
+            Err(_) => (),
+        }
+    }
+}
+
+fn untyped<T>(res: Result<T, Error>) -> bool {
+    matches!(res.as_ref().map_err(Error::inner), Err(Error::Provider(_)))
+}
+
+const GET_INFO: &str = r#"{"node":{"alias":"a","pubkey":"b","color":"c"},"methods":["getInfo"]}"#;
+const SEND_PAYMENT: &str = r#"{"preimage":"00"}"#;
+const MAKE_INVOICE: &str = r#"{"paymentRequest":"lnbc"}"#;
+const SEND_MULTI_PAYMENT: &str =
+    r#"{"payments":[{"paymentRequest":"lnbc","preimage":"00"}],"errors":[]}"#;
+const GET_BALANCE: &str = r#"{"balance":1,"currency":"sats"}"#;
+
+#[wasm_bindgen_test]
+fn test_arbitrary_values() {
+    let mut rng = Rng(0x5eed);
+    for _ in 0..ITERATIONS {
+        let value: JsValue = rng.value(3);
+        check::<GetInfoResponse>(&value);
+        check::<SendPaymentResponse>(&value);
+        check::<RequestInvoiceResponse>(&value);
+        check::<BalanceResponse>(&value);
+        check::<SendMultiPaymentResponse>(&value);
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_near_miss_values() {
+    let mut rng = Rng(0xc0ffee);
+    for _ in 0..ITERATIONS {
+        check::<GetInfoResponse>(&rng.near_miss(GET_INFO));
+        check::<SendPaymentResponse>(&rng.near_miss(SEND_PAYMENT));
+        check::<RequestInvoiceResponse>(&rng.near_miss(MAKE_INVOICE));
+        check::<BalanceResponse>(&rng.near_miss(GET_BALANCE));
+        check::<SendMultiPaymentResponse>(&rng.near_miss(SEND_MULTI_PAYMENT));
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_cyclic_values() {
+    // Branching cycle: converted once, not once per path
+    let obj = Object::new();
+    Reflect::set(&obj, &"preimage".into(), &PREIMAGE.into()).unwrap();
+    for key in ["x", "y", "z"] {
+        Reflect::set(&obj, &key.into(), &obj).unwrap();
+    }
+    let array = Array::of2(&obj, &obj);
+    Reflect::set(&obj, &"payments".into(), &array).unwrap();
+
+    let value = Value::from_js(&obj);
+    assert_eq!(value.get("preimage").as_str(), Some(PREIMAGE));
+    assert_eq!(value.get("y"), &Value::Undefined);
+    assert_eq!(value.get("x"), &Value::Undefined);
+    assert_eq!(
+        value.get("payments"),
+        &Value::Array(vec![Value::Undefined; 2])
+    );
+    let res = SendPaymentResponse::deserialize(&obj).unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+}
+
+#[wasm_bindgen_test]
+fn test_shared_values() {
+    // Same `route` object in every payment: not a cycle, converted each time
+    let route = json(r#"{"total_amt":1000,"total_fees":1}"#);
+    let payments = Array::new();
+    for _ in 0..2 {
+        let item = json(&format!(
+            r#"{{"paymentRequest":"{INVOICE}","preimage":"{PREIMAGE}"}}"#
+        ));
+        Reflect::set(&item, &"route".into(), &route).unwrap();
+        payments.push(&item);
+    }
+    let obj = Object::new();
+    Reflect::set(&obj, &"payments".into(), &payments).unwrap();
+    Reflect::set(&obj, &"errors".into(), &Array::new()).unwrap();
+
+    let value = Value::from_js(&obj);
+    let items = value.get("payments").as_array().unwrap();
+    assert_eq!(items[0].get("route"), items[1].get("route"));
+    assert_eq!(items[1].get("route").get("total_fees").as_f64(), Some(1.0));
+    let res = SendMultiPaymentResponse::deserialize(&obj).unwrap();
+    assert_eq!(res.payments[0].route, res.payments[1].route);
+    assert!(res.payments[1].route.is_some());
+}
+
+#[wasm_bindgen_test]
+async fn test_parse_modes() {
+    let balance = json(r#"{"balance":1,"currency":5}"#);
+    let res = BalanceResponse::deserialize(&balance).unwrap();
+    assert_eq!(res.currency, None);
+    assert!(matches!(
+        BalanceResponse::deserialize_with_mode(&balance, ParseMode::Strict),
+        Err(Error::TypeMismatch(m)) if m.contains("[currency]")
+    ));
+
+    // Missing and `null` optional fields are fine in both modes
+    for value in [r#"{"balance":1}"#, r#"{"balance":1,"currency":null}"#] {
+        let res = BalanceResponse::deserialize_with_mode(&json(value), ParseMode::Strict);
+        assert_eq!(res.unwrap().currency, None);
+    }
+
+    let info = json(r##"{"node":{"alias":1,"color":"#fff"},"methods":[]}"##);
+    let res = GetInfoResponse::deserialize(&info).unwrap();
+    assert_eq!(res.node.alias, None);
+    assert_eq!(res.node.color.as_deref(), Some("#fff"));
+    assert!(matches!(
+        GetInfoResponse::deserialize_with_mode(&info, ParseMode::Strict),
+        Err(Error::TypeMismatch(m)) if m.contains("[alias]")
+    ));
+
+    let mock = MockProvider::new().resolve("getBalance", balance);
+    mock.install();
+    let webln = WebLN::new().unwrap();
+    assert!(webln.get_balance().await.is_ok());
+    let webln = webln.with_parse_mode(ParseMode::Strict);
+    assert!(matches!(
+        webln.get_balance().await.unwrap_err().inner(),
+        Error::TypeMismatch(_)
+    ));
+}
+
+#[wasm_bindgen_test]
+async fn test_arbitrary_values_through_provider() {
+    let mut rng = Rng(0xbeef);
+    for _ in 0..ITERATIONS / 10 {
+        let value: JsValue = rng.value(3);
+        let mock = MockProvider::new()
+            .resolve("isEnabled", value.clone())
+            .resolve("sendPaymentAsync", value.clone())
+            .resolve("signMessage", value.clone());
+        mock.install();
+        let webln = WebLN::new().unwrap();
+        assert!(!untyped(webln.is_enabled().await));
+        assert!(!untyped(webln.send_payment_async(INVOICE).await));
+        assert!(!untyped(webln.sign_message("hello").await));
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Provider response payloads (Alby, Mutiny, Zeus) replayed through the mock provider
+
+#![cfg(target_arch = "wasm32")]
+
+use core::str::FromStr;
+
+use wasm_bindgen_test::*;
+use webln::{GetInfoMethod, KeysendArgs, NodeId, RequestInvoiceArgs, WebLN};
+
+mod common;
+
+use self::common::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+macro_rules! fixture {
+    ($provider:literal, $method:literal) => {
+        json(include_str!(concat!(
+            "fixtures/",
+            $provider,
+            "/",
+            $method,
+            ".json"
+        )))
+    };
+}
+
+macro_rules! provider {
+    ($provider:literal) => {
+        MockProvider::new()
+            .resolve("getInfo", fixture!($provider, "getInfo"))
+            .resolve("getBalance", fixture!($provider, "getBalance"))
+            .resolve("keysend", fixture!($provider, "keysend"))
+            .resolve("makeInvoice", fixture!($provider, "makeInvoice"))
+            .resolve("sendPayment", fixture!($provider, "sendPayment"))
+            .resolve("signMessage", fixture!($provider, "signMessage"))
+    };
+}
+
+async fn check_provider(mock: MockProvider) {
+    mock.install();
+    let webln = WebLN::new().unwrap();
+
+    let info = webln.get_info().await.unwrap();
+    assert!(info.node.alias.is_some());
+    assert!(info.node.pubkey.is_some());
+    assert!(info.methods.contains(&GetInfoMethod::GetInfo));
+    assert!(info.methods.contains(&GetInfoMethod::SendPayment));
+
+    let balance = webln.get_balance().await.unwrap();
+    assert!(balance.balance > 0.0);
+
+    let args = KeysendArgs::new(NodeId::from_str(PUBKEY).unwrap(), 21);
+    let res = webln.keysend(&args).await.unwrap();
+    assert_eq!(res.preimage.len(), 64);
+
+    let res = webln
+        .make_invoice(&RequestInvoiceArgs::new().amount(250_000))
+        .await
+        .unwrap();
+    assert!(res.invoice.starts_with("lnbc"));
+
+    let res = webln.send_payment(&res.invoice).await.unwrap();
+    assert_eq!(res.preimage.len(), 64);
+
+    let res = webln.sign_message("hello").await.unwrap();
+    assert!(!res.signature.is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn test_alby() {
+    check_provider(provider!("alby")).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_mutiny() {
+    check_provider(provider!("mutiny")).await;
+}
+
+#[wasm_bindgen_test]
+async fn test_zeus() {
+    check_provider(provider!("zeus")).await;
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+#![cfg(feature = "serde")]
+
+use core::str::FromStr;
+
+use webln::{
+    BalanceResponse, ErrorCode, GetInfoMethod, GetInfoResponse, KeysendArgs, NodeId,
+    RequestInvoiceArgs,
+};
+
+const PUBKEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+#[test]
+fn test_get_info_response() {
+    let json = r##"{"node":{"alias":"mock","pubkey":null,"color":"#3399ff"},"methods":["getInfo","keysend","customMethod"]}"##;
+    let info: GetInfoResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(info.node.alias.as_deref(), Some("mock"));
+    assert_eq!(info.node.pubkey, None);
+    assert_eq!(
+        info.methods,
+        vec![
+            GetInfoMethod::GetInfo,
+            GetInfoMethod::Keysend,
+            GetInfoMethod::Other(String::from("customMethod"))
+        ]
+    );
+    assert_eq!(serde_json::to_string(&info).unwrap(), json);
+
+    let info: GetInfoResponse = serde_json::from_str("{}").unwrap();
+    assert_eq!(info.node.alias, None);
+    assert!(info.methods.is_empty());
+}
+
+#[test]
+fn test_balance_response_exact() {
+    let balance = |json: &str| serde_json::from_str::<BalanceResponse>(json).unwrap();
+
+    let res = balance(r#"{"balance":21000,"currency":"sats"}"#);
+    assert_eq!(res.msat(), Some(21_000_000));
+    assert_eq!(res.sats(), Some(21_000));
+
+    // No currency: sats
+    let res = balance(r#"{"balance":21000.5,"currency":null}"#);
+    assert_eq!(res.msat(), Some(21_000_500));
+    assert_eq!(res.sats(), None);
+
+    let res = balance(r#"{"balance":0.1,"currency":"BTC"}"#);
+    assert_eq!(res.msat(), Some(10_000_000_000));
+    assert_eq!(res.sats(), Some(10_000_000));
+    let res = balance(r#"{"balance":0.00000001,"currency":"btc"}"#);
+    assert_eq!(res.sats(), Some(1));
+
+    for json in [
+        r#"{"balance":10.5,"currency":"USD"}"#,
+        r#"{"balance":-1,"currency":"sats"}"#,
+        r#"{"balance":0.0001,"currency":"sats"}"#,
+        r#"{"balance":1e30,"currency":"sats"}"#,
+    ] {
+        assert_eq!(balance(json).msat(), None, "{json}");
+    }
+}
+
+#[test]
+fn test_keysend_args() {
+    let args = KeysendArgs::new(NodeId::from_str(PUBKEY).unwrap(), 21);
+    let json: String = serde_json::to_string(&args).unwrap();
+    assert_eq!(json, format!(r#"{{"destination":"{PUBKEY}","amount":21}}"#));
+    assert_eq!(serde_json::from_str::<KeysendArgs>(&json).unwrap(), args);
+
+    let args = args.custom_record(34349334, "hello");
+    let json: String = serde_json::to_string(&args).unwrap();
+    assert_eq!(
+        json,
+        format!(
+            r#"{{"destination":"{PUBKEY}","amount":21,"customRecords":{{"34349334":"hello"}}}}"#
+        )
+    );
+    assert_eq!(serde_json::from_str::<KeysendArgs>(&json).unwrap(), args);
+}
+
+#[test]
+fn test_request_invoice_args() {
+    let args = RequestInvoiceArgs::new()
+        .amount(1000)
+        .default_memo(String::from("memo"));
+    let json: String = serde_json::to_string(&args).unwrap();
+    assert_eq!(
+        json,
+        r#"{"amount":1000,"defaultAmount":null,"minimumAmount":null,"maximumAmount":null,"defaultMemo":"memo"}"#
+    );
+    assert_eq!(
+        serde_json::from_str::<RequestInvoiceArgs>(r#"{"amount":1000,"defaultMemo":"memo"}"#)
+            .unwrap(),
+        args
+    );
+}
+
+#[test]
+fn test_error_code() {
+    assert_eq!(
+        serde_json::to_string(&ErrorCode::UserRejected).unwrap(),
+        r#""USER_REJECTED""#
+    );
+}
+
+#[test]
+fn test_get_info_method() {
+    let methods: Vec<GetInfoMethod> =
+        serde_json::from_str(r#"["makeInvoice","sendPaymentAsync","customMethod"]"#).unwrap();
+    assert_eq!(
+        methods,
+        vec![
+            GetInfoMethod::MakeInvoice,
+            GetInfoMethod::SendPaymentAsync,
+            GetInfoMethod::Other(String::from("customMethod"))
+        ]
+    );
+    assert_eq!(
+        serde_json::to_string(&methods).unwrap(),
+        r#"["makeInvoice","sendPaymentAsync","customMethod"]"#
+    );
+}
+
+#[cfg(feature = "journal")]
+#[test]
+fn test_receipt() {
+    use webln::{Amount, Receipt, ReceiptKind};
+
+    // Same keys as `Journal::export`
+    let json = r#"{"timestamp":1700000000,"kind":"keysend","amountMsat":21000,"preimage":"aa"}"#;
+    let receipt: Receipt = serde_json::from_str(json).unwrap();
+    assert_eq!(receipt.kind, ReceiptKind::Keysend);
+    assert_eq!(receipt.amount, Some(Amount::from_sats(21)));
+    let value: serde_json::Value = serde_json::to_value(&receipt).unwrap();
+    assert_eq!(value["amountMsat"], 21000);
+    assert_eq!(serde_json::from_value::<Receipt>(value).unwrap(), receipt);
+}
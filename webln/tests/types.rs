@@ -0,0 +1,492 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+use core::str::FromStr;
+
+use webln::{
+    Amount, Bolt11Invoice, Capabilities, CheckoutEvent, CheckoutState, Currency, Error, ErrorCode,
+    Event, GetInfoMethod, MessageEncoding, NodeId, ParseAmountError, ParseInvoiceError,
+    ParseNodeIdError, ParseRgbColorError, RgbColor,
+};
+
+const PUBKEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+// BOLT11 test vectors
+const INVOICE_DONATION: &str = "lnbc1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdpl2pkx2ctnv5sxxmmwwd5kgetjypeh2ursdae8g6twvus8g6rfwvs8qun0dfjkxaq8rkx3yf5tcsyz3d73gafnh3cax9rn449d9p5uxz9ezhhypd0elx87sjle52x86fux2ypatgddc6k63n7erqz25le42c4u4ecky03ylcqca784w";
+const INVOICE_COFFEE: &str = "lnbc2500u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpuaztrnwngzn3kdzw5hydlzf03qdgm2hdq27cqv3agm2awhz5se903vruatfhq77w3ls4evs3ch9zw97j25emudupq63nyw24cg27h2rspfj9srp";
+const INVOICE_HASHED: &str = "lnbc20m1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqhp58yjmdan79s6qqdhdzgynm4zwqd5d7xmw5fk98klysy043l2ahrqscc6gd6ql3jrc5yzme8v4ntcewwz5cnw92tz0pc8qcuufvq7khhr8wpald05e92xw006sq94mg8v2ndf4sefvf9sygkshp5zfem29trqq2yxxz7";
+
+#[test]
+fn test_get_info_method() {
+    assert_eq!(
+        GetInfoMethod::from_str("sendPayment").unwrap(),
+        GetInfoMethod::SendPayment
+    );
+    assert_eq!(
+        GetInfoMethod::from(String::from("getBalance")),
+        GetInfoMethod::GetBalance
+    );
+    assert_eq!(
+        "customMethod".parse::<GetInfoMethod>().unwrap(),
+        GetInfoMethod::Other(String::from("customMethod"))
+    );
+
+    // Aliases
+    for alias in [
+        " sendPayment ",
+        "SendPayment",
+        "sendpayment",
+        "send_payment",
+        "send-payment",
+        "webln.sendPayment",
+    ] {
+        assert_eq!(GetInfoMethod::from(alias), GetInfoMethod::SendPayment);
+    }
+    assert_eq!(
+        GetInfoMethod::from(String::from("GET_BALANCE")),
+        GetInfoMethod::GetBalance
+    );
+    assert_eq!(
+        GetInfoMethod::from(" customMethod\n"),
+        GetInfoMethod::Other(String::from("customMethod"))
+    );
+
+    // Round trip through the wire names
+    for method in [
+        GetInfoMethod::IsEnabled,
+        GetInfoMethod::Enable,
+        GetInfoMethod::GetInfo,
+        GetInfoMethod::Keysend,
+        GetInfoMethod::MakeInvoice,
+        GetInfoMethod::SendPayment,
+        GetInfoMethod::SendPaymentAsync,
+        GetInfoMethod::SignMessage,
+        GetInfoMethod::VerifyMessage,
+        GetInfoMethod::Request,
+        GetInfoMethod::Lnurl,
+        GetInfoMethod::On,
+        GetInfoMethod::Off,
+        GetInfoMethod::GetBalance,
+        GetInfoMethod::Other(String::from("customMethod")),
+    ] {
+        assert_eq!(GetInfoMethod::from(method.to_string()), method);
+    }
+}
+
+#[test]
+fn test_capabilities() {
+    let mut a: Capabilities = [
+        GetInfoMethod::GetInfo,
+        GetInfoMethod::Keysend,
+        GetInfoMethod::Other(String::from("zeta")),
+        GetInfoMethod::Other(String::from("alpha")),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(a.len(), 4);
+    assert!(a.contains(&GetInfoMethod::Keysend));
+    assert!(a.contains(&GetInfoMethod::Other(String::from("alpha"))));
+    assert!(!a.contains(&GetInfoMethod::SendPayment));
+    assert!(!a.insert(GetInfoMethod::Keysend));
+    assert!(a.insert(GetInfoMethod::GetBalance));
+    assert!(a.remove(&GetInfoMethod::GetBalance));
+    assert!(!a.remove(&GetInfoMethod::GetBalance));
+
+    let b: Capabilities = [
+        GetInfoMethod::Keysend,
+        GetInfoMethod::Other(String::from("zeta")),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(a.intersection(&b), b);
+    assert_eq!(a.union(&b), a);
+    assert!(Capabilities::new().intersection(&a).is_empty());
+
+    assert_eq!(
+        a.iter().collect::<Vec<_>>(),
+        vec![
+            GetInfoMethod::GetInfo,
+            GetInfoMethod::Keysend,
+            GetInfoMethod::Other(String::from("alpha")),
+            GetInfoMethod::Other(String::from("zeta")),
+        ]
+    );
+
+    let all = Capabilities::all();
+    assert!(all.contains(&GetInfoMethod::SendMultiPayment));
+    assert!(!all.contains(&GetInfoMethod::Other(String::from("customMethod"))));
+    assert_eq!(
+        all.iter()
+            .filter(|m| matches!(m, GetInfoMethod::Other(..)))
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn test_rgb_color() {
+    let color = RgbColor::from_str("#3399ff").unwrap();
+    assert_eq!(color, RgbColor::new(0x33, 0x99, 0xff));
+    assert_eq!(color.to_u32(), 0x3399ff);
+    assert_eq!(color.to_hex(), "#3399ff");
+    assert_eq!(<[u8; 3]>::from(color), [0x33, 0x99, 0xff]);
+    assert_eq!(RgbColor::from_str("3399FF").unwrap(), color);
+    assert_eq!(RgbColor::from_str("#39f").unwrap(), color);
+    assert_eq!(RgbColor::from((1, 2, 3)).to_string(), "#010203");
+
+    assert_eq!(
+        RgbColor::from_str("#3399f"),
+        Err(ParseRgbColorError::InvalidLength)
+    );
+    assert_eq!(
+        RgbColor::from_str(""),
+        Err(ParseRgbColorError::InvalidLength)
+    );
+    assert_eq!(
+        RgbColor::from_str("#33zzff"),
+        Err(ParseRgbColorError::InvalidHex)
+    );
+    assert_eq!(
+        RgbColor::from_str("#+3+3+3"),
+        Err(ParseRgbColorError::InvalidHex)
+    );
+    assert_eq!(
+        RgbColor::from_str("#ééé"),
+        Err(ParseRgbColorError::InvalidHex)
+    );
+}
+
+#[test]
+fn test_node_id() {
+    let node_id = NodeId::from_str(PUBKEY).unwrap();
+    assert_eq!(node_id.to_string(), PUBKEY);
+    assert_eq!(node_id.as_bytes()[0], 0x02);
+    assert_eq!(NodeId::from_slice(node_id.as_bytes()).unwrap(), node_id);
+    assert_eq!(NodeId::from_str(&PUBKEY.to_uppercase()).unwrap(), node_id);
+
+    assert_eq!(
+        NodeId::from_str("02").unwrap_err(),
+        ParseNodeIdError::InvalidLength
+    );
+    assert_eq!(
+        NodeId::from_slice(&[0x02; 32]).unwrap_err(),
+        ParseNodeIdError::InvalidLength
+    );
+    assert_eq!(
+        NodeId::from_str(&PUBKEY.replace('7', "z")).unwrap_err(),
+        ParseNodeIdError::InvalidHex
+    );
+    assert_eq!(
+        NodeId::from_str(&PUBKEY.replacen("02", "04", 1)).unwrap_err(),
+        ParseNodeIdError::InvalidPrefix
+    );
+}
+
+#[cfg(feature = "secp256k1")]
+#[test]
+fn test_node_id_public_key() {
+    use webln::secp256k1::PublicKey;
+
+    let node_id = NodeId::from_str(PUBKEY).unwrap();
+    let public_key: PublicKey = node_id.to_public_key().unwrap();
+    assert_eq!(NodeId::from(public_key), node_id);
+
+    // Valid encoding, but not a point on the curve
+    let mut bytes = [0xff; 33];
+    bytes[0] = 0x02;
+    let node_id = NodeId::from_slice(&bytes).unwrap();
+    assert!(PublicKey::try_from(node_id).is_err());
+}
+
+#[cfg(feature = "secp256k1")]
+#[test]
+fn test_login_proof_verify() {
+    use webln::LoginProof;
+
+    // Signed with LND `signmessage` format
+    let pubkey =
+        NodeId::from_str("034f355bdcb7cc0af728ef3cceb9615d90684bb5b2ca5f859ab0f0b704075871aa")
+            .unwrap();
+    let challenge = "webln-login:7f3a9c";
+    let signature = "dhtm3fg8n4wo7fj3ycepm8i1qf95pknudj77hnaf7c19548eegs5c6n53tb476c7qwinfdiuqf5jztcipnrw7hgpm4xknrxdsbquezaq";
+    let proof = LoginProof::new(pubkey, challenge, signature, 1_700_000_000);
+    proof.verify(challenge).unwrap();
+
+    // Another challenge
+    assert!(matches!(
+        proof.verify("webln-login:000000"),
+        Err(Error::InvalidSignature)
+    ));
+
+    // Signature of another message
+    let proof = LoginProof::new(
+        pubkey,
+        challenge,
+        "rn6wn6eassphenmbpehpzxskrqw4bwui3f6p5qusiht1tdsyxmo9atjhjngrwsnxu36391idwiznx8gkk4hpr94enk8po7o7fobegn4u",
+        1_700_000_000,
+    );
+    assert!(matches!(
+        proof.verify(challenge),
+        Err(Error::InvalidSignature)
+    ));
+
+    // Another node
+    let proof = LoginProof::new(
+        NodeId::from_str(PUBKEY).unwrap(),
+        challenge,
+        signature,
+        1_700_000_000,
+    );
+    assert!(matches!(
+        proof.verify(challenge),
+        Err(Error::InvalidSignature)
+    ));
+
+    // Malformed signatures
+    for signature in ["", "dhtm3fg8", "0000", &signature[..103]] {
+        let proof = LoginProof::new(pubkey, challenge, signature, 1_700_000_000);
+        assert!(matches!(
+            proof.verify(challenge),
+            Err(Error::InvalidSignature)
+        ));
+    }
+}
+
+#[cfg(feature = "send_wrapper")]
+#[test]
+fn test_send_webln() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<webln::SendWebLN>();
+}
+
+#[test]
+fn test_event() {
+    assert_eq!(Event::from("accountChanged"), Event::AccountChanged);
+    assert_eq!(Event::AccountChanged.to_string(), "accountChanged");
+    assert_eq!(Event::from("sendPayment:success"), Event::PaymentSucceeded);
+    assert_eq!(Event::PaymentFailed.to_string(), "sendPayment:failed");
+    assert_eq!(Event::from("paymentReceived"), Event::PaymentReceived);
+    assert_eq!(
+        Event::from_str("custom").unwrap(),
+        Event::Other(String::from("custom"))
+    );
+}
+
+#[test]
+fn test_currency() {
+    assert_eq!(Currency::from_str("sats").unwrap(), Currency::Sats);
+    assert_eq!(Currency::from_str("SAT").unwrap(), Currency::Sats);
+    assert_eq!(Currency::from_str("").unwrap(), Currency::Sats);
+    assert_eq!(Currency::from_str("btc").unwrap(), Currency::Btc);
+    assert_eq!(
+        Currency::from_str("eur").unwrap(),
+        Currency::Fiat(String::from("EUR"))
+    );
+    assert!(Currency::from_str("USD").unwrap().is_fiat());
+    assert_eq!(Currency::Sats.code(), None);
+    assert_eq!(Currency::Btc.code(), Some("BTC"));
+    assert_eq!(Currency::Sats.to_string(), "sats");
+}
+
+#[test]
+fn test_message_encoding() {
+    let message: &[u8] = &[0x00, 0xff, 0x10];
+    assert_eq!(MessageEncoding::Hex.encode(message).unwrap(), "00ff10");
+    assert_eq!(MessageEncoding::Base64.encode(message).unwrap(), "AP8Q");
+    assert_eq!(MessageEncoding::Base64.encode(b"hi").unwrap(), "aGk=");
+    assert_eq!(MessageEncoding::Utf8.encode(b"hello").unwrap(), "hello");
+    assert!(matches!(
+        MessageEncoding::Utf8.encode(message),
+        Err(Error::Utf8(_))
+    ));
+    assert_eq!(MessageEncoding::default(), MessageEncoding::Utf8);
+    assert_eq!(MessageEncoding::Base64.to_string(), "base64");
+}
+
+#[test]
+fn test_amount() {
+    let amount = Amount::from_sats(21);
+    assert_eq!(amount.msat(), 21_000);
+    assert_eq!(amount.sats(), 21);
+    assert_eq!(amount.to_string(), "21 sats");
+    assert_eq!(Amount::from_msat(21_500).sats(), 21);
+    assert_eq!(Amount::from_msat(21_500).to_string(), "21500 msat");
+    assert_eq!(Amount::from_sats(100_000_000).btc(), 1.0);
+    assert_eq!(Amount::from_sats(u64::MAX).msat(), u64::MAX);
+    assert_eq!(Amount::default(), Amount::ZERO);
+}
+
+#[test]
+fn test_parse_amount() {
+    assert_eq!(Amount::parse("21 sats").unwrap(), Amount::from_sats(21));
+    assert_eq!(Amount::parse("21sat").unwrap(), Amount::from_sats(21));
+    assert_eq!(Amount::parse(" 1500 ").unwrap(), Amount::from_sats(1500));
+    assert_eq!(
+        Amount::parse("0.001 btc").unwrap(),
+        Amount::from_sats(100_000)
+    );
+    assert_eq!(
+        Amount::parse("0.001 BTC").unwrap(),
+        Amount::from_sats(100_000)
+    );
+    assert_eq!(Amount::parse("1.5").unwrap(), Amount::from_msat(1_500));
+    assert_eq!(
+        Amount::parse("21500 msat").unwrap(),
+        Amount::from_msat(21_500)
+    );
+    assert_eq!(
+        Amount::from_str("2.100 sats").unwrap(),
+        Amount::from_msat(2_100)
+    );
+
+    assert_eq!(Amount::parse("  "), Err(ParseAmountError::Empty));
+    assert_eq!(Amount::parse("abc"), Err(ParseAmountError::InvalidNumber));
+    assert_eq!(Amount::parse("-5"), Err(ParseAmountError::InvalidNumber));
+    assert_eq!(Amount::parse("1.2.3"), Err(ParseAmountError::InvalidNumber));
+    assert_eq!(
+        Amount::parse("5 eur"),
+        Err(ParseAmountError::UnknownUnit(String::from("eur")))
+    );
+    assert_eq!(Amount::parse("1.5 msat"), Err(ParseAmountError::TooPrecise));
+    assert_eq!(Amount::parse("0.0001"), Err(ParseAmountError::TooPrecise));
+    assert_eq!(
+        Amount::parse("999999999999 btc"),
+        Err(ParseAmountError::Overflow)
+    );
+}
+
+#[test]
+fn test_bolt11_invoice() {
+    let invoice = Bolt11Invoice::parse(INVOICE_DONATION).unwrap();
+    assert_eq!(invoice.currency, "bc");
+    assert_eq!(invoice.amount, None);
+    assert!(invoice.is_zero_amount());
+    assert_eq!(invoice.timestamp, 1496314658);
+    assert_eq!(
+        invoice.payment_hash,
+        "0001020304050607080900010203040506070809000102030405060708090102"
+    );
+    assert_eq!(
+        invoice.description.as_deref(),
+        Some("Please consider supporting this project")
+    );
+    assert_eq!(invoice.description_hash, None);
+    assert_eq!(invoice.expiry, 3600);
+    assert_eq!(invoice.expires_at(), 1496314658 + 3600);
+
+    let invoice =
+        Bolt11Invoice::from_str(&format!("lightning:{}", INVOICE_COFFEE.to_uppercase())).unwrap();
+    assert_eq!(invoice.amount, Some(Amount::from_sats(250_000)));
+    assert!(!invoice.is_zero_amount());
+    assert_eq!(invoice.description.as_deref(), Some("1 cup coffee"));
+    assert_eq!(invoice.expiry, 60);
+
+    let invoice = Bolt11Invoice::parse(INVOICE_HASHED).unwrap();
+    assert_eq!(invoice.amount, Some(Amount::from_sats(2_000_000)));
+    assert_eq!(invoice.description, None);
+    assert_eq!(
+        invoice.description_hash.as_deref(),
+        Some("3925b6f67e2c340036ed12093dd44e0368df1b6ea26c53dbe4811f58fd5db8c1")
+    );
+
+    let mut tampered = String::from(INVOICE_COFFEE);
+    tampered.replace_range(20..21, "z");
+    assert_eq!(
+        Bolt11Invoice::parse(&tampered),
+        Err(ParseInvoiceError::InvalidChecksum)
+    );
+    assert_eq!(
+        Bolt11Invoice::parse("lnbc1Pvjluez"),
+        Err(ParseInvoiceError::InvalidBech32)
+    );
+    assert_eq!(
+        Bolt11Invoice::parse("not an invoice"),
+        Err(ParseInvoiceError::InvalidBech32)
+    );
+    // Valid checksum, non-ASCII HRP
+    assert_eq!(
+        Bolt11Invoice::parse("lnbc25é1qqqqqqqqqqc8jmga"),
+        Err(ParseInvoiceError::InvalidBech32)
+    );
+}
+
+#[test]
+fn test_checkout_state() {
+    let failed = |retry: bool| CheckoutEvent::PaymentFailed {
+        code: ErrorCode::Routing,
+        reason: String::from("no route"),
+        retry,
+    };
+    let paid = CheckoutEvent::PaymentSucceeded {
+        preimage: String::from("00"),
+    };
+
+    let state = CheckoutState::Created;
+    assert_eq!(state.invoice(), None);
+    assert_eq!(state.apply(&CheckoutEvent::PaymentStarted), None);
+    let state = state
+        .apply(&CheckoutEvent::InvoiceReceived(String::from(
+            INVOICE_COFFEE,
+        )))
+        .unwrap();
+    assert_eq!(state.invoice(), Some(INVOICE_COFFEE));
+
+    // Retried attempt, then the last one fails
+    let state = state.apply(&CheckoutEvent::PaymentStarted).unwrap();
+    assert!(matches!(state, CheckoutState::Paying { attempt: 1, .. }));
+    let state = state.apply(&failed(true)).unwrap();
+    assert!(matches!(
+        state,
+        CheckoutState::InvoiceReady { attempts: 1, .. }
+    ));
+    let paying = state.apply(&CheckoutEvent::PaymentStarted).unwrap();
+    assert!(matches!(paying, CheckoutState::Paying { attempt: 2, .. }));
+    let failed_state = paying.apply(&failed(false)).unwrap();
+    assert!(failed_state.is_final());
+    assert_eq!(failed_state.invoice(), Some(INVOICE_COFFEE));
+    assert!(matches!(
+        failed_state,
+        CheckoutState::Failed {
+            code: ErrorCode::Routing,
+            ..
+        }
+    ));
+
+    // Paid during the attempt, or out of band
+    assert!(matches!(
+        paying.apply(&paid),
+        Some(CheckoutState::Paid { .. })
+    ));
+    assert!(matches!(
+        state.apply(&paid),
+        Some(CheckoutState::Paid { .. })
+    ));
+
+    // Expiry only before paying
+    assert!(matches!(
+        state.apply(&CheckoutEvent::Expired),
+        Some(CheckoutState::Expired { .. })
+    ));
+    assert_eq!(paying.apply(&CheckoutEvent::Expired), None);
+
+    // A payment in flight is only cancelled by the user rejecting the prompt
+    assert_eq!(paying.apply(&CheckoutEvent::Cancelled), None);
+    assert_eq!(
+        paying.apply(&CheckoutEvent::PaymentRejected),
+        Some(CheckoutState::Cancelled)
+    );
+    assert_eq!(state.apply(&CheckoutEvent::PaymentRejected), None);
+    assert_eq!(
+        state.apply(&CheckoutEvent::Cancelled),
+        Some(CheckoutState::Cancelled)
+    );
+
+    // No transition out of the final states
+    for event in [paid, CheckoutEvent::Cancelled, CheckoutEvent::Expired] {
+        assert_eq!(failed_state.apply(&event), None);
+        assert_eq!(CheckoutState::Cancelled.apply(&event), None);
+    }
+    assert!(matches!(
+        CheckoutState::Created.apply(&failed(false)),
+        Some(CheckoutState::Failed { invoice: None, .. })
+    ));
+}
@@ -0,0 +1,250 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+//! Response parsing on the host, through the intermediate [`Value`]
+
+use webln::{
+    Amount, BalanceResponse, Deserialize, EnableResponse, Error, GetInfoMethod, GetInfoResponse,
+    MultiPaymentItem, ParseMode, SendMultiPaymentResponse, SendPaymentAsyncResponse,
+    SendPaymentResponse, Value,
+};
+
+/// Convert a JSON document, like `JSON.parse` would
+fn value(json: &str) -> Value {
+    fn convert(value: serde_json::Value) -> Value {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(value) => Value::Bool(value),
+            serde_json::Value::Number(value) => Value::Number(value.as_f64().unwrap()),
+            serde_json::Value::String(value) => Value::String(value),
+            serde_json::Value::Array(array) => {
+                Value::Array(array.into_iter().map(convert).collect())
+            }
+            serde_json::Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, convert(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    convert(serde_json::from_str(json).unwrap())
+}
+
+macro_rules! fixture {
+    ($provider:literal, $method:literal) => {
+        value(include_str!(concat!(
+            "fixtures/",
+            $provider,
+            "/",
+            $method,
+            ".json"
+        )))
+    };
+}
+
+#[test]
+fn test_value_accessors() {
+    let obj = value(r#"{"a":{"b":[1,"two",null]},"c":true}"#);
+    assert_eq!(obj.get("c").as_bool(), Some(true));
+    assert!(obj.get("a").is_object());
+    assert_eq!(obj.get("a").get("b").as_array().map(|a| a.len()), Some(3));
+    assert_eq!(obj.get("missing"), &Value::Undefined);
+    assert_eq!(obj.get("c").get("nested"), &Value::Undefined);
+    assert!(obj.get("missing").is_nullish());
+}
+
+#[test]
+fn test_get_info_fixtures() {
+    for fixture in [
+        fixture!("alby", "getInfo"),
+        fixture!("mutiny", "getInfo"),
+        fixture!("zeus", "getInfo"),
+    ] {
+        let info = GetInfoResponse::from_value(&fixture, ParseMode::Strict).unwrap();
+        assert!(info.methods.contains(&GetInfoMethod::SendPayment));
+    }
+
+    let info =
+        GetInfoResponse::from_value(&fixture!("alby", "getInfo"), ParseMode::Lenient).unwrap();
+    assert_eq!(info.node.alias.as_deref(), Some("🐝 getalby.com"));
+    assert_eq!(info.node.color.as_deref(), Some("#ffc400"));
+}
+
+#[test]
+fn test_get_info_modes() {
+    let obj = value(r#"{"node":{"alias":42},"methods":["getInfo"]}"#);
+    let info = GetInfoResponse::from_value(&obj, ParseMode::Lenient).unwrap();
+    assert_eq!(info.node.alias, None);
+    assert!(matches!(
+        GetInfoResponse::from_value(&obj, ParseMode::Strict),
+        Err(Error::TypeMismatch(..))
+    ));
+}
+
+#[test]
+fn test_send_payment() {
+    let res = SendPaymentResponse::from_value(&fixture!("alby", "sendPayment"), ParseMode::Strict)
+        .unwrap();
+    assert_eq!(
+        res.preimage,
+        "a9c7f7f5d3d2e8f2a0a74e7a7b7b5c1c3e6f0e8c2f3d1b5a8e7c9d0f1a2b3c4d"
+    );
+
+    assert!(matches!(
+        SendPaymentResponse::from_value(&value(r#"{"preimage":null}"#), ParseMode::Lenient),
+        Err(Error::TypeMismatch(..))
+    ));
+    assert!(matches!(
+        SendPaymentResponse::from_value(&value(r#""preimage""#), ParseMode::Lenient),
+        Err(Error::SomethingGoneWrong)
+    ));
+
+    let res = SendPaymentAsyncResponse::from_value(&value("{}"), ParseMode::Strict).unwrap();
+    assert_eq!(res.payment_hash, None);
+}
+
+#[test]
+fn test_enable_response() {
+    let res = EnableResponse::from_value(&Value::Undefined, ParseMode::Strict).unwrap();
+    assert!(res.enabled);
+    assert_eq!(res.remember, None);
+
+    let res = EnableResponse::from_value(&Value::Bool(false), ParseMode::Strict).unwrap();
+    assert!(!res.enabled);
+
+    let res = EnableResponse::from_value(
+        &value(r#"{"enabled":false,"remember":true}"#),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    assert!(!res.enabled);
+    assert_eq!(res.remember, Some(true));
+
+    let obj = value(r#"{"enabled":"yes"}"#);
+    assert!(
+        EnableResponse::from_value(&obj, ParseMode::Lenient)
+            .unwrap()
+            .enabled
+    );
+    assert!(EnableResponse::from_value(&obj, ParseMode::Strict).is_err());
+    assert!(EnableResponse::from_value(&value("21"), ParseMode::Lenient).is_err());
+}
+
+#[test]
+fn test_balance() {
+    for fixture in [
+        fixture!("alby", "getBalance"),
+        fixture!("mutiny", "getBalance"),
+        fixture!("zeus", "getBalance"),
+    ] {
+        BalanceResponse::from_value(&fixture, ParseMode::Strict).unwrap();
+    }
+
+    let balance = BalanceResponse::from_value(
+        &value(r#"{"balance":1.5,"currency":"BTC"}"#),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    assert_eq!(balance.amount(), Some(Amount::from_sats(150_000_000)));
+
+    let obj = value(r#"{"balance":21,"currency":7}"#);
+    assert_eq!(
+        BalanceResponse::from_value(&obj, ParseMode::Lenient)
+            .unwrap()
+            .currency,
+        None
+    );
+    assert!(BalanceResponse::from_value(&obj, ParseMode::Strict).is_err());
+    assert!(
+        BalanceResponse::from_value(&value(r#"{"balance":"21"}"#), ParseMode::Lenient).is_err()
+    );
+}
+
+#[test]
+fn test_multi_payment() {
+    let item = MultiPaymentItem::from_value(
+        &value(r#"{"paymentRequest":"lnbc1","preimage":"00","route":{"total_amt":1012,"total_fees_msat":"12000"}}"#),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    assert_eq!(item.fee, Some(Amount::from_sats(12)));
+    let route = item.route.unwrap();
+    assert_eq!(route.total_amount, Some(Amount::from_sats(1012)));
+
+    let item = MultiPaymentItem::from_value(
+        &value(r#"{"paymentRequest":"lnbc1","preimage":"00","feeMsat":1500,"fee":9}"#),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    assert_eq!(item.fee, Some(Amount::from_msat(1500)));
+    assert_eq!(item.route, None);
+
+    let res = SendMultiPaymentResponse::from_value(
+        &value(r#"{"payments":[{"paymentRequest":"lnbc1","preimage":"00"}],"errors":[{"paymentRequest":"lnbc2","message":"no route"}]}"#),
+        ParseMode::Strict,
+    )
+    .unwrap();
+    assert_eq!(res.payments.len(), 1);
+    assert_eq!(res.errors[0].message, "no route");
+    assert!(
+        SendMultiPaymentResponse::from_value(&value(r#"{"payments":[]}"#), ParseMode::Strict)
+            .is_err()
+    );
+
+    // The mode applies to the items
+    let obj = value(
+        r#"{"payments":[{"paymentRequest":"lnbc1","preimage":"00","feeMsat":"x"}],"errors":[]}"#,
+    );
+    let res = SendMultiPaymentResponse::from_value(&obj, ParseMode::Lenient).unwrap();
+    assert_eq!(res.payments[0].fee, None);
+    assert!(SendMultiPaymentResponse::from_value(&obj, ParseMode::Strict).is_err());
+
+    // Amounts aren't rounded
+    for fee in ["1.5", "-1", "1e20"] {
+        let obj = value(&format!(
+            r#"{{"paymentRequest":"lnbc1","preimage":"00","feeMsat":{fee}}}"#
+        ));
+        let item = MultiPaymentItem::from_value(&obj, ParseMode::Lenient).unwrap();
+        assert_eq!(item.fee, None);
+        assert!(MultiPaymentItem::from_value(&obj, ParseMode::Strict).is_err());
+    }
+    let obj = value(r#"{"paymentRequest":"lnbc1","preimage":"00","feeMsat":2.0}"#);
+    let item = MultiPaymentItem::from_value(&obj, ParseMode::Strict).unwrap();
+    assert_eq!(item.fee, Some(Amount::from_msat(2)));
+}
+
+#[cfg(all(feature = "nostr", feature = "secp256k1"))]
+#[test]
+fn test_signed_event_verify() {
+    use webln::{Bolt11Invoice, SignedEvent};
+
+    let receipt = fixture!("nostr", "zapReceipt");
+    let event = SignedEvent::from_value(&receipt, ParseMode::Strict).unwrap();
+    event.verify().unwrap();
+
+    // Zap request embedded in the receipt (content with escaped chars)
+    let description: &str = event.tags[3][1].as_str();
+    let request = SignedEvent::from_value(&value(description), ParseMode::Strict).unwrap();
+    request.verify().unwrap();
+    assert_eq!(request.content, "Zap!\n\"great\" post");
+
+    let invoice = Bolt11Invoice::parse(&event.tags[2][1]).unwrap();
+    assert!(invoice.description_hash.is_some());
+    assert_eq!(invoice.amount, Some(Amount::from_sats(21)));
+
+    // Tampered content
+    let mut tampered = request.clone();
+    tampered.content.push('!');
+    assert!(matches!(tampered.verify(), Err(Error::InvalidSignature)));
+
+    // Signed by another key
+    let mut tampered = request;
+    tampered.pubkey = event.pubkey.clone();
+    assert!(matches!(tampered.verify(), Err(Error::InvalidSignature)));
+
+    // Malformed signature
+    let mut tampered = event;
+    tampered.sig.truncate(64);
+    assert!(tampered.verify().is_err());
+}
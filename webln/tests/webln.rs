@@ -0,0 +1,2997 @@
+// Copyright (c) 2024 Yuki Kishimoto
+// Distributed under the MIT software license
+
+#![cfg(target_arch = "wasm32")]
+
+use core::cell::{Cell, RefCell};
+use core::str::FromStr;
+use core::time::Duration;
+use std::rc::Rc;
+
+use js_sys::{Promise, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+use webln::{
+    Amount, Clock, DestinationFilter, Error, ErrorCode, Event, GetInfoMethod, GetInfoNode,
+    KeysendArgs, LightningPayer, MessageEncoding, MultiPaymentOptions, MultiPaymentProgress,
+    NodeId, ParseMode, PaymentStatus, PromptLimit, RequestInvoiceArgs, Sleep, WebLN,
+    KEYSEND_PREIMAGE_TLV, REFUND_REFERENCE_TLV,
+};
+
+mod common;
+
+use self::common::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn client(mock: &MockProvider) -> WebLN {
+    mock.install();
+    WebLN::new().unwrap()
+}
+
+fn keysend_args() -> KeysendArgs {
+    KeysendArgs::new(NodeId::from_str(PUBKEY).unwrap(), 21)
+}
+
+fn get(obj: &JsValue, key: &str) -> JsValue {
+    Reflect::get(obj, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_new_without_provider() {
+    uninstall();
+    assert!(matches!(WebLN::new(), Err(Error::NamespaceNotFound(n)) if n == "webln"));
+    assert!(!WebLN::is_available());
+
+    MockProvider::new().install();
+    assert!(WebLN::is_available());
+}
+
+#[wasm_bindgen_test]
+async fn test_from_provider() {
+    uninstall();
+    let mock = MockProvider::new();
+    let webln = WebLN::from_provider(mock.object());
+    assert_eq!(
+        webln.get_info().await.unwrap().node.alias.as_deref(),
+        Some("mock")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_builder() {
+    uninstall();
+    assert!(matches!(WebLN::builder().build(), Err(Error::NamespaceNotFound(n)) if n == "webln"));
+
+    let mock = MockProvider::new();
+    let clock = FakeClock::new();
+    let webln = WebLN::builder()
+        .provider(mock.object())
+        .clock(clock.clone())
+        .max_amount(Amount::from_sats(10))
+        .prompt_limit(PromptLimit::new(1, Duration::from_secs(60)))
+        .parse_mode(ParseMode::Strict)
+        .paid_invoice_memory()
+        .build()
+        .unwrap();
+    assert_eq!(webln.namespace(), None);
+    assert_eq!(webln.max_amount(), Some(Amount::from_sats(10)));
+    assert_eq!(
+        webln.prompt_limit(),
+        Some(PromptLimit::new(1, Duration::from_secs(60)))
+    );
+    assert!(matches!(
+        webln.keysend(&keysend_args()).await.unwrap_err(),
+        Error::AmountExceedsLimit { .. }
+    ));
+    assert_eq!(mock.call_count("keysend"), 0);
+
+    mock.install();
+    let webln = WebLN::builder()
+        .namespace("webln")
+        .rejection_matcher(|e| e.message == "nope")
+        .build()
+        .unwrap();
+    assert_eq!(webln.namespace(), Some("webln"));
+    assert_eq!(webln.max_amount(), None);
+    assert_eq!(webln.prompt_limit(), None);
+}
+
+#[wasm_bindgen_test]
+async fn test_wait_for_provider() {
+    let mock = MockProvider::new();
+    mock.install();
+    WebLN::wait_for_provider(Duration::ZERO).await.unwrap();
+
+    uninstall();
+    let error = WebLN::wait_for_provider(Duration::from_millis(250))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), ErrorCode::MissingProvider);
+
+    // Injected while waiting
+    let install = Closure::once_into_js(move || mock.install());
+    web_sys::window()
+        .unwrap()
+        .set_timeout_with_callback_and_timeout_and_arguments_0(install.unchecked_ref(), 150)
+        .unwrap();
+    let webln = WebLN::wait_for_provider(Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(webln.is_enabled().await.unwrap());
+
+    // Timed by the injected clock
+    uninstall();
+    let clock = FakeClock::new();
+    let result = Rc::new(RefCell::new(None));
+    {
+        let result = result.clone();
+        let clock = clock.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let res = WebLN::wait_for_provider_with_clock(Duration::from_secs(1), clock).await;
+            *result.borrow_mut() = Some(res);
+        });
+    }
+    settle().await;
+    assert!(result.borrow().is_none());
+    for _ in 0..10 {
+        clock.advance(Duration::from_millis(100));
+        settle().await;
+    }
+    let error = result.borrow_mut().take().unwrap().unwrap_err();
+    assert_eq!(error.code(), ErrorCode::MissingProvider);
+}
+
+#[wasm_bindgen_test]
+async fn test_is_enabled() {
+    let webln = client(&MockProvider::new());
+    assert!(webln.is_enabled().await.unwrap());
+
+    let webln = client(&MockProvider::new().resolve("isEnabled", JsValue::FALSE));
+    assert!(!webln.is_enabled().await.unwrap());
+
+    let webln = client(&MockProvider::new().reject_user("isEnabled"));
+    assert!(matches!(
+        webln.is_enabled().await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("isEnabled", JsValue::from_str("yes")));
+    assert!(matches!(
+        webln.is_enabled().await.unwrap_err().inner(),
+        Error::TypeMismatch(_)
+    ));
+
+    let webln = client(&MockProvider::new().remove("isEnabled"));
+    assert!(
+        matches!(webln.is_enabled().await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "isEnabled")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_enable() {
+    let webln = client(&MockProvider::new());
+    webln.enable().await.unwrap();
+    assert_eq!(webln.cached_enabled(), Some(true));
+
+    let webln = client(
+        &MockProvider::new()
+            .resolve("isEnabled", JsValue::FALSE)
+            .resolve("enable", json(r#"{"enabled":false,"remember":false}"#)),
+    );
+    let res = webln.enable_with_raw().await.unwrap();
+    assert!(!res.enabled);
+    assert_eq!(res.remember, Some(false));
+    assert_eq!(webln.cached_enabled(), Some(false));
+    let err = webln.enable().await.unwrap_err();
+    assert!(matches!(err.inner(), Error::NotEnabled));
+    assert_eq!(err.code(), ErrorCode::UserRejected);
+    assert!(webln.ensure_enabled().await.is_err());
+    assert_eq!(webln.cached_enabled(), Some(false));
+
+    let webln = client(&MockProvider::new().reject_user("enable"));
+    assert!(matches!(
+        webln.enable().await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().reject("enable", JsValue::from_str("Internal error")));
+    assert!(
+        matches!(webln.enable().await.unwrap_err().inner(), Error::Provider(e) if e.message == "Internal error" && e.name.is_none())
+    );
+
+    let webln = client(&MockProvider::new().remove("enable"));
+    assert!(
+        matches!(webln.enable().await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "enable")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_provider_error() {
+    let error = json(r#"{"code":-32000,"message":"Insufficient balance","data":{"missing":21}}"#);
+    Reflect::set(
+        &error,
+        &JsValue::from_str("name"),
+        &JsValue::from_str("InsufficientBalanceError"),
+    )
+    .unwrap();
+    let webln = client(&MockProvider::new().reject("sendPayment", error.clone()));
+    let res = webln.send_payment(INVOICE).await.unwrap_err();
+    assert_eq!(res.js_value(), Some(&error));
+    match res.inner() {
+        Error::Provider(e) => {
+            assert_eq!(e.name.as_deref(), Some("InsufficientBalanceError"));
+            assert_eq!(e.code.as_deref(), Some("-32000"));
+            assert_eq!(e.message, "Insufficient balance");
+            assert_eq!(e.data.as_deref(), Some(r#"{"missing":21}"#));
+            assert_eq!(e.raw, error);
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+
+    let error = js_sys::TypeError::new("Failed to fetch");
+    let webln = client(&MockProvider::new().reject("getBalance", error.into()));
+    match webln.get_balance().await.unwrap_err().inner() {
+        Error::Provider(e) => {
+            assert_eq!(e.name.as_deref(), Some("TypeError"));
+            assert_eq!(e.code, None);
+            assert_eq!(e.message, "Failed to fetch");
+        }
+        e => panic!("unexpected error: {e:?}"),
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_user_rejected() {
+    let webln = client(
+        &MockProvider::new().reject("enable", js_sys::Error::new("Prompt was closed").into()),
+    );
+    assert!(webln.enable().await.unwrap_err().is_user_rejected());
+
+    let error = js_sys::Error::new("Solicitud rechazada");
+    error.set_name("UserRejectedError");
+    let webln = client(&MockProvider::new().reject("enable", error.into()));
+    assert!(webln.enable().await.unwrap_err().is_user_rejected());
+
+    let webln = client(
+        &MockProvider::new().reject("enable", json(r#"{"code":4001,"message":"Denegado"}"#)),
+    );
+    assert!(webln.enable().await.unwrap_err().is_user_rejected());
+
+    // Localized message: only detected with a custom matcher
+    let mock = MockProvider::new().reject("enable", JsValue::from_str("Anfrage abgelehnt"));
+    let webln = client(&mock);
+    assert!(!webln.enable().await.unwrap_err().is_user_rejected());
+    let webln = client(&mock).with_rejection_matcher(|e| e.message.contains("abgelehnt"));
+    assert!(webln.enable().await.unwrap_err().is_user_rejected());
+
+    // Clones share the configuration; configuring a clone doesn't affect the original
+    let plain = client(&mock);
+    let clone = plain.clone();
+    assert!(!clone.enable().await.unwrap_err().is_user_rejected());
+    let clone = clone.with_rejection_matcher(|e| e.message.contains("abgelehnt"));
+    assert!(clone.clone().enable().await.unwrap_err().is_user_rejected());
+    assert!(!plain.enable().await.unwrap_err().is_user_rejected());
+
+    // ... but the state stays shared
+    let mock = MockProvider::new();
+    let webln = client(&mock).with_paid_invoice_memory();
+    let limited = webln.clone().with_max_amount(Amount::from_sats(1_000_000));
+    limited.send_payment(COFFEE_INVOICE).await.unwrap();
+    let err = webln.send_payment(COFFEE_INVOICE).await.unwrap_err();
+    assert!(matches!(err, Error::AlreadyPaid(..)));
+    webln.get_info().await.unwrap();
+    assert!(limited.cached_info().is_some());
+    assert_eq!(mock.call_count("sendPayment"), 1);
+}
+
+#[wasm_bindgen_test]
+async fn test_error_code() {
+    uninstall();
+    assert_eq!(WebLN::new().unwrap_err().code(), ErrorCode::MissingProvider);
+
+    let webln = client(&MockProvider::new().remove("keysend"));
+    let error = webln.keysend(&keysend_args()).await.unwrap_err();
+    assert_eq!(error.code(), ErrorCode::UnsupportedMethod);
+
+    let webln = client(&MockProvider::new().reject_user("keysend"));
+    let error = webln.keysend(&keysend_args()).await.unwrap_err();
+    assert_eq!(error.code(), ErrorCode::UserRejected);
+    assert_eq!(error.code().as_str(), "USER_REJECTED");
+
+    let webln = client(&MockProvider::new().resolve("keysend", json("{}")));
+    let error = webln.keysend(&keysend_args()).await.unwrap_err();
+    assert_eq!(error.code(), ErrorCode::InvalidData);
+
+    let error = Error::from(NodeId::from_str("02").unwrap_err());
+    assert_eq!(error.code(), ErrorCode::InvalidData);
+    assert!(std::error::Error::source(&error).is_some());
+
+    for (name, code) in [
+        ("RoutingError", ErrorCode::Routing),
+        ("ConnectionError", ErrorCode::Connection),
+        ("UnsupportedMethodError", ErrorCode::UnsupportedMethod),
+        ("InternalError", ErrorCode::Internal),
+    ] {
+        let error = js_sys::Error::new("failed");
+        error.set_name(name);
+        let webln = client(&MockProvider::new().reject("keysend", error.into()));
+        let error = webln.keysend(&keysend_args()).await.unwrap_err();
+        assert_eq!(error.code(), code);
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_call_error() {
+    let webln = client(&MockProvider::new().reject_user("sendPayment"));
+    let error = webln.send_payment(INVOICE).await.unwrap_err();
+    assert!(matches!(
+        &error,
+        Error::Call {
+            method: GetInfoMethod::SendPayment,
+            ..
+        }
+    ));
+    assert_eq!(error.to_string(), "sendPayment failed: User rejected");
+
+    // Error chain: call -> user rejected -> provider error
+    let source = std::error::Error::source(&error).unwrap();
+    assert_eq!(source.to_string(), "User rejected");
+    let source = source.source().unwrap();
+    assert_eq!(source.to_string(), "Error: User rejected");
+    assert!(source.source().is_none());
+
+    let webln = client(&MockProvider::new().remove("getBalance"));
+    let error = webln.get_balance().await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "getBalance failed: `getBalance` namespace not found"
+    );
+
+    // Validation errors happen before calling the provider
+    assert!(matches!(
+        webln.send_payment("").await,
+        Err(Error::EmptyInvoice)
+    ));
+}
+
+#[wasm_bindgen_test]
+async fn test_get_info() {
+    let webln = client(&MockProvider::new());
+    let info = webln.get_info().await.unwrap();
+    assert_eq!(info.node.alias.as_deref(), Some("mock"));
+    assert_eq!(info.node.pubkey.as_deref(), Some(PUBKEY));
+    assert_eq!(info.node.color.as_deref(), Some("#3399ff"));
+    assert!(info.methods.contains(&GetInfoMethod::Keysend));
+    assert!(info
+        .methods
+        .contains(&GetInfoMethod::Other(String::from("customMethod"))));
+
+    let webln = client(&MockProvider::new().resolve(
+        "getInfo",
+        json(r#"{"node":{"alias":1},"methods":["getInfo",2]}"#),
+    ));
+    let info = webln.get_info().await.unwrap();
+    assert_eq!(info.node.alias, None);
+    assert_eq!(info.node.pubkey, None);
+    assert_eq!(info.methods, vec![GetInfoMethod::GetInfo]);
+
+    // Minimal responses
+    for value in [
+        r#"{}"#,
+        r#"{"node":{}}"#,
+        r#"{"node":null,"methods":null}"#,
+        r#"{"node":"mock","methods":{}}"#,
+    ] {
+        let webln = client(&MockProvider::new().resolve("getInfo", json(value)));
+        let info = webln.get_info().await.unwrap();
+        assert_eq!(info.node, GetInfoNode::default());
+        assert!(info.methods.is_empty());
+    }
+    let webln = client(&MockProvider::new().resolve("getInfo", json(r#"{"methods":{}}"#)))
+        .with_parse_mode(ParseMode::Strict);
+    assert!(matches!(
+        webln.get_info().await.unwrap_err().inner(),
+        Error::TypeMismatch(m) if m.contains("[methods]")
+    ));
+
+    let webln = client(&MockProvider::new().reject_user("getInfo"));
+    assert!(matches!(
+        webln.get_info().await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("getInfo", JsValue::NULL));
+    assert!(matches!(
+        webln.get_info().await.unwrap_err().inner(),
+        Error::SomethingGoneWrong
+    ));
+
+    let webln = client(&MockProvider::new().resolve("getInfo", json(r#"{"methods":[]}"#)));
+    assert!(matches!(
+        webln.get_info().await.unwrap_err().inner(),
+        Error::SomethingGoneWrong
+    ));
+
+    let webln = client(&MockProvider::new().remove("getInfo"));
+    assert!(
+        matches!(webln.get_info().await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "getInfo")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_keysend() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let res = webln.keysend(&keysend_args()).await.unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    let arg = mock.last_call("keysend").unwrap();
+    assert_eq!(
+        get(&arg, "destination").as_string().as_deref(),
+        Some(PUBKEY)
+    );
+    assert_eq!(get(&arg, "amount").as_string().as_deref(), Some("21"));
+    assert!(get(&arg, "customRecords").is_undefined());
+
+    let args = keysend_args()
+        .custom_record(7629169, r#"{"action":"boost"}"#)
+        .custom_record(34349334, "hello");
+    webln.keysend(&args).await.unwrap();
+    let records = get(&mock.last_call("keysend").unwrap(), "customRecords");
+    assert_eq!(
+        get(&records, "7629169").as_string().as_deref(),
+        Some(r#"{"action":"boost"}"#)
+    );
+    assert_eq!(
+        get(&records, "34349334").as_string().as_deref(),
+        Some("hello")
+    );
+
+    let webln = client(&MockProvider::new().reject_user("keysend"));
+    assert!(matches!(
+        webln.keysend(&keysend_args()).await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("keysend", json(r#"{"preimage":1}"#)));
+    assert!(matches!(
+        webln.keysend(&keysend_args()).await.unwrap_err().inner(),
+        Error::TypeMismatch(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("keysend", JsValue::UNDEFINED));
+    assert!(matches!(
+        webln.keysend(&keysend_args()).await.unwrap_err().inner(),
+        Error::SomethingGoneWrong
+    ));
+
+    let webln = client(&MockProvider::new().remove("keysend"));
+    assert!(
+        matches!(webln.keysend(&keysend_args()).await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "keysend")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_refund() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let node_id = NodeId::from_str(PUBKEY).unwrap();
+    let res = webln.refund(node_id, 21, "order-42").await.unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    let arg = mock.last_call("keysend").unwrap();
+    assert_eq!(
+        get(&arg, "destination").as_string().as_deref(),
+        Some(PUBKEY)
+    );
+    assert_eq!(get(&arg, "amount").as_string().as_deref(), Some("21"));
+    let records = get(&arg, "customRecords");
+    assert_eq!(
+        get(&records, &REFUND_REFERENCE_TLV.to_string())
+            .as_string()
+            .as_deref(),
+        Some("order-42")
+    );
+
+    let webln = client(&MockProvider::new().reject_user("keysend"));
+    assert!(matches!(
+        webln
+            .refund(node_id, 21, "order-42")
+            .await
+            .unwrap_err()
+            .inner(),
+        Error::UserRejected(_)
+    ));
+
+    // Limited like `keysend`
+    let webln = client(&mock).with_max_amount(Amount::from_sats(10));
+    assert!(matches!(
+        webln.refund(node_id, 21, "order-42").await.unwrap_err(),
+        Error::AmountExceedsLimit { .. }
+    ));
+    assert_eq!(mock.call_count("keysend"), 1);
+}
+
+#[wasm_bindgen_test]
+async fn test_keysend_async() {
+    let deferred = Deferred::new();
+    let mock = MockProvider::new().defer("keysend", &deferred);
+    let webln = client(&mock);
+
+    // Returns while the payment is still in flight
+    webln.keysend_async(&keysend_args()).await.unwrap();
+    webln.keysend_async(&keysend_args()).await.unwrap();
+    assert_eq!(mock.call_count("keysend"), 2);
+    assert_eq!(deferred.pending(), 2);
+    let arg = mock.last_call("keysend").unwrap();
+    assert_eq!(get(&arg, "amount").as_string().as_deref(), Some("21"));
+
+    // Later failures are dropped
+    deferred.resolve_next(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)));
+    deferred.reject_next(&js_sys::Error::new("no route").into());
+    settle().await;
+    assert_eq!(deferred.pending(), 0);
+
+    let webln = client(&MockProvider::new().remove("keysend"));
+    assert!(
+        matches!(webln.keysend_async(&keysend_args()).await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "keysend")
+    );
+
+    let mock = MockProvider::new();
+    let webln = client(&mock).with_max_amount(Amount::from_sats(10));
+    assert!(matches!(
+        webln.keysend_async(&keysend_args()).await.unwrap_err(),
+        Error::AmountExceedsLimit { .. }
+    ));
+    assert_eq!(mock.call_count("keysend"), 0);
+}
+
+#[wasm_bindgen_test]
+async fn test_keysend_fallback() {
+    let mock = MockProvider::new()
+        .remove("keysend")
+        .resolve(
+            "getInfo",
+            json(r#"{"node":{},"methods":["getInfo","sendPayment","request"]}"#),
+        )
+        .resolve("request", json(r#"{"result":{"status":"SUCCEEDED"}}"#));
+
+    // Disabled by default
+    let webln = client(&mock);
+    assert!(matches!(
+        webln.keysend(&keysend_args()).await.unwrap_err().inner(),
+        Error::NamespaceNotFound(_)
+    ));
+    assert_eq!(mock.call_count("request"), 0);
+
+    let webln = webln.with_keysend_fallback(true);
+    let args = keysend_args().custom_record(34349334, "hello");
+    let res = webln.keysend(&args).await.unwrap();
+    assert_eq!(res.preimage.len(), 64);
+    let call = mock.last_call_args("request").unwrap();
+    assert_eq!(call[0].as_string().as_deref(), Some("sendpaymentv2"));
+    let params = &call[1];
+    assert_eq!(get(params, "amt").as_string().as_deref(), Some("21"));
+    assert_eq!(
+        get(params, "payment_hash").as_string().map(|h| h.len()),
+        Some(44)
+    );
+    let records = get(params, "dest_custom_records");
+    assert!(get(&records, &KEYSEND_PREIMAGE_TLV.to_string())
+        .as_string()
+        .is_some());
+    assert_eq!(
+        get(&records, "34349334").as_string().as_deref(),
+        Some("aGVsbG8=")
+    );
+
+    // Failed payment
+    let mock = mock.resolve(
+        "request",
+        json(r#"{"result":{"status":"FAILED","failure_reason":"FAILURE_REASON_NO_ROUTE"}}"#),
+    );
+    let webln = client(&mock).with_keysend_fallback(true);
+    let error = webln.keysend(&keysend_args()).await.unwrap_err();
+    assert!(matches!(error.inner(), Error::Provider(e) if e.message == "FAILURE_REASON_NO_ROUTE"));
+
+    // Native `keysend` is preferred
+    let mock = MockProvider::new();
+    let webln = client(&mock).with_keysend_fallback(true);
+    webln.keysend(&keysend_args()).await.unwrap();
+    assert_eq!(mock.call_count("keysend"), 1);
+    assert_eq!(mock.call_count("request"), 0);
+}
+
+#[wasm_bindgen_test]
+async fn test_make_invoice() {
+    let args = RequestInvoiceArgs::new()
+        .amount(1000)
+        .default_memo(String::from("memo"));
+
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let res = webln.make_invoice(&args).await.unwrap();
+    assert_eq!(res.invoice, INVOICE);
+    let arg = mock.last_call("makeInvoice").unwrap();
+    assert_eq!(get(&arg, "amount").as_string().as_deref(), Some("1000"));
+    assert_eq!(
+        get(&arg, "defaultMemo").as_string().as_deref(),
+        Some("memo")
+    );
+    assert!(get(&arg, "minimumAmount").is_undefined());
+
+    let webln = client(&MockProvider::new().reject_user("makeInvoice"));
+    assert!(matches!(
+        webln.make_invoice(&args).await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("makeInvoice", json(r#"{"invoice":"lnbc"}"#)));
+    assert!(matches!(
+        webln.make_invoice(&args).await.unwrap_err().inner(),
+        Error::TypeMismatch(_)
+    ));
+
+    let webln = client(&MockProvider::new().remove("makeInvoice"));
+    assert!(
+        matches!(webln.make_invoice(&args).await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "makeInvoice")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_make_invoice_fallback() {
+    let args = RequestInvoiceArgs::new()
+        .amount(1000)
+        .default_memo(String::from("memo"));
+
+    // LND
+    let mock = MockProvider::new()
+        .remove("makeInvoice")
+        .respond("request", |method| {
+            if method.as_string().as_deref() == Some("addinvoice") {
+                Promise::resolve(&json(&format!(r#"{{"payment_request":"{INVOICE}"}}"#)))
+            } else {
+                Promise::reject(&js_sys::Error::new("unknown method").into())
+            }
+        });
+    let webln = client(&mock).with_make_invoice_fallback(true);
+    let res = webln.make_invoice(&args).await.unwrap();
+    assert_eq!(res.invoice, INVOICE);
+    let call = mock.last_call_args("request").unwrap();
+    assert_eq!(get(&call[1], "value").as_string().as_deref(), Some("1000"));
+    assert_eq!(get(&call[1], "memo").as_string().as_deref(), Some("memo"));
+
+    // CLN
+    let mock = MockProvider::new()
+        .remove("makeInvoice")
+        .respond("request", |method| {
+            if method.as_string().as_deref() == Some("invoice") {
+                Promise::resolve(&json(&format!(r#"{{"bolt11":"{INVOICE}"}}"#)))
+            } else {
+                Promise::reject(&js_sys::Error::new("unknown method").into())
+            }
+        });
+    let webln = client(&mock).with_make_invoice_fallback(true);
+    let res = webln.make_invoice(&args).await.unwrap();
+    assert_eq!(res.invoice, INVOICE);
+    let call = mock.last_call_args("request").unwrap();
+    assert_eq!(
+        get(&call[1], "amount_msat").as_string().as_deref(),
+        Some("1000000")
+    );
+
+    // Neither: the first error is returned
+    let mock = MockProvider::new()
+        .remove("makeInvoice")
+        .reject("request", js_sys::Error::new("unknown method").into());
+    let webln = client(&mock).with_make_invoice_fallback(true);
+    assert!(matches!(
+        webln.make_invoice(&args).await.unwrap_err().inner(),
+        Error::Provider(e) if e.message == "unknown method"
+    ));
+    assert_eq!(mock.call_count("request"), 2);
+
+    // Native `makeInvoice` is preferred
+    let mock = MockProvider::new().resolve(
+        "getInfo",
+        json(r#"{"node":{},"methods":["makeInvoice","request"]}"#),
+    );
+    let webln = client(&mock).with_make_invoice_fallback(true);
+    webln.make_invoice(&args).await.unwrap();
+    assert_eq!(mock.call_count("makeInvoice"), 1);
+    assert_eq!(mock.call_count("request"), 0);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_payment() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let res = webln.send_payment(INVOICE).await.unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    assert_eq!(
+        mock.last_call("sendPayment")
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some(INVOICE)
+    );
+
+    assert!(matches!(
+        webln.send_payment("").await.unwrap_err().inner(),
+        Error::EmptyInvoice
+    ));
+
+    // BOLT12 offers fail before reaching the provider
+    let err = webln
+        .send_payment("lightning:LNO1PQPS7SJQPGT")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Bolt12Unsupported));
+    assert_eq!(err.code(), ErrorCode::UnsupportedMethod);
+    assert!(webln.send_payment_async("lno1pqps7sjqpgt").await.is_err());
+    assert_eq!(mock.call_count("sendPayment"), 1);
+
+    let webln = client(&MockProvider::new().reject_user("sendPayment"));
+    assert!(matches!(
+        webln.send_payment(INVOICE).await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("sendPayment", json("{}")));
+    assert!(matches!(
+        webln.send_payment(INVOICE).await.unwrap_err().inner(),
+        Error::TypeMismatch(_)
+    ));
+
+    let webln = client(&MockProvider::new().remove("sendPayment"));
+    assert!(
+        matches!(webln.send_payment(INVOICE).await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "sendPayment")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_lightning_payer() {
+    async fn pay<P>(payer: &P) -> Result<String, P::Error>
+    where
+        P: LightningPayer,
+    {
+        payer.pay_invoice(INVOICE).await
+    }
+
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    assert_eq!(pay(&webln).await.unwrap(), PREIMAGE);
+    assert_eq!(
+        mock.last_call("sendPayment")
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some(INVOICE)
+    );
+
+    assert_eq!(webln.pay_keysend(PUBKEY, 21).await.unwrap(), PREIMAGE);
+    let arg = mock.last_call("keysend").unwrap();
+    assert_eq!(
+        get(&arg, "destination").as_string().as_deref(),
+        Some(PUBKEY)
+    );
+    assert_eq!(get(&arg, "amount").as_string().as_deref(), Some("21"));
+
+    assert!(matches!(
+        webln.pay_keysend("not a node", 21).await,
+        Err(Error::NodeId(..))
+    ));
+    assert_eq!(mock.call_count("keysend"), 1);
+}
+
+#[wasm_bindgen_test]
+async fn test_tab() {
+    use webln::{Tab, TabStatus};
+
+    let mock = MockProvider::new().script(
+        "keysend",
+        [
+            Behavior::RejectUser,
+            Behavior::Resolve(json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#))),
+        ],
+    );
+    let clock = FakeClock::new();
+    let webln = client(&mock).with_clock(clock.clone());
+    let changes: Rc<RefCell<Vec<TabStatus>>> = Rc::new(RefCell::new(Vec::new()));
+    let tab = {
+        let changes = changes.clone();
+        Rc::new(
+            Tab::keysend(webln.clone(), NodeId::from_str(PUBKEY).unwrap())
+                .threshold(Amount::from_sats(2))
+                .on_change(move |status| changes.borrow_mut().push(*status)),
+        )
+    };
+
+    // Tallied locally
+    assert!(tab.charge(Amount::from_msat(1500)).await.unwrap().is_none());
+    assert_eq!(tab.pending(), Amount::from_msat(1500));
+    assert_eq!(tab.status().charges, 1);
+    assert_eq!(mock.call_count("keysend"), 0);
+
+    // Threshold reached, but the settlement fails: the amount stays on the tab
+    assert!(tab
+        .charge(Amount::from_msat(1000))
+        .await
+        .unwrap_err()
+        .is_user_rejected());
+    let status = tab.status();
+    assert_eq!(status.pending, Amount::from_msat(2500));
+    assert_eq!(status.settling, Amount::ZERO);
+    assert_eq!(status.failures, 1);
+
+    // Whole sats only
+    let res = tab.settle().await.unwrap().unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    let arg = mock.last_call("keysend").unwrap();
+    assert_eq!(get(&arg, "amount").as_string().as_deref(), Some("2"));
+    let status = tab.status();
+    assert_eq!(status.pending, Amount::from_msat(500));
+    assert_eq!(status.settled, Amount::from_sats(2));
+    assert_eq!((status.charges, status.failures), (0, 0));
+    assert!(tab.settle().await.unwrap().is_none());
+    assert_eq!(mock.call_count("keysend"), 2);
+
+    // Every change was reported, including the settlement in progress
+    let changes = changes.borrow().clone();
+    assert_eq!(changes.len(), 6);
+    assert_eq!(changes[4].settling, Amount::from_sats(2));
+    assert_eq!(changes.last(), Some(&status));
+
+    // Periodic settlement, and a last one when closed
+    let done = Rc::new(Cell::new(false));
+    {
+        let tab = tab.clone();
+        let done = done.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            tab.run(Duration::from_secs(60)).await.unwrap();
+            done.set(true);
+        });
+    }
+    tab.charge(Amount::from_msat(1000)).await.unwrap();
+    clock.advance(Duration::from_secs(60));
+    settle().await;
+    assert_eq!(mock.call_count("keysend"), 3);
+    assert_eq!(tab.status().settled, Amount::from_sats(3));
+    assert_eq!(tab.pending(), Amount::from_msat(500));
+
+    tab.charge(Amount::from_msat(500)).await.unwrap();
+    tab.close();
+    clock.advance(Duration::from_secs(60));
+    settle().await;
+    assert!(done.get());
+    assert_eq!(mock.call_count("keysend"), 4);
+    assert_eq!(tab.pending(), Amount::ZERO);
+
+    // Invoices for another amount are rejected
+    let tab = Tab::invoice(webln, |_| async { Ok(String::from(COFFEE_INVOICE)) });
+    tab.charge(Amount::from_sats(21)).await.unwrap();
+    assert!(matches!(
+        tab.settle().await,
+        Err(Error::AmountMismatch { .. })
+    ));
+    assert_eq!(tab.pending(), Amount::from_sats(21));
+    assert_eq!(mock.call_count("sendPayment"), 0);
+}
+
+#[wasm_bindgen_test]
+async fn test_checkout() {
+    use webln::{Checkout, CheckoutEvent, CheckoutState};
+
+    let ok = json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#));
+    let mock = MockProvider::new().script(
+        "sendPayment",
+        [
+            Behavior::Reject(json(r#"{"name":"RoutingError","message":"no route"}"#)),
+            Behavior::Resolve(ok),
+        ],
+    );
+    let clock = FakeClock::new();
+    let webln = client(&mock).with_clock(clock.clone());
+
+    // Invoice from the backend, paid at the second attempt
+    let events: Rc<RefCell<Vec<CheckoutEvent>>> = Rc::new(RefCell::new(Vec::new()));
+    let checkout = {
+        let events = events.clone();
+        Rc::new(
+            Checkout::from_source(webln.clone(), Amount::from_sats(1), |amount| async move {
+                assert_eq!(amount, Amount::from_sats(1));
+                Ok(String::from(COFFEE_INVOICE))
+            })
+            .retry_delay(Duration::from_secs(5))
+            .on_event(move |event, _| events.borrow_mut().push(event.clone())),
+        )
+    };
+    let result = Rc::new(RefCell::new(None));
+    {
+        let checkout = checkout.clone();
+        let result = result.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            *result.borrow_mut() = Some(checkout.run().await);
+        });
+    }
+    settle().await;
+    assert!(matches!(
+        checkout.state(),
+        CheckoutState::InvoiceReady { attempts: 1, .. }
+    ));
+    clock.advance(Duration::from_secs(5));
+    settle().await;
+    assert_eq!(
+        result.borrow_mut().take(),
+        Some(CheckoutState::Paid {
+            invoice: String::from(COFFEE_INVOICE),
+            preimage: String::from(PREIMAGE),
+        })
+    );
+    assert_eq!(mock.call_count("sendPayment"), 2);
+    assert_eq!(events.borrow().len(), 5);
+    assert!(!checkout.cancel());
+
+    // User rejection cancels, without retrying
+    let mock = MockProvider::new().reject_user("sendPayment");
+    let webln = client(&mock).with_clock(clock.clone());
+    let checkout = Checkout::from_invoice(webln.clone(), COFFEE_INVOICE);
+    assert_eq!(checkout.run().await, CheckoutState::Cancelled);
+    assert_eq!(mock.call_count("sendPayment"), 1);
+
+    // Non-retryable errors fail at the first attempt
+    let mock = MockProvider::new().reject("sendPayment", json(r#"{"message":"bad"}"#));
+    let webln = client(&mock).with_clock(clock.clone());
+    let state = Checkout::from_invoice(webln.clone(), COFFEE_INVOICE)
+        .max_attempts(5)
+        .run()
+        .await;
+    assert!(matches!(
+        state,
+        CheckoutState::Failed {
+            code: ErrorCode::Internal,
+            ..
+        }
+    ));
+    assert_eq!(mock.call_count("sendPayment"), 1);
+
+    // Invoice source failure
+    let state = Checkout::from_source(webln.clone(), Amount::from_sats(1), |_| async {
+        Err(Error::Http(500))
+    })
+    .run()
+    .await;
+    assert!(matches!(
+        state,
+        CheckoutState::Failed {
+            invoice: None,
+            code: ErrorCode::Connection,
+            ..
+        }
+    ));
+
+    // Expired invoices are never paid (the test vector is from 2017)
+    let webln = client(&mock);
+    let checkout = Checkout::from_invoice(webln, COFFEE_INVOICE);
+    assert!(matches!(
+        checkout.run().await,
+        CheckoutState::Expired { .. }
+    ));
+    assert_eq!(mock.call_count("sendPayment"), 1);
+
+    // Paid out of band, reported by the backend
+    let checkout = Checkout::from_invoice(client(&mock), COFFEE_INVOICE);
+    assert!(checkout.handle(CheckoutEvent::PaymentSucceeded {
+        preimage: String::from(PREIMAGE),
+    }));
+    assert!(matches!(checkout.run().await, CheckoutState::Paid { .. }));
+
+    // A payment in flight can't be cancelled
+    let deferred = Deferred::new();
+    let mock = MockProvider::new().defer("sendPayment", &deferred);
+    let checkout = Rc::new(Checkout::from_invoice(
+        client(&mock).with_clock(clock.clone()),
+        COFFEE_INVOICE,
+    ));
+    {
+        let checkout = checkout.clone();
+        let result = result.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            *result.borrow_mut() = Some(checkout.run().await);
+        });
+    }
+    settle().await;
+    assert!(matches!(checkout.state(), CheckoutState::Paying { .. }));
+    assert!(!checkout.cancel());
+    assert!(!checkout.handle(CheckoutEvent::Cancelled));
+    assert!(matches!(checkout.run().await, CheckoutState::Paying { .. }));
+    deferred.resolve_next(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)));
+    settle().await;
+    assert!(matches!(
+        result.borrow_mut().take(),
+        Some(CheckoutState::Paid { .. })
+    ));
+    assert_eq!(mock.call_count("sendPayment"), 1);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_payment_async() {
+    let webln = client(&MockProvider::new());
+    let res = webln.send_payment_async(INVOICE).await.unwrap();
+    assert_eq!(res.payment_hash, None);
+
+    let webln =
+        client(&MockProvider::new().resolve("sendPaymentAsync", json(r#"{"paymentHash":"abcd"}"#)));
+    let res = webln.send_payment_async(INVOICE).await.unwrap();
+    assert_eq!(res.payment_hash.as_deref(), Some("abcd"));
+
+    assert!(matches!(
+        webln.send_payment_async("").await.unwrap_err().inner(),
+        Error::EmptyInvoice
+    ));
+
+    let webln = client(&MockProvider::new().reject_user("sendPaymentAsync"));
+    assert!(matches!(
+        webln.send_payment_async(INVOICE).await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("sendPaymentAsync", JsValue::UNDEFINED));
+    assert!(matches!(
+        webln.send_payment_async(INVOICE).await.unwrap_err().inner(),
+        Error::SomethingGoneWrong
+    ));
+
+    let webln = client(&MockProvider::new().remove("sendPaymentAsync"));
+    assert!(
+        matches!(webln.send_payment_async(INVOICE).await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "sendPaymentAsync")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let res = webln
+        .send_multi_payment(&[INVOICE, "lnbc1expired"])
+        .await
+        .unwrap();
+    assert_eq!(res.payments.len(), 1);
+    assert_eq!(res.payments[0].payment_request, INVOICE);
+    assert_eq!(res.payments[0].preimage, PREIMAGE);
+    assert_eq!(res.errors.len(), 1);
+    assert_eq!(res.errors[0].message, "invoice expired");
+    let args = mock.last_call("sendMultiPayment").unwrap();
+    assert_eq!(js_sys::Array::from(&args).length(), 2);
+
+    assert!(matches!(
+        webln
+            .send_multi_payment(&[INVOICE, ""])
+            .await
+            .unwrap_err()
+            .inner(),
+        Error::EmptyInvoice
+    ));
+    assert!(matches!(
+        webln
+            .send_multi_payment(&[INVOICE, "lno1pqps7sjqpgt"])
+            .await
+            .unwrap_err()
+            .inner(),
+        Error::Bolt12Unsupported
+    ));
+    assert_eq!(mock.call_count("sendMultiPayment"), 1);
+
+    let webln = client(&MockProvider::new().reject_user("sendMultiPayment"));
+    assert!(matches!(
+        webln
+            .send_multi_payment(&[INVOICE])
+            .await
+            .unwrap_err()
+            .inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(
+        &MockProvider::new().resolve("sendMultiPayment", json(r#"{"payments":[{}],"errors":[]}"#)),
+    );
+    assert!(matches!(
+        webln
+            .send_multi_payment(&[INVOICE])
+            .await
+            .unwrap_err()
+            .inner(),
+        Error::TypeMismatch(_)
+    ));
+
+    let webln = client(&MockProvider::new().remove("sendMultiPayment"));
+    assert!(
+        matches!(webln.send_multi_payment(&[INVOICE]).await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "sendMultiPayment")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment_fallback() {
+    let mock = MockProvider::new()
+        .remove("sendMultiPayment")
+        .respond("sendPayment", |invoice| {
+            if invoice.as_string().as_deref() == Some(INVOICE) {
+                Promise::resolve(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)))
+            } else {
+                Promise::reject(&js_sys::Error::new("invoice expired").into())
+            }
+        });
+    let webln = client(&mock);
+    let res = webln
+        .send_multi_payment(&[INVOICE, "lnbc1expired"])
+        .await
+        .unwrap();
+    assert_eq!(mock.call_count("sendPayment"), 2);
+    assert_eq!(res.payments.len(), 1);
+    assert_eq!(res.payments[0].payment_request, INVOICE);
+    assert_eq!(res.payments[0].preimage, PREIMAGE);
+    assert_eq!(res.errors.len(), 1);
+    assert_eq!(res.errors[0].payment_request, "lnbc1expired");
+    assert!(res.errors[0].message.contains("invoice expired"));
+
+    // Checked before paying any invoice
+    for invoice in ["", "lno1pqps7sjqpgt"] {
+        assert!(webln.send_multi_payment(&[INVOICE, invoice]).await.is_err());
+    }
+    assert_eq!(mock.call_count("sendPayment"), 2);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment_costs() {
+    let payments = format!(
+        r#"{{"payments":[
+            {{"paymentRequest":"{INVOICE}","preimage":"{PREIMAGE}","route":{{"total_amt":1012,"total_fees":12}}}},
+            {{"paymentRequest":"lnbc1other","preimage":"{PREIMAGE}","feeMsat":"3500"}},
+            {{"paymentRequest":"lnbc1plain","preimage":"{PREIMAGE}"}}
+        ],"errors":[]}}"#
+    );
+    let webln = client(&MockProvider::new().resolve("sendMultiPayment", json(&payments)));
+    let res = webln.send_multi_payment(&[INVOICE]).await.unwrap();
+    assert_eq!(res.payments[0].fee, Some(Amount::from_sats(12)));
+    let route = res.payments[0].route.unwrap();
+    assert_eq!(route.total_amount, Some(Amount::from_sats(1012)));
+    assert_eq!(route.total_fees, Some(Amount::from_sats(12)));
+    assert_eq!(res.payments[1].fee, Some(Amount::from_msat(3500)));
+    assert_eq!(res.payments[1].route, None);
+    assert_eq!(res.payments[2].fee, None);
+    assert_eq!(res.payments[2].route, None);
+
+    // Fallback: read from the `sendPayment` results
+    let webln = client(&MockProvider::new().remove("sendMultiPayment").resolve(
+        "sendPayment",
+        json(&format!(
+            r#"{{"preimage":"{PREIMAGE}","route":{{"total_amt_msat":"1001500","total_fees_msat":"1500"}}}}"#
+        )),
+    ));
+    let res = webln.send_multi_payment(&[INVOICE]).await.unwrap();
+    assert_eq!(res.payments[0].fee, Some(Amount::from_msat(1500)));
+    assert_eq!(
+        res.payments[0].route.unwrap().total_amount,
+        Some(Amount::from_msat(1_001_500))
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment_concurrency() {
+    let in_flight = Rc::new(Cell::new(0));
+    let peak = Rc::new(Cell::new(0));
+    let mock = {
+        let in_flight = in_flight.clone();
+        let peak = peak.clone();
+        MockProvider::new()
+            .remove("sendMultiPayment")
+            .respond("sendPayment", move |_| {
+                in_flight.set(in_flight.get() + 1);
+                peak.set(peak.get().max(in_flight.get()));
+                let in_flight = in_flight.clone();
+                resolve_after(
+                    10,
+                    json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)),
+                    move || in_flight.set(in_flight.get() - 1),
+                )
+            })
+    };
+    let webln = client(&mock);
+    let invoices: Vec<String> = (0..7).map(|i| format!("lnbc{i}")).collect();
+    let res = webln
+        .send_multi_payment_with_options(&invoices, &MultiPaymentOptions::new().concurrency(3))
+        .await
+        .unwrap();
+    assert_eq!(peak.get(), 3);
+    assert_eq!(mock.call_count("sendPayment"), 7);
+    let paid: Vec<&str> = res
+        .payments
+        .iter()
+        .map(|p| p.payment_request.as_str())
+        .collect();
+    assert_eq!(paid, invoices);
+
+    // Default: one by one
+    peak.set(0);
+    webln.send_multi_payment(&invoices).await.unwrap();
+    assert_eq!(peak.get(), 1);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment_timeout() {
+    let mock = MockProvider::new()
+        .remove("sendMultiPayment")
+        .respond("sendPayment", |invoice| {
+            if invoice.as_string().as_deref() == Some(INVOICE) {
+                Promise::resolve(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)))
+            } else {
+                // Hung payment
+                Promise::new(&mut |_, _| ())
+            }
+        });
+    let webln = client(&mock);
+    let options = MultiPaymentOptions::new()
+        .concurrency(2)
+        .timeout(Duration::from_millis(50));
+    let res = webln
+        .send_multi_payment_with_options(&["lnbc1hung", INVOICE, "lnbc2hung"], &options)
+        .await
+        .unwrap();
+    assert_eq!(res.payments.len(), 1);
+    assert_eq!(res.payments[0].payment_request, INVOICE);
+    assert_eq!(res.errors.len(), 2);
+    assert_eq!(res.errors[0].payment_request, "lnbc1hung");
+    assert_eq!(res.errors[0].message, "Timeout");
+    assert_eq!(res.errors[1].payment_request, "lnbc2hung");
+    assert_eq!(Error::Timeout.code(), ErrorCode::Connection);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment_rejected() {
+    let mock = MockProvider::new()
+        .remove("sendMultiPayment")
+        .reject_user("sendPayment");
+    let progress = Rc::new(Cell::new(0));
+    let options = {
+        let progress = progress.clone();
+        MultiPaymentOptions::new().on_progress(move |_| progress.set(progress.get() + 1))
+    };
+    let res = client(&mock)
+        .send_multi_payment_with_options(&[INVOICE, "lnbc2mock", "lnbc3mock"], &options)
+        .await
+        .unwrap();
+    assert_eq!(mock.call_count("sendPayment"), 1);
+    assert_eq!(res.errors.len(), 3);
+    assert_eq!(res.errors[0].payment_request, INVOICE);
+    assert_eq!(res.errors[2].payment_request, "lnbc3mock");
+    assert!(res.errors[1].message.starts_with("Not attempted"));
+    assert_eq!(progress.get(), 3);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment_dropped() {
+    use core::future::{self, Future};
+    use core::pin::Pin;
+    use core::task::Poll;
+
+    let deferred = Deferred::new();
+    let mock = MockProvider::new()
+        .remove("sendMultiPayment")
+        .defer("sendPayment", &deferred);
+    let webln = client(&mock);
+
+    // Task whose future can be dropped from outside (i.e. on abort)
+    type Task = Rc<RefCell<Option<Pin<Box<dyn Future<Output = ()>>>>>>;
+    let task: Task = {
+        let webln = webln.clone();
+        Rc::new(RefCell::new(Some(Box::pin(async move {
+            let _ = webln
+                .send_multi_payment(&[INVOICE, "lnbc2mock", "lnbc3mock"])
+                .await;
+        }))))
+    };
+    {
+        let task = task.clone();
+        wasm_bindgen_futures::spawn_local(future::poll_fn(move |cx| {
+            match task.borrow_mut().as_mut() {
+                Some(future) => future.as_mut().poll(cx),
+                None => Poll::Ready(()),
+            }
+        }));
+    }
+    settle().await;
+    assert_eq!(deferred.pending(), 1);
+
+    // The remaining invoices aren't paid once the call is dropped
+    task.borrow_mut().take();
+    deferred.resolve_next(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)));
+    settle().await;
+    assert_eq!(mock.call_count("sendPayment"), 1);
+}
+
+#[wasm_bindgen_test]
+async fn test_scripted_provider() {
+    let ok = json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#));
+    let mock = MockProvider::new().script(
+        "sendPayment",
+        [
+            Behavior::RejectUser,
+            Behavior::Reject(json(r#"{"code":"ROUTE_NOT_FOUND","message":"no route"}"#)),
+            Behavior::ResolveAfter(10, ok),
+        ],
+    );
+    let webln = client(&mock);
+    assert!(matches!(
+        webln.send_payment(INVOICE).await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+    assert!(matches!(
+        webln.send_payment(INVOICE).await.unwrap_err().inner(),
+        Error::Provider(e) if e.code.as_deref() == Some("ROUTE_NOT_FOUND") && e.message == "no route"
+    ));
+    for _ in 0..2 {
+        assert_eq!(
+            webln.send_payment(INVOICE).await.unwrap().preimage,
+            PREIMAGE
+        );
+    }
+    assert_eq!(mock.call_count("sendPayment"), 4);
+
+    // Hung wallet: only the timeout settles the call
+    let mock = MockProvider::new()
+        .remove("sendMultiPayment")
+        .never("sendPayment");
+    let webln = client(&mock);
+    let options = MultiPaymentOptions::new().timeout(Duration::from_millis(20));
+    let res = webln
+        .send_multi_payment_with_options(&[INVOICE], &options)
+        .await
+        .unwrap();
+    assert_eq!(res.errors[0].message, "Timeout");
+}
+
+#[wasm_bindgen_test]
+async fn test_fake_timers() {
+    let deferred = Deferred::new();
+    let mock = MockProvider::new()
+        .remove("sendMultiPayment")
+        .defer("sendPayment", &deferred);
+    let clock = FakeClock::new();
+    let webln = client(&mock).with_clock(clock.clone());
+    let options = MultiPaymentOptions::new()
+        .concurrency(2)
+        .timeout(Duration::from_secs(30));
+
+    let result = Rc::new(RefCell::new(None));
+    {
+        let result = result.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let res = webln
+                .send_multi_payment_with_options(&[INVOICE, "lnbc1hung"], &options)
+                .await;
+            *result.borrow_mut() = Some(res);
+        });
+    }
+    settle().await;
+    assert_eq!(deferred.pending(), 2);
+    assert_eq!(clock.pending_timers(), 2);
+
+    // First payment settles, the second one hangs until the deadline
+    deferred.resolve_next(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)));
+    settle().await;
+    clock.advance(Duration::from_secs(29));
+    settle().await;
+    assert!(result.borrow().is_none());
+
+    clock.advance(Duration::from_secs(1));
+    settle().await;
+    let res = result.borrow_mut().take().unwrap().unwrap();
+    assert_eq!(res.payments.len(), 1);
+    assert_eq!(res.payments[0].payment_request, INVOICE);
+    assert_eq!(res.errors.len(), 1);
+    assert_eq!(res.errors[0].message, "Timeout");
+    assert_eq!(clock.sleeps(), vec![Duration::from_secs(30); 2]);
+}
+
+#[wasm_bindgen_test]
+async fn test_prompt_limit() {
+    let mock = MockProvider::new();
+    let clock = FakeClock::new();
+    let webln = client(&mock)
+        .with_clock(clock.clone())
+        .with_prompt_limit(PromptLimit::new(2, Duration::from_secs(60)));
+    assert_eq!(webln.prompt_limit().unwrap().max_prompts, 2);
+
+    webln.enable().await.unwrap();
+    clock.advance(Duration::from_secs(20));
+    webln.send_payment(INVOICE).await.unwrap();
+
+    // Not prompting: not limited
+    webln.get_info().await.unwrap();
+
+    let err = webln.send_payment(INVOICE).await.unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::RateLimited { retry_after } if *retry_after == Duration::from_secs(40)
+    ));
+    assert_eq!(mock.call_count("sendPayment"), 1);
+
+    // The `enable` call left the window
+    clock.advance(Duration::from_secs(40));
+    webln.send_payment(INVOICE).await.unwrap();
+    assert_eq!(mock.call_count("sendPayment"), 2);
+    let err = webln.enable().await.unwrap_err();
+    assert!(matches!(err.inner(), Error::RateLimited { .. }));
+
+    // The `request` fallbacks prompt too
+    let mock = MockProvider::new()
+        .remove("keysend")
+        .resolve(
+            "getInfo",
+            json(r#"{"node":{},"methods":["getInfo","sendPayment","request"]}"#),
+        )
+        .resolve("request", json(r#"{"result":{"status":"SUCCEEDED"}}"#));
+    let webln = client(&mock)
+        .with_clock(clock.clone())
+        .with_keysend_fallback(true)
+        .with_prompt_limit(PromptLimit::new(1, Duration::from_secs(60)));
+    webln.keysend(&keysend_args()).await.unwrap();
+    let err = webln.keysend(&keysend_args()).await.unwrap_err();
+    assert!(matches!(err.inner(), Error::RateLimited { .. }));
+    assert_eq!(mock.call_count("request"), 1);
+
+    // No prompt allowed
+    let webln = client(&MockProvider::new())
+        .with_prompt_limit(PromptLimit::new(0, Duration::from_secs(60)));
+    let err = webln.send_payment(INVOICE).await.unwrap_err();
+    assert!(matches!(err.inner(), Error::RateLimited { .. }));
+
+    // `signMessage` and `request` prompt too, except the known lookups
+    let mock = MockProvider::new();
+    let webln = client(&mock).with_prompt_limit(PromptLimit::new(2, Duration::from_secs(60)));
+    webln.sign_message("hello").await.unwrap();
+    webln.request("getinfo", None).await.unwrap();
+    webln.request("LookupInvoice", None).await.unwrap();
+    webln.request("sendcoins", None).await.unwrap();
+    let err = webln.request("sendcoins", None).await.unwrap_err();
+    assert!(matches!(err.inner(), Error::RateLimited { .. }));
+    let err = webln.sign_message("hello").await.unwrap_err();
+    assert!(matches!(err.inner(), Error::RateLimited { .. }));
+    webln.request("listchannels", None).await.unwrap();
+    assert_eq!(mock.call_count("request"), 4);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_payment_coalesced() {
+    let deferred = Deferred::new();
+    let mock = MockProvider::new().defer("sendPayment", &deferred);
+    let webln = client(&mock);
+
+    let results = Rc::new(RefCell::new(Vec::new()));
+    let pay_twice = || {
+        for _ in 0..2 {
+            let webln = webln.clone();
+            let results = results.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = webln.send_payment(INVOICE).await;
+                results.borrow_mut().push(res);
+            });
+        }
+    };
+
+    pay_twice();
+    settle().await;
+    assert_eq!(mock.call_count("sendPayment"), 1);
+    deferred.resolve_next(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)));
+    settle().await;
+    assert_eq!(results.borrow().len(), 2);
+    for res in results.borrow_mut().drain(..) {
+        assert_eq!(res.unwrap().preimage, PREIMAGE);
+    }
+
+    // Settled calls aren't reused, and rejections are shared too
+    pay_twice();
+    settle().await;
+    assert_eq!(mock.call_count("sendPayment"), 2);
+    deferred.reject_next(&js_sys::Error::new("User rejected").into());
+    settle().await;
+    assert_eq!(results.borrow().len(), 2);
+    for res in results.borrow_mut().drain(..) {
+        assert!(res.unwrap_err().is_user_rejected());
+    }
+
+    // A timed out call isn't shared with the retries
+    let mock = MockProvider::new().remove("sendMultiPayment").script(
+        "sendPayment",
+        [
+            Behavior::Never,
+            Behavior::Resolve(json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#))),
+        ],
+    );
+    let webln = client(&mock);
+    let options = MultiPaymentOptions::new().timeout(Duration::from_millis(20));
+    let res = webln
+        .send_multi_payment_with_options(&[INVOICE], &options)
+        .await
+        .unwrap();
+    assert_eq!(res.errors[0].message, "Timeout");
+    assert_eq!(
+        webln.send_payment(INVOICE).await.unwrap().preimage,
+        PREIMAGE
+    );
+    assert_eq!(mock.call_count("sendPayment"), 2);
+}
+
+#[wasm_bindgen_test]
+async fn test_idempotency_keys() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+
+    let res = webln
+        .send_payment_idempotent("order-1", INVOICE)
+        .await
+        .unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    let res = webln
+        .clone()
+        .send_payment_idempotent("order-1", INVOICE)
+        .await
+        .unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    assert_eq!(mock.call_count("sendPayment"), 1);
+
+    let err = webln
+        .send_payment_idempotent("order-1", "lnbc1other")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::IdempotencyKeyReused(key) if key == "order-1"));
+
+    let args = keysend_args();
+    webln.keysend_idempotent("tip-1", &args).await.unwrap();
+    webln.keysend_idempotent("tip-1", &args).await.unwrap();
+    assert_eq!(mock.call_count("keysend"), 1);
+
+    webln.forget_idempotency_key("order-1");
+    webln
+        .send_payment_idempotent("order-1", INVOICE)
+        .await
+        .unwrap();
+    assert_eq!(mock.call_count("sendPayment"), 2);
+
+    // Failures aren't recorded
+    let mock = MockProvider::new().reject_user("sendPayment");
+    let webln = client(&mock);
+    for _ in 0..2 {
+        let err = webln
+            .send_payment_idempotent("order-2", INVOICE)
+            .await
+            .unwrap_err();
+        assert!(err.is_user_rejected());
+    }
+    assert_eq!(mock.call_count("sendPayment"), 2);
+
+    // Concurrent calls join the pending payment
+    let deferred = Deferred::new();
+    let mock = MockProvider::new().defer("keysend", &deferred);
+    let webln = client(&mock);
+    let results = Rc::new(RefCell::new(Vec::new()));
+    let pay_twice = || {
+        for _ in 0..2 {
+            let webln = webln.clone();
+            let results = results.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = webln.keysend_idempotent("tip-2", &keysend_args()).await;
+                results.borrow_mut().push(res);
+            });
+        }
+    };
+    pay_twice();
+    settle().await;
+    assert_eq!(mock.call_count("keysend"), 1);
+    deferred.resolve_next(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)));
+    settle().await;
+    for res in results.borrow_mut().drain(..) {
+        assert_eq!(res.unwrap().preimage, PREIMAGE);
+    }
+
+    // A failed payment releases the key: the waiting call pays
+    webln.forget_idempotency_key("tip-2");
+    pay_twice();
+    settle().await;
+    assert_eq!(mock.call_count("keysend"), 2);
+    deferred.reject_next(&js_sys::Error::new("User rejected").into());
+    settle().await;
+    assert_eq!(mock.call_count("keysend"), 3);
+    deferred.resolve_next(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)));
+    settle().await;
+    let results = results.borrow();
+    assert!(results[0].as_ref().unwrap_err().is_user_rejected());
+    assert_eq!(results[1].as_ref().unwrap().preimage, PREIMAGE);
+}
+
+#[wasm_bindgen_test]
+async fn test_paid_invoice_memory() {
+    const KEY: &str = "webln-test-paid";
+
+    let mock = MockProvider::new();
+    let webln = client(&mock).with_paid_invoice_memory();
+    assert!(!webln.is_invoice_paid(COFFEE_INVOICE));
+    webln.send_payment(COFFEE_INVOICE).await.unwrap();
+    assert!(webln.is_invoice_paid(COFFEE_INVOICE));
+
+    let err = webln.send_payment(COFFEE_INVOICE).await.unwrap_err();
+    assert!(matches!(err, Error::AlreadyPaid(hash) if hash.starts_with("0001020304")));
+    let err = webln.send_payment_async(COFFEE_INVOICE).await.unwrap_err();
+    assert!(matches!(err, Error::AlreadyPaid(..)));
+    assert_eq!(mock.call_count("sendPayment"), 1);
+
+    // Multi-payments too
+    let res = webln
+        .send_multi_payment(&[COFFEE_INVOICE, INVOICE])
+        .await
+        .unwrap();
+    assert_eq!(res.errors.last().unwrap().payment_request, COFFEE_INVOICE);
+    let args = mock.last_call("sendMultiPayment").unwrap();
+    assert_eq!(js_sys::Array::from(&args).length(), 1);
+    let multi = MockProvider::new().resolve(
+        "sendMultiPayment",
+        json(&format!(
+            r#"{{"payments":[{{"paymentRequest":"{COFFEE_INVOICE}","preimage":"{PREIMAGE}"}}],"errors":[]}}"#
+        )),
+    );
+    let other = client(&multi).with_paid_invoice_memory();
+    other.send_multi_payment(&[COFFEE_INVOICE]).await.unwrap();
+    assert!(other.is_invoice_paid(COFFEE_INVOICE));
+
+    // Async payments are only paid once settled
+    const HASH: &str = "0001020304050607080900010203040506070809000102030405060708090102";
+    let tracked = MockProvider::new().resolve(
+        "request",
+        json(&format!(
+            r#"{{"payments":[{{"payment_hash":"{HASH}","status":"SUCCEEDED"}}]}}"#
+        )),
+    );
+    let other = client(&tracked).with_paid_invoice_memory();
+    other.send_payment_async(COFFEE_INVOICE).await.unwrap();
+    assert!(!other.is_invoice_paid(COFFEE_INVOICE));
+    other.send_payment_async(COFFEE_INVOICE).await.unwrap();
+    other.track_payment(HASH).settled().await.unwrap();
+    assert!(other.is_invoice_paid(COFFEE_INVOICE));
+
+    // Not a BOLT11 invoice: not tracked
+    webln.send_payment(INVOICE).await.unwrap();
+    webln.send_payment(INVOICE).await.unwrap();
+
+    webln.forget_paid_invoices();
+    webln.send_payment(COFFEE_INVOICE).await.unwrap();
+    assert_eq!(mock.call_count("sendPayment"), 4);
+
+    // Disabled by default
+    let webln = client(&mock);
+    webln.send_payment(COFFEE_INVOICE).await.unwrap();
+    assert!(!webln.is_invoice_paid(COFFEE_INVOICE));
+
+    // Persisted
+    let webln = client(&mock).with_persisted_paid_invoice_memory(KEY);
+    webln.send_payment(COFFEE_INVOICE).await.unwrap();
+    let webln = client(&mock).with_persisted_paid_invoice_memory(KEY);
+    assert!(webln.is_invoice_paid(COFFEE_INVOICE));
+    webln.forget_paid_invoices();
+    let webln = client(&mock).with_persisted_paid_invoice_memory(KEY);
+    assert!(!webln.is_invoice_paid(COFFEE_INVOICE));
+}
+
+#[wasm_bindgen_test]
+async fn test_max_amount() {
+    let mock = MockProvider::new();
+    let webln = client(&mock).with_max_amount(Amount::from_sats(1_000));
+    assert_eq!(webln.max_amount(), Some(Amount::from_sats(1_000)));
+
+    // 250000 sats invoice
+    let err = webln.send_payment(COFFEE_INVOICE).await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::AmountExceedsLimit { amount, limit }
+            if amount.sats() == 250_000 && limit.sats() == 1_000
+    ));
+    let err = webln
+        .send_multi_payment(&[COFFEE_INVOICE, INVOICE])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::AmountExceedsLimit { .. }));
+    let err = webln
+        .send_payment_with_amount(ZERO_AMOUNT_INVOICE, Amount::from_sats(1_001))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::AmountExceedsLimit { .. }));
+    assert_eq!(mock.call_count("sendPayment"), 0);
+    assert_eq!(mock.call_count("sendMultiPayment"), 0);
+    assert_eq!(mock.call_count("request"), 0);
+
+    let mut args = keysend_args();
+    args.amount = 1_001;
+    let err = webln.keysend(&args).await.unwrap_err();
+    assert!(matches!(err, Error::AmountExceedsLimit { .. }));
+    assert_eq!(mock.call_count("keysend"), 0);
+
+    // Within the limit
+    args.amount = 1_000;
+    webln.keysend(&args).await.unwrap();
+    assert_eq!(mock.call_count("keysend"), 1);
+
+    // Unknown amount
+    let err = webln.send_payment(INVOICE).await.unwrap_err();
+    assert!(matches!(err, Error::Invoice(..)));
+    let err = webln.send_payment(ZERO_AMOUNT_INVOICE).await.unwrap_err();
+    assert!(matches!(err, Error::UnknownAmount));
+    let err = webln
+        .send_payment_async(ZERO_AMOUNT_INVOICE)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::UnknownAmount));
+    assert_eq!(mock.call_count("sendPayment"), 0);
+    assert_eq!(mock.call_count("sendPaymentAsync"), 0);
+}
+
+#[wasm_bindgen_test]
+async fn test_keysend_filter() {
+    const OTHER: &str = "02f6725f9c1c40333b67faea92fd211c183050f28df32cac3f9d69685fe9665432";
+
+    let pubkey = NodeId::from_str(PUBKEY).unwrap();
+    let other = NodeId::from_str(OTHER).unwrap();
+    let mock = MockProvider::new();
+
+    let webln = client(&mock).with_keysend_filter(DestinationFilter::allow([pubkey]));
+    assert!(webln.keysend_filter().unwrap().is_allowed(&pubkey));
+    webln.keysend(&keysend_args()).await.unwrap();
+    let err = webln
+        .keysend(&KeysendArgs::new(other, 21))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::DestinationNotAllowed(node_id) if node_id == other));
+    assert_eq!(mock.call_count("keysend"), 1);
+
+    let webln = client(&mock).with_keysend_filter(DestinationFilter::deny([pubkey]));
+    let err = webln.keysend(&keysend_args()).await.unwrap_err();
+    assert!(matches!(err, Error::DestinationNotAllowed(..)));
+    webln.keysend(&KeysendArgs::new(other, 21)).await.unwrap();
+    assert_eq!(mock.call_count("keysend"), 2);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment_progress() {
+    let mock = MockProvider::new()
+        .remove("sendMultiPayment")
+        .respond("sendPayment", |invoice| {
+            if invoice.as_string().as_deref() == Some(INVOICE) {
+                Promise::resolve(&json(&format!(r#"{{"preimage":"{PREIMAGE}"}}"#)))
+            } else {
+                Promise::reject(&js_sys::Error::new("invoice expired").into())
+            }
+        });
+    let webln = client(&mock);
+    let progress = Rc::new(RefCell::new(Vec::new()));
+    let options = {
+        let progress = progress.clone();
+        MultiPaymentOptions::new().on_progress(move |p: &MultiPaymentProgress| {
+            progress
+                .borrow_mut()
+                .push((p.completed, p.total, p.result.is_ok()));
+        })
+    };
+    webln
+        .send_multi_payment_with_options(&[INVOICE, "lnbc1expired", INVOICE], &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        *progress.borrow(),
+        [(1, 3, true), (2, 3, false), (3, 3, true)]
+    );
+
+    // Native `sendMultiPayment`: reported at the end
+    progress.borrow_mut().clear();
+    let webln = client(&MockProvider::new());
+    let res = webln
+        .send_multi_payment_with_options(&[INVOICE], &options)
+        .await
+        .unwrap();
+    let reported = progress.borrow().len();
+    assert_eq!(reported, res.payments.len() + res.errors.len());
+}
+
+#[wasm_bindgen_test]
+async fn test_send_multi_payment_duplicates() {
+    let mock = MockProvider::new().remove("sendMultiPayment");
+    let webln = client(&mock);
+    let upper = INVOICE.to_uppercase();
+    let res = webln
+        .send_multi_payment(&[INVOICE, "lnbc1other", &upper])
+        .await
+        .unwrap();
+    assert_eq!(mock.call_count("sendPayment"), 2);
+    assert_eq!(res.payments.len(), 2);
+    assert_eq!(res.duplicates, [upper.as_str()]);
+
+    // Opt-out
+    let options = MultiPaymentOptions::new().deduplicate(false);
+    let res = webln
+        .send_multi_payment_with_options(&[INVOICE, INVOICE], &options)
+        .await
+        .unwrap();
+    assert_eq!(mock.call_count("sendPayment"), 4);
+    assert!(res.duplicates.is_empty());
+
+    // Native `sendMultiPayment` gets the deduplicated invoices
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let res = webln.send_multi_payment(&[INVOICE, INVOICE]).await.unwrap();
+    let args = mock.last_call_args("sendMultiPayment").unwrap();
+    assert_eq!(js_sys::Array::from(&args[0]).length(), 1);
+    assert_eq!(res.duplicates, [INVOICE]);
+}
+
+#[wasm_bindgen_test]
+fn test_payment_events() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+
+    let successes = Rc::new(RefCell::new(Vec::new()));
+    let _succeeded = webln
+        .on_payment_succeeded({
+            let successes = successes.clone();
+            move |success| successes.borrow_mut().push(success)
+        })
+        .unwrap();
+    let failures = Rc::new(RefCell::new(Vec::new()));
+    let _failed = webln
+        .on_payment_failed({
+            let failures = failures.clone();
+            move |failure| failures.borrow_mut().push(failure)
+        })
+        .unwrap();
+    assert_eq!(mock.listener_count("sendPayment:success"), 1);
+    assert_eq!(mock.listener_count("sendPayment:failed"), 1);
+
+    mock.emit(
+        "sendPayment:success",
+        &json(&format!(
+            r#"{{"preimage":"{PREIMAGE}","paymentHash":"abcd","paymentRequest":"{INVOICE}"}}"#
+        )),
+    );
+    let success = successes.borrow()[0].clone();
+    assert_eq!(success.preimage.as_deref(), Some(PREIMAGE));
+    assert_eq!(success.payment_hash.as_deref(), Some("abcd"));
+    assert_eq!(success.payment_request.as_deref(), Some(INVOICE));
+
+    mock.emit(
+        "sendPayment:failed",
+        &json(&format!(
+            r#"{{"paymentRequest":"{INVOICE}","error":{{"message":"no route"}}}}"#
+        )),
+    );
+    mock.emit("sendPayment:failed", &JsValue::from_str("expired"));
+    let failures = failures.borrow();
+    assert_eq!(failures[0].payment_request.as_deref(), Some(INVOICE));
+    assert_eq!(failures[0].error.as_deref(), Some("no route"));
+    assert_eq!(failures[1].error.as_deref(), Some("expired"));
+    assert_eq!(failures[1].payment_request, None);
+}
+
+#[wasm_bindgen_test]
+async fn test_response_with_raw() {
+    let mock = MockProvider::new()
+        .resolve(
+            "sendPayment",
+            json(&format!(
+                r#"{{"preimage":"{PREIMAGE}","paymentHash":"abcd","route":{{"total_fees":2}}}}"#
+            )),
+        )
+        .resolve(
+            "getBalance",
+            json(r#"{"balance":21000,"currency":"sats","max_amount":1000}"#),
+        );
+    let webln = client(&mock);
+
+    let res = webln.send_payment_with_raw(INVOICE).await.unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    assert_eq!(
+        get(&res.raw, "paymentHash").as_string().as_deref(),
+        Some("abcd")
+    );
+    assert_eq!(
+        get(&get(&res.raw, "route"), "total_fees").as_f64(),
+        Some(2.0)
+    );
+
+    let res = webln.get_balance_with_raw().await.unwrap();
+    assert_eq!(res.balance, 21000.0);
+    assert_eq!(get(&res.raw, "max_amount").as_f64(), Some(1000.0));
+    assert_eq!(webln.cached_balance(), Some(res.into_inner()));
+
+    let res = webln.get_info_with_raw().await.unwrap();
+    assert_eq!(
+        get(&get(&res.raw, "node"), "alias"),
+        JsValue::from_str("mock")
+    );
+
+    let args = RequestInvoiceArgs::new().amount(1000);
+    let res = webln.make_invoice_with_raw(&args).await.unwrap();
+    assert_eq!(res.invoice, INVOICE);
+    assert_eq!(
+        get(&res.raw, "paymentRequest").as_string().as_deref(),
+        Some(INVOICE)
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_ensure_enabled() {
+    let mock = MockProvider::new().resolve("isEnabled", JsValue::FALSE);
+    let webln = client(&mock);
+    assert_eq!(webln.cached_enabled(), None);
+    webln.ensure_enabled().await.unwrap();
+    assert_eq!(webln.cached_enabled(), Some(true));
+    assert_eq!(mock.call_count("enable"), 1);
+
+    // Cached
+    webln.ensure_enabled().await.unwrap();
+    assert_eq!(mock.call_count("isEnabled"), 1);
+    assert_eq!(mock.call_count("enable"), 1);
+
+    // Already enabled: no popup
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    webln.ensure_enabled().await.unwrap();
+    assert_eq!(mock.call_count("enable"), 0);
+}
+
+#[wasm_bindgen_test]
+async fn test_on_account_changed() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    webln.ensure_enabled().await.unwrap();
+    webln.get_info().await.unwrap();
+    webln.get_balance().await.unwrap();
+    assert!(webln.cached_info().is_some());
+    assert_eq!(webln.cached_balance().unwrap().balance, 21000.0);
+
+    let changes = Rc::new(Cell::new(0));
+    let subscription = webln
+        .on_account_changed(false, {
+            let changes = changes.clone();
+            move |res| {
+                assert!(res.is_ok());
+                changes.set(changes.get() + 1);
+            }
+        })
+        .unwrap();
+    mock.emit("accountChanged", &JsValue::UNDEFINED);
+    assert_eq!(changes.get(), 1);
+    assert_eq!(webln.cached_enabled(), None);
+    assert!(webln.cached_info().is_none());
+    assert!(webln.cached_balance().is_none());
+    drop(subscription);
+
+    // Re-enable
+    let _subscription = webln
+        .on_account_changed(true, {
+            let changes = changes.clone();
+            move |res| {
+                assert!(res.is_ok());
+                changes.set(changes.get() + 1);
+            }
+        })
+        .unwrap();
+    mock.emit("accountChanged", &JsValue::UNDEFINED);
+    wasm_bindgen_futures::JsFuture::from(resolve_after(10, JsValue::UNDEFINED, || ()))
+        .await
+        .unwrap();
+    assert_eq!(changes.get(), 2);
+    assert_eq!(webln.cached_enabled(), Some(true));
+    assert_eq!(mock.call_count("isEnabled"), 2);
+}
+
+#[wasm_bindgen_test]
+async fn test_balance_watcher() {
+    let balances = Rc::new(RefCell::new(vec![100, 100, 200]));
+    let mock = {
+        let balances = balances.clone();
+        MockProvider::new().respond("getBalance", move |_| {
+            let mut balances = balances.borrow_mut();
+            let balance = if balances.len() > 1 {
+                balances.remove(0)
+            } else {
+                balances[0]
+            };
+            Promise::resolve(&json(&format!(r#"{{"balance":{balance}}}"#)))
+        })
+    };
+    let webln = client(&mock);
+    let mut watcher = webln.watch_balance(Duration::from_millis(10));
+    assert_eq!(watcher.next().await.unwrap().balance, 100.0);
+    assert_eq!(mock.call_count("getBalance"), 1);
+
+    // Unchanged balances are skipped
+    assert_eq!(watcher.next().await.unwrap().balance, 200.0);
+    assert_eq!(mock.call_count("getBalance"), 3);
+    assert_eq!(watcher.last().unwrap().balance, 200.0);
+}
+
+/// Clock whose sleeps resolve immediately, advancing its time
+#[derive(Debug, Default)]
+struct InstantClock {
+    now: Cell<Duration>,
+    sleeps: RefCell<Vec<Duration>>,
+}
+
+impl Clock for InstantClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        self.now.set(self.now.get() + duration);
+        self.sleeps.borrow_mut().push(duration);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_clock() {
+    let balances = Rc::new(RefCell::new(vec![100, 200]));
+    let mock = {
+        let balances = balances.clone();
+        MockProvider::new().respond("getBalance", move |_| {
+            let balance = balances.borrow_mut().remove(0);
+            Promise::resolve(&json(&format!(r#"{{"balance":{balance}}}"#)))
+        })
+    };
+    let clock = Rc::new(InstantClock::default());
+    let webln = client(&mock).with_clock(clock.clone());
+    assert_eq!(webln.clock().now(), Duration::ZERO);
+
+    // An hour-long interval elapses instantly
+    let mut watcher = webln.watch_balance(Duration::from_secs(3600));
+    assert_eq!(watcher.next().await.unwrap().balance, 100.0);
+    assert!(clock.sleeps.borrow().is_empty());
+    assert_eq!(watcher.next().await.unwrap().balance, 200.0);
+    assert_eq!(*clock.sleeps.borrow(), vec![Duration::from_secs(3600)]);
+    assert_eq!(webln.clock().now(), Duration::from_secs(3600));
+}
+
+#[cfg(feature = "rates")]
+#[wasm_bindgen_test]
+async fn test_rates() {
+    use webln::{Amount, BalanceResponse, Deserialize, RateFuture, RateSource, Rates};
+
+    struct FixedRates(Cell<usize>);
+
+    impl RateSource for FixedRates {
+        fn btc_price<'a>(&'a self, currency: &'a str) -> RateFuture<'a> {
+            self.0.set(self.0.get() + 1);
+            let price = match currency {
+                "USD" => Ok(50_000.0),
+                "EUR" => Ok(40_000.0),
+                _ => Err(Error::RateUnavailable(currency.to_string())),
+            };
+            Box::pin(async move { price })
+        }
+    }
+
+    let rates = Rates::new(FixedRates(Cell::new(0)));
+    let usd = rates
+        .to_fiat(Amount::from_sats(21_000), "usd")
+        .await
+        .unwrap();
+    assert_eq!(usd, 10.5);
+    rates.to_fiat(Amount::from_sats(1), "USD").await.unwrap();
+    assert_eq!(rates.source().0.get(), 1);
+
+    let balance =
+        BalanceResponse::deserialize(&json(r#"{"balance":20,"currency":"EUR"}"#)).unwrap();
+    assert_eq!(rates.balance_to_fiat(&balance, "EUR").await.unwrap(), 20.0);
+    assert_eq!(rates.balance_to_fiat(&balance, "USD").await.unwrap(), 25.0);
+    assert_eq!(rates.source().0.get(), 2);
+
+    let err = rates.to_fiat(Amount::ZERO, "XYZ").await.unwrap_err();
+    assert!(matches!(err, Error::RateUnavailable(c) if c == "XYZ"));
+
+    rates.invalidate();
+    rates.btc_price("USD").await.unwrap();
+    assert_eq!(rates.source().0.get(), 4);
+
+    // Expired prices are fetched again
+    let clock = Rc::new(InstantClock::default());
+    let rates = Rates::new(FixedRates(Cell::new(0))).clock(clock.clone());
+    rates.btc_price("USD").await.unwrap();
+    clock.sleep(Duration::from_secs(59)).await.unwrap();
+    rates.btc_price("USD").await.unwrap();
+    assert_eq!(rates.source().0.get(), 1);
+    clock.sleep(Duration::from_secs(1)).await.unwrap();
+    rates.btc_price("USD").await.unwrap();
+    assert_eq!(rates.source().0.get(), 2);
+}
+
+#[wasm_bindgen_test]
+async fn test_send_payment_with_amount() {
+    let mock = MockProvider::new().resolve(
+        "request",
+        json(&format!(
+            r#"{{"result":{{"status":"SUCCEEDED","payment_preimage":"{PREIMAGE}"}}}}"#
+        )),
+    );
+    let webln = client(&mock);
+
+    // Zero-amount invoice: paid through `request`
+    let res = webln
+        .send_payment_with_amount(ZERO_AMOUNT_INVOICE, Amount::from_sats(21))
+        .await
+        .unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    assert_eq!(mock.call_count("sendPayment"), 0);
+    let call = mock.last_call_args("request").unwrap();
+    assert_eq!(call[0].as_string().as_deref(), Some("sendpaymentv2"));
+    assert_eq!(
+        get(&call[1], "payment_request").as_string().as_deref(),
+        Some(ZERO_AMOUNT_INVOICE)
+    );
+    assert_eq!(
+        get(&call[1], "amt_msat").as_string().as_deref(),
+        Some("21000")
+    );
+
+    // Invoice with amount: plain `sendPayment`
+    webln
+        .send_payment_with_amount(COFFEE_INVOICE, Amount::from_sats(250_000))
+        .await
+        .unwrap();
+    assert_eq!(mock.call_count("sendPayment"), 1);
+    let err = webln
+        .send_payment_with_amount(COFFEE_INVOICE, Amount::from_sats(21))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::AmountMismatch { invoice, .. } if invoice.sats() == 250_000));
+    assert_eq!(mock.call_count("sendPayment"), 1);
+
+    // CLN fallback
+    let mock = MockProvider::new().respond("request", |method| {
+        if method.as_string().as_deref() == Some("pay") {
+            Promise::resolve(&json(&format!(
+                r#"{{"payment_preimage":"{PREIMAGE}","status":"complete"}}"#
+            )))
+        } else {
+            Promise::reject(&js_sys::Error::new("unknown method").into())
+        }
+    });
+    let webln = client(&mock);
+    let res = webln
+        .send_payment_with_amount(ZERO_AMOUNT_INVOICE, Amount::from_msat(1_500))
+        .await
+        .unwrap();
+    assert_eq!(res.preimage, PREIMAGE);
+    let call = mock.last_call_args("request").unwrap();
+    assert_eq!(
+        get(&call[1], "amount_msat").as_string().as_deref(),
+        Some("1500")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_payment_tracker() {
+    const HASH: &str = "0001020304050607080900010203040506070809000102030405060708090102";
+
+    let statuses = Rc::new(RefCell::new(vec!["IN_FLIGHT", "IN_FLIGHT", "SUCCEEDED"]));
+    let mock = {
+        let statuses = statuses.clone();
+        MockProvider::new().respond("request", move |_| {
+            let mut statuses = statuses.borrow_mut();
+            let status = if statuses.len() > 1 {
+                statuses.remove(0)
+            } else {
+                statuses[0]
+            };
+            Promise::resolve(&json(&format!(
+                r#"{{"payments":[{{"payment_hash":"ff"}},{{"payment_hash":"{HASH}","status":"{status}","payment_preimage":"{PREIMAGE}"}}]}}"#
+            )))
+        })
+    };
+    let webln = client(&mock);
+    let mut tracker = webln
+        .track_payment(HASH)
+        .interval(Duration::from_millis(10));
+    assert_eq!(
+        tracker.next().await.unwrap().unwrap(),
+        PaymentStatus::Pending
+    );
+    assert_eq!(mock.call_count("request"), 1);
+    let call = mock.last_call_args("request").unwrap();
+    assert_eq!(call[0].as_string().as_deref(), Some("listpayments"));
+
+    // Unchanged statuses are skipped, and the tracker ends after the final one
+    assert_eq!(
+        tracker.next().await.unwrap().unwrap(),
+        PaymentStatus::Settled {
+            preimage: Some(PREIMAGE.to_string())
+        }
+    );
+    assert_eq!(mock.call_count("request"), 3);
+    assert!(tracker.next().await.is_none());
+
+    let preimage = webln.track_payment(HASH).settled().await.unwrap();
+    assert_eq!(preimage.as_deref(), Some(PREIMAGE));
+
+    // Failed payment
+    let mock = MockProvider::new().resolve(
+        "request",
+        json(&format!(
+            r#"{{"payments":[{{"payment_hash":"{HASH}","status":"FAILED","failure_reason":"FAILURE_REASON_TIMEOUT"}}]}}"#
+        )),
+    );
+    let webln = client(&mock);
+    let err = webln.track_payment(HASH).settled().await.unwrap_err();
+    assert!(matches!(&err, Error::PaymentFailed(reason) if reason == "FAILURE_REASON_TIMEOUT"));
+    assert_eq!(err.code(), ErrorCode::Routing);
+}
+
+#[wasm_bindgen_test]
+async fn test_receive() {
+    /// Payment hash of [`COFFEE_INVOICE`]
+    const HASH: &str = "0001020304050607080900010203040506070809000102030405060708090102";
+
+    // Settlement found by LND `lookupinvoice` (preimage in base64)
+    let mock = MockProvider::new()
+        .resolve(
+            "makeInvoice",
+            json(&format!(r#"{{"paymentRequest":"{COFFEE_INVOICE}"}}"#)),
+        )
+        .script(
+            "request",
+            [
+                Behavior::Resolve(json(r#"{"state":"OPEN"}"#)),
+                Behavior::Resolve(json(
+                    r#"{"state":"SETTLED","r_preimage":"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE="}"#,
+                )),
+            ],
+        );
+    // Before the invoice expiry (the test vector is from 2017)
+    let clock = FakeClock::new();
+    let webln = client(&mock).with_clock(clock.clone());
+    let incoming = webln.receive(250_000, "coffee").await.unwrap();
+    assert_eq!(incoming.invoice(), COFFEE_INVOICE);
+    assert_eq!(incoming.payment_hash(), HASH);
+    let arg = mock.last_call("makeInvoice").unwrap();
+    assert_eq!(get(&arg, "amount").as_string().as_deref(), Some("250000"));
+    assert_eq!(
+        get(&arg, "defaultMemo").as_string().as_deref(),
+        Some("coffee")
+    );
+    let result = Rc::new(RefCell::new(None));
+    {
+        let result = result.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            *result.borrow_mut() = Some(incoming.interval(Duration::from_secs(1)).settled().await);
+        });
+    }
+    settle().await;
+    clock.advance(Duration::from_secs(1));
+    settle().await;
+    assert_eq!(result.borrow_mut().take().unwrap().unwrap(), PREIMAGE);
+    let call = mock.last_call_args("request").unwrap();
+    assert_eq!(call[0].as_string().as_deref(), Some("lookupinvoice"));
+    assert_eq!(
+        get(&call[1], "r_hash_str").as_string().as_deref(),
+        Some(HASH)
+    );
+    assert_eq!(mock.listener_count("paymentReceived"), 0);
+
+    // No node lookups: settlement reported by the provider event
+    let mock = MockProvider::new()
+        .resolve(
+            "makeInvoice",
+            json(&format!(r#"{{"paymentRequest":"{COFFEE_INVOICE}"}}"#)),
+        )
+        .reject("request", json(r#"{"message":"not supported"}"#));
+    let webln = client(&mock).with_clock(clock.clone());
+    let incoming = webln.receive(250_000, "coffee").await.unwrap();
+    {
+        let result = result.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            *result.borrow_mut() = Some(incoming.settled().await);
+        });
+    }
+    settle().await;
+    assert_eq!(mock.listener_count("paymentReceived"), 1);
+    mock.emit(
+        "paymentReceived",
+        &json(r#"{"paymentHash":"ff","preimage":"00"}"#),
+    );
+    settle().await;
+    assert!(result.borrow().is_none());
+    mock.emit(
+        "paymentReceived",
+        &json(&format!(
+            r#"{{"paymentHash":"{HASH}","preimage":"{PREIMAGE}"}}"#
+        )),
+    );
+    settle().await;
+    assert_eq!(result.borrow_mut().take().unwrap().unwrap(), PREIMAGE);
+    assert_eq!(mock.listener_count("paymentReceived"), 0);
+
+    // Expired, from CLN `listinvoices`
+    let mock = MockProvider::new()
+        .resolve(
+            "makeInvoice",
+            json(&format!(r#"{{"paymentRequest":"{COFFEE_INVOICE}"}}"#)),
+        )
+        .respond("request", |args| {
+            if args.as_string().as_deref() == Some("listinvoices") {
+                Promise::resolve(&json(&format!(
+                    r#"{{"invoices":[{{"payment_hash":"{HASH}","status":"expired"}}]}}"#
+                )))
+            } else {
+                Promise::reject(&json(r#"{"message":"not LND"}"#))
+            }
+        });
+    let webln = client(&mock).with_clock(clock.clone());
+    let err = webln
+        .receive(250_000, "coffee")
+        .await
+        .unwrap()
+        .settled()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::PaymentFailed(reason) if reason == "Invoice expired"));
+
+    // Polling errors are retried, with a backoff, while the event is listened for
+    let mock = MockProvider::new()
+        .resolve(
+            "makeInvoice",
+            json(&format!(r#"{{"paymentRequest":"{COFFEE_INVOICE}"}}"#)),
+        )
+        .script(
+            "request",
+            [
+                Behavior::Reject(json(r#"{"message":"offline"}"#)),
+                Behavior::Reject(json(r#"{"message":"offline"}"#)),
+                Behavior::Reject(json(r#"{"message":"offline"}"#)),
+                Behavior::Reject(json(r#"{"message":"offline"}"#)),
+                Behavior::Resolve(json(
+                    r#"{"state":"SETTLED","r_preimage":"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE="}"#,
+                )),
+            ],
+        );
+    let clock = FakeClock::new();
+    let webln = client(&mock).with_clock(clock.clone());
+    let incoming = webln.receive(250_000, "coffee").await.unwrap();
+    {
+        let result = result.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            *result.borrow_mut() = Some(incoming.interval(Duration::from_secs(1)).settled().await);
+        });
+    }
+    for _ in 0..5 {
+        settle().await;
+        clock.advance(Duration::from_secs(1));
+    }
+    settle().await;
+    assert_eq!(result.borrow_mut().take().unwrap().unwrap(), PREIMAGE);
+    // Two failed checks (LND, then CLN lookup each)
+    assert_eq!(mock.call_count("request"), 5);
+    assert!(clock.sleeps().contains(&Duration::from_secs(2)));
+
+    // Expiry enforced by the clock, without node lookups
+    let mock = MockProvider::new()
+        .resolve(
+            "makeInvoice",
+            json(&format!(r#"{{"paymentRequest":"{COFFEE_INVOICE}"}}"#)),
+        )
+        .never("request");
+    let clock = FakeClock::new();
+    let webln = client(&mock).with_clock(clock.clone());
+    let incoming = webln.receive(250_000, "coffee").await.unwrap();
+    let expires_at = incoming.decoded().expires_at();
+    {
+        let result = result.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            *result.borrow_mut() = Some(incoming.settled().await);
+        });
+    }
+    settle().await;
+    clock.advance(Duration::from_secs(expires_at - 1));
+    settle().await;
+    assert!(result.borrow().is_none());
+    clock.advance(Duration::from_secs(1));
+    settle().await;
+    let err = result.borrow_mut().take().unwrap().unwrap_err();
+    assert!(matches!(err, Error::InvoiceExpired));
+    assert_eq!(err.code(), ErrorCode::Expired);
+    assert_eq!(mock.listener_count("paymentReceived"), 0);
+
+    // Already expired
+    let webln = client(&mock);
+    let incoming = webln.receive(250_000, "coffee").await.unwrap();
+    assert!(matches!(
+        incoming.settled().await.unwrap_err(),
+        Error::InvoiceExpired
+    ));
+}
+
+#[wasm_bindgen_test]
+async fn test_persistence() {
+    const KEY: &str = "webln-test-persistence";
+
+    let mock = MockProvider::new();
+    let webln = client(&mock).with_persistence(KEY);
+    assert_eq!(webln.namespace(), Some("webln"));
+    webln.forget();
+    assert!(WebLN::restore(KEY).await.unwrap().is_none());
+    assert_eq!(mock.call_count("enable"), 0);
+
+    webln.enable().await.unwrap();
+    let state = WebLN::persisted_state(KEY).unwrap().unwrap();
+    assert!(state.enabled);
+    assert_eq!(state.namespace.as_deref(), Some("webln"));
+
+    // Page reload
+    let restored = WebLN::restore(KEY).await.unwrap().unwrap();
+    assert_eq!(restored.cached_enabled(), Some(true));
+    assert_eq!(mock.call_count("enable"), 2);
+
+    // Disconnect
+    restored.forget();
+    assert!(WebLN::persisted_state(KEY).unwrap().is_none());
+    assert_eq!(restored.cached_enabled(), None);
+
+    // Authorization revoked
+    webln.enable().await.unwrap();
+    client(&MockProvider::new().reject_user("enable"));
+    assert!(WebLN::restore(KEY).await.unwrap_err().is_user_rejected());
+    assert!(WebLN::persisted_state(KEY).unwrap().is_none());
+
+    // Resolved without enabling
+    webln.enable().await.unwrap();
+    client(&MockProvider::new().resolve("enable", json(r#"{"enabled":false}"#)));
+    let err = WebLN::restore(KEY).await.unwrap_err();
+    assert!(matches!(err.inner(), Error::NotEnabled));
+    assert!(WebLN::persisted_state(KEY).unwrap().is_none());
+}
+
+#[cfg(feature = "journal")]
+#[wasm_bindgen_test]
+async fn test_journal() {
+    use webln::{Journal, JournalQuery, ReceiptKind};
+
+    let journal = Journal::open("webln-test-journal")
+        .await
+        .unwrap()
+        .max_entries(2);
+    journal.clear().await.unwrap();
+
+    let mock = MockProvider::new();
+    let clock = FakeClock::new();
+    clock.advance(Duration::from_secs(1_700_000_000));
+    let webln = client(&mock)
+        .with_journal(journal.clone())
+        .with_clock(clock);
+    webln.send_payment(COFFEE_INVOICE).await.unwrap();
+    let receipts = journal.receipts().await.unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].timestamp, 1_700_000_000);
+    assert_eq!(receipts[0].kind, ReceiptKind::Invoice);
+    assert_eq!(receipts[0].preimage, PREIMAGE);
+    assert_eq!(receipts[0].amount, Some(Amount::from_sats(250_000)));
+    assert_eq!(receipts[0].description.as_deref(), Some("1 cup coffee"));
+    assert!(receipts[0].id.is_some());
+
+    // Failed payments are not recorded
+    let failing = client(&MockProvider::new().reject_user("keysend")).with_journal(journal.clone());
+    assert!(failing.keysend(&keysend_args()).await.is_err());
+    assert_eq!(journal.count().await.unwrap(), 1);
+
+    // Capped to the newest receipts
+    let webln = client(&mock).with_journal(journal.clone());
+    webln.keysend(&keysend_args()).await.unwrap();
+    webln.keysend(&keysend_args()).await.unwrap();
+    assert_eq!(journal.count().await.unwrap(), 2);
+    let receipts = journal
+        .query(&JournalQuery::new().kind(ReceiptKind::Invoice))
+        .await
+        .unwrap();
+    assert!(receipts.is_empty());
+    let receipts = journal.query(&JournalQuery::new().limit(1)).await.unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].amount, Some(Amount::from_sats(21)));
+    assert_eq!(receipts[0].destination.unwrap().to_hex(), PUBKEY);
+
+    // Refunds carry their reference
+    let node_id = NodeId::from_str(PUBKEY).unwrap();
+    webln.refund(node_id, 5, "order-42").await.unwrap();
+    let receipts = journal
+        .query(&JournalQuery::new().kind(ReceiptKind::Refund))
+        .await
+        .unwrap();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].amount, Some(Amount::from_sats(5)));
+    assert_eq!(receipts[0].reference.as_deref(), Some("order-42"));
+
+    journal.clear().await.unwrap();
+    assert_eq!(journal.count().await.unwrap(), 0);
+}
+
+#[cfg(feature = "journal")]
+#[wasm_bindgen_test]
+async fn test_journal_export_import() {
+    use webln::Journal;
+
+    let source = Journal::open("webln-test-journal-export").await.unwrap();
+    source.clear().await.unwrap();
+    let webln = client(&MockProvider::new()).with_journal(source.clone());
+    webln.send_payment(COFFEE_INVOICE).await.unwrap();
+    webln.keysend(&keysend_args()).await.unwrap();
+    let blob = source.export().await.unwrap();
+    assert!(blob.contains(r#""amountMsat":250000000"#));
+
+    let target = Journal::open("webln-test-journal-import").await.unwrap();
+    target.clear().await.unwrap();
+    assert_eq!(target.import(&blob).await.unwrap(), 2);
+    let imported = target.receipts().await.unwrap();
+    let exported = source.receipts().await.unwrap();
+    assert_eq!(imported.len(), 2);
+    for (imported, exported) in imported.iter().zip(exported.iter()) {
+        assert_eq!(imported.kind, exported.kind);
+        assert_eq!(imported.timestamp, exported.timestamp);
+        assert_eq!(imported.invoice, exported.invoice);
+    }
+
+    // Already imported
+    assert_eq!(target.import(&blob).await.unwrap(), 0);
+    assert_eq!(target.count().await.unwrap(), 2);
+
+    // Importing older receipts into a full journal keeps the newest ones
+    let full = Journal::open("webln-test-journal-import-full")
+        .await
+        .unwrap()
+        .max_entries(2);
+    full.clear().await.unwrap();
+    let webln = client(&MockProvider::new()).with_journal(full.clone());
+    webln.keysend(&keysend_args()).await.unwrap();
+    webln.keysend(&keysend_args()).await.unwrap();
+    let old = r#"{"version":1,"receipts":[{"timestamp":1000,"kind":"keysend","preimage":"aa"},{"timestamp":2000,"kind":"keysend","preimage":"bb"}]}"#;
+    assert_eq!(full.import(old).await.unwrap(), 2);
+    let receipts = full.receipts().await.unwrap();
+    assert_eq!(receipts.len(), 2);
+    assert!(receipts.iter().all(|receipt| receipt.preimage == PREIMAGE));
+
+    assert!(matches!(
+        target.import("not json").await.unwrap_err(),
+        Error::TypeMismatch(_)
+    ));
+    assert!(matches!(
+        target
+            .import(r#"{"version":2,"receipts":[]}"#)
+            .await
+            .unwrap_err(),
+        Error::TypeMismatch(_)
+    ));
+}
+
+#[wasm_bindgen_test]
+async fn test_request() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let res = webln.request("getinfo", None).await.unwrap();
+    assert_eq!(get(&res, "alias").as_string().as_deref(), Some("mock"));
+    assert_eq!(get(&res, "num_peers").as_f64(), Some(3.0));
+    let args = mock.last_call_args("request").unwrap();
+    assert_eq!(args[0].as_string().as_deref(), Some("getinfo"));
+    assert!(args[1].is_undefined());
+
+    let params = json(r#"{"active_only":true}"#);
+    webln.request("listchannels", Some(&params)).await.unwrap();
+    let args = mock.last_call_args("request").unwrap();
+    assert_eq!(args[0].as_string().as_deref(), Some("listchannels"));
+    assert_eq!(get(&args[1], "active_only"), JsValue::TRUE);
+
+    let webln = client(&MockProvider::new().reject_user("request"));
+    assert!(matches!(
+        webln.request("getinfo", None).await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().remove("request"));
+    assert!(
+        matches!(webln.request("getinfo", None).await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "request")
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_events() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+
+    let count = Rc::new(Cell::new(0));
+    let subscription = webln
+        .subscribe(Event::AccountChanged, {
+            let count = count.clone();
+            move |_| count.set(count.get() + 1)
+        })
+        .unwrap();
+    assert_eq!(subscription.event(), &Event::AccountChanged);
+    assert_eq!(mock.listener_count("accountChanged"), 1);
+
+    mock.emit("accountChanged", &JsValue::UNDEFINED);
+    mock.emit("accountChanged", &JsValue::UNDEFINED);
+    mock.emit("other", &JsValue::UNDEFINED);
+    assert_eq!(count.get(), 2);
+
+    drop(subscription);
+    assert_eq!(mock.listener_count("accountChanged"), 0);
+    mock.emit("accountChanged", &JsValue::UNDEFINED);
+    assert_eq!(count.get(), 2);
+
+    let subscription = webln.subscribe(Event::from("custom"), |_| ()).unwrap();
+    assert_eq!(mock.listener_count("custom"), 1);
+    subscription.unsubscribe().unwrap();
+    assert_eq!(mock.listener_count("custom"), 0);
+
+    let webln = client(&MockProvider::new().remove("on"));
+    assert!(
+        matches!(webln.subscribe(Event::AccountChanged, |_| ()).unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "on")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_sign_message() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let res = webln.sign_message("hello").await.unwrap();
+    assert_eq!(res.message, "hello");
+    assert_eq!(res.signature, SIGNATURE);
+    assert_eq!(
+        mock.last_call("signMessage")
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some("hello")
+    );
+
+    let webln = client(&MockProvider::new().reject_user("signMessage"));
+    assert!(matches!(
+        webln.sign_message("hello").await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("signMessage", json(r#"{"message":"hello"}"#)));
+    assert!(matches!(
+        webln.sign_message("hello").await.unwrap_err().inner(),
+        Error::TypeMismatch(_)
+    ));
+
+    let webln = client(&MockProvider::new().remove("signMessage"));
+    assert!(
+        matches!(webln.sign_message("hello").await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "signMessage")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn test_login() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let proof = webln.login("webln-login:7f3a9c").await.unwrap();
+    assert_eq!(proof.pubkey, NodeId::from_str(PUBKEY).unwrap());
+    assert_eq!(proof.message, "webln-login:7f3a9c");
+    assert_eq!(proof.signature, SIGNATURE);
+    assert!(proof.timestamp > 0);
+    assert_eq!(
+        mock.last_call("signMessage")
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some("webln-login:7f3a9c")
+    );
+
+    let webln = client(&MockProvider::new().resolve("getInfo", json(r#"{"node":{}}"#)));
+    assert!(
+        matches!(webln.login("webln-login:7f3a9c").await.unwrap_err(), Error::ObjectKeyNotFound(k) if k == "pubkey")
+    );
+
+    let webln = client(&MockProvider::new().reject_user("signMessage"));
+    assert!(webln
+        .login("webln-login:7f3a9c")
+        .await
+        .unwrap_err()
+        .is_user_rejected());
+}
+
+#[wasm_bindgen_test]
+async fn test_sign_message_bytes() {
+    let mock = MockProvider::new();
+    let webln = client(&mock);
+    let challenge: &[u8] = &[0xde, 0xad, 0xbe, 0xef];
+
+    let res = webln
+        .sign_message_bytes(challenge, MessageEncoding::Hex)
+        .await
+        .unwrap();
+    assert_eq!(res.message, "deadbeef");
+    assert_eq!(
+        mock.last_call("signMessage")
+            .unwrap()
+            .as_string()
+            .as_deref(),
+        Some("deadbeef")
+    );
+
+    let res = webln
+        .sign_message_bytes(challenge, MessageEncoding::Base64)
+        .await
+        .unwrap();
+    assert_eq!(res.message, "3q2+7w==");
+
+    let res = webln
+        .sign_message_bytes(b"hello", MessageEncoding::Utf8)
+        .await
+        .unwrap();
+    assert_eq!(res.message, "hello");
+
+    assert!(matches!(
+        webln
+            .sign_message_bytes(challenge, MessageEncoding::Utf8)
+            .await
+            .unwrap_err(),
+        Error::Utf8(_)
+    ));
+    assert_eq!(mock.call_count("signMessage"), 3);
+}
+
+#[wasm_bindgen_test]
+async fn test_get_balance() {
+    let webln = client(&MockProvider::new());
+    let res = webln.get_balance().await.unwrap();
+    assert_eq!(res.balance, 21000.0);
+    assert_eq!(res.currency.as_deref(), Some("sats"));
+
+    let webln = client(&MockProvider::new().resolve("getBalance", json(r#"{"balance":1.5}"#)));
+    let res = webln.get_balance().await.unwrap();
+    assert_eq!(res.balance, 1.5);
+    assert_eq!(res.currency, None);
+
+    let webln = client(&MockProvider::new().reject_user("getBalance"));
+    assert!(matches!(
+        webln.get_balance().await.unwrap_err().inner(),
+        Error::UserRejected(_)
+    ));
+
+    let webln = client(&MockProvider::new().resolve("getBalance", json(r#"{"balance":"21000"}"#)));
+    assert!(matches!(
+        webln.get_balance().await.unwrap_err().inner(),
+        Error::TypeMismatch(_)
+    ));
+
+    let webln = client(&MockProvider::new().remove("getBalance"));
+    assert!(
+        matches!(webln.get_balance().await.unwrap_err().inner(), Error::NamespaceNotFound(n) if n == "getBalance")
+    );
+}
+
+#[cfg(feature = "webbtc")]
+#[wasm_bindgen_test]
+async fn test_webbtc() {
+    use webln::WebBTC;
+
+    const ADDRESS: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+    let mock = MockProvider::new()
+        .resolve(
+            "getInfo",
+            json(
+                r#"{"version":"1.0","supports":["lightning","onchain"],"methods":["getAddress"]}"#,
+            ),
+        )
+        .resolve(
+            "getAddress",
+            json(&format!(
+                r#"{{"address":"{ADDRESS}","derivationPath":"m/84'/0'/0'/0/3","index":3}}"#
+            )),
+        )
+        .resolve("sendTransaction", json(r#"{"txId":"ff00"}"#));
+    let webbtc = WebBTC::from_provider(mock.object());
+    webbtc.enable().await.unwrap();
+
+    let info = webbtc.get_info().await.unwrap();
+    assert_eq!(info.version.as_deref(), Some("1.0"));
+    assert_eq!(info.supports, vec!["lightning", "onchain"]);
+
+    let address = webbtc.get_address().await.unwrap();
+    assert_eq!(address.address, ADDRESS);
+    assert_eq!(address.index, Some(3));
+    assert_eq!(address.public_key, None);
+
+    let res = webbtc
+        .send_transaction(ADDRESS, Amount::from_msat(21_000_999))
+        .await
+        .unwrap();
+    assert_eq!(res.txid.as_deref(), Some("ff00"));
+    let call = mock.last_call_args("sendTransaction").unwrap();
+    assert_eq!(call[0].as_string().as_deref(), Some(ADDRESS));
+    assert_eq!(call[1].as_string().as_deref(), Some("21000"));
+
+    let mock = MockProvider::new().reject_user("sendTransaction");
+    let err = WebBTC::from_provider(mock.object())
+        .send_transaction(ADDRESS, Amount::from_sats(1))
+        .await
+        .unwrap_err();
+    assert!(err.is_user_rejected());
+
+    let err = WebBTC::new().unwrap_err();
+    assert_eq!(err.code(), ErrorCode::MissingProvider);
+}
+
+#[cfg(feature = "nostr")]
+#[wasm_bindgen_test]
+async fn test_nostr() {
+    use webln::{Nostr, UnsignedEvent};
+
+    const NOSTR_PUBKEY: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    let mock = MockProvider::new()
+        .resolve("getPublicKey", JsValue::from_str(NOSTR_PUBKEY))
+        .respond("signEvent", |event| {
+            let signed = js_sys::Object::assign(&js_sys::Object::new(), event.unchecked_ref());
+            Reflect::set(&signed, &"id".into(), &"ee".into()).unwrap();
+            Reflect::set(&signed, &"pubkey".into(), &NOSTR_PUBKEY.into()).unwrap();
+            Reflect::set(&signed, &"sig".into(), &"ff".into()).unwrap();
+            Promise::resolve(&signed)
+        });
+    let nostr = Nostr::from_provider(mock.object());
+    assert_eq!(nostr.get_public_key().await.unwrap(), NOSTR_PUBKEY);
+
+    let event = UnsignedEvent::new(1, "gm")
+        .created_at(1_700_000_000)
+        .tag(["p", NOSTR_PUBKEY]);
+    let signed = nostr.sign_event(&event).await.unwrap();
+    assert_eq!(signed.pubkey, NOSTR_PUBKEY);
+    assert_eq!(signed.created_at, 1_700_000_000);
+    assert_eq!(signed.kind, 1);
+    assert_eq!(
+        signed.tags,
+        vec![vec!["p".to_string(), NOSTR_PUBKEY.to_string()]]
+    );
+    assert_eq!(signed.content, "gm");
+    assert_eq!(signed.sig, "ff");
+
+    // NIP-04
+    let err = nostr.nip04_encrypt(NOSTR_PUBKEY, "hi").await.unwrap_err();
+    assert_eq!(err.code(), ErrorCode::UnsupportedMethod);
+    let nip04 = MockProvider::new()
+        .resolve("encrypt", JsValue::from_str("ciphertext?iv=aa"))
+        .reject_user("decrypt");
+    Reflect::set(&mock.object(), &"nip04".into(), &nip04.object()).unwrap();
+    let ciphertext = nostr.nip04_encrypt(NOSTR_PUBKEY, "hi").await.unwrap();
+    assert_eq!(ciphertext, "ciphertext?iv=aa");
+    let call = nip04.last_call_args("encrypt").unwrap();
+    assert_eq!(call[1].as_string().as_deref(), Some("hi"));
+    let err = nostr
+        .nip04_decrypt(NOSTR_PUBKEY, &ciphertext)
+        .await
+        .unwrap_err();
+    assert!(err.is_user_rejected());
+
+    assert_eq!(Nostr::new().unwrap_err().code(), ErrorCode::MissingProvider);
+}
+
+#[cfg(all(feature = "nostr", feature = "secp256k1"))]
+#[wasm_bindgen_test]
+fn test_zap_receipt() {
+    use webln::{Deserialize, SignedEvent, ZapReceipt};
+
+    /// `nostrPubkey` of the LNURL server
+    const SERVER_PUBKEY: &str = "e493dbf1c10d80f3581e4904930b1404cc6c13900ee0758474fa94abe8c4cd13";
+
+    let event =
+        SignedEvent::deserialize(&json(include_str!("fixtures/nostr/zapReceipt.json"))).unwrap();
+    let receipt = ZapReceipt::from_event(event.clone()).unwrap();
+    receipt.verify(SERVER_PUBKEY).unwrap();
+    assert_eq!(receipt.amount(), Some(Amount::from_sats(21)));
+    assert_eq!(
+        receipt.sender(),
+        "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5"
+    );
+    assert_eq!(receipt.request.kind, 9734);
+
+    // Published by someone else than the recipient's LNURL server
+    let err = receipt.verify(&receipt.request.pubkey).unwrap_err();
+    assert!(matches!(err, Error::InvalidSignature));
+
+    // Zap request altered after signing
+    let mut tampered = receipt.clone();
+    tampered.request.tags.retain(|tag| tag[0] != "amount");
+    assert!(matches!(
+        tampered.verify(SERVER_PUBKEY),
+        Err(Error::InvalidSignature)
+    ));
+
+    // Not a zap receipt
+    let mut note = event.clone();
+    note.kind = 1;
+    let err = ZapReceipt::from_event(note).unwrap_err();
+    assert!(matches!(err, Error::InvalidZapReceipt(..)));
+    assert_eq!(err.code(), ErrorCode::InvalidData);
+
+    // Description isn't a zap request
+    let mut event = event;
+    event.tags.retain(|tag| tag[0] != "description");
+    event.tags.push(vec!["description".into(), "{}".into()]);
+    assert!(ZapReceipt::from_event(event.clone()).is_err());
+    event.tags.retain(|tag| tag[0] != "bolt11");
+    assert!(matches!(
+        ZapReceipt::from_event(event),
+        Err(Error::InvalidZapReceipt(..))
+    ));
+}
+
+#[cfg(feature = "alby")]
+#[wasm_bindgen_test]
+async fn test_alby() {
+    use webln::{AddAccountArgs, Alby};
+
+    let mock = MockProvider::new().resolve(
+        "getInfo",
+        json(&format!(
+            r#"{{"version":"Alby","supports":["lightning","nostr"],"node":{{"alias":"alby","pubkey":"{PUBKEY}"}},"methods":["getInfo","sendPayment"]}}"#
+        )),
+    );
+    let info = client(&mock).alby_info().await.unwrap().unwrap();
+    assert_eq!(info.version, "Alby");
+    assert!(info.supports("Nostr"));
+    assert_eq!(info.info.node.alias.as_deref(), Some("alby"));
+
+    // Other providers
+    let mock = MockProvider::new();
+    assert!(client(&mock).alby_info().await.unwrap().is_none());
+
+    let alby = MockProvider::new().resolve("addAccount", json(r#"{"success":true}"#));
+    let args = AddAccountArgs::new("Wallet", "nwc")
+        .config("nostrWalletConnectUrl", "nostr+walletconnect://ff");
+    assert!(Alby::from_provider(alby.object())
+        .add_account(&args)
+        .await
+        .unwrap());
+    let call = alby.last_call("addAccount").unwrap();
+    assert_eq!(get(&call, "connector").as_string().as_deref(), Some("nwc"));
+    assert_eq!(
+        get(&get(&call, "config"), "nostrWalletConnectUrl")
+            .as_string()
+            .as_deref(),
+        Some("nostr+walletconnect://ff")
+    );
+
+    assert!(!Alby::is_available());
+    assert_eq!(Alby::new().unwrap_err().code(), ErrorCode::MissingProvider);
+}
+
+#[cfg(feature = "json")]
+#[wasm_bindgen_test]
+async fn test_request_json() {
+    let mock = MockProvider::new().resolve(
+        "request",
+        json(r#"{"channels":[{"chan_id":"1","active":true,"capacity":"100000"}]}"#),
+    );
+    let webln = client(&mock);
+    let res = webln
+        .request_json("listchannels", serde_json::json!({"active_only": true}))
+        .await
+        .unwrap();
+    assert_eq!(res["channels"][0]["chan_id"], "1");
+    assert_eq!(res["channels"][0]["active"], true);
+
+    // Params are plain objects
+    let call = mock.last_call_args("request").unwrap();
+    assert_eq!(call[0].as_string().as_deref(), Some("listchannels"));
+    assert_eq!(get(&call[1], "active_only").as_bool(), Some(true));
+
+    webln
+        .request_json("getinfo", serde_json::Value::Null)
+        .await
+        .unwrap();
+    let call = mock.last_call_args("request").unwrap();
+    assert!(call[1].is_undefined());
+}